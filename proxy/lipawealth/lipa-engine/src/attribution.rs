@@ -0,0 +1,580 @@
+//! Brinson-Fachler performance attribution against a benchmark, with multi-period
+//! linking so effects add up to the compounded active return.
+
+use std::collections::HashSet;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::js::{from_js, js_err, to_js};
+
+/// How far weights may stray from summing to one before we reject the input.
+const WEIGHT_TOLERANCE: f64 = 1e-6;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum AttributionError {
+    NoPeriods,
+    EmptyPeriod {
+        period: usize,
+    },
+    DuplicateSegment {
+        period: usize,
+        segment: String,
+    },
+    /// Portfolio or benchmark weights do not sum to one.
+    WeightsDoNotSum {
+        period: usize,
+        side: &'static str,
+        total: f64,
+    },
+    InvalidReturn {
+        period: usize,
+        segment: String,
+    },
+}
+
+impl fmt::Display for AttributionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AttributionError::NoPeriods => f.write_str("no periods to attribute"),
+            AttributionError::EmptyPeriod { period } => {
+                write!(f, "period {} has no segments", period)
+            }
+            AttributionError::DuplicateSegment { period, segment } => {
+                write!(f, "segment {} appears twice in period {}", segment, period)
+            }
+            AttributionError::WeightsDoNotSum {
+                period,
+                side,
+                total,
+            } => write!(
+                f,
+                "{} weights in period {} sum to {}, not 1",
+                side, period, total
+            ),
+            AttributionError::InvalidReturn { period, segment } => write!(
+                f,
+                "segment {} in period {} has a non-finite weight or return",
+                segment, period
+            ),
+        }
+    }
+}
+
+impl std::error::Error for AttributionError {}
+
+/// One sector (or asset class) in one period. Weights are fractions of the whole.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SegmentInput {
+    pub segment: String,
+    pub portfolio_weight: f64,
+    pub portfolio_return: f64,
+    pub benchmark_weight: f64,
+    pub benchmark_return: f64,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttributionPeriod {
+    #[serde(default)]
+    pub label: Option<String>,
+    pub segments: Vec<SegmentInput>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LinkingMethod {
+    #[default]
+    Carino,
+    Menchero,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SegmentEffect {
+    pub segment: String,
+    pub allocation: f64,
+    pub selection: f64,
+    pub interaction: f64,
+    pub total: f64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum StepKind {
+    /// A bar drawn from zero (benchmark and portfolio returns).
+    Total,
+    /// A floating bar from `start` to `end`.
+    Delta,
+}
+
+/// One bar of the benchmark → allocation → selection → interaction → portfolio chart.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WaterfallStep {
+    pub label: String,
+    pub kind: StepKind,
+    pub value: f64,
+    pub start: f64,
+    pub end: f64,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PeriodAttribution {
+    pub label: Option<String>,
+    pub portfolio_return: f64,
+    pub benchmark_return: f64,
+    pub active_return: f64,
+    pub allocation: f64,
+    pub selection: f64,
+    pub interaction: f64,
+    pub segments: Vec<SegmentEffect>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LinkedAttribution {
+    pub method: LinkingMethod,
+    /// Compounded over all periods.
+    pub portfolio_return: f64,
+    pub benchmark_return: f64,
+    pub active_return: f64,
+    pub allocation: f64,
+    pub selection: f64,
+    pub interaction: f64,
+    /// Linked effects per segment, in order of first appearance.
+    pub segments: Vec<SegmentEffect>,
+    /// The unlinked single-period results.
+    pub periods: Vec<PeriodAttribution>,
+    pub waterfall: Vec<WaterfallStep>,
+}
+
+fn check_period(index: usize, period: &AttributionPeriod) -> Result<(), AttributionError> {
+    if period.segments.is_empty() {
+        return Err(AttributionError::EmptyPeriod { period: index });
+    }
+    let mut seen = HashSet::new();
+    for s in &period.segments {
+        if !seen.insert(s.segment.as_str()) {
+            return Err(AttributionError::DuplicateSegment {
+                period: index,
+                segment: s.segment.clone(),
+            });
+        }
+        let values = [
+            s.portfolio_weight,
+            s.portfolio_return,
+            s.benchmark_weight,
+            s.benchmark_return,
+        ];
+        if values.iter().any(|v| !v.is_finite()) {
+            return Err(AttributionError::InvalidReturn {
+                period: index,
+                segment: s.segment.clone(),
+            });
+        }
+    }
+    for (side, total) in [
+        (
+            "portfolio",
+            period.segments.iter().map(|s| s.portfolio_weight).sum(),
+        ),
+        (
+            "benchmark",
+            period
+                .segments
+                .iter()
+                .map(|s| s.benchmark_weight)
+                .sum::<f64>(),
+        ),
+    ] {
+        if (total - 1.0).abs() > WEIGHT_TOLERANCE {
+            return Err(AttributionError::WeightsDoNotSum {
+                period: index,
+                side,
+                total,
+            });
+        }
+    }
+    Ok(())
+}
+
+fn attribute_period(period: &AttributionPeriod) -> PeriodAttribution {
+    let portfolio_return: f64 = period
+        .segments
+        .iter()
+        .map(|s| s.portfolio_weight * s.portfolio_return)
+        .sum();
+    let benchmark_return: f64 = period
+        .segments
+        .iter()
+        .map(|s| s.benchmark_weight * s.benchmark_return)
+        .sum();
+    let segments: Vec<SegmentEffect> = period
+        .segments
+        .iter()
+        .map(|s| {
+            let active_weight = s.portfolio_weight - s.benchmark_weight;
+            let allocation = active_weight * (s.benchmark_return - benchmark_return);
+            let selection = s.benchmark_weight * (s.portfolio_return - s.benchmark_return);
+            let interaction = active_weight * (s.portfolio_return - s.benchmark_return);
+            SegmentEffect {
+                segment: s.segment.clone(),
+                allocation,
+                selection,
+                interaction,
+                total: allocation + selection + interaction,
+            }
+        })
+        .collect();
+    PeriodAttribution {
+        label: period.label.clone(),
+        portfolio_return,
+        benchmark_return,
+        active_return: portfolio_return - benchmark_return,
+        allocation: segments.iter().map(|s| s.allocation).sum(),
+        selection: segments.iter().map(|s| s.selection).sum(),
+        interaction: segments.iter().map(|s| s.interaction).sum(),
+        segments,
+    }
+}
+
+/// Single-period Brinson-Fachler: allocation is measured against the overall
+/// benchmark return, so overweighting a sector only scores if it beat the index.
+pub fn brinson_fachler(period: &AttributionPeriod) -> Result<PeriodAttribution, AttributionError> {
+    check_period(0, period)?;
+    Ok(attribute_period(period))
+}
+
+/// Carino's log ratio `ln(1+r) - ln(1+b)` over `r - b`, with its limit when they meet.
+fn carino_k(r: f64, b: f64) -> f64 {
+    if (r - b).abs() < 1e-12 {
+        1.0 / (1.0 + r)
+    } else {
+        ((1.0 + r).ln() - (1.0 + b).ln()) / (r - b)
+    }
+}
+
+/// Per-period multipliers that make the summed, scaled effects equal the compounded
+/// active return.
+fn link_coefficients(periods: &[PeriodAttribution], method: LinkingMethod) -> Vec<f64> {
+    let r = periods
+        .iter()
+        .fold(1.0, |g, p| g * (1.0 + p.portfolio_return))
+        - 1.0;
+    let b = periods
+        .iter()
+        .fold(1.0, |g, p| g * (1.0 + p.benchmark_return))
+        - 1.0;
+    match method {
+        LinkingMethod::Carino => {
+            let k = carino_k(r, b);
+            periods
+                .iter()
+                .map(|p| carino_k(p.portfolio_return, p.benchmark_return) / k)
+                .collect()
+        }
+        LinkingMethod::Menchero => {
+            let t = periods.len() as f64;
+            let a = if (r - b).abs() < 1e-12 {
+                (1.0 + r).powf((t - 1.0) / t)
+            } else {
+                (r - b) / t / ((1.0 + r).powf(1.0 / t) - (1.0 + b).powf(1.0 / t))
+            };
+            let sum_active: f64 = periods.iter().map(|p| p.active_return).sum();
+            let sum_sq: f64 = periods.iter().map(|p| p.active_return.powi(2)).sum();
+            let alpha = if sum_sq == 0.0 {
+                0.0
+            } else {
+                (r - b - a * sum_active) / sum_sq
+            };
+            periods
+                .iter()
+                .map(|p| a + alpha * p.active_return)
+                .collect()
+        }
+    }
+}
+
+/// Builds the benchmark → effects → portfolio bars for the dashboard.
+pub fn waterfall(
+    benchmark_return: f64,
+    allocation: f64,
+    selection: f64,
+    interaction: f64,
+) -> Vec<WaterfallStep> {
+    let mut steps = vec![WaterfallStep {
+        label: "Benchmark".to_string(),
+        kind: StepKind::Total,
+        value: benchmark_return,
+        start: 0.0,
+        end: benchmark_return,
+    }];
+    let mut level = benchmark_return;
+    for (label, value) in [
+        ("Allocation", allocation),
+        ("Selection", selection),
+        ("Interaction", interaction),
+    ] {
+        steps.push(WaterfallStep {
+            label: label.to_string(),
+            kind: StepKind::Delta,
+            value,
+            start: level,
+            end: level + value,
+        });
+        level += value;
+    }
+    steps.push(WaterfallStep {
+        label: "Portfolio".to_string(),
+        kind: StepKind::Total,
+        value: level,
+        start: 0.0,
+        end: level,
+    });
+    steps
+}
+
+/// Attributes each period and links the effects across them.
+///
+/// With a single period the linked result equals the single-period one.
+pub fn attribute(
+    periods: &[AttributionPeriod],
+    method: LinkingMethod,
+) -> Result<LinkedAttribution, AttributionError> {
+    if periods.is_empty() {
+        return Err(AttributionError::NoPeriods);
+    }
+    for (i, p) in periods.iter().enumerate() {
+        check_period(i, p)?;
+    }
+    let single: Vec<PeriodAttribution> = periods.iter().map(attribute_period).collect();
+    let coefficients = link_coefficients(&single, method);
+
+    let mut segments: Vec<SegmentEffect> = Vec::new();
+    for (p, c) in single.iter().zip(&coefficients) {
+        for s in &p.segments {
+            let pos = match segments.iter().position(|x| x.segment == s.segment) {
+                Some(pos) => pos,
+                None => {
+                    segments.push(SegmentEffect {
+                        segment: s.segment.clone(),
+                        allocation: 0.0,
+                        selection: 0.0,
+                        interaction: 0.0,
+                        total: 0.0,
+                    });
+                    segments.len() - 1
+                }
+            };
+            let linked = &mut segments[pos];
+            linked.allocation += c * s.allocation;
+            linked.selection += c * s.selection;
+            linked.interaction += c * s.interaction;
+            linked.total += c * s.total;
+        }
+    }
+
+    let portfolio_return = single
+        .iter()
+        .fold(1.0, |g, p| g * (1.0 + p.portfolio_return))
+        - 1.0;
+    let benchmark_return = single
+        .iter()
+        .fold(1.0, |g, p| g * (1.0 + p.benchmark_return))
+        - 1.0;
+    let allocation = segments.iter().map(|s| s.allocation).sum();
+    let selection = segments.iter().map(|s| s.selection).sum();
+    let interaction = segments.iter().map(|s| s.interaction).sum();
+    Ok(LinkedAttribution {
+        method,
+        portfolio_return,
+        benchmark_return,
+        active_return: portfolio_return - benchmark_return,
+        allocation,
+        selection,
+        interaction,
+        segments,
+        periods: single,
+        waterfall: waterfall(benchmark_return, allocation, selection, interaction),
+    })
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AttributionInput {
+    periods: Vec<AttributionPeriod>,
+    #[serde(default)]
+    linking: LinkingMethod,
+}
+
+/// JS entry point: `{ periods: [{ label?, segments }], linking? }` in, `LinkedAttribution` out.
+#[wasm_bindgen(js_name = brinsonAttribution)]
+pub fn attribute_js(input: JsValue) -> Result<JsValue, JsError> {
+    let input: AttributionInput = from_js(input)?;
+    to_js(&attribute(&input.periods, input.linking).map_err(js_err)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64, tol: f64) {
+        assert!((a - b).abs() < tol, "{} != {}", a, b);
+    }
+
+    fn segment(name: &str, pw: f64, pr: f64, bw: f64, br: f64) -> SegmentInput {
+        SegmentInput {
+            segment: name.to_string(),
+            portfolio_weight: pw,
+            portfolio_return: pr,
+            benchmark_weight: bw,
+            benchmark_return: br,
+        }
+    }
+
+    fn period(segments: Vec<SegmentInput>) -> AttributionPeriod {
+        AttributionPeriod {
+            label: None,
+            segments,
+        }
+    }
+
+    fn quarters() -> Vec<AttributionPeriod> {
+        vec![
+            period(vec![
+                segment("Equity", 0.6, 0.10, 0.5, 0.08),
+                segment("Bonds", 0.4, 0.03, 0.5, 0.04),
+            ]),
+            period(vec![
+                segment("Equity", 0.7, -0.12, 0.5, -0.09),
+                segment("Bonds", 0.3, 0.02, 0.5, 0.015),
+            ]),
+            period(vec![
+                segment("Equity", 0.55, 0.05, 0.6, 0.06),
+                segment("Bonds", 0.35, 0.01, 0.4, 0.012),
+                segment("Cash", 0.10, 0.003, 0.0, 0.0),
+            ]),
+            period(vec![
+                segment("Equity", 0.5, 0.20, 0.6, 0.15),
+                segment("Bonds", 0.5, -0.01, 0.4, 0.0),
+            ]),
+        ]
+    }
+
+    #[test]
+    fn brinson_fachler_single_period() {
+        let p = brinson_fachler(&quarters()[0]).unwrap();
+        close(p.portfolio_return, 0.072, 1e-12);
+        close(p.benchmark_return, 0.06, 1e-12);
+        close(p.allocation, 0.004, 1e-12);
+        close(p.selection, 0.005, 1e-12);
+        close(p.interaction, 0.003, 1e-12);
+        close(p.segments[0].allocation, 0.002, 1e-12);
+        close(p.segments[1].selection, -0.005, 1e-12);
+        close(
+            p.allocation + p.selection + p.interaction,
+            p.active_return,
+            1e-12,
+        );
+    }
+
+    #[test]
+    fn linked_effects_sum_to_the_compounded_active_return() {
+        let periods = quarters();
+        let compound = |f: fn(&PeriodAttribution) -> f64| {
+            periods
+                .iter()
+                .map(|p| f(&brinson_fachler(p).unwrap()))
+                .fold(1.0, |g, r| g * (1.0 + r))
+                - 1.0
+        };
+        let active = compound(|p| p.portfolio_return) - compound(|p| p.benchmark_return);
+        // Arithmetic addition of the period effects would not reconcile.
+        let naive: f64 = periods
+            .iter()
+            .map(|p| brinson_fachler(p).unwrap().active_return)
+            .sum();
+        assert!((naive - active).abs() > 1e-4);
+
+        for method in [LinkingMethod::Carino, LinkingMethod::Menchero] {
+            let linked = attribute(&periods, method).unwrap();
+            close(linked.active_return, active, 1e-12);
+            close(
+                linked.allocation + linked.selection + linked.interaction,
+                active,
+                1e-12,
+            );
+            let by_segment: f64 = linked.segments.iter().map(|s| s.total).sum();
+            close(by_segment, active, 1e-12);
+            assert_eq!(linked.segments.len(), 3);
+            let last = linked.waterfall.last().unwrap();
+            close(last.end, linked.portfolio_return, 1e-12);
+        }
+    }
+
+    #[test]
+    fn linking_survives_equal_portfolio_and_benchmark_returns() {
+        let flat = period(vec![
+            segment("Equity", 0.6, 0.05, 0.4, 0.05),
+            segment("Bonds", 0.4, 0.05, 0.6, 0.05),
+        ]);
+        for method in [LinkingMethod::Carino, LinkingMethod::Menchero] {
+            let linked = attribute(&[flat.clone(), flat.clone()], method).unwrap();
+            close(linked.active_return, 0.0, 1e-12);
+            close(
+                linked.allocation + linked.selection + linked.interaction,
+                0.0,
+                1e-12,
+            );
+            assert!(linked.allocation.is_finite());
+        }
+    }
+
+    #[test]
+    fn single_period_linking_is_the_identity() {
+        let one = &quarters()[..1];
+        let single = brinson_fachler(&one[0]).unwrap();
+        for method in [LinkingMethod::Carino, LinkingMethod::Menchero] {
+            let linked = attribute(one, method).unwrap();
+            close(linked.allocation, single.allocation, 1e-12);
+            close(linked.selection, single.selection, 1e-12);
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_periods() {
+        assert_eq!(
+            attribute(&[], LinkingMethod::Carino),
+            Err(AttributionError::NoPeriods)
+        );
+        assert_eq!(
+            brinson_fachler(&period(vec![])),
+            Err(AttributionError::EmptyPeriod { period: 0 })
+        );
+        let twice = period(vec![
+            segment("Equity", 0.5, 0.0, 0.5, 0.0),
+            segment("Equity", 0.5, 0.0, 0.5, 0.0),
+        ]);
+        assert!(matches!(
+            brinson_fachler(&twice),
+            Err(AttributionError::DuplicateSegment { .. })
+        ));
+        let short = period(vec![segment("Equity", 0.9, 0.0, 1.0, 0.0)]);
+        assert!(matches!(
+            brinson_fachler(&short),
+            Err(AttributionError::WeightsDoNotSum {
+                side: "portfolio",
+                ..
+            })
+        ));
+        let nan = period(vec![segment("Equity", 1.0, f64::NAN, 1.0, 0.0)]);
+        assert!(matches!(
+            brinson_fachler(&nan),
+            Err(AttributionError::InvalidReturn { .. })
+        ));
+    }
+}
@@ -0,0 +1,477 @@
+//! CBK primary auction simulator for Treasury bills and bonds.
+//!
+//! CBK runs multiple-price auctions: competitive bids below the cut-off rate are
+//! accepted in full at their own rate, bids at the cut-off are scaled pro rata to
+//! fill the amount accepted, and bids above it are rejected. Non-competitive bids
+//! are filled first and priced at the weighted average of the accepted
+//! competitive rates.
+
+use std::fmt;
+
+use chrono::NaiveDate;
+use rust_decimal::prelude::*;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::fixed_income::{bill_price, to_money, value_bond, Bond, DayCount, FixedIncomeError};
+use crate::js::{from_js, js_err, to_js};
+use crate::money::{Money, MoneyError};
+
+/// Rates within this distance of the cut-off count as bids at the cut-off.
+const RATE_TOLERANCE: f64 = 1e-9;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum AuctionError {
+    NoBids,
+    InvalidBid {
+        bid: String,
+        why: &'static str,
+    },
+    InvalidCutOff,
+    /// The amount accepted cannot cover the non-competitive bids and those below the cut-off.
+    AmountAcceptedTooSmall,
+    FixedIncome(FixedIncomeError),
+    Money(MoneyError),
+}
+
+impl fmt::Display for AuctionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AuctionError::NoBids => f.write_str("the auction has no bids"),
+            AuctionError::InvalidBid { bid, why } => write!(f, "invalid bid {}: {}", bid, why),
+            AuctionError::InvalidCutOff => {
+                f.write_str("cut-off rate must be finite and above -100%")
+            }
+            AuctionError::AmountAcceptedTooSmall => f.write_str(
+                "amount accepted is less than the non-competitive bids and the bids below the cut-off",
+            ),
+            AuctionError::FixedIncome(err) => err.fmt(f),
+            AuctionError::Money(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for AuctionError {}
+
+impl From<FixedIncomeError> for AuctionError {
+    fn from(err: FixedIncomeError) -> Self {
+        AuctionError::FixedIncome(err)
+    }
+}
+
+impl From<MoneyError> for AuctionError {
+    fn from(err: MoneyError) -> Self {
+        AuctionError::Money(err)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase",
+    tag = "kind"
+)]
+pub enum AuctionSecurity {
+    Bill {
+        maturity: NaiveDate,
+        #[serde(default)]
+        day_count: DayCount,
+    },
+    /// A new issue or a reopening; reopenings settle with accrued interest.
+    Bond { bond: Bond },
+}
+
+impl AuctionSecurity {
+    /// Cash per 100 of face at `rate`, for value on `settlement`.
+    fn price(&self, rate: f64, settlement: NaiveDate) -> Result<f64, FixedIncomeError> {
+        match self {
+            AuctionSecurity::Bill {
+                maturity,
+                day_count,
+            } => {
+                let days = day_count.days(settlement, *maturity);
+                if days <= 0 {
+                    return Err(FixedIncomeError::SettlementOutsideLife);
+                }
+                Ok(bill_price(rate, days, *day_count))
+            }
+            AuctionSecurity::Bond { bond } => Ok(value_bond(bond, settlement, rate)?.dirty_price),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Bid {
+    pub id: String,
+    /// Face value bid for.
+    pub face: Money,
+    /// Yield bid; `None` makes the bid non-competitive.
+    #[serde(default)]
+    pub rate: Option<f64>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuctionTerms {
+    pub security: AuctionSecurity,
+    pub settlement: NaiveDate,
+    /// Highest rate accepted.
+    pub cut_off_rate: f64,
+    /// Face value the CBK takes up; without it every bid at the cut-off is filled.
+    #[serde(default)]
+    pub amount_accepted: Option<Money>,
+    /// Largest face accepted from one non-competitive bid.
+    #[serde(default)]
+    pub non_competitive_limit: Option<Money>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BidOutcome {
+    Accepted,
+    PartiallyAccepted,
+    Rejected,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Allotment {
+    pub bid: String,
+    pub bid_rate: Option<f64>,
+    pub face_bid: Money,
+    pub outcome: BidOutcome,
+    pub face_allotted: Money,
+    /// Rate the allotment is priced at: the bid rate, or the weighted average for
+    /// non-competitive bids.
+    pub rate: Option<f64>,
+    /// Price per 100 of face.
+    pub price: Option<f64>,
+    /// Cash due on the settlement date.
+    pub settlement: Money,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuctionResult {
+    pub settlement: NaiveDate,
+    pub cut_off_rate: f64,
+    pub total_bid: Money,
+    pub competitive_accepted: Money,
+    pub non_competitive_accepted: Money,
+    pub total_accepted: Money,
+    /// Share of each bid at the cut-off that is filled.
+    pub pro_rata: f64,
+    pub weighted_average_rate: Option<f64>,
+    pub weighted_average_price: Option<f64>,
+    pub total_settlement: Money,
+    /// In bid order.
+    pub allotments: Vec<Allotment>,
+}
+
+fn as_f64(money: &Money) -> f64 {
+    money.amount().to_f64().unwrap_or(0.0)
+}
+
+/// Works out who gets what, at which price, and the cash each successful bidder owes.
+pub fn run_auction(terms: &AuctionTerms, bids: &[Bid]) -> Result<AuctionResult, AuctionError> {
+    let first = bids.first().ok_or(AuctionError::NoBids)?;
+    let zero = Money::zero(first.face.currency());
+    let cut_off = terms.cut_off_rate;
+    if !cut_off.is_finite() || cut_off <= -1.0 {
+        return Err(AuctionError::InvalidCutOff);
+    }
+    for bid in bids {
+        let invalid = |why| AuctionError::InvalidBid {
+            bid: bid.id.clone(),
+            why,
+        };
+        if !bid.face.is_positive() {
+            return Err(invalid("face value must be positive"));
+        }
+        if bid.rate.is_some_and(|r| !r.is_finite() || r <= -1.0) {
+            return Err(invalid("rate must be finite and above -100%"));
+        }
+    }
+    let total_bid = Money::sum(bids.iter().map(|b| &b.face), zero.currency())?;
+    // Adding zero checks the limit is in the bids' currency before it is compared.
+    let limit = terms
+        .non_competitive_limit
+        .as_ref()
+        .map(|limit| zero.checked_add(limit))
+        .transpose()?;
+
+    // Non-competitive bids go first, up to the per-bid limit.
+    let mut allotted = vec![zero; bids.len()];
+    for (i, bid) in bids.iter().enumerate() {
+        if bid.rate.is_none() {
+            allotted[i] = match limit {
+                Some(limit) if bid.face > limit => limit,
+                _ => bid.face,
+            };
+        }
+    }
+    let non_competitive = Money::sum(
+        bids.iter()
+            .zip(&allotted)
+            .filter(|(b, _)| b.rate.is_none())
+            .map(|(_, a)| a),
+        zero.currency(),
+    )?;
+
+    let below: Vec<usize> = (0..bids.len())
+        .filter(|&i| bids[i].rate.is_some_and(|r| r < cut_off - RATE_TOLERANCE))
+        .collect();
+    let at: Vec<usize> = (0..bids.len())
+        .filter(|&i| {
+            bids[i]
+                .rate
+                .is_some_and(|r| (r - cut_off).abs() <= RATE_TOLERANCE)
+        })
+        .collect();
+    for &i in &below {
+        allotted[i] = bids[i].face;
+    }
+    let at_cut_off = Money::sum(at.iter().map(|&i| &bids[i].face), zero.currency())?;
+    let filled_below = Money::sum(below.iter().map(|&i| &bids[i].face), zero.currency())?;
+    let room = match &terms.amount_accepted {
+        Some(target) => target
+            .checked_sub(&non_competitive)?
+            .checked_sub(&filled_below)?,
+        None => at_cut_off,
+    };
+    if room.is_negative() {
+        return Err(AuctionError::AmountAcceptedTooSmall);
+    }
+    let pro_rata = if at_cut_off.is_zero() {
+        0.0
+    } else {
+        (as_f64(&room) / as_f64(&at_cut_off)).min(1.0)
+    };
+    if !at.is_empty() && room.is_positive() {
+        let fill = if room > at_cut_off { at_cut_off } else { room };
+        let weights: Vec<Decimal> = at.iter().map(|&i| bids[i].face.amount()).collect();
+        for (&i, share) in at.iter().zip(fill.allocate(&weights)?) {
+            allotted[i] = share;
+        }
+    }
+
+    // Weighted average of the accepted competitive rates, falling back to the cut-off.
+    let mut face_weight = 0.0;
+    let mut rate_weight = 0.0;
+    for (bid, face) in bids.iter().zip(&allotted) {
+        if let (Some(rate), false) = (bid.rate, face.is_zero()) {
+            face_weight += as_f64(face);
+            rate_weight += as_f64(face) * rate;
+        }
+    }
+    let weighted_average_rate = (face_weight > 0.0).then(|| rate_weight / face_weight);
+    let non_competitive_rate = weighted_average_rate.unwrap_or(cut_off);
+
+    let mut allotments = Vec::with_capacity(bids.len());
+    let mut total_settlement = zero;
+    let mut priced_face = 0.0;
+    let mut priced_cash = 0.0;
+    for (bid, face) in bids.iter().zip(allotted) {
+        let outcome = if face.is_zero() {
+            BidOutcome::Rejected
+        } else if face == bid.face {
+            BidOutcome::Accepted
+        } else {
+            BidOutcome::PartiallyAccepted
+        };
+        let (rate, price, settlement) = if face.is_zero() {
+            (None, None, zero)
+        } else {
+            let rate = bid.rate.unwrap_or(non_competitive_rate);
+            let price = terms.security.price(rate, terms.settlement)?;
+            let cash = to_money(as_f64(&face) * price / 100.0, &face)?;
+            priced_face += as_f64(&face);
+            priced_cash += as_f64(&cash);
+            (Some(rate), Some(price), cash)
+        };
+        total_settlement = total_settlement.checked_add(&settlement)?;
+        allotments.push(Allotment {
+            bid: bid.id.clone(),
+            bid_rate: bid.rate,
+            face_bid: bid.face,
+            outcome,
+            face_allotted: face,
+            rate,
+            price,
+            settlement,
+        });
+    }
+
+    let total_accepted = Money::sum(allotments.iter().map(|a| &a.face_allotted), zero.currency())?;
+    Ok(AuctionResult {
+        settlement: terms.settlement,
+        cut_off_rate: cut_off,
+        total_bid,
+        competitive_accepted: total_accepted.checked_sub(&non_competitive)?,
+        non_competitive_accepted: non_competitive,
+        total_accepted,
+        pro_rata,
+        weighted_average_rate,
+        weighted_average_price: (priced_face > 0.0).then(|| priced_cash / priced_face * 100.0),
+        total_settlement,
+        allotments,
+    })
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AuctionInput {
+    terms: AuctionTerms,
+    bids: Vec<Bid>,
+}
+
+/// JS entry point: `{ terms: { security, settlement, cutOffRate, amountAccepted?,
+/// nonCompetitiveLimit? }, bids }` in, `AuctionResult` out.
+#[wasm_bindgen(js_name = simulateAuction)]
+pub fn run_auction_js(input: JsValue) -> Result<JsValue, JsError> {
+    let input: AuctionInput = from_js(input)?;
+    let result = run_auction(&input.terms, &input.bids).map_err(js_err)?;
+    to_js(&result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::money::Currency;
+
+    fn d(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn kes(s: &str) -> Money {
+        Money::parse(s, Currency::Kes).unwrap()
+    }
+
+    fn close(a: f64, b: f64, tol: f64) {
+        assert!((a - b).abs() < tol, "{} != {}", a, b);
+    }
+
+    fn bid(id: &str, face: &str, rate: Option<f64>) -> Bid {
+        Bid {
+            id: id.to_string(),
+            face: kes(face),
+            rate,
+        }
+    }
+
+    fn bill_terms(amount_accepted: Option<&str>) -> AuctionTerms {
+        AuctionTerms {
+            security: AuctionSecurity::Bill {
+                maturity: d("2024-04-08"),
+                day_count: DayCount::Actual364,
+            },
+            settlement: d("2024-01-08"),
+            cut_off_rate: 0.16,
+            amount_accepted: amount_accepted.map(kes),
+            non_competitive_limit: None,
+        }
+    }
+
+    fn bids() -> Vec<Bid> {
+        vec![
+            bid("NC1", "1000000", None),
+            bid("A", "2000000", Some(0.155)),
+            bid("B", "3000000", Some(0.16)),
+            bid("C", "1000000", Some(0.16)),
+            bid("D", "1000000", Some(0.17)),
+        ]
+    }
+
+    #[test]
+    fn bids_at_the_cut_off_are_scaled_pro_rata() {
+        let result = run_auction(&bill_terms(Some("5000000")), &bids()).unwrap();
+        // 2m of room left for 4m bid at the cut-off.
+        close(result.pro_rata, 0.5, 1e-12);
+        let allotted: Vec<(BidOutcome, Money)> = result
+            .allotments
+            .iter()
+            .map(|a| (a.outcome, a.face_allotted))
+            .collect();
+        assert_eq!(
+            allotted,
+            vec![
+                (BidOutcome::Accepted, kes("1000000")),
+                (BidOutcome::Accepted, kes("2000000")),
+                (BidOutcome::PartiallyAccepted, kes("1500000")),
+                (BidOutcome::PartiallyAccepted, kes("500000")),
+                (BidOutcome::Rejected, kes("0")),
+            ]
+        );
+        assert_eq!(result.total_accepted, kes("5000000"));
+        assert_eq!(result.non_competitive_accepted, kes("1000000"));
+        assert_eq!(result.competitive_accepted, kes("4000000"));
+        // Each competitive bid pays its own rate.
+        close(result.allotments[1].price.unwrap(), 96.269555, 1e-6);
+        close(result.allotments[2].price.unwrap(), 96.153846, 1e-6);
+    }
+
+    #[test]
+    fn non_competitive_bids_pay_the_weighted_average_rate() {
+        let result = run_auction(&bill_terms(Some("5000000")), &bids()).unwrap();
+        // (2m × 15.5% + 2m × 16%) / 4m.
+        close(result.weighted_average_rate.unwrap(), 0.1575, 1e-12);
+        let nc = &result.allotments[0];
+        close(nc.rate.unwrap(), 0.1575, 1e-12);
+        // 100 / (1 + 0.1575 × 91 / 364).
+        close(nc.price.unwrap(), 96.211665, 1e-6);
+        assert_eq!(nc.settlement, kes("962116.66"));
+    }
+
+    #[test]
+    fn amount_accepted_below_the_committed_bids_is_an_error() {
+        // The non-competitive and below-cut-off bids alone come to 3m.
+        assert_eq!(
+            run_auction(&bill_terms(Some("2500000")), &bids()),
+            Err(AuctionError::AmountAcceptedTooSmall)
+        );
+        // Exactly 3m fills them and nothing at the cut-off.
+        let result = run_auction(&bill_terms(Some("3000000")), &bids()).unwrap();
+        assert_eq!(result.pro_rata, 0.0);
+        assert_eq!(result.total_accepted, kes("3000000"));
+    }
+
+    #[test]
+    fn bond_reopening_settles_with_accrued_interest() {
+        // Ten half-years of 6% coupons, reopened 50 days after issue.
+        let bond = Bond {
+            coupon_rate: 0.12,
+            issue_date: d("2024-01-08"),
+            maturity: d("2029-01-01"),
+            frequency: 2,
+            day_count: DayCount::Actual364,
+            amortisation: Vec::new(),
+        };
+        let settlement = d("2024-02-27");
+        let priced = value_bond(&bond, settlement, 0.13).unwrap();
+        assert!(priced.accrued_interest > 0.0);
+        let terms = AuctionTerms {
+            security: AuctionSecurity::Bond { bond },
+            settlement,
+            cut_off_rate: 0.135,
+            amount_accepted: None,
+            non_competitive_limit: None,
+        };
+        let result = run_auction(&terms, &[bid("A", "1000000", Some(0.13))]).unwrap();
+        let a = &result.allotments[0];
+        // Bidders pay the dirty price: the clean price plus accrued interest.
+        close(a.price.unwrap(), priced.dirty_price, 1e-12);
+        close(as_f64(&a.settlement), priced.dirty_price * 10_000.0, 0.005);
+        assert_eq!(result.total_settlement, a.settlement);
+    }
+
+    #[test]
+    fn non_competitive_limit_must_match_the_bid_currency() {
+        let mut terms = bill_terms(None);
+        terms.non_competitive_limit = Some(Money::parse("50000", Currency::Usd).unwrap());
+        assert!(matches!(
+            run_auction(&terms, &bids()),
+            Err(AuctionError::Money(MoneyError::CurrencyMismatch { .. }))
+        ));
+    }
+}
@@ -0,0 +1,571 @@
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::rc::Rc;
+use std::str::FromStr;
+
+use chrono::{Datelike, Days, NaiveDate, Weekday};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::js::{js_err, to_js};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Market {
+    Nse,
+    Nyse,
+    Nasdaq,
+    Lse,
+}
+
+impl Market {
+    /// Standard equity settlement lag in business days.
+    pub fn settlement_lag(self) -> u32 {
+        match self {
+            Market::Nse => 3,
+            Market::Nyse | Market::Nasdaq => 1,
+            Market::Lse => 2,
+        }
+    }
+}
+
+impl FromStr for Market {
+    type Err = CalendarError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "nse" => Ok(Market::Nse),
+            "nyse" => Ok(Market::Nyse),
+            "nasdaq" => Ok(Market::Nasdaq),
+            "lse" => Ok(Market::Lse),
+            _ => Err(CalendarError::UnknownMarket(s.to_string())),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum CalendarError {
+    UnknownMarket(String),
+    InvalidDate(String),
+    OutOfRange,
+}
+
+impl fmt::Display for CalendarError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CalendarError::UnknownMarket(m) => write!(f, "unknown market: {}", m),
+            CalendarError::InvalidDate(d) => write!(f, "invalid date: {}", d),
+            CalendarError::OutOfRange => f.write_str("date arithmetic out of range"),
+        }
+    }
+}
+
+impl std::error::Error for CalendarError {}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Holiday {
+    pub date: NaiveDate,
+    pub name: String,
+}
+
+fn ymd(year: i32, month: u32, day: u32) -> Option<NaiveDate> {
+    NaiveDate::from_ymd_opt(year, month, day)
+}
+
+fn is_weekend(date: NaiveDate) -> bool {
+    matches!(date.weekday(), Weekday::Sat | Weekday::Sun)
+}
+
+/// Easter Sunday by the anonymous Gregorian algorithm; `None` outside chrono's range.
+pub fn easter_sunday(year: i32) -> Option<NaiveDate> {
+    let a = year % 19;
+    let b = year / 100;
+    let c = year % 100;
+    let d = b / 4;
+    let e = b % 4;
+    let f = (b + 8) / 25;
+    let g = (b - f + 1) / 3;
+    let h = (19 * a + b - d - g + 15) % 30;
+    let i = c / 4;
+    let k = c % 4;
+    let l = (32 + 2 * e + 2 * i - h - k) % 7;
+    let m = (a + 11 * h + 22 * l) / 451;
+    let month = (h + l - 7 * m + 114) / 31;
+    let day = (h + l - 7 * m + 114) % 31 + 1;
+    ymd(year, month as u32, day as u32)
+}
+
+/// The `n`th `weekday` of a month (1-based), or the last one when `n` is zero.
+fn nth_weekday(year: i32, month: u32, weekday: Weekday, n: u32) -> Option<NaiveDate> {
+    if n == 0 {
+        let next_month = if month == 12 {
+            ymd(year + 1, 1, 1)?
+        } else {
+            ymd(year, month + 1, 1)?
+        };
+        let mut d = next_month.pred_opt()?;
+        while d.weekday() != weekday {
+            d = d.pred_opt()?;
+        }
+        Some(d)
+    } else {
+        NaiveDate::from_weekday_of_month_opt(year, month, weekday, n as u8)
+    }
+}
+
+/// Moves each weekend holiday to the next weekday not already taken, in date order.
+///
+/// `saturday_rolls` controls whether Saturday holidays move at all; Kenya only
+/// substitutes Sundays, the UK substitutes both.
+fn substitute(fixed: Vec<(NaiveDate, &str)>, saturday_rolls: bool) -> Option<Vec<Holiday>> {
+    let mut taken: BTreeMap<NaiveDate, String> = BTreeMap::new();
+    let mut rolled = Vec::new();
+    for (date, name) in fixed {
+        let rolls = match date.weekday() {
+            Weekday::Sun => true,
+            Weekday::Sat => saturday_rolls,
+            _ => false,
+        };
+        if rolls {
+            rolled.push((date, name));
+        } else if !is_weekend(date) {
+            taken.insert(date, name.to_string());
+        }
+    }
+    for (date, name) in rolled {
+        let mut d = date;
+        while is_weekend(d) || taken.contains_key(&d) {
+            d = d.succ_opt()?;
+        }
+        taken.insert(d, format!("{} (observed)", name));
+    }
+    Some(
+        taken
+            .into_iter()
+            .map(|(date, name)| Holiday { date, name })
+            .collect(),
+    )
+}
+
+fn kenya_holidays(year: i32) -> Option<Vec<Holiday>> {
+    let easter = easter_sunday(year)?;
+    let mut fixed = vec![
+        (ymd(year, 1, 1)?, "New Year's Day"),
+        (easter.checked_sub_days(Days::new(2))?, "Good Friday"),
+        (easter.checked_add_days(Days::new(1))?, "Easter Monday"),
+        (ymd(year, 5, 1)?, "Labour Day"),
+        (ymd(year, 6, 1)?, "Madaraka Day"),
+        (ymd(year, 10, 20)?, "Mashujaa Day"),
+        (ymd(year, 12, 12)?, "Jamhuri Day"),
+        (ymd(year, 12, 25)?, "Christmas Day"),
+        (ymd(year, 12, 26)?, "Boxing Day"),
+    ];
+    if year >= 2024 {
+        fixed.push((ymd(year, 10, 10)?, "Mazingira Day"));
+    }
+    substitute(fixed, false)
+}
+
+fn us_observed(date: NaiveDate) -> Option<NaiveDate> {
+    match date.weekday() {
+        Weekday::Sat => date.pred_opt(),
+        Weekday::Sun => date.succ_opt(),
+        _ => Some(date),
+    }
+}
+
+fn nyse_holidays(year: i32) -> Option<Vec<Holiday>> {
+    let mut days = Vec::new();
+    // A Saturday New Year's Day is not observed on the preceding Friday.
+    let new_year = ymd(year, 1, 1)?;
+    if new_year.weekday() != Weekday::Sat {
+        days.push((us_observed(new_year)?, "New Year's Day"));
+    }
+    days.push((
+        nth_weekday(year, 1, Weekday::Mon, 3)?,
+        "Martin Luther King Jr. Day",
+    ));
+    days.push((
+        nth_weekday(year, 2, Weekday::Mon, 3)?,
+        "Washington's Birthday",
+    ));
+    days.push((
+        easter_sunday(year)?.checked_sub_days(Days::new(2))?,
+        "Good Friday",
+    ));
+    days.push((nth_weekday(year, 5, Weekday::Mon, 0)?, "Memorial Day"));
+    if year >= 2022 {
+        days.push((us_observed(ymd(year, 6, 19)?)?, "Juneteenth"));
+    }
+    days.push((us_observed(ymd(year, 7, 4)?)?, "Independence Day"));
+    days.push((nth_weekday(year, 9, Weekday::Mon, 1)?, "Labor Day"));
+    days.push((nth_weekday(year, 11, Weekday::Thu, 4)?, "Thanksgiving Day"));
+    days.push((us_observed(ymd(year, 12, 25)?)?, "Christmas Day"));
+    days.sort();
+    Some(
+        days.into_iter()
+            .map(|(date, name)| Holiday {
+                date,
+                name: name.to_string(),
+            })
+            .collect(),
+    )
+}
+
+fn lse_holidays(year: i32) -> Option<Vec<Holiday>> {
+    let easter = easter_sunday(year)?;
+    let mut days = substitute(
+        vec![
+            (ymd(year, 1, 1)?, "New Year's Day"),
+            (ymd(year, 12, 25)?, "Christmas Day"),
+            (ymd(year, 12, 26)?, "Boxing Day"),
+        ],
+        true,
+    )?;
+    for (date, name) in [
+        (easter.checked_sub_days(Days::new(2))?, "Good Friday"),
+        (easter.checked_add_days(Days::new(1))?, "Easter Monday"),
+        (
+            nth_weekday(year, 5, Weekday::Mon, 1)?,
+            "Early May Bank Holiday",
+        ),
+        (
+            nth_weekday(year, 5, Weekday::Mon, 0)?,
+            "Spring Bank Holiday",
+        ),
+        (
+            nth_weekday(year, 8, Weekday::Mon, 0)?,
+            "Summer Bank Holiday",
+        ),
+    ] {
+        days.push(Holiday {
+            date,
+            name: name.to_string(),
+        });
+    }
+    days.sort_by_key(|h| h.date);
+    Some(days)
+}
+
+fn rule_holidays(market: Market, year: i32) -> Result<Vec<Holiday>, CalendarError> {
+    match market {
+        Market::Nse => kenya_holidays(year),
+        Market::Nyse | Market::Nasdaq => nyse_holidays(year),
+        Market::Lse => lse_holidays(year),
+    }
+    .ok_or(CalendarError::OutOfRange)
+}
+
+/// Business-day calendar for one market, with room for ad-hoc closures
+/// (gazetted Eid holidays, days of mourning, exchange outages).
+#[wasm_bindgen]
+#[derive(Clone, Debug)]
+pub struct MarketCalendar {
+    market: Market,
+    closures: BTreeMap<NaiveDate, String>,
+    /// Rule-based holiday dates per year, built on first use.
+    cache: RefCell<BTreeMap<i32, Rc<BTreeSet<NaiveDate>>>>,
+}
+
+impl MarketCalendar {
+    pub fn new(market: Market) -> MarketCalendar {
+        MarketCalendar {
+            market,
+            closures: BTreeMap::new(),
+            cache: RefCell::new(BTreeMap::new()),
+        }
+    }
+
+    pub fn market(&self) -> Market {
+        self.market
+    }
+
+    pub fn add_closure(&mut self, date: NaiveDate, name: &str) {
+        self.closures.insert(date, name.to_string());
+    }
+
+    /// Rule-based holidays plus any ad-hoc closures falling in `year`, in date order.
+    pub fn holidays(&self, year: i32) -> Result<Vec<Holiday>, CalendarError> {
+        let mut days = rule_holidays(self.market, year)?;
+        let first = ymd(year, 1, 1).ok_or(CalendarError::OutOfRange)?;
+        let last = ymd(year, 12, 31).ok_or(CalendarError::OutOfRange)?;
+        for (date, name) in self.closures.range(first..=last) {
+            if !days.iter().any(|h| h.date == *date) {
+                days.push(Holiday {
+                    date: *date,
+                    name: name.clone(),
+                });
+            }
+        }
+        days.sort_by_key(|h| h.date);
+        Ok(days)
+    }
+
+    fn rule_dates(&self, year: i32) -> Result<Rc<BTreeSet<NaiveDate>>, CalendarError> {
+        if let Some(dates) = self.cache.borrow().get(&year) {
+            return Ok(Rc::clone(dates));
+        }
+        let dates: Rc<BTreeSet<NaiveDate>> = rule_holidays(self.market, year)?
+            .into_iter()
+            .map(|h| h.date)
+            .collect::<BTreeSet<_>>()
+            .into();
+        self.cache.borrow_mut().insert(year, Rc::clone(&dates));
+        Ok(dates)
+    }
+
+    pub fn is_holiday(&self, date: NaiveDate) -> Result<bool, CalendarError> {
+        Ok(self.closures.contains_key(&date) || self.rule_dates(date.year())?.contains(&date))
+    }
+
+    pub fn is_business_day(&self, date: NaiveDate) -> Result<bool, CalendarError> {
+        Ok(!is_weekend(date) && !self.is_holiday(date)?)
+    }
+
+    /// `date` itself if it is a business day, otherwise the next one.
+    pub fn following(&self, date: NaiveDate) -> Result<NaiveDate, CalendarError> {
+        let mut d = date;
+        while !self.is_business_day(d)? {
+            d = d.succ_opt().ok_or(CalendarError::OutOfRange)?;
+        }
+        Ok(d)
+    }
+
+    /// Moves `n` business days forward (or backward when negative) from `date`.
+    pub fn add_business_days(&self, date: NaiveDate, n: i32) -> Result<NaiveDate, CalendarError> {
+        let mut d = date;
+        let mut remaining = n.unsigned_abs();
+        while remaining > 0 {
+            d = if n > 0 { d.succ_opt() } else { d.pred_opt() }.ok_or(CalendarError::OutOfRange)?;
+            if self.is_business_day(d)? {
+                remaining -= 1;
+            }
+        }
+        Ok(d)
+    }
+
+    /// Business days in `(from, to]`; negative when `to` is before `from`.
+    pub fn business_days_between(
+        &self,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<i64, CalendarError> {
+        let (start, end, sign) = if from <= to {
+            (from, to, 1)
+        } else {
+            (to, from, -1)
+        };
+        let mut count = 0;
+        for d in start.iter_days().skip(1).take_while(|d| *d <= end) {
+            if self.is_business_day(d)? {
+                count += 1;
+            }
+        }
+        Ok(sign * count)
+    }
+
+    /// T+N settlement date for a trade on `trade_date` using the market's standard lag.
+    ///
+    /// Trades booked on a non-business day are treated as done on the next one.
+    pub fn settlement_date(&self, trade_date: NaiveDate) -> Result<NaiveDate, CalendarError> {
+        self.settlement_date_with_lag(trade_date, self.market.settlement_lag())
+    }
+
+    pub fn settlement_date_with_lag(
+        &self,
+        trade_date: NaiveDate,
+        lag: u32,
+    ) -> Result<NaiveDate, CalendarError> {
+        let trade_date = self.following(trade_date)?;
+        self.add_business_days(trade_date, lag as i32)
+    }
+}
+
+fn parse_date(raw: &str) -> Result<NaiveDate, CalendarError> {
+    NaiveDate::from_str(raw.trim()).map_err(|_| CalendarError::InvalidDate(raw.to_string()))
+}
+
+#[wasm_bindgen]
+impl MarketCalendar {
+    /// `market` is one of `"nse"`, `"nyse"`, `"nasdaq"` or `"lse"`.
+    #[wasm_bindgen(constructor)]
+    pub fn js_new(market: &str) -> Result<MarketCalendar, JsError> {
+        let market = Market::from_str(market).map_err(js_err)?;
+        Ok(MarketCalendar::new(market))
+    }
+
+    #[wasm_bindgen(js_name = addClosure)]
+    pub fn js_add_closure(&mut self, date: &str, name: &str) -> Result<(), JsError> {
+        let date = parse_date(date).map_err(js_err)?;
+        self.add_closure(date, name);
+        Ok(())
+    }
+
+    #[wasm_bindgen(js_name = holidays)]
+    pub fn js_holidays(&self, year: i32) -> Result<JsValue, JsError> {
+        to_js(&self.holidays(year).map_err(js_err)?)
+    }
+
+    #[wasm_bindgen(js_name = isBusinessDay)]
+    pub fn js_is_business_day(&self, date: &str) -> Result<bool, JsError> {
+        let date = parse_date(date).map_err(js_err)?;
+        self.is_business_day(date).map_err(js_err)
+    }
+
+    #[wasm_bindgen(js_name = addBusinessDays)]
+    pub fn js_add_business_days(&self, date: &str, n: i32) -> Result<String, JsError> {
+        let date = parse_date(date).map_err(js_err)?;
+        self.add_business_days(date, n)
+            .map(|d| d.to_string())
+            .map_err(js_err)
+    }
+
+    #[wasm_bindgen(js_name = businessDaysBetween)]
+    pub fn js_business_days_between(&self, from: &str, to: &str) -> Result<i64, JsError> {
+        let from = parse_date(from).map_err(js_err)?;
+        let to = parse_date(to).map_err(js_err)?;
+        self.business_days_between(from, to).map_err(js_err)
+    }
+
+    /// Settlement date as `YYYY-MM-DD`; `lag` overrides the market's standard T+N.
+    #[wasm_bindgen(js_name = settlementDate)]
+    pub fn js_settlement_date(
+        &self,
+        trade_date: &str,
+        lag: Option<u32>,
+    ) -> Result<String, JsError> {
+        let trade_date = parse_date(trade_date).map_err(js_err)?;
+        let lag = lag.unwrap_or(self.market.settlement_lag());
+        self.settlement_date_with_lag(trade_date, lag)
+            .map(|d| d.to_string())
+            .map_err(js_err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn d(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn dates(cal: &MarketCalendar, year: i32) -> Vec<NaiveDate> {
+        cal.holidays(year)
+            .unwrap()
+            .into_iter()
+            .map(|h| h.date)
+            .collect()
+    }
+
+    #[test]
+    fn easter_dates() {
+        for (year, easter) in [
+            (1818, "1818-03-22"),
+            (2000, "2000-04-23"),
+            (2019, "2019-04-21"),
+            (2024, "2024-03-31"),
+            (2025, "2025-04-20"),
+            (2038, "2038-04-25"),
+        ] {
+            assert_eq!(easter_sunday(year), Some(d(easter)), "{}", year);
+        }
+    }
+
+    #[test]
+    fn kenya_moves_sunday_holidays_to_monday() {
+        let nse = MarketCalendar::new(Market::Nse);
+        let days = dates(&nse, 2025);
+        // Madaraka Day 2025 fell on a Sunday.
+        assert!(days.contains(&d("2025-06-02")));
+        assert!(!days.contains(&d("2025-06-01")));
+        let observed = nse.holidays(2025).unwrap();
+        let madaraka = observed.iter().find(|h| h.date == d("2025-06-02")).unwrap();
+        assert_eq!(madaraka.name, "Madaraka Day (observed)");
+
+        // Christmas 2022 was a Sunday and Boxing Day already held the Monday.
+        let days = dates(&nse, 2022);
+        assert!(days.contains(&d("2022-12-26")));
+        assert!(days.contains(&d("2022-12-27")));
+        // A Saturday Christmas is not substituted in Kenya; the Sunday Boxing Day is.
+        let days = dates(&nse, 2021);
+        assert!(!days.contains(&d("2021-12-24")) && !days.contains(&d("2021-12-28")));
+        assert!(days.contains(&d("2021-12-27")));
+        assert!(days.contains(&d("2021-12-13")));
+        // Mazingira Day only from 2024.
+        assert!(!dates(&nse, 2023).contains(&d("2023-10-10")));
+        assert!(dates(&nse, 2024).contains(&d("2024-10-10")));
+    }
+
+    #[test]
+    fn us_and_uk_observance_rules() {
+        let nyse = MarketCalendar::new(Market::Nyse);
+        // New Year's Day 2022 fell on a Saturday and was not observed.
+        assert!(nyse.is_business_day(d("2021-12-31")).unwrap());
+        assert!(!nyse.is_business_day(d("2024-11-28")).unwrap());
+        assert!(!nyse.is_business_day(d("2023-06-19")).unwrap());
+
+        let lse = MarketCalendar::new(Market::Lse);
+        let days = dates(&lse, 2021);
+        // Christmas on Saturday and Boxing Day on Sunday roll to Monday and Tuesday.
+        assert!(days.contains(&d("2021-12-27")) && days.contains(&d("2021-12-28")));
+        // Spring bank holiday is the last Monday of May.
+        assert!(dates(&lse, 2024).contains(&d("2024-05-27")));
+    }
+
+    #[test]
+    fn nse_settles_t_plus_three_across_holidays() {
+        let nse = MarketCalendar::new(Market::Nse);
+        // Over Christmas and Boxing Day.
+        assert_eq!(
+            nse.settlement_date(d("2024-12-20")).unwrap(),
+            d("2024-12-27")
+        );
+        // Over Good Friday and Easter Monday.
+        assert_eq!(
+            nse.settlement_date(d("2024-03-27")).unwrap(),
+            d("2024-04-03")
+        );
+        // A Saturday trade counts from Monday.
+        assert_eq!(
+            nse.settlement_date(d("2024-06-08")).unwrap(),
+            d("2024-06-13")
+        );
+        assert_eq!(
+            nse.add_business_days(d("2024-04-03"), -3).unwrap(),
+            d("2024-03-27")
+        );
+        assert_eq!(
+            nse.business_days_between(d("2024-03-27"), d("2024-04-03"))
+                .unwrap(),
+            3
+        );
+        assert_eq!(
+            nse.business_days_between(d("2024-04-03"), d("2024-03-27"))
+                .unwrap(),
+            -3
+        );
+    }
+
+    #[test]
+    fn closures_apply_after_the_year_is_cached() {
+        let mut nse = MarketCalendar::new(Market::Nse);
+        assert!(nse.is_business_day(d("2024-05-10")).unwrap());
+        nse.add_closure(d("2024-05-10"), "Day of mourning");
+        assert!(!nse.is_business_day(d("2024-05-10")).unwrap());
+        assert!(nse
+            .holidays(2024)
+            .unwrap()
+            .iter()
+            .any(|h| h.name == "Day of mourning"));
+    }
+
+    #[test]
+    fn years_outside_the_date_range_are_errors_not_panics() {
+        assert_eq!(easter_sunday(300_000), None);
+        let nyse = MarketCalendar::new(Market::Nyse);
+        assert_eq!(nyse.holidays(300_000), Err(CalendarError::OutOfRange));
+        assert_eq!(nyse.holidays(i32::MIN), Err(CalendarError::OutOfRange));
+    }
+}
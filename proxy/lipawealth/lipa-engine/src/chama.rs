@@ -0,0 +1,1391 @@
+//! Chama (investment group) accounting on top of the double-entry ledger.
+//!
+//! Members owe a savings contribution and, optionally, a merry-go-round
+//! contribution every cycle. Payments clear the oldest dues first, then fines,
+//! and anything left over is saved. Dues still open at the end of the grace period
+//! attract a fine, and the cycle's merry-go-round pot is paid to that cycle's
+//! member in the rotation; late pot money is passed on as it arrives. Savings buy
+//! shared investments, and on each distribution the group's fines, investment
+//! income and expenses since the last one are split across members pro rata to
+//! their capital weighted by the days it was in the pool.
+//!
+//! The books are the group's own, so every account sits in the house pool.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+
+use chrono::{Days, Months, NaiveDate};
+use rust_decimal::prelude::*;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::js::{from_js, js_err, to_js};
+use crate::ledger::{
+    Account, AccountKind, JournalEntry, Ledger, LedgerError, Pool, Posting, Side, TrialBalance,
+};
+use crate::money::{Currency, Money, MoneyError};
+
+const BANK: &str = "BANK";
+const POT: &str = "POT";
+const FINES: &str = "FINES";
+const INCOME: &str = "INCOME";
+const EXPENSES: &str = "EXPENSES";
+
+fn equity_account(member: &str) -> String {
+    format!("EQ:{}", member)
+}
+
+fn investment_account(investment: &str) -> String {
+    format!("INV:{}", investment)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ChamaError {
+    InvalidPlan(&'static str),
+    DuplicateMember(String),
+    UnknownMember {
+        event: String,
+        member: String,
+    },
+    UnknownInvestment {
+        event: String,
+        investment: String,
+    },
+    InvalidEvent {
+        event: String,
+        why: &'static str,
+    },
+    /// The group's bank balance does not cover the outgoing.
+    InsufficientCash(String),
+    Ledger(LedgerError),
+    Money(MoneyError),
+}
+
+impl fmt::Display for ChamaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChamaError::InvalidPlan(why) => write!(f, "invalid contribution plan: {}", why),
+            ChamaError::DuplicateMember(id) => write!(f, "member {} is listed twice", id),
+            ChamaError::UnknownMember { event, member } => {
+                write!(f, "event {} names unknown member {}", event, member)
+            }
+            ChamaError::UnknownInvestment { event, investment } => {
+                write!(f, "event {} names unknown investment {}", event, investment)
+            }
+            ChamaError::InvalidEvent { event, why } => {
+                write!(f, "invalid event {}: {}", event, why)
+            }
+            ChamaError::InsufficientCash(event) => {
+                write!(f, "event {} needs more cash than the group holds", event)
+            }
+            ChamaError::Ledger(err) => err.fmt(f),
+            ChamaError::Money(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for ChamaError {}
+
+impl From<LedgerError> for ChamaError {
+    fn from(err: LedgerError) -> Self {
+        ChamaError::Ledger(err)
+    }
+}
+
+impl From<MoneyError> for ChamaError {
+    fn from(err: MoneyError) -> Self {
+        ChamaError::Money(err)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Member {
+    pub id: String,
+    pub name: String,
+    /// First due date the member owes contributions for is on or after this.
+    pub joined: NaiveDate,
+    /// No contributions fall due from this date.
+    #[serde(default)]
+    pub left: Option<NaiveDate>,
+}
+
+impl Member {
+    fn owes_on(&self, date: NaiveDate) -> bool {
+        self.joined <= date && self.left.is_none_or(|left| date < left)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Frequency {
+    Weekly,
+    Fortnightly,
+    Monthly,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContributionPlan {
+    pub first_due: NaiveDate,
+    pub frequency: Frequency,
+    /// Saved into the shared pool by each member every cycle.
+    pub savings: Money,
+    /// Paid into the rotating pot by each member every cycle.
+    #[serde(default)]
+    pub merry_go_round: Option<Money>,
+    /// Days after the due date before a payment is late.
+    #[serde(default)]
+    pub grace_days: u32,
+    /// Charged once for each cycle a member has not paid in full by the deadline.
+    #[serde(default)]
+    pub late_fine: Option<Money>,
+}
+
+impl ContributionPlan {
+    fn due_date(&self, cycle: u32) -> Option<NaiveDate> {
+        match self.frequency {
+            Frequency::Weekly => self.first_due.checked_add_days(Days::new(7 * cycle as u64)),
+            Frequency::Fortnightly => self
+                .first_due
+                .checked_add_days(Days::new(14 * cycle as u64)),
+            Frequency::Monthly => self.first_due.checked_add_months(Months::new(cycle)),
+        }
+    }
+
+    fn deadline(&self, due: NaiveDate) -> Option<NaiveDate> {
+        due.checked_add_days(Days::new(self.grace_days as u64))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Chama {
+    pub name: String,
+    pub currency: Currency,
+    pub members: Vec<Member>,
+    pub plan: ContributionPlan,
+    /// Order members receive the merry-go-round pot in; defaults to `members` order.
+    #[serde(default)]
+    pub rotation: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase",
+    tag = "kind"
+)]
+pub enum ChamaEvent {
+    /// Money received from a member.
+    Payment {
+        id: String,
+        date: NaiveDate,
+        member: String,
+        amount: Money,
+    },
+    /// Pool cash put into a shared investment.
+    Invest {
+        id: String,
+        date: NaiveDate,
+        investment: String,
+        amount: Money,
+    },
+    /// Marks an investment to market; the change is investment income.
+    Revalue {
+        id: String,
+        date: NaiveDate,
+        investment: String,
+        value: Money,
+    },
+    /// Interest, dividends or rent received in cash.
+    Income {
+        id: String,
+        date: NaiveDate,
+        investment: String,
+        amount: Money,
+    },
+    /// Sells the whole of an investment for `proceeds`.
+    Divest {
+        id: String,
+        date: NaiveDate,
+        investment: String,
+        proceeds: Money,
+    },
+    Expense {
+        id: String,
+        date: NaiveDate,
+        description: String,
+        amount: Money,
+    },
+    /// Splits profit since the last distribution; kept as capital unless paid out.
+    Distribute {
+        id: String,
+        date: NaiveDate,
+        #[serde(default)]
+        pay_out: bool,
+    },
+}
+
+impl ChamaEvent {
+    pub fn id(&self) -> &str {
+        match self {
+            ChamaEvent::Payment { id, .. }
+            | ChamaEvent::Invest { id, .. }
+            | ChamaEvent::Revalue { id, .. }
+            | ChamaEvent::Income { id, .. }
+            | ChamaEvent::Divest { id, .. }
+            | ChamaEvent::Expense { id, .. }
+            | ChamaEvent::Distribute { id, .. } => id,
+        }
+    }
+
+    pub fn date(&self) -> NaiveDate {
+        match self {
+            ChamaEvent::Payment { date, .. }
+            | ChamaEvent::Invest { date, .. }
+            | ChamaEvent::Revalue { date, .. }
+            | ChamaEvent::Income { date, .. }
+            | ChamaEvent::Divest { date, .. }
+            | ChamaEvent::Expense { date, .. }
+            | ChamaEvent::Distribute { date, .. } => *date,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RotationPayout {
+    pub cycle: u32,
+    pub date: NaiveDate,
+    pub member: String,
+    pub amount: Money,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfitShare {
+    pub member: String,
+    /// Capital multiplied by days in the pool since the last distribution.
+    pub weight: Decimal,
+    pub share: Money,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfitDistribution {
+    pub event: String,
+    pub date: NaiveDate,
+    /// Negative when expenses and losses outweighed income.
+    pub profit: Money,
+    pub paid_out: bool,
+    pub shares: Vec<ProfitShare>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemberStatement {
+    pub member: String,
+    pub name: String,
+    /// Scheduled and voluntary savings paid in.
+    pub savings: Money,
+    pub merry_go_round_paid: Money,
+    pub merry_go_round_received: Money,
+    pub fines_charged: Money,
+    pub fines_paid: Money,
+    pub fines_outstanding: Money,
+    /// Contributions due but unpaid.
+    pub arrears: Money,
+    pub profit_share: Money,
+    pub profit_paid_out: Money,
+    /// The member's capital in the pool.
+    pub equity: Money,
+    pub pool_share: f64,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InvestmentValue {
+    pub investment: String,
+    pub value: Money,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChamaReport {
+    pub as_of: NaiveDate,
+    pub statements: Vec<MemberStatement>,
+    pub payouts: Vec<RotationPayout>,
+    pub distributions: Vec<ProfitDistribution>,
+    pub bank: Money,
+    pub investments: Vec<InvestmentValue>,
+    /// Merry-go-round money collected but not yet paid to the recipient.
+    pub pot: Money,
+    /// Fines, income and expenses since the last distribution.
+    pub undistributed_profit: Money,
+    pub trial_balance: TrialBalance,
+}
+
+/// One member's share of one cycle still to be paid.
+struct Due {
+    cycle: u32,
+    merry_go_round: Money,
+    savings: Money,
+}
+
+struct MemberState {
+    dues: VecDeque<Due>,
+    fines_outstanding: Money,
+    fines_charged: Money,
+    fines_paid: Money,
+    savings: Money,
+    merry_go_round_paid: Money,
+    merry_go_round_received: Money,
+    profit_share: Money,
+    profit_paid_out: Money,
+    /// Capital in the pool, as on the member's equity account.
+    capital: Money,
+    /// Capital-days accumulated since the last distribution.
+    weight: Decimal,
+    since: Option<NaiveDate>,
+}
+
+impl MemberState {
+    fn new(currency: Currency) -> MemberState {
+        let zero = Money::zero(currency);
+        MemberState {
+            dues: VecDeque::new(),
+            fines_outstanding: zero,
+            fines_charged: zero,
+            fines_paid: zero,
+            savings: zero,
+            merry_go_round_paid: zero,
+            merry_go_round_received: zero,
+            profit_share: zero,
+            profit_paid_out: zero,
+            capital: zero,
+            weight: Decimal::ZERO,
+            since: None,
+        }
+    }
+
+    /// Brings the capital-days up to `date` before capital changes.
+    fn accrue(&mut self, date: NaiveDate) -> Result<(), MoneyError> {
+        if let Some(since) = self.since {
+            let days = (date - since).num_days().max(0);
+            self.weight = self
+                .capital
+                .amount()
+                .checked_mul(Decimal::from(days))
+                .and_then(|days| self.weight.checked_add(days))
+                .ok_or(MoneyError::Overflow)?;
+        }
+        self.since = Some(date);
+        Ok(())
+    }
+
+    fn add_capital(&mut self, date: NaiveDate, amount: &Money) -> Result<(), MoneyError> {
+        self.accrue(date)?;
+        self.capital = self.capital.checked_add(amount)?;
+        Ok(())
+    }
+}
+
+/// What happens at a point on the timeline; same-day steps run in this order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Step {
+    Due(u32),
+    Event(usize),
+    Deadline(u32),
+}
+
+/// Builds a journal entry, flipping the side of negative amounts and dropping zeros.
+fn journal(
+    id: String,
+    date: NaiveDate,
+    description: String,
+    lines: Vec<(String, Side, Money)>,
+) -> JournalEntry {
+    let postings = lines
+        .into_iter()
+        .filter(|(_, _, amount)| !amount.is_zero())
+        .map(|(account, side, amount)| {
+            let side = match (amount.is_negative(), side) {
+                (false, side) => side,
+                (true, Side::Debit) => Side::Credit,
+                (true, Side::Credit) => Side::Debit,
+            };
+            Posting {
+                account,
+                side,
+                amount: amount.abs(),
+            }
+        })
+        .collect();
+    JournalEntry {
+        id,
+        date,
+        description,
+        postings,
+    }
+}
+
+struct Books<'a> {
+    chama: &'a Chama,
+    ledger: Ledger,
+    members: BTreeMap<&'a str, MemberState>,
+    /// Merry-go-round money collected per cycle, and whether its deadline has passed.
+    pots: BTreeMap<u32, (Money, bool)>,
+    payouts: Vec<RotationPayout>,
+    distributions: Vec<ProfitDistribution>,
+    investments: Vec<String>,
+}
+
+impl<'a> Books<'a> {
+    fn open(&mut self, code: String, name: String, kind: AccountKind) -> Result<(), ChamaError> {
+        self.ledger.open_account(Account {
+            code,
+            name,
+            kind,
+            pool: Pool::House,
+            currency: self.chama.currency,
+            client_id: None,
+        })?;
+        Ok(())
+    }
+
+    fn post(&mut self, entry: JournalEntry) -> Result<(), ChamaError> {
+        if entry.postings.len() >= 2 {
+            self.ledger.post(entry)?;
+        }
+        Ok(())
+    }
+
+    fn spend(&self, event: &str, amount: &Money) -> Result<(), ChamaError> {
+        if self.ledger.balance(BANK)? < *amount {
+            return Err(ChamaError::InsufficientCash(event.to_string()));
+        }
+        Ok(())
+    }
+
+    /// The cycle's place in the rotation, moving on past anyone not a member on the due date.
+    fn recipient(&self, cycle: u32) -> Option<&'a str> {
+        let chama = self.chama;
+        let due = chama.plan.due_date(cycle)?;
+        let rotation: Vec<&'a str> = match chama.rotation.len() {
+            0 => chama.members.iter().map(|m| m.id.as_str()).collect(),
+            _ => chama.rotation.iter().map(String::as_str).collect(),
+        };
+        let start = cycle as usize % rotation.len();
+        rotation
+            .iter()
+            .cycle()
+            .skip(start)
+            .take(rotation.len())
+            .copied()
+            .find(|id| chama.members.iter().any(|m| m.id == *id && m.owes_on(due)))
+    }
+
+    fn pay_pot(
+        &mut self,
+        id: String,
+        cycle: u32,
+        date: NaiveDate,
+        amount: Money,
+    ) -> Result<(), ChamaError> {
+        if !amount.is_positive() {
+            return Ok(());
+        }
+        // Pot money only comes from members who owed the cycle, so one of them is in the rotation.
+        let member = self.recipient(cycle).expect("a member owed the cycle");
+        self.post(journal(
+            id,
+            date,
+            format!("Merry-go-round payout to {}", member),
+            vec![
+                (POT.to_string(), Side::Debit, amount),
+                (BANK.to_string(), Side::Credit, amount),
+            ],
+        ))?;
+        let state = self.members.get_mut(member).expect("rotation is validated");
+        state.merry_go_round_received = state.merry_go_round_received.checked_add(&amount)?;
+        self.payouts.push(RotationPayout {
+            cycle,
+            date,
+            member: member.to_string(),
+            amount,
+        });
+        Ok(())
+    }
+
+    fn due(&mut self, cycle: u32, date: NaiveDate) {
+        let plan = &self.chama.plan;
+        let zero = Money::zero(self.chama.currency);
+        let merry_go_round = plan.merry_go_round.unwrap_or(zero);
+        // A cycle that asks for nothing is settled already, so there is nothing to fine.
+        let owed = merry_go_round.is_positive() || plan.savings.is_positive();
+        for member in &self.chama.members {
+            if owed && member.owes_on(date) {
+                let state = self
+                    .members
+                    .get_mut(member.id.as_str())
+                    .expect("member state");
+                state.dues.push_back(Due {
+                    cycle,
+                    merry_go_round,
+                    savings: plan.savings,
+                });
+            }
+        }
+        self.pots.insert(cycle, (zero, false));
+    }
+
+    fn deadline(&mut self, cycle: u32, date: NaiveDate) -> Result<(), ChamaError> {
+        if let Some(fine) = &self.chama.plan.late_fine {
+            for state in self.members.values_mut() {
+                if state.dues.iter().any(|d| d.cycle == cycle) {
+                    state.fines_outstanding = state.fines_outstanding.checked_add(fine)?;
+                    state.fines_charged = state.fines_charged.checked_add(fine)?;
+                }
+            }
+        }
+        let pot = self
+            .pots
+            .get_mut(&cycle)
+            .expect("cycle opened before its deadline");
+        pot.1 = true;
+        let collected = pot.0;
+        self.pay_pot(format!("payout-{}", cycle), cycle, date, collected)
+    }
+
+    fn payment(
+        &mut self,
+        id: &str,
+        date: NaiveDate,
+        member: &str,
+        amount: Money,
+    ) -> Result<(), ChamaError> {
+        let zero = Money::zero(self.chama.currency);
+        let state = self.members.get_mut(member).expect("member checked");
+        let mut left = amount;
+        let mut to_pot = Vec::new();
+        let mut to_savings = zero;
+        while let Some(due) = state.dues.front_mut() {
+            let mgr = if left < due.merry_go_round {
+                left
+            } else {
+                due.merry_go_round
+            };
+            due.merry_go_round = due.merry_go_round.checked_sub(&mgr)?;
+            left = left.checked_sub(&mgr)?;
+            if mgr.is_positive() {
+                to_pot.push((due.cycle, mgr));
+            }
+            let saved = if left < due.savings {
+                left
+            } else {
+                due.savings
+            };
+            due.savings = due.savings.checked_sub(&saved)?;
+            left = left.checked_sub(&saved)?;
+            to_savings = to_savings.checked_add(&saved)?;
+            if !due.merry_go_round.is_zero() || !due.savings.is_zero() {
+                break;
+            }
+            state.dues.pop_front();
+        }
+        let fines = if left < state.fines_outstanding {
+            left
+        } else {
+            state.fines_outstanding
+        };
+        state.fines_outstanding = state.fines_outstanding.checked_sub(&fines)?;
+        state.fines_paid = state.fines_paid.checked_add(&fines)?;
+        left = left.checked_sub(&fines)?;
+        to_savings = to_savings.checked_add(&left)?;
+
+        let pot_total = Money::sum(to_pot.iter().map(|(_, m)| m), self.chama.currency)?;
+        state.merry_go_round_paid = state.merry_go_round_paid.checked_add(&pot_total)?;
+        state.savings = state.savings.checked_add(&to_savings)?;
+        state.add_capital(date, &to_savings)?;
+        self.post(journal(
+            id.to_string(),
+            date,
+            format!("Payment from {}", member),
+            vec![
+                (BANK.to_string(), Side::Debit, amount),
+                (POT.to_string(), Side::Credit, pot_total),
+                (FINES.to_string(), Side::Credit, fines),
+                (equity_account(member), Side::Credit, to_savings),
+            ],
+        ))?;
+
+        // Pot money for a cycle whose payout has gone is passed straight on.
+        for (cycle, mgr) in to_pot {
+            let pot = self.pots.get_mut(&cycle).expect("cycle opened");
+            if pot.1 {
+                self.pay_pot(format!("{}-payout-{}", id, cycle), cycle, date, mgr)?;
+            } else {
+                pot.0 = pot.0.checked_add(&mgr)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn undistributed(&self) -> Result<Money, ChamaError> {
+        Ok(self
+            .ledger
+            .balance(FINES)?
+            .checked_add(&self.ledger.balance(INCOME)?)?
+            .checked_sub(&self.ledger.balance(EXPENSES)?)?)
+    }
+
+    fn distribute(&mut self, id: &str, date: NaiveDate, pay_out: bool) -> Result<(), ChamaError> {
+        let profit = self.undistributed()?;
+        let mut ids = Vec::with_capacity(self.members.len());
+        let mut weights = Vec::with_capacity(self.members.len());
+        for (member, state) in self.members.iter_mut() {
+            state.accrue(date)?;
+            ids.push(*member);
+            weights.push(state.weight.max(Decimal::ZERO));
+        }
+        let total = weights
+            .iter()
+            .try_fold(Decimal::ZERO, |acc, w| acc.checked_add(*w))
+            .ok_or(MoneyError::Overflow)?;
+        // With no capital to weight by, profit waits for the next distribution.
+        if total.is_zero() && !profit.is_zero() {
+            return Ok(());
+        }
+        let split = if profit.is_zero() {
+            vec![profit; ids.len()]
+        } else {
+            profit.abs().allocate(&weights)?
+        };
+        let split: Vec<Money> = if profit.is_negative() {
+            split.iter().map(|m| m.negate()).collect()
+        } else {
+            split
+        };
+
+        let mut lines = vec![
+            (FINES.to_string(), Side::Debit, self.ledger.balance(FINES)?),
+            (
+                INCOME.to_string(),
+                Side::Debit,
+                self.ledger.balance(INCOME)?,
+            ),
+            (
+                EXPENSES.to_string(),
+                Side::Credit,
+                self.ledger.balance(EXPENSES)?,
+            ),
+        ];
+        let mut shares = Vec::with_capacity(ids.len());
+        for ((member, weight), share) in ids.iter().zip(&weights).zip(&split) {
+            lines.push((equity_account(member), Side::Credit, *share));
+            shares.push(ProfitShare {
+                member: member.to_string(),
+                weight: *weight,
+                share: *share,
+            });
+        }
+        self.post(journal(
+            id.to_string(),
+            date,
+            "Profit split by capital-days".to_string(),
+            lines,
+        ))?;
+
+        let paid_out = pay_out && profit.is_positive();
+        if paid_out {
+            self.spend(id, &profit)?;
+            let mut lines = vec![(BANK.to_string(), Side::Credit, profit)];
+            lines.extend(
+                shares
+                    .iter()
+                    .map(|s| (equity_account(&s.member), Side::Debit, s.share)),
+            );
+            self.post(journal(
+                format!("{}-pay-out", id),
+                date,
+                "Profit paid out".to_string(),
+                lines,
+            ))?;
+        }
+        for share in &shares {
+            let state = self.members.get_mut(share.member.as_str()).expect("member");
+            state.profit_share = state.profit_share.checked_add(&share.share)?;
+            state.weight = Decimal::ZERO;
+            if paid_out {
+                state.profit_paid_out = state.profit_paid_out.checked_add(&share.share)?;
+            } else {
+                state.capital = state.capital.checked_add(&share.share)?;
+            }
+        }
+        self.distributions.push(ProfitDistribution {
+            event: id.to_string(),
+            date,
+            profit,
+            paid_out,
+            shares,
+        });
+        Ok(())
+    }
+
+    fn event(&mut self, event: &ChamaEvent) -> Result<(), ChamaError> {
+        let id = event.id();
+        let date = event.date();
+        let invalid = |why| ChamaError::InvalidEvent {
+            event: id.to_string(),
+            why,
+        };
+        let amount = match event {
+            ChamaEvent::Payment { amount, .. }
+            | ChamaEvent::Invest { amount, .. }
+            | ChamaEvent::Income { amount, .. }
+            | ChamaEvent::Expense { amount, .. } => Some(amount),
+            ChamaEvent::Revalue { value, .. } => Some(value),
+            ChamaEvent::Divest { proceeds, .. } => Some(proceeds),
+            ChamaEvent::Distribute { .. } => None,
+        };
+        if let Some(amount) = amount {
+            if amount.currency() != self.chama.currency {
+                return Err(invalid("amount is not in the group's currency"));
+            }
+            if amount.is_negative() {
+                return Err(invalid("amount must not be negative"));
+            }
+        }
+        let investment = match event {
+            ChamaEvent::Invest { investment, .. }
+            | ChamaEvent::Revalue { investment, .. }
+            | ChamaEvent::Income { investment, .. }
+            | ChamaEvent::Divest { investment, .. } => Some(investment),
+            _ => None,
+        };
+        if let Some(investment) = investment {
+            let known = self.investments.contains(investment);
+            if !known && !matches!(event, ChamaEvent::Invest { .. }) {
+                return Err(ChamaError::UnknownInvestment {
+                    event: id.to_string(),
+                    investment: investment.clone(),
+                });
+            }
+            if !known {
+                self.open(
+                    investment_account(investment),
+                    investment.clone(),
+                    AccountKind::Asset,
+                )?;
+                self.investments.push(investment.clone());
+            }
+        }
+
+        match event {
+            ChamaEvent::Payment { member, amount, .. } => {
+                if !self.members.contains_key(member.as_str()) {
+                    return Err(ChamaError::UnknownMember {
+                        event: id.to_string(),
+                        member: member.clone(),
+                    });
+                }
+                if !amount.is_positive() {
+                    return Err(invalid("payment must be positive"));
+                }
+                self.payment(id, date, member, *amount)
+            }
+            ChamaEvent::Invest {
+                investment, amount, ..
+            } => {
+                self.spend(id, amount)?;
+                self.post(journal(
+                    id.to_string(),
+                    date,
+                    format!("Invest in {}", investment),
+                    vec![
+                        (investment_account(investment), Side::Debit, *amount),
+                        (BANK.to_string(), Side::Credit, *amount),
+                    ],
+                ))
+            }
+            ChamaEvent::Revalue {
+                investment, value, ..
+            } => {
+                let code = investment_account(investment);
+                let change = value.checked_sub(&self.ledger.balance(&code)?)?;
+                self.post(journal(
+                    id.to_string(),
+                    date,
+                    format!("Revalue {}", investment),
+                    vec![
+                        (code, Side::Debit, change),
+                        (INCOME.to_string(), Side::Credit, change),
+                    ],
+                ))
+            }
+            ChamaEvent::Income {
+                investment, amount, ..
+            } => self.post(journal(
+                id.to_string(),
+                date,
+                format!("Income from {}", investment),
+                vec![
+                    (BANK.to_string(), Side::Debit, *amount),
+                    (INCOME.to_string(), Side::Credit, *amount),
+                ],
+            )),
+            ChamaEvent::Divest {
+                investment,
+                proceeds,
+                ..
+            } => {
+                let code = investment_account(investment);
+                let carrying = self.ledger.balance(&code)?;
+                self.post(journal(
+                    id.to_string(),
+                    date,
+                    format!("Sell {}", investment),
+                    vec![
+                        (BANK.to_string(), Side::Debit, *proceeds),
+                        (code, Side::Credit, carrying),
+                        (
+                            INCOME.to_string(),
+                            Side::Credit,
+                            proceeds.checked_sub(&carrying)?,
+                        ),
+                    ],
+                ))
+            }
+            ChamaEvent::Expense {
+                description,
+                amount,
+                ..
+            } => {
+                self.spend(id, amount)?;
+                self.post(journal(
+                    id.to_string(),
+                    date,
+                    description.clone(),
+                    vec![
+                        (EXPENSES.to_string(), Side::Debit, *amount),
+                        (BANK.to_string(), Side::Credit, *amount),
+                    ],
+                ))
+            }
+            ChamaEvent::Distribute { pay_out, .. } => self.distribute(id, date, *pay_out),
+        }
+    }
+}
+
+fn validate(chama: &Chama) -> Result<(), ChamaError> {
+    let plan = &chama.plan;
+    if chama.members.is_empty() {
+        return Err(ChamaError::InvalidPlan("a chama needs members"));
+    }
+    let amounts = std::iter::once(&plan.savings)
+        .chain(&plan.merry_go_round)
+        .chain(&plan.late_fine);
+    for amount in amounts {
+        if amount.currency() != chama.currency || amount.is_negative() {
+            return Err(ChamaError::InvalidPlan(
+                "amounts must be non-negative and in the group's currency",
+            ));
+        }
+    }
+    for (i, member) in chama.members.iter().enumerate() {
+        if chama.members[..i].iter().any(|m| m.id == member.id) {
+            return Err(ChamaError::DuplicateMember(member.id.clone()));
+        }
+    }
+    if let Some(unknown) = chama
+        .rotation
+        .iter()
+        .find(|r| !chama.members.iter().any(|m| &m.id == *r))
+    {
+        return Err(ChamaError::UnknownMember {
+            event: "rotation".to_string(),
+            member: unknown.clone(),
+        });
+    }
+    Ok(())
+}
+
+/// Runs the group's contributions and `events` up to and including `as_of`,
+/// returning the books and each member's statement.
+pub fn run_chama(
+    chama: &Chama,
+    events: &[ChamaEvent],
+    as_of: NaiveDate,
+) -> Result<(Ledger, ChamaReport), ChamaError> {
+    validate(chama)?;
+    let currency = chama.currency;
+    let mut books = Books {
+        chama,
+        ledger: Ledger::new(),
+        members: chama
+            .members
+            .iter()
+            .map(|m| (m.id.as_str(), MemberState::new(currency)))
+            .collect(),
+        pots: BTreeMap::new(),
+        payouts: Vec::new(),
+        distributions: Vec::new(),
+        investments: Vec::new(),
+    };
+    books.open(
+        BANK.to_string(),
+        "Group bank".to_string(),
+        AccountKind::Asset,
+    )?;
+    books.open(
+        POT.to_string(),
+        "Merry-go-round pot".to_string(),
+        AccountKind::Liability,
+    )?;
+    books.open(FINES.to_string(), "Fines".to_string(), AccountKind::Income)?;
+    books.open(
+        INCOME.to_string(),
+        "Investment income".to_string(),
+        AccountKind::Income,
+    )?;
+    books.open(
+        EXPENSES.to_string(),
+        "Expenses".to_string(),
+        AccountKind::Expense,
+    )?;
+    for member in &chama.members {
+        books.open(
+            equity_account(&member.id),
+            member.name.clone(),
+            AccountKind::Equity,
+        )?;
+    }
+
+    let mut timeline: Vec<(NaiveDate, Step)> = Vec::new();
+    for (i, event) in events.iter().enumerate() {
+        if event.date() > as_of {
+            return Err(ChamaError::InvalidEvent {
+                event: event.id().to_string(),
+                why: "dated after the statement date",
+            });
+        }
+        timeline.push((event.date(), Step::Event(i)));
+    }
+    let mut cycle = 0;
+    while let Some(due) = chama.plan.due_date(cycle).filter(|d| *d <= as_of) {
+        timeline.push((due, Step::Due(cycle)));
+        if let Some(deadline) = chama.plan.deadline(due).filter(|d| *d <= as_of) {
+            timeline.push((deadline, Step::Deadline(cycle)));
+        }
+        cycle += 1;
+    }
+    timeline.sort();
+
+    for (date, step) in timeline {
+        match step {
+            Step::Due(cycle) => books.due(cycle, date),
+            Step::Event(i) => books.event(&events[i])?,
+            Step::Deadline(cycle) => books.deadline(cycle, date)?,
+        }
+    }
+
+    let total_equity = Money::sum(books.members.values().map(|s| &s.capital), currency)?;
+    let mut statements = Vec::with_capacity(chama.members.len());
+    for member in &chama.members {
+        let state = &books.members[member.id.as_str()];
+        let arrears = Money::sum(
+            state
+                .dues
+                .iter()
+                .flat_map(|d| [&d.merry_go_round, &d.savings]),
+            currency,
+        )?;
+        let equity = books.ledger.balance(&equity_account(&member.id))?;
+        statements.push(MemberStatement {
+            member: member.id.clone(),
+            name: member.name.clone(),
+            savings: state.savings,
+            merry_go_round_paid: state.merry_go_round_paid,
+            merry_go_round_received: state.merry_go_round_received,
+            fines_charged: state.fines_charged,
+            fines_paid: state.fines_paid,
+            fines_outstanding: state.fines_outstanding,
+            arrears,
+            profit_share: state.profit_share,
+            profit_paid_out: state.profit_paid_out,
+            equity,
+            pool_share: if total_equity.is_zero() {
+                0.0
+            } else {
+                (equity.amount() / total_equity.amount())
+                    .to_f64()
+                    .unwrap_or(0.0)
+            },
+        });
+    }
+    let investments = books
+        .investments
+        .iter()
+        .map(|i| {
+            Ok(InvestmentValue {
+                investment: i.clone(),
+                value: books.ledger.balance(&investment_account(i))?,
+            })
+        })
+        .collect::<Result<Vec<_>, ChamaError>>()?;
+    let undistributed_profit = books.undistributed()?;
+    let report = ChamaReport {
+        as_of,
+        statements,
+        payouts: books.payouts,
+        distributions: books.distributions,
+        bank: books.ledger.balance(BANK)?,
+        investments,
+        pot: books.ledger.balance(POT)?,
+        undistributed_profit,
+        trial_balance: books.ledger.trial_balance()?,
+    };
+    Ok((books.ledger, report))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ChamaInput {
+    chama: Chama,
+    #[serde(default)]
+    events: Vec<ChamaEvent>,
+    as_of: NaiveDate,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ChamaOutput {
+    #[serde(flatten)]
+    report: ChamaReport,
+    /// Loadable with `Ledger.fromJSON`.
+    ledger: String,
+}
+
+/// JS entry point: `{ chama, events?, asOf }` in, `ChamaReport` plus `ledger` JSON out.
+#[wasm_bindgen(js_name = runChama)]
+pub fn run_chama_js(input: JsValue) -> Result<JsValue, JsError> {
+    let input: ChamaInput = from_js(input)?;
+    let (ledger, report) = run_chama(&input.chama, &input.events, input.as_of).map_err(js_err)?;
+    to_js(&ChamaOutput {
+        ledger: ledger.to_json().map_err(js_err)?,
+        report,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn d(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn kes(s: &str) -> Money {
+        Money::parse(s, Currency::Kes).unwrap()
+    }
+
+    fn member(id: &str) -> Member {
+        Member {
+            id: id.to_string(),
+            name: id.to_string(),
+            joined: d("2024-01-01"),
+            left: None,
+        }
+    }
+
+    /// Two members owing 1,000 savings and 500 to the pot on the 5th of each month,
+    /// with three days' grace and a 200 fine.
+    fn chama() -> Chama {
+        Chama {
+            name: "Umoja".to_string(),
+            currency: Currency::Kes,
+            members: vec![member("A"), member("B")],
+            plan: ContributionPlan {
+                first_due: d("2024-01-05"),
+                frequency: Frequency::Monthly,
+                savings: kes("1000"),
+                merry_go_round: Some(kes("500")),
+                grace_days: 3,
+                late_fine: Some(kes("200")),
+            },
+            rotation: Vec::new(),
+        }
+    }
+
+    fn pay(id: &str, date: &str, member: &str, amount: &str) -> ChamaEvent {
+        ChamaEvent::Payment {
+            id: id.to_string(),
+            date: d(date),
+            member: member.to_string(),
+            amount: kes(amount),
+        }
+    }
+
+    fn statement<'a>(report: &'a ChamaReport, member: &str) -> &'a MemberStatement {
+        report
+            .statements
+            .iter()
+            .find(|s| s.member == member)
+            .unwrap()
+    }
+
+    #[test]
+    fn payments_clear_dues_then_fines_then_save() {
+        let events = [
+            pay("P1", "2024-01-05", "A", "1500"),
+            pay("P2", "2024-01-05", "B", "1500"),
+            // A misses the 8 February deadline and is fined.
+            pay("P3", "2024-02-10", "A", "2000"),
+            pay("P4", "2024-02-05", "B", "1500"),
+        ];
+        let (_, report) = run_chama(&chama(), &events, d("2024-02-20")).unwrap();
+        let a = statement(&report, "A");
+        assert_eq!(a.fines_charged, kes("200"));
+        assert_eq!(a.fines_paid, kes("200"));
+        assert_eq!(a.fines_outstanding, kes("0"));
+        assert_eq!(a.merry_go_round_paid, kes("1000"));
+        // Two cycles of savings plus the 300 left after the fine.
+        assert_eq!(a.savings, kes("2300"));
+        assert_eq!(a.arrears, kes("0"));
+        assert_eq!(a.equity, kes("2300"));
+        let b = statement(&report, "B");
+        assert_eq!(b.fines_charged, kes("0"));
+        assert_eq!(b.savings, kes("2000"));
+    }
+
+    #[test]
+    fn short_payments_leave_arrears_and_a_fine() {
+        let events = [pay("P1", "2024-01-07", "A", "700")];
+        let (_, report) = run_chama(&chama(), &events, d("2024-01-20")).unwrap();
+        let a = statement(&report, "A");
+        // 500 to the pot, 200 towards savings; 800 still owed.
+        assert_eq!(a.merry_go_round_paid, kes("500"));
+        assert_eq!(a.savings, kes("200"));
+        assert_eq!(a.arrears, kes("800"));
+        assert_eq!(a.fines_outstanding, kes("200"));
+        let b = statement(&report, "B");
+        assert_eq!(b.arrears, kes("1500"));
+        assert_eq!(b.fines_outstanding, kes("200"));
+    }
+
+    #[test]
+    fn pot_is_paid_at_the_deadline_and_late_money_follows() {
+        let events = [
+            pay("P1", "2024-01-05", "A", "1500"),
+            pay("P2", "2024-01-12", "B", "1500"),
+        ];
+        let (_, report) = run_chama(&chama(), &events, d("2024-01-20")).unwrap();
+        let payouts: Vec<(NaiveDate, &str, Money)> = report
+            .payouts
+            .iter()
+            .map(|p| (p.date, p.member.as_str(), p.amount))
+            .collect();
+        // A is first in the rotation: A's own 500 at the deadline, B's when it lands.
+        assert_eq!(
+            payouts,
+            vec![
+                (d("2024-01-08"), "A", kes("500")),
+                (d("2024-01-12"), "A", kes("500")),
+            ]
+        );
+        assert_eq!(statement(&report, "A").merry_go_round_received, kes("1000"));
+        assert_eq!(report.pot, kes("0"));
+        // B's payment cleared the dues but not the fine.
+        assert_eq!(statement(&report, "B").fines_outstanding, kes("200"));
+    }
+
+    #[test]
+    fn nothing_due_means_no_fine() {
+        let mut free = chama();
+        free.plan.savings = kes("0");
+        free.plan.merry_go_round = None;
+        let (_, report) = run_chama(&free, &[], d("2024-03-20")).unwrap();
+        for s in &report.statements {
+            assert_eq!(s.fines_charged, kes("0"));
+            assert_eq!(s.arrears, kes("0"));
+        }
+
+        // A payment that exactly settles a cycle leaves nothing to fine either.
+        let events = [pay("P1", "2024-01-05", "A", "1500")];
+        let (_, report) = run_chama(&chama(), &events, d("2024-01-20")).unwrap();
+        assert_eq!(statement(&report, "A").fines_charged, kes("0"));
+    }
+
+    #[test]
+    fn profit_is_split_by_capital_days() {
+        let mut chama = chama();
+        chama.plan.merry_go_round = None;
+        chama.plan.late_fine = None;
+        let events = [
+            pay("P1", "2024-01-05", "A", "1000"),
+            pay("P2", "2024-01-20", "B", "1000"),
+            ChamaEvent::Invest {
+                id: "I1".to_string(),
+                date: d("2024-01-21"),
+                investment: "MMF".to_string(),
+                amount: kes("1500"),
+            },
+            ChamaEvent::Income {
+                id: "I2".to_string(),
+                date: d("2024-01-31"),
+                investment: "MMF".to_string(),
+                amount: kes("300"),
+            },
+            ChamaEvent::Distribute {
+                id: "D1".to_string(),
+                date: d("2024-02-04"),
+                pay_out: false,
+            },
+        ];
+        let (_, report) = run_chama(&chama, &events, d("2024-02-04")).unwrap();
+        let shares: Vec<(&str, Decimal, Money)> = report.distributions[0]
+            .shares
+            .iter()
+            .map(|s| (s.member.as_str(), s.weight, s.share))
+            .collect();
+        // A's 1,000 sat in the pool for 30 days, B's for 15.
+        assert_eq!(
+            shares,
+            vec![
+                ("A", Decimal::from(30_000), kes("200")),
+                ("B", Decimal::from(15_000), kes("100")),
+            ]
+        );
+        assert_eq!(statement(&report, "A").equity, kes("1200"));
+        assert_eq!(report.undistributed_profit, kes("0"));
+    }
+
+    #[test]
+    fn trial_balance_balances() {
+        let events = [
+            pay("P1", "2024-01-05", "A", "1500"),
+            pay("P2", "2024-01-12", "B", "1800"),
+            ChamaEvent::Invest {
+                id: "I1".to_string(),
+                date: d("2024-01-15"),
+                investment: "MMF".to_string(),
+                amount: kes("1500"),
+            },
+            ChamaEvent::Revalue {
+                id: "R1".to_string(),
+                date: d("2024-01-31"),
+                investment: "MMF".to_string(),
+                value: kes("1520"),
+            },
+            ChamaEvent::Expense {
+                id: "E1".to_string(),
+                date: d("2024-01-31"),
+                description: "Bank charges".to_string(),
+                amount: kes("50"),
+            },
+            ChamaEvent::Distribute {
+                id: "D1".to_string(),
+                date: d("2024-02-01"),
+                pay_out: false,
+            },
+        ];
+        let (ledger, report) = run_chama(&chama(), &events, d("2024-02-01")).unwrap();
+        assert!(report.trial_balance.balanced);
+        assert_eq!(ledger.trial_balance().unwrap(), report.trial_balance);
+        // Fine 200 plus revaluation 20 less charges 50.
+        assert_eq!(report.distributions[0].profit, kes("170"));
+        let equity =
+            Money::sum(report.statements.iter().map(|s| &s.equity), Currency::Kes).unwrap();
+        // Everything the group holds belongs to the members once the pot is paid.
+        assert_eq!(report.pot, kes("0"));
+        assert_eq!(
+            report
+                .bank
+                .checked_add(&report.investments[0].value)
+                .unwrap(),
+            equity
+        );
+    }
+
+    #[test]
+    fn a_zero_profit_distribution_is_recorded_and_restarts_the_weights() {
+        let mut chama = chama();
+        chama.plan.merry_go_round = None;
+        chama.plan.late_fine = None;
+        let events = [
+            pay("P1", "2024-01-05", "A", "1000"),
+            pay("P2", "2024-01-20", "B", "1000"),
+            ChamaEvent::Distribute {
+                id: "D1".to_string(),
+                date: d("2024-02-04"),
+                pay_out: false,
+            },
+            ChamaEvent::Invest {
+                id: "I1".to_string(),
+                date: d("2024-02-05"),
+                investment: "MMF".to_string(),
+                amount: kes("1500"),
+            },
+            ChamaEvent::Income {
+                id: "I2".to_string(),
+                date: d("2024-02-10"),
+                investment: "MMF".to_string(),
+                amount: kes("300"),
+            },
+            ChamaEvent::Distribute {
+                id: "D2".to_string(),
+                date: d("2024-02-14"),
+                pay_out: false,
+            },
+        ];
+        let (_, report) = run_chama(&chama, &events, d("2024-02-14")).unwrap();
+        assert_eq!(report.distributions.len(), 2);
+        let first = &report.distributions[0];
+        assert_eq!(first.profit, kes("0"));
+        assert!(first.shares.iter().all(|s| s.share == kes("0")));
+        // Weights restart at D1, so each member's 1,000 counts for ten days.
+        let shares: Vec<(&str, Decimal, Money)> = report.distributions[1]
+            .shares
+            .iter()
+            .map(|s| (s.member.as_str(), s.weight, s.share))
+            .collect();
+        assert_eq!(
+            shares,
+            vec![
+                ("A", Decimal::from(10_000), kes("150")),
+                ("B", Decimal::from(10_000), kes("150")),
+            ]
+        );
+    }
+
+    #[test]
+    fn the_pot_skips_a_member_who_has_left() {
+        let mut chama = chama();
+        chama.members.push(member("C"));
+        chama.members[1].left = Some(d("2024-02-01"));
+        let events = [
+            pay("P1", "2024-01-05", "A", "1500"),
+            pay("P2", "2024-01-05", "C", "1500"),
+            pay("P3", "2024-02-05", "A", "1500"),
+            pay("P4", "2024-02-05", "C", "1500"),
+        ];
+        let (_, report) = run_chama(&chama, &events, d("2024-02-10")).unwrap();
+        let payouts: Vec<(u32, &str, Money)> = report
+            .payouts
+            .iter()
+            .map(|p| (p.cycle, p.member.as_str(), p.amount))
+            .collect();
+        // B's turn in February passes to C.
+        assert_eq!(payouts, vec![(0, "A", kes("1000")), (1, "C", kes("1000"))]);
+        assert_eq!(statement(&report, "B").merry_go_round_received, kes("0"));
+    }
+
+    #[test]
+    fn capital_days_overflow_is_an_error() {
+        let mut state = MemberState::new(Currency::Kes);
+        state.capital = Money::new(Decimal::MAX.trunc_with_scale(2), Currency::Kes);
+        state.since = Some(d("2024-01-01"));
+        assert_eq!(state.accrue(d("2024-01-03")), Err(MoneyError::Overflow));
+    }
+}
@@ -0,0 +1,821 @@
+//! Corporate actions: cash dividends, splits, bonus issues and rights issues applied
+//! to tax lots, plus the back-adjustment that keeps a price history continuous.
+//!
+//! Entitlements follow the ex-date: trades dated before it count towards the
+//! holding on the record date, trades on or after it settle too late.
+
+use std::fmt;
+
+use chrono::NaiveDate;
+use rust_decimal::prelude::*;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::js::{from_js, js_err, to_js};
+use crate::lots::{self, Disposal, Lot, LotBook, LotError, LotEvent, ReliefMethod};
+use crate::money::{Currency, Money, MoneyError};
+use crate::tax::{
+    apply_withholding, IncomeItem, IncomeType, Residency, TaxError, TaxRule, TaxTable, TaxedIncome,
+};
+
+/// Decimal places on back-adjusted prices.
+const PRICE_DP: u32 = 6;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum CorporateActionError {
+    InvalidAction { action: String, why: &'static str },
+    Lot(LotError),
+    Tax(TaxError),
+    Money(MoneyError),
+}
+
+impl fmt::Display for CorporateActionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CorporateActionError::InvalidAction { action, why } => {
+                write!(f, "invalid corporate action {}: {}", action, why)
+            }
+            CorporateActionError::Lot(err) => err.fmt(f),
+            CorporateActionError::Tax(err) => err.fmt(f),
+            CorporateActionError::Money(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for CorporateActionError {}
+
+impl From<LotError> for CorporateActionError {
+    fn from(err: LotError) -> Self {
+        CorporateActionError::Lot(err)
+    }
+}
+
+impl From<TaxError> for CorporateActionError {
+    fn from(err: TaxError) -> Self {
+        CorporateActionError::Tax(err)
+    }
+}
+
+impl From<MoneyError> for CorporateActionError {
+    fn from(err: MoneyError) -> Self {
+        CorporateActionError::Money(err)
+    }
+}
+
+/// Ratios read as "`new_shares` for every `for_every` held": a 2-for-1 split is
+/// `2` for `1`, a 1-for-10 bonus is `1` for `10`. A split replaces the holding;
+/// bonus and rights shares come on top of it.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase",
+    tag = "kind"
+)]
+pub enum CorporateAction {
+    CashDividend {
+        id: String,
+        instrument: String,
+        ex_date: NaiveDate,
+        record_date: NaiveDate,
+        pay_date: NaiveDate,
+        per_share: Decimal,
+        currency: Currency,
+        /// Foreign withholding (e.g. 30% on US dividends); defaults to the Kenyan rules.
+        #[serde(default)]
+        withholding_rate: Option<Decimal>,
+    },
+    Split {
+        id: String,
+        instrument: String,
+        ex_date: NaiveDate,
+        new_shares: Decimal,
+        for_every: Decimal,
+    },
+    /// Fractional entitlements are disregarded, as on the NSE.
+    Bonus {
+        id: String,
+        instrument: String,
+        ex_date: NaiveDate,
+        new_shares: Decimal,
+        for_every: Decimal,
+    },
+    /// Rights to subscribe at `subscription_price`; `take_up` of them are exercised,
+    /// the rest are sold nil-paid at `renunciation_price` or left to lapse.
+    Rights {
+        id: String,
+        instrument: String,
+        ex_date: NaiveDate,
+        pay_date: NaiveDate,
+        new_shares: Decimal,
+        for_every: Decimal,
+        subscription_price: Decimal,
+        currency: Currency,
+        #[serde(default)]
+        kes_rate: Option<Decimal>,
+        #[serde(default)]
+        take_up: Decimal,
+        #[serde(default)]
+        renunciation_price: Option<Decimal>,
+    },
+}
+
+impl CorporateAction {
+    pub fn id(&self) -> &str {
+        match self {
+            CorporateAction::CashDividend { id, .. }
+            | CorporateAction::Split { id, .. }
+            | CorporateAction::Bonus { id, .. }
+            | CorporateAction::Rights { id, .. } => id,
+        }
+    }
+
+    pub fn instrument(&self) -> &str {
+        match self {
+            CorporateAction::CashDividend { instrument, .. }
+            | CorporateAction::Split { instrument, .. }
+            | CorporateAction::Bonus { instrument, .. }
+            | CorporateAction::Rights { instrument, .. } => instrument,
+        }
+    }
+
+    pub fn ex_date(&self) -> NaiveDate {
+        match self {
+            CorporateAction::CashDividend { ex_date, .. }
+            | CorporateAction::Split { ex_date, .. }
+            | CorporateAction::Bonus { ex_date, .. }
+            | CorporateAction::Rights { ex_date, .. } => *ex_date,
+        }
+    }
+
+    fn invalid(&self, why: &'static str) -> CorporateActionError {
+        CorporateActionError::InvalidAction {
+            action: self.id().to_string(),
+            why,
+        }
+    }
+
+    fn ratio(
+        &self,
+        new_shares: Decimal,
+        for_every: Decimal,
+    ) -> Result<Decimal, CorporateActionError> {
+        if new_shares <= Decimal::ZERO || for_every <= Decimal::ZERO {
+            return Err(self.invalid("ratio terms must be positive"));
+        }
+        new_shares
+            .checked_div(for_every)
+            .ok_or(CorporateActionError::Money(MoneyError::Overflow))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DividendEntitlement {
+    pub action: String,
+    pub instrument: String,
+    pub record_date: NaiveDate,
+    pub pay_date: NaiveDate,
+    pub quantity: Decimal,
+    pub per_share: Decimal,
+    pub payment: TaxedIncome,
+}
+
+/// A split or bonus issue's effect on the holding.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuantityChange {
+    pub action: String,
+    pub instrument: String,
+    pub ex_date: NaiveDate,
+    pub before: Decimal,
+    pub after: Decimal,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RightsOutcome {
+    pub action: String,
+    pub instrument: String,
+    pub entitlement: Decimal,
+    pub taken_up: Decimal,
+    pub subscription_cost: Money,
+    pub renounced: Decimal,
+    /// Cash from selling the renounced rights nil-paid.
+    pub renunciation_proceeds: Option<Money>,
+    pub lapsed: Decimal,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CorporateActionReport {
+    pub lots: Vec<Lot>,
+    pub disposals: Vec<Disposal>,
+    pub quantity_changes: Vec<QuantityChange>,
+    pub dividends: Vec<DividendEntitlement>,
+    pub rights: Vec<RightsOutcome>,
+}
+
+/// Whole new shares for `held`, multiplying before dividing so 1-for-3 on 300 is 100.
+fn whole_entitlement(
+    held: Decimal,
+    new_shares: Decimal,
+    for_every: Decimal,
+) -> Result<Decimal, MoneyError> {
+    let gross = held.checked_mul(new_shares).ok_or(MoneyError::Overflow)?;
+    Ok((gross / for_every).floor())
+}
+
+fn apply_action(
+    book: &mut LotBook,
+    action: &CorporateAction,
+    residency: Residency,
+    tax: &TaxTable,
+    report: &mut CorporateActionReport,
+) -> Result<(), CorporateActionError> {
+    let held = book.quantity(action.instrument());
+    match action {
+        CorporateAction::CashDividend {
+            id,
+            instrument,
+            record_date,
+            pay_date,
+            per_share,
+            currency,
+            withholding_rate,
+            ..
+        } => {
+            if *per_share < Decimal::ZERO {
+                return Err(action.invalid("dividend per share must not be negative"));
+            }
+            if held.is_zero() {
+                return Ok(());
+            }
+            let item = IncomeItem {
+                date: *pay_date,
+                income_type: IncomeType::Dividend,
+                gross: Money::new(
+                    held.checked_mul(*per_share).ok_or(MoneyError::Overflow)?,
+                    *currency,
+                ),
+                reference: Some(id.clone()),
+            };
+            let payment = match withholding_rate {
+                Some(rate) if *rate < Decimal::ZERO || *rate > Decimal::ONE => {
+                    return Err(action.invalid("withholding rate must be between 0 and 1"));
+                }
+                Some(rate) => {
+                    let withheld = item.gross.checked_mul(*rate)?;
+                    TaxedIncome {
+                        date: item.date,
+                        income_type: item.income_type,
+                        net: item.gross.checked_sub(&withheld)?,
+                        reference: item.reference,
+                        gross: item.gross,
+                        rate: *rate,
+                        withheld,
+                    }
+                }
+                None => apply_withholding(tax, residency, &item)?,
+            };
+            report.dividends.push(DividendEntitlement {
+                action: id.clone(),
+                instrument: instrument.clone(),
+                record_date: *record_date,
+                pay_date: *pay_date,
+                quantity: held,
+                per_share: *per_share,
+                payment,
+            });
+        }
+        CorporateAction::Split {
+            id,
+            instrument,
+            ex_date,
+            new_shares,
+            for_every,
+        } => {
+            let ratio = action.ratio(*new_shares, *for_every)?;
+            if held.is_zero() {
+                return Ok(());
+            }
+            book.scale(instrument, ratio)?;
+            report.quantity_changes.push(QuantityChange {
+                action: id.clone(),
+                instrument: instrument.clone(),
+                ex_date: *ex_date,
+                before: held,
+                after: book.quantity(instrument),
+            });
+        }
+        CorporateAction::Bonus {
+            id,
+            instrument,
+            ex_date,
+            new_shares,
+            for_every,
+        } => {
+            action.ratio(*new_shares, *for_every)?;
+            let issued = whole_entitlement(held, *new_shares, *for_every)?;
+            if issued.is_zero() {
+                return Ok(());
+            }
+            book.add_shares(instrument, issued)?;
+            report.quantity_changes.push(QuantityChange {
+                action: id.clone(),
+                instrument: instrument.clone(),
+                ex_date: *ex_date,
+                before: held,
+                after: held + issued,
+            });
+        }
+        CorporateAction::Rights {
+            id,
+            instrument,
+            pay_date,
+            new_shares,
+            for_every,
+            subscription_price,
+            currency,
+            kes_rate,
+            take_up,
+            renunciation_price,
+            ..
+        } => {
+            action.ratio(*new_shares, *for_every)?;
+            if *subscription_price <= Decimal::ZERO {
+                return Err(action.invalid("subscription price must be positive"));
+            }
+            if renunciation_price.is_some_and(|p| p < Decimal::ZERO) {
+                return Err(action.invalid("renunciation price must not be negative"));
+            }
+            let entitlement = whole_entitlement(held, *new_shares, *for_every)?;
+            if *take_up < Decimal::ZERO || *take_up > entitlement {
+                return Err(action.invalid("take-up must be between 0 and the entitlement"));
+            }
+            let cost = Money::new(
+                take_up
+                    .checked_mul(*subscription_price)
+                    .ok_or(MoneyError::Overflow)?,
+                *currency,
+            );
+            if *take_up > Decimal::ZERO {
+                let rate = lots::kes_rate(id, *currency, *kes_rate)?;
+                book.add_lot(Lot {
+                    id: id.clone(),
+                    instrument: instrument.clone(),
+                    acquired: *pay_date,
+                    quantity: *take_up,
+                    cost,
+                    cost_kes: Money::new(
+                        cost.amount()
+                            .checked_mul(rate)
+                            .ok_or(MoneyError::Overflow)?,
+                        Currency::Kes,
+                    ),
+                })?;
+            }
+            let unexercised = entitlement - take_up;
+            let (renounced, lapsed) = match renunciation_price {
+                Some(_) => (unexercised, Decimal::ZERO),
+                None => (Decimal::ZERO, unexercised),
+            };
+            let renunciation_proceeds = renunciation_price
+                .map(|price| {
+                    renounced
+                        .checked_mul(price)
+                        .map(|amount| Money::new(amount, *currency))
+                        .ok_or(MoneyError::Overflow)
+                })
+                .transpose()?;
+            report.rights.push(RightsOutcome {
+                action: id.clone(),
+                instrument: instrument.clone(),
+                entitlement,
+                taken_up: *take_up,
+                subscription_cost: cost,
+                renounced,
+                renunciation_proceeds,
+                lapsed,
+            });
+        }
+    }
+    Ok(())
+}
+
+enum Step<'a> {
+    Action(&'a CorporateAction),
+    Event(&'a LotEvent),
+}
+
+/// Replays lot `events` with `actions` interleaved on their ex-dates (an action
+/// runs before any trade dated on its ex-date) and reports what each one did.
+pub fn apply_corporate_actions(
+    method: ReliefMethod,
+    events: &[LotEvent],
+    actions: &[CorporateAction],
+    residency: Residency,
+    tax: &TaxTable,
+) -> Result<CorporateActionReport, CorporateActionError> {
+    let mut steps: Vec<(NaiveDate, u8, Step)> = actions
+        .iter()
+        .map(|a| (a.ex_date(), 0, Step::Action(a)))
+        .chain(events.iter().map(|e| (e.date(), 1, Step::Event(e))))
+        .collect();
+    steps.sort_by_key(|(date, rank, _)| (*date, *rank));
+
+    let mut book = LotBook::new(method);
+    let mut report = CorporateActionReport {
+        lots: Vec::new(),
+        disposals: Vec::new(),
+        quantity_changes: Vec::new(),
+        dividends: Vec::new(),
+        rights: Vec::new(),
+    };
+    for (_, _, step) in steps {
+        match step {
+            Step::Action(action) => apply_action(&mut book, action, residency, tax, &mut report)?,
+            Step::Event(event) => book.apply(event)?,
+        }
+    }
+    report.lots = book.lots().to_vec();
+    report.disposals = book.disposals().to_vec();
+    Ok(report)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PricePoint {
+    pub date: NaiveDate,
+    pub close: Decimal,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AdjustedPrice {
+    pub date: NaiveDate,
+    pub close: Decimal,
+    /// Product of the factors of every later action; one from the last ex-date on.
+    pub factor: Decimal,
+    pub adjusted: Decimal,
+}
+
+/// Price factor applied to closes before the ex-date, given the last cum close.
+fn adjustment_factor(
+    action: &CorporateAction,
+    cum: Decimal,
+) -> Result<Decimal, CorporateActionError> {
+    match action {
+        CorporateAction::CashDividend { per_share, .. } => {
+            if *per_share < Decimal::ZERO || *per_share >= cum {
+                return Err(action.invalid("dividend must be below the cum-dividend price"));
+            }
+            Ok((cum - per_share) / cum)
+        }
+        CorporateAction::Split {
+            new_shares,
+            for_every,
+            ..
+        } => Ok(Decimal::ONE / action.ratio(*new_shares, *for_every)?),
+        CorporateAction::Bonus {
+            new_shares,
+            for_every,
+            ..
+        } => Ok(Decimal::ONE / (Decimal::ONE + action.ratio(*new_shares, *for_every)?)),
+        CorporateAction::Rights {
+            new_shares,
+            for_every,
+            subscription_price,
+            ..
+        } => {
+            // Theoretical ex-rights price; rights priced at or above the market are worthless.
+            let ratio = action.ratio(*new_shares, *for_every)?;
+            let terp = (cum + ratio * subscription_price) / (Decimal::ONE + ratio);
+            Ok((terp / cum).min(Decimal::ONE))
+        }
+    }
+}
+
+/// Back-adjusts the closes of `instrument` for every action on it, so the series
+/// has no artificial jumps at ex-dates.
+pub fn adjust_prices(
+    instrument: &str,
+    prices: &[PricePoint],
+    actions: &[CorporateAction],
+) -> Result<Vec<AdjustedPrice>, CorporateActionError> {
+    let mut series = prices.to_vec();
+    series.sort_by_key(|p| p.date);
+    if let Some(bad) = series.iter().find(|p| p.close <= Decimal::ZERO) {
+        return Err(CorporateActionError::InvalidAction {
+            action: bad.date.to_string(),
+            why: "closing prices must be positive",
+        });
+    }
+    let mut factors: Vec<(NaiveDate, Decimal)> = Vec::new();
+    for action in actions.iter().filter(|a| a.instrument() == instrument) {
+        let ex_date = action.ex_date();
+        let Some(cum) = series.iter().rev().find(|p| p.date < ex_date) else {
+            continue;
+        };
+        factors.push((ex_date, adjustment_factor(action, cum.close)?));
+    }
+    Ok(series
+        .iter()
+        .map(|p| {
+            let factor: Decimal = factors
+                .iter()
+                .filter(|(ex_date, _)| p.date < *ex_date)
+                .map(|(_, f)| *f)
+                .product();
+            AdjustedPrice {
+                date: p.date,
+                close: p.close,
+                factor,
+                adjusted: (p.close * factor).round_dp(PRICE_DP).normalize(),
+            }
+        })
+        .collect())
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CorporateActionsInput {
+    #[serde(default)]
+    method: ReliefMethod,
+    events: Vec<LotEvent>,
+    actions: Vec<CorporateAction>,
+    residency: Residency,
+    /// Overrides the built-in Kenyan withholding table when present.
+    #[serde(default)]
+    rules: Option<Vec<TaxRule>>,
+}
+
+/// JS entry point: `{ method?, events, actions, residency, rules? }` in,
+/// `CorporateActionReport` out.
+#[wasm_bindgen(js_name = applyCorporateActions)]
+pub fn apply_corporate_actions_js(input: JsValue) -> Result<JsValue, JsError> {
+    let input: CorporateActionsInput = from_js(input)?;
+    let table = match input.rules {
+        Some(rules) => TaxTable::new(rules).map_err(js_err)?,
+        None => TaxTable::kenya(),
+    };
+    let report = apply_corporate_actions(
+        input.method,
+        &input.events,
+        &input.actions,
+        input.residency,
+        &table,
+    )
+    .map_err(js_err)?;
+    to_js(&report)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AdjustPricesInput {
+    instrument: String,
+    prices: Vec<PricePoint>,
+    actions: Vec<CorporateAction>,
+}
+
+/// JS entry point: `{ instrument, prices, actions }` in, adjusted price series out.
+#[wasm_bindgen(js_name = adjustPriceHistory)]
+pub fn adjust_prices_js(input: JsValue) -> Result<JsValue, JsError> {
+    let input: AdjustPricesInput = from_js(input)?;
+    let adjusted =
+        adjust_prices(&input.instrument, &input.prices, &input.actions).map_err(js_err)?;
+    to_js(&adjusted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn d(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn dec(s: &str) -> Decimal {
+        Decimal::from_str(s).unwrap()
+    }
+
+    fn kes(s: &str) -> Money {
+        Money::parse(s, Currency::Kes).unwrap()
+    }
+
+    fn buy(id: &str, date: &str, quantity: &str, price: &str) -> LotEvent {
+        LotEvent::Buy {
+            id: id.to_string(),
+            date: d(date),
+            instrument: "EQTY".to_string(),
+            quantity: dec(quantity),
+            price: dec(price),
+            fees: Decimal::ZERO,
+            currency: Currency::Kes,
+            kes_rate: None,
+        }
+    }
+
+    fn split(new_shares: &str, for_every: &str) -> CorporateAction {
+        CorporateAction::Split {
+            id: "S1".to_string(),
+            instrument: "EQTY".to_string(),
+            ex_date: d("2024-05-02"),
+            new_shares: dec(new_shares),
+            for_every: dec(for_every),
+        }
+    }
+
+    fn bonus(new_shares: &str, for_every: &str) -> CorporateAction {
+        CorporateAction::Bonus {
+            id: "B1".to_string(),
+            instrument: "EQTY".to_string(),
+            ex_date: d("2024-05-02"),
+            new_shares: dec(new_shares),
+            for_every: dec(for_every),
+        }
+    }
+
+    fn dividend(per_share: &str, currency: Currency, withholding: Option<&str>) -> CorporateAction {
+        CorporateAction::CashDividend {
+            id: "D1".to_string(),
+            instrument: "EQTY".to_string(),
+            ex_date: d("2024-05-02"),
+            record_date: d("2024-05-03"),
+            pay_date: d("2024-06-14"),
+            per_share: dec(per_share),
+            currency,
+            withholding_rate: withholding.map(dec),
+        }
+    }
+
+    fn rights(take_up: &str, renunciation_price: Option<&str>) -> CorporateAction {
+        CorporateAction::Rights {
+            id: "R1".to_string(),
+            instrument: "EQTY".to_string(),
+            ex_date: d("2024-05-02"),
+            pay_date: d("2024-05-30"),
+            new_shares: dec("1"),
+            for_every: dec("4"),
+            subscription_price: dec("80"),
+            currency: Currency::Kes,
+            kes_rate: None,
+            take_up: dec(take_up),
+            renunciation_price: renunciation_price.map(dec),
+        }
+    }
+
+    fn run(events: &[LotEvent], actions: &[CorporateAction]) -> CorporateActionReport {
+        apply_corporate_actions(
+            ReliefMethod::Fifo,
+            events,
+            actions,
+            Residency::Resident,
+            &TaxTable::kenya(),
+        )
+        .unwrap()
+    }
+
+    fn held(report: &CorporateActionReport) -> Decimal {
+        report.lots.iter().map(|l| l.quantity).sum()
+    }
+
+    #[test]
+    fn bonus_disregards_fractional_entitlements() {
+        let events = [
+            buy("T1", "2024-01-10", "100", "20"),
+            buy("T2", "2024-02-10", "55", "30"),
+        ];
+        let report = run(&events, &[bonus("1", "10")]);
+        // 155 shares earn 15.5 bonus shares; the half is dropped.
+        assert_eq!(report.quantity_changes[0].before, dec("155"));
+        assert_eq!(report.quantity_changes[0].after, dec("170"));
+        assert_eq!(held(&report), dec("170"));
+        // Bonus shares come free, so the cost basis does not move.
+        let cost = Money::sum(report.lots.iter().map(|l| &l.cost), Currency::Kes).unwrap();
+        assert_eq!(cost, kes("3650"));
+    }
+
+    #[test]
+    fn split_keeps_each_lot_cost() {
+        let events = [buy("T1", "2024-01-10", "100", "20")];
+        let report = run(&events, &[split("2", "1")]);
+        assert_eq!(report.lots[0].quantity, dec("200"));
+        assert_eq!(report.lots[0].cost, kes("2000"));
+        assert_eq!(report.lots[0].acquired, d("2024-01-10"));
+    }
+
+    #[test]
+    fn rights_take_up_opens_a_lot_and_the_rest_is_renounced_or_lapses() {
+        let events = [buy("T1", "2024-01-10", "400", "100")];
+        let report = run(&events, &[rights("60", Some("3.50"))]);
+        let outcome = &report.rights[0];
+        assert_eq!(outcome.entitlement, dec("100"));
+        assert_eq!(outcome.taken_up, dec("60"));
+        assert_eq!(outcome.subscription_cost, kes("4800"));
+        assert_eq!(outcome.renounced, dec("40"));
+        assert_eq!(outcome.renunciation_proceeds, Some(kes("140")));
+        assert_eq!(outcome.lapsed, Decimal::ZERO);
+        let taken = report.lots.iter().find(|l| l.id == "R1").unwrap();
+        assert_eq!(taken.quantity, dec("60"));
+        assert_eq!(taken.cost, kes("4800"));
+        assert_eq!(taken.acquired, d("2024-05-30"));
+
+        let report = run(&events, &[rights("0", None)]);
+        let outcome = &report.rights[0];
+        assert_eq!(outcome.renounced, Decimal::ZERO);
+        assert_eq!(outcome.renunciation_proceeds, None);
+        assert_eq!(outcome.lapsed, dec("100"));
+        assert_eq!(report.lots.len(), 1);
+
+        let err = apply_corporate_actions(
+            ReliefMethod::Fifo,
+            &events,
+            &[rights("101", None)],
+            Residency::Resident,
+            &TaxTable::kenya(),
+        );
+        assert!(matches!(
+            err,
+            Err(CorporateActionError::InvalidAction { .. })
+        ));
+    }
+
+    #[test]
+    fn trades_on_the_ex_date_miss_the_entitlement() {
+        let events = [
+            buy("T1", "2024-04-30", "100", "40"),
+            buy("T2", "2024-05-02", "50", "38"),
+            LotEvent::Sell {
+                id: "T3".to_string(),
+                date: d("2024-05-02"),
+                instrument: "EQTY".to_string(),
+                quantity: dec("30"),
+                price: dec("38"),
+                fees: Decimal::ZERO,
+                currency: Currency::Kes,
+                kes_rate: None,
+                lots: None,
+            },
+        ];
+        let report = run(&events, &[dividend("4", Currency::Kes, None)]);
+        // The buy misses the dividend and the seller keeps it.
+        let paid = &report.dividends[0];
+        assert_eq!(paid.quantity, dec("100"));
+        assert_eq!(paid.payment.gross, kes("400"));
+        // Resident dividends bear 5% withholding.
+        assert_eq!(paid.payment.withheld, kes("20"));
+        assert_eq!(paid.payment.net, kes("380"));
+        assert_eq!(held(&report), dec("120"));
+    }
+
+    #[test]
+    fn foreign_dividends_use_their_own_withholding_rate() {
+        let events = [buy("T1", "2024-01-10", "100", "40")];
+        let report = run(&events, &[dividend("0.50", Currency::Usd, Some("0.30"))]);
+        let paid = &report.dividends[0].payment;
+        assert_eq!(paid.gross, Money::parse("50", Currency::Usd).unwrap());
+        assert_eq!(paid.withheld, Money::parse("15", Currency::Usd).unwrap());
+        assert_eq!(paid.net, Money::parse("35", Currency::Usd).unwrap());
+        assert_eq!(paid.rate, dec("0.30"));
+    }
+
+    #[test]
+    fn price_history_is_adjusted_by_each_action_factor() {
+        let prices = [
+            PricePoint {
+                date: d("2024-04-30"),
+                close: dec("98"),
+            },
+            PricePoint {
+                date: d("2024-05-01"),
+                close: dec("100"),
+            },
+            PricePoint {
+                date: d("2024-05-02"),
+                close: dec("95"),
+            },
+        ];
+        let factor = |action: CorporateAction| {
+            let adjusted = adjust_prices("EQTY", &prices, &[action]).unwrap();
+            assert_eq!(adjusted[2].factor, Decimal::ONE);
+            assert_eq!(adjusted[0].factor, adjusted[1].factor);
+            adjusted[1].factor
+        };
+        // Dividend: (100 - 5) / 100.
+        assert_eq!(factor(dividend("5", Currency::Kes, None)), dec("0.95"));
+        assert_eq!(factor(split("2", "1")), dec("0.5"));
+        // 1-for-4 bonus: 4 shares become 5.
+        assert_eq!(factor(bonus("1", "4")), dec("0.8"));
+        // TERP of 1-for-4 at 80 on a 100 cum price is (100 + 0.25 × 80) / 1.25 = 96.
+        assert_eq!(factor(rights("0", None)), dec("0.96"));
+
+        let adjusted = adjust_prices(
+            "EQTY",
+            &prices,
+            &[dividend("5", Currency::Kes, None), split("2", "1")],
+        )
+        .unwrap();
+        assert_eq!(adjusted[0].adjusted, dec("46.55"));
+        assert_eq!(adjusted[2].adjusted, dec("95"));
+    }
+}
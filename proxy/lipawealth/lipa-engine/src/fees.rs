@@ -0,0 +1,783 @@
+//! Advisory fee billing: AUM fees from a tiered or blended rate card accrued daily,
+//! performance fees over a hurdle and high-water mark, minimums, caps, and the VAT
+//! and excise due on the fee, rolled up into invoice lines per client per period.
+//!
+//! AUM fees accrue each calendar day on that day's balance at annual rate / 365, so
+//! a client active for part of a period pays only for the days they were active, and
+//! the period's fee equals the annual rate on the average balance. Minimums and caps
+//! are annual figures pro-rated the same way. Performance fees crystallise at the
+//! end of each period.
+
+use std::fmt;
+
+use chrono::NaiveDate;
+use rust_decimal::prelude::*;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::js::{from_js, js_err, to_js};
+use crate::money::{Currency, Money, MoneyError};
+
+/// Days an annual rate is spread over.
+const DAYS_IN_YEAR: i64 = 365;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum FeeError {
+    InvalidSchedule(&'static str),
+    InvalidPeriod {
+        start: NaiveDate,
+        end: NaiveDate,
+    },
+    /// Billing periods must not overlap and must be in date order.
+    UnorderedPeriods(NaiveDate),
+    NoBalances(String),
+    Money(MoneyError),
+}
+
+impl fmt::Display for FeeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FeeError::InvalidSchedule(why) => write!(f, "invalid fee schedule: {}", why),
+            FeeError::InvalidPeriod { start, end } => {
+                write!(
+                    f,
+                    "billing period {} to {} ends before it starts",
+                    start, end
+                )
+            }
+            FeeError::UnorderedPeriods(start) => {
+                write!(
+                    f,
+                    "billing period starting {} overlaps the one before",
+                    start
+                )
+            }
+            FeeError::NoBalances(client) => write!(f, "client {} has no balances", client),
+            FeeError::Money(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for FeeError {}
+
+impl From<MoneyError> for FeeError {
+    fn from(err: MoneyError) -> Self {
+        FeeError::Money(err)
+    }
+}
+
+/// A band of the rate card; `up_to` is `None` on the top band.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeeTier {
+    #[serde(default)]
+    pub up_to: Option<Money>,
+    pub annual_rate: Decimal,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TierMethod {
+    /// Each slice of the balance is charged at its own band's rate.
+    #[default]
+    Blended,
+    /// The whole balance is charged at the rate of the band it falls in.
+    Tiered,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PerformanceFee {
+    /// Share of the gain above the threshold taken as a fee.
+    pub rate: Decimal,
+    /// Annual return the account must beat before any fee is due.
+    #[serde(default)]
+    pub hurdle_rate: Decimal,
+}
+
+/// A tax charged on the fee itself, e.g. VAT at 16% or excise duty.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeeTax {
+    pub name: String,
+    pub rate: Decimal,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeeSchedule {
+    pub currency: Currency,
+    pub tiers: Vec<FeeTier>,
+    #[serde(default)]
+    pub method: TierMethod,
+    /// Annual minimum fee, pro-rated by days active.
+    #[serde(default)]
+    pub minimum_fee: Option<Money>,
+    /// Most the client pays in fees a year, as a share of average AUM.
+    #[serde(default)]
+    pub cap_rate: Option<Decimal>,
+    #[serde(default)]
+    pub performance: Option<PerformanceFee>,
+    #[serde(default)]
+    pub taxes: Vec<FeeTax>,
+}
+
+/// End-of-day account value; carried forward until the next point.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BalancePoint {
+    pub date: NaiveDate,
+    pub value: Money,
+}
+
+/// A deposit (positive) or withdrawal (negative), included in that day's balance.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExternalFlow {
+    pub date: NaiveDate,
+    pub amount: Money,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClientAccount {
+    pub client: String,
+    pub balances: Vec<BalancePoint>,
+    #[serde(default)]
+    pub flows: Vec<ExternalFlow>,
+    /// Last day the account is billed for.
+    #[serde(default)]
+    pub closed: Option<NaiveDate>,
+    /// Value the account must exceed, after flows, before a performance fee is due.
+    #[serde(default)]
+    pub high_water_mark: Option<Money>,
+}
+
+/// Both ends inclusive.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BillingPeriod {
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FeeLineKind {
+    Management,
+    /// Top-up to the pro-rated minimum fee.
+    Minimum,
+    Performance,
+    /// Credit bringing fees down to the pro-rated cap.
+    Cap,
+    Tax,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InvoiceLine {
+    pub kind: FeeLineKind,
+    pub description: String,
+    pub amount: Money,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Invoice {
+    pub client: String,
+    pub period: BillingPeriod,
+    /// Days in the period the account was billed for.
+    pub days_billed: i64,
+    pub average_balance: Money,
+    pub lines: Vec<InvoiceLine>,
+    /// Fees before tax.
+    pub fees: Money,
+    pub tax: Money,
+    pub total: Money,
+    /// Carried into the next period.
+    pub high_water_mark: Option<Money>,
+}
+
+fn validate(schedule: &FeeSchedule, periods: &[BillingPeriod]) -> Result<(), FeeError> {
+    let Some((top, bands)) = schedule.tiers.split_last() else {
+        return Err(FeeError::InvalidSchedule("at least one tier is required"));
+    };
+    if top.up_to.is_some() || bands.iter().any(|t| t.up_to.is_none()) {
+        return Err(FeeError::InvalidSchedule(
+            "only the last tier may be open-ended",
+        ));
+    }
+    for pair in bands.windows(2) {
+        if pair[1].up_to <= pair[0].up_to {
+            return Err(FeeError::InvalidSchedule("tier bounds must increase"));
+        }
+    }
+    let currency = schedule.currency;
+    if bands
+        .iter()
+        .filter_map(|t| t.up_to.as_ref())
+        .chain(&schedule.minimum_fee)
+        .any(|m| m.currency() != currency || m.is_negative())
+    {
+        return Err(FeeError::InvalidSchedule(
+            "amounts must be non-negative and in the schedule currency",
+        ));
+    }
+    let rates = schedule
+        .tiers
+        .iter()
+        .map(|t| t.annual_rate)
+        .chain(schedule.cap_rate)
+        .chain(schedule.performance.iter().map(|p| p.rate))
+        .chain(schedule.taxes.iter().map(|t| t.rate));
+    for rate in rates {
+        if rate < Decimal::ZERO {
+            return Err(FeeError::InvalidSchedule("rates must not be negative"));
+        }
+    }
+    for period in periods {
+        if period.end < period.start {
+            return Err(FeeError::InvalidPeriod {
+                start: period.start,
+                end: period.end,
+            });
+        }
+    }
+    for pair in periods.windows(2) {
+        if pair[1].start <= pair[0].end {
+            return Err(FeeError::UnorderedPeriods(pair[1].start));
+        }
+    }
+    Ok(())
+}
+
+/// Annual fee on `balance` under the rate card.
+pub fn annual_fee(schedule: &FeeSchedule, balance: Decimal) -> Decimal {
+    if balance <= Decimal::ZERO {
+        return Decimal::ZERO;
+    }
+    match schedule.method {
+        TierMethod::Tiered => schedule
+            .tiers
+            .iter()
+            .find(|t| t.up_to.as_ref().is_none_or(|b| balance <= b.amount()))
+            .map_or(Decimal::ZERO, |t| balance * t.annual_rate),
+        TierMethod::Blended => {
+            let mut fee = Decimal::ZERO;
+            let mut floor = Decimal::ZERO;
+            for tier in &schedule.tiers {
+                let ceiling = tier
+                    .up_to
+                    .as_ref()
+                    .map_or(balance, |b| b.amount().min(balance));
+                if ceiling > floor {
+                    fee += (ceiling - floor) * tier.annual_rate;
+                    floor = ceiling;
+                }
+                if floor >= balance {
+                    break;
+                }
+            }
+            fee
+        }
+    }
+}
+
+/// Value carried forward to the end of `date`, if the account existed by then.
+fn value_on(balances: &[BalancePoint], date: NaiveDate) -> Option<Decimal> {
+    balances
+        .iter()
+        .take_while(|b| b.date <= date)
+        .last()
+        .map(|b| b.value.amount())
+}
+
+fn pro_rate(annual: Decimal, days: i64) -> Result<Decimal, MoneyError> {
+    annual
+        .checked_mul(Decimal::from(days))
+        .and_then(|share| share.checked_div(Decimal::from(DAYS_IN_YEAR)))
+        .ok_or(MoneyError::Overflow)
+}
+
+fn bill_period(
+    schedule: &FeeSchedule,
+    account: &ClientAccount,
+    balances: &[BalancePoint],
+    period: BillingPeriod,
+    high_water_mark: &mut Option<Money>,
+) -> Result<Invoice, FeeError> {
+    let currency = schedule.currency;
+    let zero = Money::zero(currency);
+    let opened = balances[0].date;
+    let first = period.start.max(opened);
+    let last = account.closed.map_or(period.end, |c| c.min(period.end));
+    let days_billed = (last - first).num_days() + 1;
+
+    let mut lines = Vec::new();
+    let mut average_balance = zero;
+    if days_billed > 0 {
+        let mut accrued = Decimal::ZERO;
+        let mut balance_days = Decimal::ZERO;
+        // One pass over the balances: each point holds until the next one's date.
+        let mut points = balances.iter().peekable();
+        let mut balance = Decimal::ZERO;
+        for date in first.iter_days().take(days_billed as usize) {
+            while let Some(point) = points.next_if(|b| b.date <= date) {
+                balance = point.value.amount();
+            }
+            accrued = accrued
+                .checked_add(annual_fee(schedule, balance) / Decimal::from(DAYS_IN_YEAR))
+                .ok_or(MoneyError::Overflow)?;
+            balance_days = balance_days
+                .checked_add(balance)
+                .ok_or(MoneyError::Overflow)?;
+        }
+        average_balance = Money::new(balance_days / Decimal::from(days_billed), currency);
+        let management = Money::new(accrued, currency);
+        lines.push(InvoiceLine {
+            kind: FeeLineKind::Management,
+            description: format!("AUM fee, {} days", days_billed),
+            amount: management,
+        });
+        if let Some(minimum) = &schedule.minimum_fee {
+            let floor = Money::new(pro_rate(minimum.amount(), days_billed)?, currency);
+            if management < floor {
+                lines.push(InvoiceLine {
+                    kind: FeeLineKind::Minimum,
+                    description: "Minimum fee top-up".to_string(),
+                    amount: floor.checked_sub(&management)?,
+                });
+            }
+        }
+
+        if let Some(performance) = &schedule.performance {
+            // Start from the value brought into the period, or the opening balance.
+            let start_date = match first.pred_opt() {
+                Some(eve) if first > opened => eve,
+                _ => opened,
+            };
+            let start_value = value_on(balances, start_date).unwrap_or_default();
+            let end_value = value_on(balances, last).unwrap_or_default();
+            let net_flows = Money::sum(
+                account
+                    .flows
+                    .iter()
+                    .filter(|f| f.date > start_date && f.date <= last)
+                    .map(|f| &f.amount),
+                currency,
+            )?
+            .amount();
+            let brought_in = start_value
+                .checked_add(net_flows)
+                .ok_or(MoneyError::Overflow)?;
+            let hurdle_return = performance
+                .hurdle_rate
+                .checked_mul(average_balance.amount())
+                .ok_or(MoneyError::Overflow)?;
+            let hurdle = brought_in
+                .checked_add(pro_rate(hurdle_return, days_billed)?)
+                .ok_or(MoneyError::Overflow)?;
+            let mark = high_water_mark
+                .as_ref()
+                .map(|m| {
+                    m.amount()
+                        .checked_add(net_flows)
+                        .ok_or(MoneyError::Overflow)
+                })
+                .transpose()?;
+            let threshold = mark.map_or(hurdle, |m| m.max(hurdle));
+            if end_value > threshold {
+                lines.push(InvoiceLine {
+                    kind: FeeLineKind::Performance,
+                    description: format!(
+                        "Performance fee on gain above {}",
+                        Money::new(threshold, currency)
+                    ),
+                    amount: Money::new(
+                        end_value
+                            .checked_sub(threshold)
+                            .and_then(|gain| gain.checked_mul(performance.rate))
+                            .ok_or(MoneyError::Overflow)?,
+                        currency,
+                    ),
+                });
+            }
+            // With no mark yet, the value brought in sets it, so a loss is made good first.
+            let new_mark = mark.unwrap_or(brought_in).max(end_value);
+            *high_water_mark = Some(Money::new(new_mark, currency));
+        }
+
+        if let Some(cap_rate) = schedule.cap_rate {
+            let cap = Money::new(
+                pro_rate(
+                    cap_rate
+                        .checked_mul(average_balance.amount())
+                        .ok_or(MoneyError::Overflow)?,
+                    days_billed,
+                )?,
+                currency,
+            );
+            let charged = Money::sum(lines.iter().map(|l| &l.amount), currency)?;
+            if charged > cap {
+                lines.push(InvoiceLine {
+                    kind: FeeLineKind::Cap,
+                    description: format!(
+                        "Fees capped at {}% of AUM a year",
+                        (cap_rate * Decimal::ONE_HUNDRED).normalize()
+                    ),
+                    amount: cap.checked_sub(&charged)?,
+                });
+            }
+        }
+    }
+
+    let fees = Money::sum(lines.iter().map(|l| &l.amount), currency)?;
+    // An account closed before the period starts gets no lines at all.
+    if days_billed > 0 {
+        for tax in &schedule.taxes {
+            lines.push(InvoiceLine {
+                kind: FeeLineKind::Tax,
+                description: tax.name.clone(),
+                amount: fees.checked_mul(tax.rate)?,
+            });
+        }
+    }
+    let total = Money::sum(lines.iter().map(|l| &l.amount), currency)?;
+    Ok(Invoice {
+        client: account.client.clone(),
+        period,
+        days_billed: days_billed.max(0),
+        average_balance,
+        lines,
+        fees,
+        tax: total.checked_sub(&fees)?,
+        total,
+        high_water_mark: *high_water_mark,
+    })
+}
+
+/// Bills every client for every period, carrying high-water marks forward.
+/// Invoices come out client by client, each in period order.
+pub fn bill_fees(
+    schedule: &FeeSchedule,
+    clients: &[ClientAccount],
+    periods: &[BillingPeriod],
+) -> Result<Vec<Invoice>, FeeError> {
+    validate(schedule, periods)?;
+    let mut invoices = Vec::with_capacity(clients.len() * periods.len());
+    for account in clients {
+        if account.balances.is_empty() {
+            return Err(FeeError::NoBalances(account.client.clone()));
+        }
+        let mismatch = account
+            .balances
+            .iter()
+            .map(|b| &b.value)
+            .chain(account.flows.iter().map(|f| &f.amount))
+            .chain(&account.high_water_mark)
+            .find(|m| m.currency() != schedule.currency);
+        if let Some(money) = mismatch {
+            return Err(FeeError::Money(MoneyError::CurrencyMismatch {
+                left: schedule.currency,
+                right: money.currency(),
+            }));
+        }
+        let mut balances = account.balances.clone();
+        balances.sort_by_key(|b| b.date);
+        let mut high_water_mark = account.high_water_mark;
+        for &period in periods {
+            invoices.push(bill_period(
+                schedule,
+                account,
+                &balances,
+                period,
+                &mut high_water_mark,
+            )?);
+        }
+    }
+    Ok(invoices)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FeeInput {
+    schedule: FeeSchedule,
+    clients: Vec<ClientAccount>,
+    periods: Vec<BillingPeriod>,
+}
+
+/// JS entry point: `{ schedule, clients, periods }` in, `Invoice[]` out.
+#[wasm_bindgen(js_name = billAdvisoryFees)]
+pub fn bill_fees_js(input: JsValue) -> Result<JsValue, JsError> {
+    let input: FeeInput = from_js(input)?;
+    let invoices = bill_fees(&input.schedule, &input.clients, &input.periods).map_err(js_err)?;
+    to_js(&invoices)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn d(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn dec(s: &str) -> Decimal {
+        Decimal::from_str(s).unwrap()
+    }
+
+    fn kes(s: &str) -> Money {
+        Money::parse(s, Currency::Kes).unwrap()
+    }
+
+    /// 1% on the first million, 0.5% above it.
+    fn schedule(method: TierMethod) -> FeeSchedule {
+        FeeSchedule {
+            currency: Currency::Kes,
+            tiers: vec![
+                FeeTier {
+                    up_to: Some(kes("1000000")),
+                    annual_rate: dec("0.01"),
+                },
+                FeeTier {
+                    up_to: None,
+                    annual_rate: dec("0.005"),
+                },
+            ],
+            method,
+            minimum_fee: None,
+            cap_rate: None,
+            performance: None,
+            taxes: Vec::new(),
+        }
+    }
+
+    fn account(balances: &[(&str, &str)]) -> ClientAccount {
+        ClientAccount {
+            client: "C1".to_string(),
+            balances: balances
+                .iter()
+                .map(|(date, value)| BalancePoint {
+                    date: d(date),
+                    value: kes(value),
+                })
+                .collect(),
+            flows: Vec::new(),
+            closed: None,
+            high_water_mark: None,
+        }
+    }
+
+    fn period(start: &str, end: &str) -> BillingPeriod {
+        BillingPeriod {
+            start: d(start),
+            end: d(end),
+        }
+    }
+
+    fn line(invoice: &Invoice, kind: FeeLineKind) -> Option<Money> {
+        invoice
+            .lines
+            .iter()
+            .find(|l| l.kind == kind)
+            .map(|l| l.amount)
+    }
+
+    #[test]
+    fn blended_charges_each_band_and_tiered_the_whole_balance() {
+        let balance = dec("3000000");
+        // 1% of 1m plus 0.5% of 2m.
+        assert_eq!(
+            annual_fee(&schedule(TierMethod::Blended), balance),
+            dec("20000")
+        );
+        assert_eq!(
+            annual_fee(&schedule(TierMethod::Tiered), balance),
+            dec("15000")
+        );
+        assert_eq!(
+            annual_fee(&schedule(TierMethod::Tiered), dec("1000000")),
+            dec("10000")
+        );
+    }
+
+    #[test]
+    fn part_periods_are_billed_for_the_days_active() {
+        let mut client = account(&[("2024-01-17", "1000000")]);
+        client.closed = Some(d("2024-02-10"));
+        let invoices = bill_fees(
+            &schedule(TierMethod::Blended),
+            &[client],
+            &[
+                period("2024-01-01", "2024-01-31"),
+                period("2024-02-01", "2024-02-29"),
+            ],
+        )
+        .unwrap();
+        // Opened on the 17th: 15 days at 10,000 a year.
+        assert_eq!(invoices[0].days_billed, 15);
+        assert_eq!(
+            line(&invoices[0], FeeLineKind::Management),
+            Some(kes("410.96"))
+        );
+        // Closed on the 10th.
+        assert_eq!(invoices[1].days_billed, 10);
+        assert_eq!(
+            line(&invoices[1], FeeLineKind::Management),
+            Some(kes("273.97"))
+        );
+    }
+
+    #[test]
+    fn minimum_tops_up_and_cap_credits_back() {
+        let mut schedule = schedule(TierMethod::Blended);
+        schedule.minimum_fee = Some(kes("36500"));
+        let client = account(&[("2023-12-31", "100000")]);
+        let billed = |schedule: &FeeSchedule| {
+            bill_fees(
+                schedule,
+                std::slice::from_ref(&client),
+                &[period("2024-04-01", "2024-04-30")],
+            )
+            .unwrap()
+            .remove(0)
+        };
+        let invoice = billed(&schedule);
+        // 1,000 a year on the balance against a 36,500 minimum, both over 30 days.
+        assert_eq!(line(&invoice, FeeLineKind::Management), Some(kes("82.19")));
+        assert_eq!(line(&invoice, FeeLineKind::Minimum), Some(kes("2917.81")));
+        assert_eq!(invoice.fees, kes("3000"));
+
+        schedule.cap_rate = Some(dec("0.02"));
+        schedule.taxes = vec![FeeTax {
+            name: "VAT".to_string(),
+            rate: dec("0.16"),
+        }];
+        let invoice = billed(&schedule);
+        // 2% of 100,000 over 30 days, with VAT on the capped fee.
+        assert_eq!(line(&invoice, FeeLineKind::Cap), Some(kes("-2835.62")));
+        assert_eq!(invoice.fees, kes("164.38"));
+        assert_eq!(invoice.tax, kes("26.30"));
+        assert_eq!(invoice.total, kes("190.68"));
+    }
+
+    #[test]
+    fn performance_fee_is_charged_only_above_the_hurdle() {
+        let mut schedule = schedule(TierMethod::Blended);
+        schedule.tiers = vec![FeeTier {
+            up_to: None,
+            annual_rate: Decimal::ZERO,
+        }];
+        schedule.performance = Some(PerformanceFee {
+            rate: dec("0.2"),
+            hurdle_rate: dec("0.1"),
+        });
+        let year = [period("2024-01-01", "2024-12-30")];
+        let missed = account(&[("2023-12-31", "1000000"), ("2024-12-30", "1100000")]);
+        let invoice = &bill_fees(&schedule, &[missed], &year).unwrap()[0];
+        assert_eq!(line(invoice, FeeLineKind::Performance), None);
+
+        let beat = account(&[("2023-12-31", "1000000"), ("2024-12-30", "1200000")]);
+        let invoice = &bill_fees(&schedule, &[beat], &year).unwrap()[0];
+        // Hurdle: 10% on the 1,000,547.95 average balance over the 1m brought in.
+        assert_eq!(
+            line(invoice, FeeLineKind::Performance),
+            Some(kes("19989.04"))
+        );
+    }
+
+    #[test]
+    fn high_water_mark_carries_across_periods() {
+        let mut schedule = schedule(TierMethod::Blended);
+        schedule.tiers = vec![FeeTier {
+            up_to: None,
+            annual_rate: Decimal::ZERO,
+        }];
+        schedule.performance = Some(PerformanceFee {
+            rate: dec("0.2"),
+            hurdle_rate: Decimal::ZERO,
+        });
+        let quarters = [
+            period("2024-01-01", "2024-03-31"),
+            period("2024-04-01", "2024-06-30"),
+            period("2024-07-01", "2024-09-30"),
+        ];
+        let client = account(&[
+            ("2023-12-31", "1000000"),
+            ("2024-03-31", "1100000"),
+            ("2024-06-30", "1050000"),
+            ("2024-09-30", "1150000"),
+        ]);
+        let invoices = bill_fees(&schedule, &[client], &quarters).unwrap();
+        let fees: Vec<Option<Money>> = invoices
+            .iter()
+            .map(|i| line(i, FeeLineKind::Performance))
+            .collect();
+        // Q3 only earns on the climb back above Q1's 1.1m.
+        assert_eq!(fees, vec![Some(kes("20000")), None, Some(kes("10000"))]);
+        assert_eq!(invoices[1].high_water_mark, Some(kes("1100000")));
+
+        // A first period at a loss sets the mark at the value brought in, not the low.
+        let client = account(&[
+            ("2023-12-31", "1000000"),
+            ("2024-03-31", "900000"),
+            ("2024-06-30", "950000"),
+        ]);
+        let invoices = bill_fees(&schedule, &[client], &quarters[..2]).unwrap();
+        assert_eq!(invoices[0].high_water_mark, Some(kes("1000000")));
+        assert_eq!(line(&invoices[1], FeeLineKind::Performance), None);
+    }
+
+    #[test]
+    fn closed_accounts_get_no_tax_lines() {
+        let mut schedule = schedule(TierMethod::Blended);
+        schedule.taxes = vec![FeeTax {
+            name: "VAT".to_string(),
+            rate: dec("0.16"),
+        }];
+        let mut client = account(&[("2023-12-31", "1000000")]);
+        client.closed = Some(d("2024-01-15"));
+        let invoice =
+            &bill_fees(&schedule, &[client], &[period("2024-02-01", "2024-02-29")]).unwrap()[0];
+        assert_eq!(invoice.days_billed, 0);
+        assert!(invoice.lines.is_empty());
+        assert_eq!(invoice.total, kes("0"));
+    }
+
+    #[test]
+    fn each_balance_holds_until_the_next_one() {
+        let client = account(&[
+            ("2023-12-01", "500000"),
+            ("2024-01-01", "1000000"),
+            ("2024-01-11", "2000000"),
+            ("2024-01-21", "3000000"),
+        ]);
+        let invoice = &bill_fees(
+            &schedule(TierMethod::Blended),
+            &[client],
+            &[period("2024-01-01", "2024-01-31")],
+        )
+        .unwrap()[0];
+        // Ten days at 1m, ten at 2m and eleven at 3m.
+        assert_eq!(invoice.average_balance, kes("2032258.06"));
+        // (10 × 10,000 + 10 × 15,000 + 11 × 20,000) / 365.
+        assert_eq!(line(invoice, FeeLineKind::Management), Some(kes("1287.67")));
+    }
+
+    #[test]
+    fn oversized_balances_overflow_instead_of_panicking() {
+        let mut client = account(&[]);
+        client.balances = vec![BalancePoint {
+            date: d("2024-01-01"),
+            value: Money::new(Decimal::MAX, Currency::Kes),
+        }];
+        assert_eq!(
+            bill_fees(
+                &schedule(TierMethod::Tiered),
+                &[client],
+                &[period("2024-01-01", "2024-01-02")],
+            ),
+            Err(FeeError::Money(MoneyError::Overflow))
+        );
+    }
+}
@@ -0,0 +1,690 @@
+//! Fixed income: CBK Treasury bills priced from their auction yield, and fixed-coupon
+//! and amortising (infrastructure) bonds with accrued interest, clean and dirty
+//! price, yield to maturity, duration and convexity.
+//!
+//! CBK works on an actual/364 basis: a bill at rate `r` for `d` days costs
+//! `100 / (1 + r·d/364)`, and bond coupons fall every 182 days, so accrued interest
+//! is the annual coupon times the days elapsed over 364. Prices are per 100 of
+//! original face value; rates and yields are fractions (0.155 = 15.5%).
+
+use std::fmt;
+
+use chrono::{Datelike, Days, Months, NaiveDate};
+use rust_decimal::prelude::*;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::js::{from_js, js_err, to_js};
+use crate::money::{Money, MoneyError};
+use crate::returns::solve_bracketed;
+
+/// Yield search range for [`bond_yield`].
+const YIELD_BRACKET: (f64, f64) = (-0.5, 5.0);
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum FixedIncomeError {
+    InvalidBill(&'static str),
+    InvalidBond(&'static str),
+    /// Settlement is before issue or on or after maturity.
+    SettlementOutsideLife,
+    /// No yield in the search range reproduces the price.
+    NoYield,
+    Money(MoneyError),
+}
+
+impl fmt::Display for FixedIncomeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FixedIncomeError::InvalidBill(why) => write!(f, "invalid Treasury bill: {}", why),
+            FixedIncomeError::InvalidBond(why) => write!(f, "invalid bond: {}", why),
+            FixedIncomeError::SettlementOutsideLife => {
+                f.write_str("settlement must fall between issue and maturity")
+            }
+            FixedIncomeError::NoYield => f.write_str("no yield reproduces the given price"),
+            FixedIncomeError::Money(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for FixedIncomeError {}
+
+impl From<MoneyError> for FixedIncomeError {
+    fn from(err: MoneyError) -> Self {
+        FixedIncomeError::Money(err)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DayCount {
+    /// CBK bills and bonds: actual days over a 364-day year of 182-day halves.
+    #[default]
+    Actual364,
+    Actual365,
+    /// 30E/360, for Eurobonds.
+    Thirty360,
+}
+
+impl DayCount {
+    pub fn days_in_year(self) -> f64 {
+        match self {
+            DayCount::Actual364 => 364.0,
+            DayCount::Actual365 => 365.0,
+            DayCount::Thirty360 => 360.0,
+        }
+    }
+
+    pub fn days(self, start: NaiveDate, end: NaiveDate) -> i64 {
+        match self {
+            DayCount::Actual364 | DayCount::Actual365 => (end - start).num_days(),
+            DayCount::Thirty360 => {
+                let d1 = i64::from(start.day().min(30));
+                let d2 = i64::from(end.day().min(30));
+                360 * i64::from(end.year() - start.year())
+                    + 30 * (i64::from(end.month()) - i64::from(start.month()))
+                    + (d2 - d1)
+            }
+        }
+    }
+
+    pub fn year_fraction(self, start: NaiveDate, end: NaiveDate) -> f64 {
+        self.days(start, end) as f64 / self.days_in_year()
+    }
+}
+
+pub(crate) fn to_money(value: f64, like: &Money) -> Result<Money, MoneyError> {
+    let amount = Decimal::from_f64(value).ok_or(MoneyError::Overflow)?;
+    Ok(Money::new(amount, like.currency()))
+}
+
+/// Price per 100 of a bill with `days` to run at `rate`.
+pub fn bill_price(rate: f64, days: i64, basis: DayCount) -> f64 {
+    100.0 / (1.0 + rate * days as f64 / basis.days_in_year())
+}
+
+/// The rate implied by a bill price per 100; the inverse of [`bill_price`].
+pub fn bill_yield(price: f64, days: i64, basis: DayCount) -> f64 {
+    (100.0 / price - 1.0) * basis.days_in_year() / days as f64
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BillPricing {
+    pub days: i64,
+    pub rate: f64,
+    pub price: f64,
+    /// Discount per 100 as an annual rate on face value.
+    pub discount_rate: f64,
+    /// Cash paid at settlement for `face`.
+    pub cost: Money,
+    /// Face value less cost: the interest earned by holding to maturity.
+    pub interest: Money,
+}
+
+/// Prices `face` of a bill bought on `settlement` at `rate` (the auction yield).
+pub fn price_bill(
+    face: &Money,
+    rate: f64,
+    settlement: NaiveDate,
+    maturity: NaiveDate,
+    basis: DayCount,
+) -> Result<BillPricing, FixedIncomeError> {
+    if !face.is_positive() {
+        return Err(FixedIncomeError::InvalidBill("face value must be positive"));
+    }
+    if !rate.is_finite() || rate <= -1.0 {
+        return Err(FixedIncomeError::InvalidBill("rate must be above -100%"));
+    }
+    let days = basis.days(settlement, maturity);
+    if days <= 0 {
+        return Err(FixedIncomeError::SettlementOutsideLife);
+    }
+    let price = bill_price(rate, days, basis);
+    let face_value = face.amount().to_f64().unwrap_or(0.0);
+    let cost = to_money(face_value * price / 100.0, face)?;
+    Ok(BillPricing {
+        days,
+        rate,
+        price,
+        discount_rate: (100.0 - price) / 100.0 * basis.days_in_year() / days as f64,
+        cost,
+        interest: face.checked_sub(&cost)?,
+    })
+}
+
+/// Principal repaid ahead of maturity, as a fraction of original face.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Redemption {
+    pub date: NaiveDate,
+    pub fraction: f64,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Bond {
+    /// Annual coupon on the outstanding principal.
+    pub coupon_rate: f64,
+    pub issue_date: NaiveDate,
+    pub maturity: NaiveDate,
+    #[serde(default = "default_frequency")]
+    pub frequency: u32,
+    #[serde(default)]
+    pub day_count: DayCount,
+    /// Scheduled partial redemptions; each must fall on a coupon date.
+    #[serde(default)]
+    pub amortisation: Vec<Redemption>,
+}
+
+fn default_frequency() -> u32 {
+    2
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CashFlow {
+    pub date: NaiveDate,
+    pub coupon: f64,
+    pub principal: f64,
+}
+
+impl CashFlow {
+    pub fn total(&self) -> f64 {
+        self.coupon + self.principal
+    }
+}
+
+/// One coupon period: `regular_start` is where a full period would begin, which is
+/// before `start` only for a short first coupon.
+#[derive(Clone, Copy, Debug)]
+struct Period {
+    regular_start: NaiveDate,
+    start: NaiveDate,
+    end: NaiveDate,
+    flow: CashFlow,
+}
+
+impl Bond {
+    fn validate(&self) -> Result<(), FixedIncomeError> {
+        if !self.coupon_rate.is_finite() || self.coupon_rate < 0.0 {
+            return Err(FixedIncomeError::InvalidBond(
+                "coupon rate must not be negative",
+            ));
+        }
+        if self.maturity <= self.issue_date {
+            return Err(FixedIncomeError::InvalidBond("maturity must follow issue"));
+        }
+        let divides = match self.day_count {
+            DayCount::Actual364 => self.frequency > 0 && 364 % self.frequency == 0,
+            _ => self.frequency > 0 && 12 % self.frequency == 0,
+        };
+        if !divides {
+            return Err(FixedIncomeError::InvalidBond(
+                "frequency must divide the year into whole periods",
+            ));
+        }
+        let redeemed: f64 = self.amortisation.iter().map(|r| r.fraction).sum();
+        if self.amortisation.iter().any(|r| {
+            r.fraction.is_nan()
+                || r.fraction <= 0.0
+                || r.date >= self.maturity
+                || r.date <= self.issue_date
+        }) || redeemed > 1.0 + 1e-12
+        {
+            return Err(FixedIncomeError::InvalidBond(
+                "redemptions must be positive, before maturity and at most the face value",
+            ));
+        }
+        Ok(())
+    }
+
+    /// The `k`-th regular coupon date counting back from maturity (k = 0 is maturity).
+    fn date_before_maturity(&self, k: u32) -> Option<NaiveDate> {
+        match self.day_count {
+            DayCount::Actual364 => self
+                .maturity
+                .checked_sub_days(Days::new(u64::from(364 / self.frequency * k))),
+            _ => self
+                .maturity
+                .checked_sub_months(Months::new(12 / self.frequency * k)),
+        }
+    }
+
+    fn periods(&self) -> Result<Vec<Period>, FixedIncomeError> {
+        self.validate()?;
+        let mut dates = vec![self.maturity];
+        let regular_start = loop {
+            let date = self.date_before_maturity(dates.len() as u32).ok_or(
+                FixedIncomeError::InvalidBond("coupon schedule out of range"),
+            )?;
+            if date <= self.issue_date {
+                break date;
+            }
+            dates.push(date);
+        };
+        dates.reverse();
+        if self.amortisation.iter().any(|r| !dates.contains(&r.date)) {
+            return Err(FixedIncomeError::InvalidBond(
+                "redemptions must fall on coupon dates",
+            ));
+        }
+
+        let rate = self.coupon_rate / f64::from(self.frequency);
+        let mut outstanding = 100.0;
+        let mut periods = Vec::with_capacity(dates.len());
+        let mut previous = regular_start;
+        for (i, &end) in dates.iter().enumerate() {
+            let start = if i == 0 { self.issue_date } else { previous };
+            let stub =
+                self.day_count.days(start, end) as f64 / self.day_count.days(previous, end) as f64;
+            let principal = if end == self.maturity {
+                outstanding
+            } else {
+                100.0
+                    * self
+                        .amortisation
+                        .iter()
+                        .filter(|r| r.date == end)
+                        .map(|r| r.fraction)
+                        .sum::<f64>()
+            };
+            periods.push(Period {
+                regular_start: previous,
+                start,
+                end,
+                flow: CashFlow {
+                    date: end,
+                    coupon: rate * outstanding * stub,
+                    principal,
+                },
+            });
+            outstanding -= principal;
+            previous = end;
+        }
+        Ok(periods)
+    }
+
+    /// Every coupon and principal payment per 100 of original face.
+    pub fn cash_flows(&self) -> Result<Vec<CashFlow>, FixedIncomeError> {
+        Ok(self.periods()?.into_iter().map(|p| p.flow).collect())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BondValuation {
+    pub settlement: NaiveDate,
+    pub yield_to_maturity: f64,
+    pub dirty_price: f64,
+    pub clean_price: f64,
+    pub accrued_interest: f64,
+    pub accrued_days: i64,
+    pub next_coupon: NaiveDate,
+    /// Principal still outstanding per 100 of original face.
+    pub outstanding: f64,
+    /// Years.
+    pub macaulay_duration: f64,
+    pub modified_duration: f64,
+    pub convexity: f64,
+    /// Payments still to come.
+    pub cash_flows: Vec<CashFlow>,
+}
+
+/// The remaining flows at `settlement`, each with its time in coupon periods, plus
+/// the accrued interest and the current period.
+struct Position {
+    flows: Vec<(f64, CashFlow)>,
+    accrued: f64,
+    accrued_days: i64,
+    outstanding: f64,
+}
+
+fn position(bond: &Bond, settlement: NaiveDate) -> Result<Position, FixedIncomeError> {
+    if settlement < bond.issue_date || settlement >= bond.maturity {
+        return Err(FixedIncomeError::SettlementOutsideLife);
+    }
+    let periods = bond.periods()?;
+    let current = periods
+        .iter()
+        .position(|p| p.end > settlement)
+        .ok_or(FixedIncomeError::SettlementOutsideLife)?;
+    let period = periods[current];
+    let dc = bond.day_count;
+    let accrued_days = dc.days(period.start, settlement);
+    let accrued =
+        period.flow.coupon * accrued_days as f64 / dc.days(period.start, period.end) as f64;
+    // Fraction of a regular period left until the next coupon.
+    let w =
+        dc.days(settlement, period.end) as f64 / dc.days(period.regular_start, period.end) as f64;
+    let flows = periods[current..]
+        .iter()
+        .enumerate()
+        .map(|(k, p)| (w + k as f64, p.flow))
+        .collect();
+    let outstanding = periods[current..].iter().map(|p| p.flow.principal).sum();
+    Ok(Position {
+        flows,
+        accrued,
+        accrued_days,
+        outstanding,
+    })
+}
+
+fn dirty_at(flows: &[(f64, CashFlow)], per_period: f64) -> f64 {
+    flows
+        .iter()
+        .map(|(t, cf)| cf.total() / (1.0 + per_period).powf(*t))
+        .sum()
+}
+
+/// Values `bond` for settlement on `settlement` at yield `ytm` (compounded at the
+/// coupon frequency).
+pub fn value_bond(
+    bond: &Bond,
+    settlement: NaiveDate,
+    ytm: f64,
+) -> Result<BondValuation, FixedIncomeError> {
+    let f = f64::from(bond.frequency);
+    if !ytm.is_finite() || ytm <= -f {
+        return Err(FixedIncomeError::InvalidBond("yield is out of range"));
+    }
+    let pos = position(bond, settlement)?;
+    let y = ytm / f;
+    let dirty = dirty_at(&pos.flows, y);
+    let mut weighted = 0.0;
+    let mut curved = 0.0;
+    for (t, cf) in &pos.flows {
+        let pv = cf.total() / (1.0 + y).powf(*t);
+        weighted += t * pv;
+        curved += t * (t + 1.0) * pv;
+    }
+    let macaulay = weighted / dirty / f;
+    Ok(BondValuation {
+        settlement,
+        yield_to_maturity: ytm,
+        dirty_price: dirty,
+        clean_price: dirty - pos.accrued,
+        accrued_interest: pos.accrued,
+        accrued_days: pos.accrued_days,
+        next_coupon: pos.flows[0].1.date,
+        outstanding: pos.outstanding,
+        macaulay_duration: macaulay,
+        modified_duration: macaulay / (1.0 + y),
+        convexity: curved / (dirty * f * f * (1.0 + y).powi(2)),
+        cash_flows: pos.flows.into_iter().map(|(_, cf)| cf).collect(),
+    })
+}
+
+/// Yield to maturity implied by a clean price per 100 of original face.
+pub fn bond_yield(
+    bond: &Bond,
+    settlement: NaiveDate,
+    clean_price: f64,
+) -> Result<f64, FixedIncomeError> {
+    if clean_price.is_nan() || clean_price <= 0.0 {
+        return Err(FixedIncomeError::InvalidBond("price must be positive"));
+    }
+    let pos = position(bond, settlement)?;
+    let f = f64::from(bond.frequency);
+    let target = clean_price + pos.accrued;
+    let (lo, hi) = YIELD_BRACKET;
+    solve_bracketed(|ytm| dirty_at(&pos.flows, ytm / f) - target, lo, hi)
+        .ok_or(FixedIncomeError::NoYield)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BillInput {
+    face: Money,
+    rate: f64,
+    settlement: NaiveDate,
+    maturity: NaiveDate,
+    #[serde(default)]
+    day_count: DayCount,
+}
+
+/// JS entry point: `{ face, rate, settlement, maturity, dayCount? }` in, `BillPricing` out.
+#[wasm_bindgen(js_name = priceTreasuryBill)]
+pub fn price_bill_js(input: JsValue) -> Result<JsValue, JsError> {
+    let input: BillInput = from_js(input)?;
+    let pricing = price_bill(
+        &input.face,
+        input.rate,
+        input.settlement,
+        input.maturity,
+        input.day_count,
+    )
+    .map_err(js_err)?;
+    to_js(&pricing)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BondInput {
+    bond: Bond,
+    settlement: NaiveDate,
+    /// Either the yield to value at, or the clean price to solve the yield from.
+    #[serde(default)]
+    yield_to_maturity: Option<f64>,
+    #[serde(default)]
+    clean_price: Option<f64>,
+    /// Face value held; adds the settlement consideration to the result.
+    #[serde(default)]
+    face: Option<Money>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BondOutput {
+    valuation: BondValuation,
+    consideration: Option<Money>,
+}
+
+/// JS entry point: `{ bond, settlement, yieldToMaturity? | cleanPrice?, face? }` in,
+/// `{ valuation, consideration }` out.
+#[wasm_bindgen(js_name = valueBond)]
+pub fn value_bond_js(input: JsValue) -> Result<JsValue, JsError> {
+    let input: BondInput = from_js(input)?;
+    let ytm = match (input.yield_to_maturity, input.clean_price) {
+        (Some(ytm), _) => ytm,
+        (None, Some(price)) => bond_yield(&input.bond, input.settlement, price).map_err(js_err)?,
+        (None, None) => return Err(JsError::new("give either yieldToMaturity or cleanPrice")),
+    };
+    let valuation = value_bond(&input.bond, input.settlement, ytm).map_err(js_err)?;
+    let consideration = input
+        .face
+        .map(|face| {
+            let held = face.amount().to_f64().unwrap_or(0.0);
+            to_money(held * valuation.dirty_price / 100.0, &face)
+        })
+        .transpose()
+        .map_err(js_err)?;
+    to_js(&BondOutput {
+        valuation,
+        consideration,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::money::Currency;
+
+    fn d(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn cbk_bond(coupon_rate: f64, periods: u64) -> Bond {
+        let issue_date = d("2024-01-08");
+        Bond {
+            coupon_rate,
+            issue_date,
+            maturity: issue_date + Days::new(182 * periods),
+            frequency: 2,
+            day_count: DayCount::Actual364,
+            amortisation: Vec::new(),
+        }
+    }
+
+    fn close(a: f64, b: f64, tol: f64) -> bool {
+        (a - b).abs() < tol
+    }
+
+    #[test]
+    fn bills_follow_the_cbk_price_formula() {
+        // 100 / (1 + r·d/364) for a 91-day bill at 15.9787% and a 364-day bill at 16%.
+        assert!(close(
+            bill_price(0.159787, 91, DayCount::Actual364),
+            96.158770,
+            1e-6
+        ));
+        assert!(close(
+            bill_price(0.16, 364, DayCount::Actual364),
+            86.206897,
+            1e-6
+        ));
+        assert!(close(
+            bill_yield(96.158770, 91, DayCount::Actual364),
+            0.159787,
+            1e-7
+        ));
+
+        let face = Money::new(Decimal::from(1_000_000), Currency::Kes);
+        let pricing = price_bill(
+            &face,
+            0.159787,
+            d("2024-01-08"),
+            d("2024-04-08"),
+            DayCount::Actual364,
+        )
+        .unwrap();
+        assert_eq!(pricing.days, 91);
+        assert_eq!(pricing.cost.amount(), Decimal::new(96158770, 2));
+        assert_eq!(pricing.interest.amount(), Decimal::new(3841230, 2));
+    }
+
+    #[test]
+    fn bond_at_its_coupon_yield_prices_at_par() {
+        let bond = cbk_bond(0.12, 20);
+        let v = value_bond(&bond, bond.issue_date, 0.12).unwrap();
+        assert!(close(v.dirty_price, 100.0, 1e-9));
+        assert_eq!(v.accrued_interest, 0.0);
+        assert_eq!(v.cash_flows.len(), 20);
+    }
+
+    #[test]
+    fn textbook_price_on_a_coupon_date() {
+        // 20-year 9% semi-annual bond at a 12% yield: 77.4306 per 100.
+        let bond = Bond {
+            coupon_rate: 0.09,
+            issue_date: d("2000-01-15"),
+            maturity: d("2020-01-15"),
+            frequency: 2,
+            day_count: DayCount::Thirty360,
+            amortisation: Vec::new(),
+        };
+        let v = value_bond(&bond, bond.issue_date, 0.12).unwrap();
+        assert!(close(v.clean_price, 77.430555, 1e-5));
+    }
+
+    #[test]
+    fn accrued_interest_is_actual_over_364() {
+        let bond = cbk_bond(0.12, 20);
+        let settlement = bond.issue_date + Days::new(91);
+        let v = value_bond(&bond, settlement, 0.14).unwrap();
+        assert_eq!(v.accrued_days, 91);
+        assert!(close(v.accrued_interest, 3.0, 1e-12));
+        assert!(close(v.dirty_price - v.clean_price, 3.0, 1e-12));
+        assert_eq!(v.next_coupon, bond.issue_date + Days::new(182));
+    }
+
+    #[test]
+    fn cbk_fxd_auction_and_reopening_settlement() {
+        // FXD1/2023/003, CBK Treasury bond auction results, January 2023 (press
+        // release on centralbank.go.ke): 3-year fixed coupon of 14.228%, value date
+        // 23 January 2023, maturing 19 January 2026. The published weighted average
+        // rate of accepted bids is 14.228% and the published average price per
+        // KSh 100 is 100.0000, since a new issue's coupon is set at that rate.
+        const PUBLISHED_RATE: f64 = 0.14228;
+        const PUBLISHED_PRICE: f64 = 100.0;
+        let bond = Bond {
+            coupon_rate: 0.14228,
+            issue_date: d("2023-01-23"),
+            maturity: d("2026-01-19"),
+            frequency: 2,
+            day_count: DayCount::Actual364,
+            amortisation: Vec::new(),
+        };
+        let v = value_bond(&bond, bond.issue_date, PUBLISHED_RATE).unwrap();
+        assert!(close(v.clean_price, PUBLISHED_PRICE, 5e-5));
+        assert_eq!(v.next_coupon, d("2023-07-24"));
+
+        // Reopening for value 3 April 2023 at 16%. NOTE: CBK's published results for
+        // this reopening could not be retrieved when this test was written, so these
+        // are not published figures. They are worked by hand from the pricing formula
+        // in CBK's bond prospectuses and should be replaced with the weighted average
+        // price from the results release once it is checked:
+        //   accrued = 14.228 × 70 / 364 = 2.736154 per 100;
+        //   dirty = six coupons of 7.114 and the principal, discounted at 8% a
+        //   half-year, 112/182 of a period to the first and a whole period after;
+        //   clean = dirty − accrued.
+        let v = value_bond(&bond, d("2023-04-03"), 0.16).unwrap();
+        assert_eq!(v.accrued_days, 70);
+        assert!(close(v.accrued_interest, 2.736154, 1e-6));
+        assert!(close(v.dirty_price, 98.785362, 1e-6));
+        assert!(close(v.clean_price, 96.049208, 1e-6));
+        let ytm = bond_yield(&bond, d("2023-04-03"), v.clean_price).unwrap();
+        assert!(close(ytm, 0.16, 1e-10));
+    }
+
+    #[test]
+    fn yield_round_trips_through_the_clean_price() {
+        let bond = cbk_bond(0.1285, 30);
+        let settlement = bond.issue_date + Days::new(400);
+        let v = value_bond(&bond, settlement, 0.1675).unwrap();
+        let ytm = bond_yield(&bond, settlement, v.clean_price).unwrap();
+        assert!(close(ytm, 0.1675, 1e-10));
+    }
+
+    #[test]
+    fn amortising_bond_pays_coupons_on_the_outstanding_principal() {
+        let mut bond = cbk_bond(0.12, 12);
+        bond.amortisation = vec![
+            Redemption {
+                date: bond.issue_date + Days::new(182 * 4),
+                fraction: 1.0 / 3.0,
+            },
+            Redemption {
+                date: bond.issue_date + Days::new(182 * 8),
+                fraction: 1.0 / 3.0,
+            },
+        ];
+        let flows = bond.cash_flows().unwrap();
+        let principal: f64 = flows.iter().map(|f| f.principal).sum();
+        assert!(close(principal, 100.0, 1e-9));
+        assert!(close(flows[4].coupon, 4.0, 1e-9));
+        assert!(close(flows[11].coupon, 2.0, 1e-9));
+
+        let v = value_bond(&bond, bond.issue_date, 0.12).unwrap();
+        assert!(close(v.dirty_price, 100.0, 1e-9));
+        let later = value_bond(&bond, bond.issue_date + Days::new(182 * 5), 0.12).unwrap();
+        assert!(close(later.outstanding, 200.0 / 3.0, 1e-9));
+    }
+
+    #[test]
+    fn duration_and_convexity_match_finite_differences() {
+        let zero = cbk_bond(0.0, 6);
+        let v = value_bond(&zero, zero.issue_date, 0.15).unwrap();
+        assert!(close(v.macaulay_duration, 3.0, 1e-12));
+
+        let bond = cbk_bond(0.13, 20);
+        let settlement = bond.issue_date + Days::new(50);
+        let h = 1e-4;
+        let p = |y: f64| value_bond(&bond, settlement, y).unwrap().dirty_price;
+        let v = value_bond(&bond, settlement, 0.15).unwrap();
+        let slope = (p(0.15 + h) - p(0.15 - h)) / (2.0 * h);
+        let curve = (p(0.15 + h) + p(0.15 - h) - 2.0 * v.dirty_price) / (h * h);
+        assert!(close(v.modified_duration, -slope / v.dirty_price, 1e-6));
+        assert!(close(v.convexity, curve / v.dirty_price, 1e-3));
+    }
+}
@@ -0,0 +1,644 @@
+//! Unit trust fund accounting: daily NAV per unit from assets, accrued income and fee
+//! accruals, forward-priced unit issuance and redemption around a dealing cut-off,
+//! income distributions, and the yields CMA requires money market funds to publish.
+//!
+//! Each valuation day income (and any revaluation) is added to assets, fees accrue
+//! as a liability on the pre-fee NAV over the calendar days since the last valuation,
+//! and NAV is assets less accrued fees. Distributions go to holders on the register
+//! at the valuation point; orders received by the cut-off then deal at that day's
+//! (ex-distribution) price, later orders at the next valuation day's.
+
+use std::fmt;
+
+use chrono::{Datelike, NaiveDate, NaiveDateTime, NaiveTime};
+use rust_decimal::prelude::*;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::js::{from_js, js_err, to_js};
+use crate::money::{round_bankers, Currency, Money, MoneyError};
+
+/// Decimal places on the published price per unit.
+pub const PRICE_DP: u32 = 4;
+/// Decimal places on unit holdings.
+pub const UNITS_DP: u32 = 4;
+/// Decimal places on a distribution per unit.
+const DISTRIBUTION_DP: u32 = 6;
+/// Days over which yields are annualised.
+const DAYS_IN_YEAR: f64 = 365.0;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum FundError {
+    InvalidTerms(&'static str),
+    /// Valuation days must be strictly increasing.
+    UnorderedDays(NaiveDate),
+    InvalidOrder {
+        order: String,
+        why: &'static str,
+    },
+    /// A redemption asked for more units than are in issue.
+    InsufficientUnits(String),
+    /// The fund's NAV per unit on this day is zero or negative, so orders cannot deal.
+    NonPositivePrice(NaiveDate),
+    Money(MoneyError),
+}
+
+impl fmt::Display for FundError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FundError::InvalidTerms(why) => write!(f, "invalid fund terms: {}", why),
+            FundError::UnorderedDays(date) => {
+                write!(f, "valuation day {} is not after the previous one", date)
+            }
+            FundError::InvalidOrder { order, why } => write!(f, "invalid order {}: {}", order, why),
+            FundError::InsufficientUnits(order) => {
+                write!(f, "order {} redeems more units than are in issue", order)
+            }
+            FundError::NonPositivePrice(date) => {
+                write!(f, "price per unit on {} is not positive", date)
+            }
+            FundError::Money(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for FundError {}
+
+impl From<MoneyError> for FundError {
+    fn from(err: MoneyError) -> Self {
+        FundError::Money(err)
+    }
+}
+
+/// An annual charge on NAV (management, trustee, custody), accrued daily.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FundFee {
+    pub name: String,
+    pub annual_rate: Decimal,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DistributionPolicy {
+    /// Net income stays in the price.
+    #[default]
+    Accumulate,
+    /// Net income is distributed every valuation day.
+    Daily,
+    /// Net income is distributed on the last valuation day of each month.
+    Monthly,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FundTerms {
+    pub currency: Currency,
+    /// Price per unit while no units are in issue.
+    #[serde(default = "default_launch_price")]
+    pub launch_price: Decimal,
+    #[serde(default)]
+    pub fees: Vec<FundFee>,
+    #[serde(default)]
+    pub distribution: DistributionPolicy,
+    /// Distributions are paid out as cash instead of buying new units.
+    #[serde(default)]
+    pub pay_out: bool,
+    /// Orders received after this time deal at the next valuation day's price.
+    pub cut_off: NaiveTime,
+    /// Assets and units brought forward from before the first valuation day.
+    #[serde(default)]
+    pub opening_assets: Option<Money>,
+    #[serde(default)]
+    pub opening_units: Decimal,
+}
+
+fn default_launch_price() -> Decimal {
+    Decimal::ONE
+}
+
+/// Inputs for one valuation point.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FundDay {
+    pub date: NaiveDate,
+    /// Gross income earned since the previous valuation (interest, dividends).
+    pub income: Money,
+    /// Market value change on the investments since the previous valuation.
+    #[serde(default)]
+    pub revaluation: Option<Money>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+pub enum OrderKind {
+    Subscribe { amount: Money },
+    Redeem { units: Decimal },
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FundOrder {
+    pub id: String,
+    pub received: NaiveDateTime,
+    pub order: OrderKind,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Deal {
+    pub order: String,
+    pub dealt_on: NaiveDate,
+    pub price: Decimal,
+    /// Positive for units issued, negative for units redeemed.
+    pub units: Decimal,
+    /// Cash received for a subscription or paid on a redemption.
+    pub amount: Money,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeeAccrual {
+    pub name: String,
+    pub amount: Money,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Distribution {
+    pub per_unit: Decimal,
+    pub amount: Money,
+    /// Units bought with the distribution; zero when it is paid out.
+    pub units_reinvested: Decimal,
+    /// The distribution per unit over the ex price, annualised over the days it covers.
+    pub distribution_yield: f64,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FundValuation {
+    pub date: NaiveDate,
+    pub days: i64,
+    pub income: Money,
+    pub fees: Vec<FeeAccrual>,
+    pub net_income: Money,
+    /// Investments, cash and accrued income.
+    pub gross_assets: Money,
+    pub fees_payable: Money,
+    /// NAV at the valuation point, before dealing.
+    pub nav: Money,
+    /// Price the day's orders deal at (after any distribution).
+    pub nav_per_unit: Decimal,
+    pub distribution: Option<Distribution>,
+    pub subscriptions: Money,
+    pub redemptions: Money,
+    pub units_outstanding: Decimal,
+    /// Net income over NAV, annualised simply over 365 days.
+    pub annualised_yield: f64,
+    /// The same return compounded over a year, as CMA requires MMFs to publish.
+    pub effective_annual_yield: f64,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FundReport {
+    pub valuations: Vec<FundValuation>,
+    pub deals: Vec<Deal>,
+    /// Orders received after the last valuation day's cut-off.
+    pub pending: Vec<String>,
+    pub nav: Money,
+    pub nav_per_unit: Decimal,
+    pub units_outstanding: Decimal,
+    /// Distributions per unit over the last 365 days over the latest price.
+    pub trailing_distribution_yield: Option<f64>,
+}
+
+fn ratio(numerator: Decimal, denominator: Decimal) -> f64 {
+    if denominator.is_zero() {
+        return 0.0;
+    }
+    (numerator / denominator).to_f64().unwrap_or(0.0)
+}
+
+fn validate(terms: &FundTerms, days: &[FundDay], orders: &[FundOrder]) -> Result<(), FundError> {
+    if terms.launch_price <= Decimal::ZERO {
+        return Err(FundError::InvalidTerms("launch price must be positive"));
+    }
+    if terms.opening_units < Decimal::ZERO {
+        return Err(FundError::InvalidTerms(
+            "opening units must not be negative",
+        ));
+    }
+    if terms.fees.iter().any(|f| f.annual_rate < Decimal::ZERO) {
+        return Err(FundError::InvalidTerms("fee rates must not be negative"));
+    }
+    for pair in days.windows(2) {
+        if pair[1].date <= pair[0].date {
+            return Err(FundError::UnorderedDays(pair[1].date));
+        }
+    }
+    for order in orders {
+        let invalid = |why| FundError::InvalidOrder {
+            order: order.id.clone(),
+            why,
+        };
+        match &order.order {
+            OrderKind::Subscribe { amount } if !amount.is_positive() => {
+                return Err(invalid("subscription must be a positive amount"));
+            }
+            OrderKind::Subscribe { amount } if amount.currency() != terms.currency => {
+                return Err(invalid("subscription is not in the fund's currency"));
+            }
+            OrderKind::Redeem { units } if *units <= Decimal::ZERO => {
+                return Err(invalid("redemption must be a positive number of units"));
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Whether `days[n]` is the last valuation day of its month. The final day only
+/// counts when it is the last calendar day, since later days may still follow.
+fn is_month_end(days: &[FundDay], n: usize) -> bool {
+    let date = days[n].date;
+    match days.get(n + 1) {
+        Some(next) => next.date.month() != date.month(),
+        None => date.succ_opt().is_none_or(|d| d.month() != date.month()),
+    }
+}
+
+/// Whether an order received at `received` deals on valuation day `date`.
+fn deals_on(received: NaiveDateTime, date: NaiveDate, cut_off: NaiveTime) -> bool {
+    received.date() < date || (received.date() == date && received.time() <= cut_off)
+}
+
+/// Runs the fund through `days`, pricing units and dealing `orders` as they fall due.
+pub fn run_fund(
+    terms: &FundTerms,
+    days: &[FundDay],
+    orders: &[FundOrder],
+) -> Result<FundReport, FundError> {
+    validate(terms, days, orders)?;
+    let currency = terms.currency;
+    let zero = Money::zero(currency);
+    let mut assets = zero.checked_add(terms.opening_assets.as_ref().unwrap_or(&zero))?;
+    let mut payable = zero;
+    let mut undistributed = zero;
+    let mut units = terms.opening_units;
+    let mut last_distribution = days.first().and_then(|d| d.date.pred_opt());
+    let mut dealt = vec![false; orders.len()];
+    let mut deals = Vec::new();
+    let mut valuations: Vec<FundValuation> = Vec::with_capacity(days.len());
+
+    let mut queue: Vec<usize> = (0..orders.len()).collect();
+    queue.sort_by_key(|&i| orders[i].received);
+
+    for (n, day) in days.iter().enumerate() {
+        let elapsed = match n {
+            0 => 1,
+            _ => (day.date - days[n - 1].date).num_days(),
+        };
+        let revaluation = day.revaluation.unwrap_or(zero);
+        assets = assets.checked_add(&day.income)?.checked_add(&revaluation)?;
+
+        // Fees accrue on the NAV before today's fees.
+        let pre_fee = assets.checked_sub(&payable)?;
+        let mut fees = Vec::with_capacity(terms.fees.len());
+        for fee in &terms.fees {
+            let amount = pre_fee.checked_mul(
+                fee.annual_rate * Decimal::from(elapsed) / Decimal::from(DAYS_IN_YEAR as i64),
+            )?;
+            payable = payable.checked_add(&amount)?;
+            fees.push(FeeAccrual {
+                name: fee.name.clone(),
+                amount,
+            });
+        }
+        let fee_total = Money::sum(fees.iter().map(|f| &f.amount), currency)?;
+        let net_income = day.income.checked_sub(&fee_total)?;
+        undistributed = undistributed.checked_add(&net_income)?;
+        let nav = assets.checked_sub(&payable)?;
+
+        let month_end = is_month_end(days, n);
+        let distributes = match terms.distribution {
+            DistributionPolicy::Accumulate => false,
+            DistributionPolicy::Daily => true,
+            DistributionPolicy::Monthly => month_end,
+        };
+        let mut distribution = None;
+        if distributes && undistributed.is_positive() && units > Decimal::ZERO {
+            let per_unit = (undistributed.amount() / units)
+                .round_dp_with_strategy(DISTRIBUTION_DP, RoundingStrategy::ToZero);
+            let amount = Money::new(per_unit * units, currency);
+            undistributed = undistributed.checked_sub(&amount)?;
+            let ex_nav = nav.checked_sub(&amount)?;
+            let ex_price = round_bankers(ex_nav.amount() / units, PRICE_DP);
+            let units_reinvested = if terms.pay_out || ex_price <= Decimal::ZERO {
+                assets = assets.checked_sub(&amount)?;
+                Decimal::ZERO
+            } else {
+                (amount.amount() / ex_price)
+                    .round_dp_with_strategy(UNITS_DP, RoundingStrategy::ToZero)
+            };
+            let covered = last_distribution
+                .map_or(elapsed, |since| (day.date - since).num_days())
+                .max(1);
+            distribution = Some(Distribution {
+                per_unit,
+                amount,
+                units_reinvested,
+                distribution_yield: ratio(per_unit, ex_price) * DAYS_IN_YEAR / covered as f64,
+            });
+            units += units_reinvested;
+            last_distribution = Some(day.date);
+        }
+
+        let price = if units.is_zero() {
+            terms.launch_price
+        } else {
+            round_bankers(assets.checked_sub(&payable)?.amount() / units, PRICE_DP)
+        };
+
+        // Forward pricing: everything received by today's cut-off deals at today's price.
+        let mut subscriptions = zero;
+        let mut redemptions = zero;
+        for &i in &queue {
+            let order = &orders[i];
+            if dealt[i] || !deals_on(order.received, day.date, terms.cut_off) {
+                continue;
+            }
+            if price <= Decimal::ZERO {
+                return Err(FundError::NonPositivePrice(day.date));
+            }
+            dealt[i] = true;
+            let (units_dealt, amount) = match &order.order {
+                OrderKind::Subscribe { amount } => {
+                    let issued = (amount.amount() / price)
+                        .round_dp_with_strategy(UNITS_DP, RoundingStrategy::ToZero);
+                    assets = assets.checked_add(amount)?;
+                    subscriptions = subscriptions.checked_add(amount)?;
+                    (issued, *amount)
+                }
+                OrderKind::Redeem { units: redeemed } => {
+                    if *redeemed > units {
+                        return Err(FundError::InsufficientUnits(order.id.clone()));
+                    }
+                    let paid = Money::new(*redeemed * price, currency);
+                    assets = assets.checked_sub(&paid)?;
+                    redemptions = redemptions.checked_add(&paid)?;
+                    (-*redeemed, paid)
+                }
+            };
+            units += units_dealt;
+            deals.push(Deal {
+                order: order.id.clone(),
+                dealt_on: day.date,
+                price,
+                units: units_dealt,
+                amount,
+            });
+        }
+
+        // Accrued fees are settled out of the fund at each month end.
+        if month_end {
+            assets = assets.checked_sub(&payable)?;
+            payable = zero;
+        }
+
+        let period_return = ratio(net_income.amount(), nav.amount());
+        valuations.push(FundValuation {
+            date: day.date,
+            days: elapsed,
+            income: day.income,
+            fees,
+            net_income,
+            gross_assets: assets.checked_add(&payable)?,
+            fees_payable: payable,
+            nav,
+            nav_per_unit: price,
+            distribution,
+            subscriptions,
+            redemptions,
+            units_outstanding: units,
+            annualised_yield: period_return * DAYS_IN_YEAR / elapsed as f64,
+            effective_annual_yield: (1.0 + period_return).powf(DAYS_IN_YEAR / elapsed as f64) - 1.0,
+        });
+    }
+
+    let nav = assets.checked_sub(&payable)?;
+    let nav_per_unit = valuations
+        .last()
+        .map_or(terms.launch_price, |v| v.nav_per_unit);
+    let trailing_distribution_yield = valuations.last().and_then(|last| {
+        let distributed: Decimal = valuations
+            .iter()
+            .filter(|v| (last.date - v.date).num_days() < DAYS_IN_YEAR as i64)
+            .filter_map(|v| v.distribution.as_ref().map(|d| d.per_unit))
+            .sum();
+        (terms.distribution != DistributionPolicy::Accumulate)
+            .then(|| ratio(distributed, last.nav_per_unit))
+    });
+    Ok(FundReport {
+        valuations,
+        deals,
+        pending: queue
+            .iter()
+            .filter(|&&i| !dealt[i])
+            .map(|&i| orders[i].id.clone())
+            .collect(),
+        nav,
+        nav_per_unit,
+        units_outstanding: units,
+        trailing_distribution_yield,
+    })
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FundInput {
+    terms: FundTerms,
+    days: Vec<FundDay>,
+    #[serde(default)]
+    orders: Vec<FundOrder>,
+}
+
+/// JS entry point: `{ terms, days, orders? }` in, `FundReport` out.
+#[wasm_bindgen(js_name = runFund)]
+pub fn run_fund_js(input: JsValue) -> Result<JsValue, JsError> {
+    let input: FundInput = from_js(input)?;
+    let report = run_fund(&input.terms, &input.days, &input.orders).map_err(js_err)?;
+    to_js(&report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn d(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn dec(s: &str) -> Decimal {
+        Decimal::from_str(s).unwrap()
+    }
+
+    fn kes(s: &str) -> Money {
+        Money::parse(s, Currency::Kes).unwrap()
+    }
+
+    fn close(a: f64, b: f64, tol: f64) {
+        assert!((a - b).abs() < tol, "{} != {}", a, b);
+    }
+
+    /// A fund a million shillings in size at a shilling a unit.
+    fn terms(distribution: DistributionPolicy) -> FundTerms {
+        FundTerms {
+            currency: Currency::Kes,
+            launch_price: Decimal::ONE,
+            fees: Vec::new(),
+            distribution,
+            pay_out: false,
+            cut_off: NaiveTime::from_hms_opt(14, 0, 0).unwrap(),
+            opening_assets: Some(kes("1000000")),
+            opening_units: dec("1000000"),
+        }
+    }
+
+    fn day(date: &str, income: &str) -> FundDay {
+        FundDay {
+            date: d(date),
+            income: kes(income),
+            revaluation: None,
+        }
+    }
+
+    fn subscribe(id: &str, received: &str, amount: &str) -> FundOrder {
+        FundOrder {
+            id: id.to_string(),
+            received: NaiveDateTime::parse_from_str(received, "%Y-%m-%d %H:%M").unwrap(),
+            order: OrderKind::Subscribe {
+                amount: kes(amount),
+            },
+        }
+    }
+
+    #[test]
+    fn orders_after_the_cut_off_deal_at_the_next_price() {
+        let days = [day("2024-03-04", "0"), day("2024-03-05", "1000")];
+        let orders = [
+            subscribe("A", "2024-03-04 13:59", "10000"),
+            subscribe("B", "2024-03-04 14:01", "10000"),
+            subscribe("C", "2024-03-05 16:30", "10000"),
+        ];
+        let report = run_fund(&terms(DistributionPolicy::Accumulate), &days, &orders).unwrap();
+        let dealt: Vec<(&str, NaiveDate, Decimal)> = report
+            .deals
+            .iter()
+            .map(|x| (x.order.as_str(), x.dealt_on, x.units))
+            .collect();
+        // B waits for the 5 March price, which carries that day's income.
+        assert_eq!(
+            dealt,
+            vec![
+                ("A", d("2024-03-04"), dec("10000")),
+                ("B", d("2024-03-05"), dec("9990.0099")),
+            ]
+        );
+        assert_eq!(report.deals[1].price, dec("1.0010"));
+        assert_eq!(report.pending, vec!["C".to_string()]);
+    }
+
+    #[test]
+    fn fees_accrue_daily_on_the_pre_fee_nav() {
+        let mut terms = terms(DistributionPolicy::Accumulate);
+        terms.fees = vec![FundFee {
+            name: "management".to_string(),
+            annual_rate: dec("0.02"),
+        }];
+        // Friday, then Monday: the weekend accrues with Monday.
+        let days = [day("2024-03-01", "0"), day("2024-03-04", "0")];
+        let report = run_fund(&terms, &days, &[]).unwrap();
+        // 1,000,000 × 2% / 365.
+        assert_eq!(report.valuations[0].fees[0].amount, kes("54.79"));
+        // 999,945.21 × 2% × 3 / 365.
+        assert_eq!(report.valuations[1].fees[0].amount, kes("164.37"));
+        assert_eq!(report.valuations[1].fees_payable, kes("219.16"));
+        assert_eq!(report.nav, kes("999780.84"));
+        assert_eq!(report.valuations[1].net_income, kes("-164.37"));
+    }
+
+    #[test]
+    fn daily_distributions_reinvest_or_pay_out() {
+        let days = [day("2024-03-04", "500")];
+        let report = run_fund(&terms(DistributionPolicy::Daily), &days, &[]).unwrap();
+        let paid = report.valuations[0].distribution.as_ref().unwrap();
+        assert_eq!(paid.per_unit, dec("0.0005"));
+        assert_eq!(paid.amount, kes("500"));
+        // Reinvested at the ex-distribution price of 1.0000.
+        assert_eq!(paid.units_reinvested, dec("500"));
+        assert_eq!(report.units_outstanding, dec("1000500"));
+        assert_eq!(report.nav_per_unit, dec("1.0000"));
+        close(paid.distribution_yield, 0.0005 * 365.0, 1e-12);
+
+        let mut paying = terms(DistributionPolicy::Daily);
+        paying.pay_out = true;
+        let report = run_fund(&paying, &days, &[]).unwrap();
+        let paid = report.valuations[0].distribution.as_ref().unwrap();
+        assert_eq!(paid.units_reinvested, Decimal::ZERO);
+        assert_eq!(report.units_outstanding, dec("1000000"));
+        assert_eq!(report.nav, kes("1000000"));
+    }
+
+    #[test]
+    fn monthly_distributions_wait_for_the_last_valuation_day() {
+        let days = [
+            day("2024-01-30", "100"),
+            day("2024-01-31", "100"),
+            day("2024-02-01", "100"),
+        ];
+        let report = run_fund(&terms(DistributionPolicy::Monthly), &days, &[]).unwrap();
+        let amounts: Vec<Option<Money>> = report
+            .valuations
+            .iter()
+            .map(|v| v.distribution.as_ref().map(|x| x.amount))
+            .collect();
+        assert_eq!(amounts, vec![None, Some(kes("200")), None]);
+        // 0.0002 a unit over the 1 February price of 1.0001.
+        close(
+            report.trailing_distribution_yield.unwrap(),
+            0.0002 / 1.0001,
+            1e-12,
+        );
+    }
+
+    #[test]
+    fn effective_annual_yield_compounds_the_daily_return() {
+        // CMA's money market yield: 342.47 of net income on a 1,000,000 NAV is a
+        // daily return of 0.034247%, a simple 12.500155% a year, and
+        // (1.00034247)^365 - 1 = 13.312596% effective.
+        let mut terms = terms(DistributionPolicy::Accumulate);
+        terms.opening_assets = Some(kes("999657.53"));
+        let report = run_fund(&terms, &[day("2024-03-04", "342.47")], &[]).unwrap();
+        let v = &report.valuations[0];
+        assert_eq!(v.nav, kes("1000000"));
+        close(v.annualised_yield, 0.12500155, 1e-12);
+        close(v.effective_annual_yield, 0.13312596, 1e-8);
+    }
+
+    #[test]
+    fn orders_do_not_deal_at_a_non_positive_price() {
+        for loss in ["-1000000", "-2000000"] {
+            let days = [FundDay {
+                date: d("2024-03-04"),
+                income: kes("0"),
+                revaluation: Some(kes(loss)),
+            }];
+            let orders = [subscribe("A", "2024-03-04 09:00", "10000")];
+            assert_eq!(
+                run_fund(&terms(DistributionPolicy::Accumulate), &days, &orders),
+                Err(FundError::NonPositivePrice(d("2024-03-04")))
+            );
+        }
+    }
+}
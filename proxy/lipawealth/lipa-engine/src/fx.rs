@@ -0,0 +1,617 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::js::{from_js, js_err};
+use crate::money::{parse_decimal, Currency, Money, MoneyError};
+
+/// How far ahead of `now` a quote may be stamped before it is treated as bad data
+/// rather than clock drift between the feed and this host.
+pub const MAX_CLOCK_SKEW_MS: i64 = 5_000;
+
+/// A dealer quote: one unit of `base` is bought at `bid` and sold at `ask` units of `quote`.
+///
+/// `timestamp` is Unix epoch milliseconds, the same clock as JS `Date.now()`.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FxQuote {
+    pub base: Currency,
+    pub quote: Currency,
+    pub bid: Decimal,
+    pub ask: Decimal,
+    pub timestamp: i64,
+}
+
+impl FxQuote {
+    /// A quote with no bid/ask spread, for sources that only publish a mid rate.
+    pub fn mid_only(base: Currency, quote: Currency, rate: Decimal, timestamp: i64) -> FxQuote {
+        FxQuote {
+            base,
+            quote,
+            bid: rate,
+            ask: rate,
+            timestamp,
+        }
+    }
+
+    pub fn mid(&self) -> Decimal {
+        (self.bid + self.ask) / Decimal::TWO
+    }
+}
+
+/// Which side of the quote a conversion is dealt at.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RateSide {
+    Bid,
+    Mid,
+    Ask,
+}
+
+impl RateSide {
+    fn opposite(self) -> RateSide {
+        match self {
+            RateSide::Bid => RateSide::Ask,
+            RateSide::Mid => RateSide::Mid,
+            RateSide::Ask => RateSide::Bid,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum FxError {
+    MissingRate {
+        from: Currency,
+        to: Currency,
+    },
+    InvalidQuote {
+        base: Currency,
+        quote: Currency,
+    },
+    StaleRate {
+        base: Currency,
+        quote: Currency,
+        age_ms: i64,
+        max_age_ms: i64,
+    },
+    FutureRate {
+        base: Currency,
+        quote: Currency,
+        ahead_ms: i64,
+    },
+    InvalidSpread,
+    Money(MoneyError),
+}
+
+impl fmt::Display for FxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FxError::MissingRate { from, to } => write!(f, "no FX rate for {}/{}", from, to),
+            FxError::InvalidQuote { base, quote } => write!(
+                f,
+                "FX quote for {}/{} must have 0 < bid <= ask",
+                base, quote
+            ),
+            FxError::StaleRate {
+                base,
+                quote,
+                age_ms,
+                max_age_ms,
+            } => write!(
+                f,
+                "FX rate for {}/{} is {}ms old (limit {}ms)",
+                base, quote, age_ms, max_age_ms
+            ),
+            FxError::FutureRate {
+                base,
+                quote,
+                ahead_ms,
+            } => write!(
+                f,
+                "FX rate for {}/{} is timestamped {}ms in the future",
+                base, quote, ahead_ms
+            ),
+            FxError::InvalidSpread => f.write_str("conversion spread must be in [0, 1)"),
+            FxError::Money(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for FxError {}
+
+impl From<MoneyError> for FxError {
+    fn from(err: MoneyError) -> Self {
+        FxError::Money(err)
+    }
+}
+
+fn apply_rate(money: &Money, rate: Decimal, to: Currency) -> Result<Money, FxError> {
+    let amount = money
+        .amount()
+        .checked_mul(rate)
+        .ok_or(FxError::Money(MoneyError::Overflow))?;
+    Ok(Money::new(amount, to))
+}
+
+/// Dealer quotes keyed by currency pair, with triangulation and freshness checks.
+///
+/// Lookups try the direct pair, then the inverse pair, then each pivot currency in
+/// turn (USD, then KES by default). Every leg used must be younger than `max_age_ms`
+/// when one is set, and no more than `MAX_CLOCK_SKEW_MS` in the future; a stale route
+/// is skipped in favour of the next fresh one.
+#[wasm_bindgen]
+#[derive(Clone, Debug)]
+pub struct FxTable {
+    quotes: HashMap<(Currency, Currency), FxQuote>,
+    pivots: Vec<Currency>,
+    spread: Decimal,
+    max_age_ms: Option<i64>,
+}
+
+impl Default for FxTable {
+    fn default() -> Self {
+        FxTable {
+            quotes: HashMap::new(),
+            pivots: vec![Currency::Usd, Currency::Kes],
+            spread: Decimal::ZERO,
+            max_age_ms: None,
+        }
+    }
+}
+
+impl FxTable {
+    pub fn new() -> FxTable {
+        FxTable::default()
+    }
+
+    pub fn from_quotes(quotes: &[FxQuote]) -> Result<FxTable, FxError> {
+        let mut table = FxTable::new();
+        for q in quotes {
+            table.insert(*q)?;
+        }
+        Ok(table)
+    }
+
+    /// Adds or replaces the quote for its pair.
+    pub fn insert(&mut self, quote: FxQuote) -> Result<(), FxError> {
+        if quote.bid <= Decimal::ZERO || quote.ask < quote.bid || quote.base == quote.quote {
+            return Err(FxError::InvalidQuote {
+                base: quote.base,
+                quote: quote.quote,
+            });
+        }
+        self.quotes.insert((quote.base, quote.quote), quote);
+        Ok(())
+    }
+
+    pub fn set_pivots(&mut self, pivots: Vec<Currency>) {
+        self.pivots = pivots;
+    }
+
+    /// Sets the house margin applied by [`FxTable::convert_for_client`], as a fraction (0.005 = 50bp).
+    pub fn set_spread(&mut self, spread: Decimal) -> Result<(), FxError> {
+        if spread < Decimal::ZERO || spread >= Decimal::ONE {
+            return Err(FxError::InvalidSpread);
+        }
+        self.spread = spread;
+        Ok(())
+    }
+
+    pub fn spread(&self) -> Decimal {
+        self.spread
+    }
+
+    pub fn set_max_age_ms(&mut self, max_age_ms: Option<i64>) {
+        self.max_age_ms = max_age_ms;
+    }
+
+    fn check_fresh(&self, quote: &FxQuote, now: i64) -> Result<(), FxError> {
+        let Some(max_age_ms) = self.max_age_ms else {
+            return Ok(());
+        };
+        // Ages too large for an i64 are as stale as it gets; the sign of the gap
+        // tells which way it overflowed.
+        let age_ms = now
+            .checked_sub(quote.timestamp)
+            .unwrap_or(if now > quote.timestamp {
+                i64::MAX
+            } else {
+                i64::MIN
+            });
+        if age_ms < -MAX_CLOCK_SKEW_MS {
+            return Err(FxError::FutureRate {
+                base: quote.base,
+                quote: quote.quote,
+                ahead_ms: age_ms.checked_neg().unwrap_or(i64::MAX),
+            });
+        }
+        if age_ms > max_age_ms {
+            return Err(FxError::StaleRate {
+                base: quote.base,
+                quote: quote.quote,
+                age_ms,
+                max_age_ms,
+            });
+        }
+        Ok(())
+    }
+
+    fn side_rate(quote: &FxQuote, side: RateSide) -> Decimal {
+        match side {
+            RateSide::Bid => quote.bid,
+            RateSide::Mid => quote.mid(),
+            RateSide::Ask => quote.ask,
+        }
+    }
+
+    /// Rate for a single hop, without triangulating.
+    fn leg(
+        &self,
+        from: Currency,
+        to: Currency,
+        side: RateSide,
+        now: i64,
+    ) -> Option<Result<Decimal, FxError>> {
+        let direct = self
+            .quotes
+            .get(&(from, to))
+            .map(|q| self.check_fresh(q, now).map(|_| Self::side_rate(q, side)));
+        if let Some(Ok(rate)) = direct {
+            return Some(Ok(rate));
+        }
+        // Selling the quote currency for the base deals on the other side of the book.
+        let inverse = self.quotes.get(&(to, from)).map(|q| {
+            self.check_fresh(q, now)
+                .map(|_| Decimal::ONE / Self::side_rate(q, side.opposite()))
+        });
+        match (direct, inverse) {
+            (_, Some(Ok(rate))) => Some(Ok(rate)),
+            (Some(err), _) => Some(err),
+            (None, inverse) => inverse,
+        }
+    }
+
+    /// Units of `to` received per unit of `from`.
+    pub fn rate(
+        &self,
+        from: Currency,
+        to: Currency,
+        side: RateSide,
+        now: i64,
+    ) -> Result<Decimal, FxError> {
+        if from == to {
+            return Ok(Decimal::ONE);
+        }
+        // A stale leg only fails the lookup once every other route has been tried.
+        let mut stale = None;
+        match self.leg(from, to, side, now) {
+            Some(Ok(rate)) => return Ok(rate),
+            Some(Err(err)) => stale = Some(err),
+            None => {}
+        }
+        for pivot in &self.pivots {
+            if *pivot == from || *pivot == to {
+                continue;
+            }
+            if let (Some(first), Some(second)) = (
+                self.leg(from, *pivot, side, now),
+                self.leg(*pivot, to, side, now),
+            ) {
+                match (first, second) {
+                    (Ok(first), Ok(second)) => {
+                        return first
+                            .checked_mul(second)
+                            .ok_or(FxError::Money(MoneyError::Overflow));
+                    }
+                    (Err(err), _) | (_, Err(err)) => {
+                        stale.get_or_insert(err);
+                    }
+                }
+            }
+        }
+        Err(stale.unwrap_or(FxError::MissingRate { from, to }))
+    }
+
+    pub fn convert(
+        &self,
+        money: &Money,
+        to: Currency,
+        side: RateSide,
+        now: i64,
+    ) -> Result<Money, FxError> {
+        let rate = self.rate(money.currency(), to, side, now)?;
+        apply_rate(money, rate, to)
+    }
+
+    /// Converts at the mid rate; the path used for valuation and reporting.
+    pub fn convert_mid(&self, money: &Money, to: Currency, now: i64) -> Result<Money, FxError> {
+        self.convert(money, to, RateSide::Mid, now)
+    }
+
+    /// Converts at the dealer's bid less the house spread: what a client actually receives.
+    pub fn convert_for_client(
+        &self,
+        money: &Money,
+        to: Currency,
+        now: i64,
+    ) -> Result<Money, FxError> {
+        if money.currency() == to {
+            return Ok(*money);
+        }
+        let rate = self.rate(money.currency(), to, RateSide::Bid, now)?;
+        apply_rate(money, rate * (Decimal::ONE - self.spread), to)
+    }
+}
+
+#[wasm_bindgen]
+impl FxTable {
+    #[wasm_bindgen(constructor)]
+    pub fn js_new() -> FxTable {
+        FxTable::new()
+    }
+
+    /// Adds a `{ base, quote, bid, ask, timestamp }` quote.
+    #[wasm_bindgen(js_name = addQuote)]
+    pub fn js_add_quote(&mut self, quote: JsValue) -> Result<(), JsError> {
+        let quote: FxQuote = from_js(quote)?;
+        self.insert(quote).map_err(js_err)
+    }
+
+    #[wasm_bindgen(js_name = setSpread)]
+    pub fn js_set_spread(&mut self, spread: &str) -> Result<(), JsError> {
+        let spread = parse_decimal(spread).map_err(js_err)?;
+        self.set_spread(spread).map_err(js_err)
+    }
+
+    /// Rejects any rate older than `maxAgeMs`; pass `undefined` to disable the check.
+    #[wasm_bindgen(js_name = setMaxAge)]
+    pub fn js_set_max_age(&mut self, max_age_ms: Option<i64>) {
+        self.set_max_age_ms(max_age_ms);
+    }
+
+    /// Mid rate as a decimal string.
+    #[wasm_bindgen(js_name = rate)]
+    pub fn js_rate(&self, from: &str, to: &str, now: i64) -> Result<String, JsError> {
+        let from = Currency::from_str(from).map_err(js_err)?;
+        let to = Currency::from_str(to).map_err(js_err)?;
+        self.rate(from, to, RateSide::Mid, now)
+            .map(|r| r.normalize().to_string())
+            .map_err(js_err)
+    }
+
+    #[wasm_bindgen(js_name = convert)]
+    pub fn js_convert(&self, money: &Money, to: &str, now: i64) -> Result<Money, JsError> {
+        let to = Currency::from_str(to).map_err(js_err)?;
+        self.convert_mid(money, to, now).map_err(js_err)
+    }
+
+    #[wasm_bindgen(js_name = convertForClient)]
+    pub fn js_convert_for_client(
+        &self,
+        money: &Money,
+        to: &str,
+        now: i64,
+    ) -> Result<Money, JsError> {
+        let to = Currency::from_str(to).map_err(js_err)?;
+        self.convert_for_client(money, to, now).map_err(js_err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dec(s: &str) -> Decimal {
+        Decimal::from_str(s).unwrap()
+    }
+
+    fn quote(base: Currency, quote: Currency, bid: &str, ask: &str, timestamp: i64) -> FxQuote {
+        FxQuote {
+            base,
+            quote,
+            bid: dec(bid),
+            ask: dec(ask),
+            timestamp,
+        }
+    }
+
+    fn table() -> FxTable {
+        FxTable::from_quotes(&[
+            quote(Currency::Usd, Currency::Kes, "129.00", "130.00", 1_000),
+            quote(Currency::Gbp, Currency::Usd, "1.25", "1.27", 1_000),
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn direct_quotes_deal_on_the_requested_side() {
+        let fx = table();
+        let now = 1_000;
+        assert_eq!(
+            fx.rate(Currency::Usd, Currency::Kes, RateSide::Bid, now),
+            Ok(dec("129.00"))
+        );
+        assert_eq!(
+            fx.rate(Currency::Usd, Currency::Kes, RateSide::Ask, now),
+            Ok(dec("130.00"))
+        );
+        assert_eq!(
+            fx.rate(Currency::Usd, Currency::Kes, RateSide::Mid, now),
+            Ok(dec("129.50"))
+        );
+        let usd = Money::parse("100", Currency::Usd).unwrap();
+        assert_eq!(
+            fx.convert_mid(&usd, Currency::Kes, now).unwrap(),
+            Money::parse("12950", Currency::Kes).unwrap()
+        );
+    }
+
+    #[test]
+    fn inverse_quotes_use_the_other_side_of_the_book() {
+        let fx = table();
+        // Selling KES for USD is buying USD at the dealer's ask.
+        let rate = fx
+            .rate(Currency::Kes, Currency::Usd, RateSide::Bid, 1_000)
+            .unwrap();
+        assert_eq!(rate, Decimal::ONE / dec("130.00"));
+        let kes = Money::parse("13000", Currency::Kes).unwrap();
+        assert_eq!(
+            fx.convert(&kes, Currency::Usd, RateSide::Bid, 1_000)
+                .unwrap(),
+            Money::parse("100", Currency::Usd).unwrap()
+        );
+    }
+
+    #[test]
+    fn triangulates_through_usd() {
+        let fx = table();
+        let rate = fx
+            .rate(Currency::Gbp, Currency::Kes, RateSide::Mid, 1_000)
+            .unwrap();
+        assert_eq!(rate, dec("1.26") * dec("129.50"));
+        let rate = fx
+            .rate(Currency::Kes, Currency::Gbp, RateSide::Bid, 1_000)
+            .unwrap();
+        assert_eq!(
+            rate,
+            (Decimal::ONE / dec("130.00")) * (Decimal::ONE / dec("1.27"))
+        );
+        assert_eq!(
+            fx.rate(Currency::Ugx, Currency::Kes, RateSide::Mid, 1_000),
+            Err(FxError::MissingRate {
+                from: Currency::Ugx,
+                to: Currency::Kes,
+            })
+        );
+    }
+
+    #[test]
+    fn stale_rates_are_rejected() {
+        let mut fx = table();
+        fx.set_max_age_ms(Some(60_000));
+        assert!(fx
+            .rate(Currency::Usd, Currency::Kes, RateSide::Mid, 61_000)
+            .is_ok());
+        assert_eq!(
+            fx.rate(Currency::Usd, Currency::Kes, RateSide::Mid, 61_001),
+            Err(FxError::StaleRate {
+                base: Currency::Usd,
+                quote: Currency::Kes,
+                age_ms: 60_001,
+                max_age_ms: 60_000,
+            })
+        );
+    }
+
+    #[test]
+    fn a_stale_direct_quote_falls_through_to_a_fresh_inverse() {
+        let mut fx = FxTable::from_quotes(&[
+            quote(Currency::Usd, Currency::Kes, "129.00", "130.00", 0),
+            quote(Currency::Kes, Currency::Usd, "0.0077", "0.0078", 100_000),
+        ])
+        .unwrap();
+        fx.set_max_age_ms(Some(60_000));
+        assert_eq!(
+            fx.rate(Currency::Usd, Currency::Kes, RateSide::Bid, 100_000),
+            Ok(Decimal::ONE / dec("0.0078"))
+        );
+        // With both sides stale the direct quote's age is reported.
+        assert_eq!(
+            fx.rate(Currency::Usd, Currency::Kes, RateSide::Bid, 200_000),
+            Err(FxError::StaleRate {
+                base: Currency::Usd,
+                quote: Currency::Kes,
+                age_ms: 200_000,
+                max_age_ms: 60_000,
+            })
+        );
+    }
+
+    #[test]
+    fn future_dated_quotes_are_rejected_beyond_the_clock_skew() {
+        let mut fx = table();
+        fx.set_max_age_ms(Some(60_000));
+        let now = 1_000 - MAX_CLOCK_SKEW_MS;
+        assert!(fx
+            .rate(Currency::Usd, Currency::Kes, RateSide::Mid, now)
+            .is_ok());
+        assert_eq!(
+            fx.rate(Currency::Usd, Currency::Kes, RateSide::Mid, now - 1),
+            Err(FxError::FutureRate {
+                base: Currency::Usd,
+                quote: Currency::Kes,
+                ahead_ms: MAX_CLOCK_SKEW_MS + 1,
+            })
+        );
+        // Gaps too wide for an i64 count as stale or future rather than panicking.
+        assert!(matches!(
+            fx.rate(Currency::Usd, Currency::Kes, RateSide::Mid, i64::MIN),
+            Err(FxError::FutureRate { .. })
+        ));
+        let mut far = FxTable::from_quotes(&[quote(
+            Currency::Usd,
+            Currency::Kes,
+            "129.00",
+            "130.00",
+            i64::MIN,
+        )])
+        .unwrap();
+        far.set_max_age_ms(Some(60_000));
+        assert!(matches!(
+            far.rate(Currency::Usd, Currency::Kes, RateSide::Mid, i64::MAX),
+            Err(FxError::StaleRate { .. })
+        ));
+    }
+
+    #[test]
+    fn a_stale_pivot_falls_through_to_the_next_one() {
+        let mut fx = FxTable::from_quotes(&[
+            quote(Currency::Eur, Currency::Usd, "1.08", "1.08", 0),
+            quote(Currency::Usd, Currency::Ugx, "3700", "3700", 0),
+            quote(Currency::Eur, Currency::Kes, "140", "140", 100_000),
+            quote(Currency::Kes, Currency::Ugx, "28.5", "28.5", 100_000),
+        ])
+        .unwrap();
+        fx.set_max_age_ms(Some(60_000));
+        let rate = fx
+            .rate(Currency::Eur, Currency::Ugx, RateSide::Mid, 100_000)
+            .unwrap();
+        assert_eq!(rate, dec("140") * dec("28.5"));
+        // With no fresh route the stale leg is what gets reported.
+        fx.set_pivots(vec![Currency::Usd]);
+        assert!(matches!(
+            fx.rate(Currency::Eur, Currency::Ugx, RateSide::Mid, 100_000),
+            Err(FxError::StaleRate { .. })
+        ));
+    }
+
+    #[test]
+    fn client_conversions_pay_the_bid_less_the_spread() {
+        let mut fx = table();
+        fx.set_spread(dec("0.005")).unwrap();
+        let usd = Money::parse("100", Currency::Usd).unwrap();
+        // 100 x 129.00 x 0.995
+        assert_eq!(
+            fx.convert_for_client(&usd, Currency::Kes, 1_000).unwrap(),
+            Money::parse("12835.50", Currency::Kes).unwrap()
+        );
+        assert_eq!(
+            fx.convert_for_client(&usd, Currency::Usd, 1_000).unwrap(),
+            usd
+        );
+        assert_eq!(fx.set_spread(dec("1")), Err(FxError::InvalidSpread));
+        assert_eq!(fx.set_spread(dec("-0.01")), Err(FxError::InvalidSpread));
+    }
+
+    #[test]
+    fn rejects_crossed_or_empty_quotes() {
+        let mut fx = FxTable::new();
+        let bad = quote(Currency::Usd, Currency::Kes, "130", "129", 0);
+        assert!(matches!(fx.insert(bad), Err(FxError::InvalidQuote { .. })));
+        let zero = quote(Currency::Usd, Currency::Kes, "0", "1", 0);
+        assert!(matches!(fx.insert(zero), Err(FxError::InvalidQuote { .. })));
+    }
+}
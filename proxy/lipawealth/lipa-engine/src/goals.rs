@@ -0,0 +1,666 @@
+//! Goal-based planning: how much to save each month for retirement, school fees or a
+//! home deposit, and how likely each goal is to be met on the current savings rate.
+
+use std::fmt;
+
+use chrono::{Datelike, NaiveDate};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::js::{from_js, js_err, to_js};
+use crate::money::{Money, MoneyError};
+use crate::montecarlo::{as_f64, log_params, percentile, to_money, MAX_PATHS};
+use crate::rng::{self, Rng};
+
+/// Every goal draws one return per path per month, so capping paths × months
+/// summed over the goals bounds the work a single plan can ask for.
+pub const MAX_PATH_MONTHS: u64 = 12_000_000;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum GoalError {
+    NoGoals,
+    GoalInPast {
+        goal: String,
+        target_date: NaiveDate,
+    },
+    InvalidGlidePath {
+        goal: String,
+    },
+    InvalidAssumption(&'static str),
+    InvalidPaths(u32),
+    TooLarge {
+        paths: u32,
+        months: u64,
+    },
+    Money(MoneyError),
+}
+
+impl fmt::Display for GoalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GoalError::NoGoals => f.write_str("no goals to plan"),
+            GoalError::GoalInPast { goal, target_date } => write!(
+                f,
+                "goal {} targets {}, which is not at least a month away",
+                goal, target_date
+            ),
+            GoalError::InvalidGlidePath { goal } => write!(
+                f,
+                "glide path for {} needs at least one point with a growth share between 0 and 1",
+                goal
+            ),
+            GoalError::InvalidAssumption(what) => write!(f, "invalid assumption: {}", what),
+            GoalError::InvalidPaths(n) => {
+                write!(
+                    f,
+                    "path count must be between 1 and {}, got {}",
+                    MAX_PATHS, n
+                )
+            }
+            GoalError::TooLarge { paths, months } => write!(
+                f,
+                "{} paths over {} goal-months is too large (limit {} path-months)",
+                paths, months, MAX_PATH_MONTHS
+            ),
+            GoalError::Money(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for GoalError {}
+
+impl From<MoneyError> for GoalError {
+    fn from(err: MoneyError) -> Self {
+        GoalError::Money(err)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum GoalKind {
+    Retirement,
+    Education,
+    HomeDeposit,
+    Other,
+}
+
+/// Annual arithmetic mean and volatility of one sleeve of the portfolio.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Sleeve {
+    pub expected_return: f64,
+    pub volatility: f64,
+}
+
+/// Goals invest in a mix of a growth sleeve (equities) and a defensive sleeve
+/// (bonds, money market) whose proportions follow each goal's glide path.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CapitalMarketAssumptions {
+    pub growth: Sleeve,
+    pub defensive: Sleeve,
+    #[serde(default)]
+    pub correlation: f64,
+    /// Expected annual inflation, used to turn today's-money targets into nominal ones.
+    pub inflation: f64,
+}
+
+/// Share of the goal's money in the growth sleeve at a given distance from the goal.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GlidePoint {
+    pub years_to_goal: f64,
+    pub growth_share: f64,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Goal {
+    pub id: String,
+    pub name: String,
+    pub kind: GoalKind,
+    /// In today's money.
+    pub target: Money,
+    pub target_date: NaiveDate,
+    /// 1 is funded first.
+    pub priority: u32,
+    #[serde(default)]
+    pub current_savings: Option<Money>,
+    /// Interpolated linearly between points; flat beyond the ends. A single point
+    /// is a static allocation.
+    pub glide_path: Vec<GlidePoint>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlanInput {
+    pub as_of: NaiveDate,
+    /// What the client can put away each month across all goals.
+    pub monthly_savings: Money,
+    pub assumptions: CapitalMarketAssumptions,
+    pub goals: Vec<Goal>,
+    #[serde(default = "default_paths")]
+    pub paths: u32,
+    #[serde(default)]
+    pub seed: Option<u64>,
+}
+
+fn default_paths() -> u32 {
+    2_000
+}
+
+/// An amount at the goal date, and the same amount in today's money.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Amounts {
+    pub nominal: Money,
+    pub real: Money,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GoalPlan {
+    pub id: String,
+    pub name: String,
+    pub kind: GoalKind,
+    pub priority: u32,
+    pub months: u32,
+    pub target: Amounts,
+    /// Level monthly saving that reaches the target if returns meet expectations.
+    pub required_monthly: Money,
+    /// What the goal actually receives from the savings budget, by priority.
+    pub allocated_monthly: Money,
+    pub probability_of_success: f64,
+    pub median_outcome: Amounts,
+    /// A poor-markets outcome: only one path in ten ends lower.
+    pub p10_outcome: Amounts,
+    pub median_shortfall: Amounts,
+    /// Mean shortfall across all paths, counting met goals as zero.
+    pub expected_shortfall: Amounts,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlanResult {
+    pub seed: u64,
+    /// In priority order.
+    pub goals: Vec<GoalPlan>,
+    pub total_required_monthly: Money,
+    /// Savings left after every goal has its required contribution.
+    pub unallocated_monthly: Money,
+}
+
+/// Whole months from `from` to `to`; a partial month does not count.
+fn months_between(from: NaiveDate, to: NaiveDate) -> i64 {
+    let months = (to.year() - from.year()) as i64 * 12 + to.month() as i64 - from.month() as i64;
+    if to.day() < from.day() {
+        months - 1
+    } else {
+        months
+    }
+}
+
+fn growth_share(path: &[GlidePoint], years_to_goal: f64) -> f64 {
+    // `path` is sorted by `years_to_goal`, nearest the goal first.
+    let first = path[0];
+    let last = path[path.len() - 1];
+    if years_to_goal <= first.years_to_goal {
+        return first.growth_share;
+    }
+    if years_to_goal >= last.years_to_goal {
+        return last.growth_share;
+    }
+    let pair = path
+        .windows(2)
+        .find(|w| years_to_goal <= w[1].years_to_goal)
+        .unwrap();
+    let (a, b) = (pair[0], pair[1]);
+    let t = (years_to_goal - a.years_to_goal) / (b.years_to_goal - a.years_to_goal);
+    a.growth_share + (b.growth_share - a.growth_share) * t
+}
+
+/// Annual mean and volatility of a growth/defensive mix.
+fn mix(a: &CapitalMarketAssumptions, share: f64) -> (f64, f64) {
+    let (g, d) = (a.growth, a.defensive);
+    let mean = share * g.expected_return + (1.0 - share) * d.expected_return;
+    let variance = (share * g.volatility).powi(2)
+        + ((1.0 - share) * d.volatility).powi(2)
+        + 2.0 * share * (1.0 - share) * a.correlation * g.volatility * d.volatility;
+    (mean, variance.max(0.0).sqrt())
+}
+
+/// Per-month parameters for one goal: expected growth factor and lognormal (mu, sigma).
+struct MonthlyModel {
+    expected_growth: Vec<f64>,
+    log_params: Vec<(f64, f64)>,
+}
+
+fn monthly_model(a: &CapitalMarketAssumptions, glide: &[GlidePoint], months: u32) -> MonthlyModel {
+    let mut expected_growth = Vec::with_capacity(months as usize);
+    let mut params = Vec::with_capacity(months as usize);
+    for m in 0..months {
+        let years_to_goal = (months - m) as f64 / 12.0;
+        let (mean, vol) = mix(a, growth_share(glide, years_to_goal));
+        let (mu, sigma) = log_params(mean, vol);
+        expected_growth.push((1.0 + mean).powf(1.0 / 12.0));
+        params.push((mu / 12.0, sigma / 12f64.sqrt()));
+    }
+    MonthlyModel {
+        expected_growth,
+        log_params: params,
+    }
+}
+
+/// Final value of `start` plus `contribution` paid at the start of each month.
+fn future_value(growth: &[f64], start: f64, contribution: f64) -> f64 {
+    growth.iter().fold(start, |w, g| (w + contribution) * g)
+}
+
+fn validate(input: &PlanInput) -> Result<(), GoalError> {
+    if input.goals.is_empty() {
+        return Err(GoalError::NoGoals);
+    }
+    if input.paths == 0 || input.paths > MAX_PATHS {
+        return Err(GoalError::InvalidPaths(input.paths));
+    }
+    let a = &input.assumptions;
+    for s in [a.growth, a.defensive] {
+        let sane = s.expected_return > -1.0
+            && s.expected_return.is_finite()
+            && s.volatility >= 0.0
+            && s.volatility.is_finite();
+        if !sane {
+            return Err(GoalError::InvalidAssumption("sleeve return or volatility"));
+        }
+    }
+    if !a.correlation.is_finite() || a.correlation.abs() > 1.0 {
+        return Err(GoalError::InvalidAssumption(
+            "correlation must be within [-1, 1]",
+        ));
+    }
+    if a.inflation <= -1.0 || !a.inflation.is_finite() {
+        return Err(GoalError::InvalidAssumption("inflation"));
+    }
+    let currency = input.monthly_savings.currency();
+    let mut months = 0u64;
+    for g in &input.goals {
+        g.target.checked_add(&Money::zero(currency))?;
+        if let Some(s) = &g.current_savings {
+            s.checked_add(&Money::zero(currency))?;
+        }
+        let goal_months = months_between(input.as_of, g.target_date);
+        if goal_months < 1 {
+            return Err(GoalError::GoalInPast {
+                goal: g.id.clone(),
+                target_date: g.target_date,
+            });
+        }
+        months = months.saturating_add(goal_months as u64);
+        let valid = !g.glide_path.is_empty()
+            && g.glide_path
+                .iter()
+                .all(|p| (0.0..=1.0).contains(&p.growth_share) && p.years_to_goal >= 0.0);
+        if !valid {
+            return Err(GoalError::InvalidGlidePath { goal: g.id.clone() });
+        }
+    }
+    if u64::from(input.paths).saturating_mul(months) > MAX_PATH_MONTHS {
+        return Err(GoalError::TooLarge {
+            paths: input.paths,
+            months,
+        });
+    }
+    Ok(())
+}
+
+/// Plans every goal: required contributions from expected returns, then a
+/// Monte Carlo run on what the savings budget actually allows, funding goals
+/// in priority order (earlier target date first on ties).
+pub fn plan_goals(input: &PlanInput) -> Result<PlanResult, GoalError> {
+    validate(input)?;
+    let currency = input.monthly_savings.currency();
+    let a = &input.assumptions;
+    let seed = input.seed.unwrap_or_else(rng::random_seed);
+    let mut rng = Rng::seed_from_u64(seed);
+
+    let mut order: Vec<&Goal> = input.goals.iter().collect();
+    order.sort_by(|x, y| {
+        x.priority
+            .cmp(&y.priority)
+            .then(x.target_date.cmp(&y.target_date))
+            .then(x.id.cmp(&y.id))
+    });
+
+    let mut budget = as_f64(&input.monthly_savings).max(0.0);
+    let mut total_required = 0.0;
+    let mut plans = Vec::with_capacity(order.len());
+    let mut outcomes = vec![0.0; input.paths as usize];
+    for goal in order {
+        let months = months_between(input.as_of, goal.target_date) as u32;
+        let mut glide = goal.glide_path.clone();
+        glide.sort_by(|p, q| p.years_to_goal.total_cmp(&q.years_to_goal));
+        let model = monthly_model(a, &glide, months);
+
+        let deflator = (1.0 + a.inflation).powf(months as f64 / 12.0);
+        let target_real = as_f64(&goal.target);
+        let target_nominal = target_real * deflator;
+        let start = goal.current_savings.as_ref().map_or(0.0, as_f64);
+
+        // Future value is linear in the contribution: FV = base + c · per_unit.
+        let base = future_value(&model.expected_growth, start, 0.0);
+        let per_unit = future_value(&model.expected_growth, 0.0, 1.0);
+        let required = ((target_nominal - base) / per_unit).max(0.0);
+        total_required += required;
+        let allocated = required.min(budget);
+        budget -= allocated;
+
+        for outcome in outcomes.iter_mut() {
+            *outcome = model.log_params.iter().fold(start, |w, (mu, sigma)| {
+                (w + allocated) * (mu + sigma * rng.standard_normal()).exp()
+            });
+        }
+        let paths = outcomes.len() as f64;
+        // Tolerance so an outcome that lands on the target by expectation counts as met.
+        let successes = outcomes
+            .iter()
+            .filter(|o| **o >= target_nominal * (1.0 - 1e-9))
+            .count();
+        let expected_shortfall = outcomes
+            .iter()
+            .map(|o| (target_nominal - o).max(0.0))
+            .sum::<f64>()
+            / paths;
+        outcomes.sort_by(|x, y| x.total_cmp(y));
+        let median = percentile(&outcomes, 0.5);
+        let p10 = percentile(&outcomes, 0.1);
+        let amounts = |nominal: f64| -> Result<Amounts, MoneyError> {
+            Ok(Amounts {
+                nominal: to_money(nominal, currency)?,
+                real: to_money(nominal / deflator, currency)?,
+            })
+        };
+
+        plans.push(GoalPlan {
+            id: goal.id.clone(),
+            name: goal.name.clone(),
+            kind: goal.kind,
+            priority: goal.priority,
+            months,
+            target: amounts(target_nominal)?,
+            required_monthly: to_money(required, currency)?,
+            allocated_monthly: to_money(allocated, currency)?,
+            probability_of_success: successes as f64 / paths,
+            median_outcome: amounts(median)?,
+            p10_outcome: amounts(p10)?,
+            median_shortfall: amounts((target_nominal - median).max(0.0))?,
+            expected_shortfall: amounts(expected_shortfall)?,
+        });
+    }
+
+    Ok(PlanResult {
+        seed,
+        goals: plans,
+        total_required_monthly: to_money(total_required, currency)?,
+        unallocated_monthly: to_money(budget, currency)?,
+    })
+}
+
+/// JS entry point: a `PlanInput`-shaped object in, `PlanResult` out.
+#[wasm_bindgen(js_name = planGoals)]
+pub fn plan_goals_js(input: JsValue) -> Result<JsValue, JsError> {
+    let input: PlanInput = from_js(input)?;
+    to_js(&plan_goals(&input).map_err(js_err)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal::Decimal;
+
+    use crate::money::Currency;
+
+    fn d(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn kes(v: i64) -> Money {
+        Money::new(Decimal::from(v), Currency::Kes)
+    }
+
+    fn close(a: f64, b: f64, tol: f64) {
+        assert!((a - b).abs() < tol, "{} != {}", a, b);
+    }
+
+    fn sleeve(expected_return: f64, volatility: f64) -> Sleeve {
+        Sleeve {
+            expected_return,
+            volatility,
+        }
+    }
+
+    fn goal(id: &str, priority: u32, target: i64, target_date: &str) -> Goal {
+        Goal {
+            id: id.to_string(),
+            name: id.to_string(),
+            kind: GoalKind::Other,
+            target: kes(target),
+            target_date: d(target_date),
+            priority,
+            current_savings: None,
+            glide_path: vec![GlidePoint {
+                years_to_goal: 0.0,
+                growth_share: 0.5,
+            }],
+        }
+    }
+
+    /// No volatility and no inflation: every path follows the expected return.
+    fn certain(goals: Vec<Goal>, monthly_savings: i64) -> PlanInput {
+        PlanInput {
+            as_of: d("2024-01-15"),
+            monthly_savings: kes(monthly_savings),
+            assumptions: CapitalMarketAssumptions {
+                growth: sleeve(0.12, 0.0),
+                defensive: sleeve(0.12, 0.0),
+                correlation: 0.0,
+                inflation: 0.0,
+            },
+            goals,
+            paths: 100,
+            seed: Some(1),
+        }
+    }
+
+    #[test]
+    fn required_saving_is_the_annuity_due_payment() {
+        let plan = plan_goals(&certain(
+            vec![goal("car", 1, 1_200_000, "2025-01-15")],
+            500_000,
+        ))
+        .unwrap();
+        let g = &plan.goals[0];
+        assert_eq!(g.months, 12);
+        let m = 1.12_f64.powf(1.0 / 12.0);
+        let annuity_due = m * (1.12 - 1.0) / (m - 1.0);
+        close(as_f64(&g.required_monthly), 1_200_000.0 / annuity_due, 0.01);
+        assert_eq!(g.allocated_monthly, g.required_monthly);
+        assert_eq!(g.probability_of_success, 1.0);
+        assert_eq!(g.expected_shortfall.nominal, kes(0));
+    }
+
+    #[test]
+    fn targets_are_inflated_to_the_goal_date() {
+        let mut input = certain(vec![goal("school", 1, 100_000, "2027-01-15")], 50_000);
+        input.assumptions.inflation = 0.07;
+        let g = &plan_goals(&input).unwrap().goals[0];
+        close(
+            as_f64(&g.target.nominal),
+            100_000.0 * 1.07_f64.powi(3),
+            0.01,
+        );
+        assert_eq!(g.target.real, kes(100_000));
+    }
+
+    #[test]
+    fn budget_funds_goals_in_priority_order() {
+        let mut school = goal("school", 1, 600_000, "2025-01-15");
+        let home = goal("home", 2, 600_000, "2025-01-15");
+        school.current_savings = Some(kes(100_000));
+        let ample = plan_goals(&certain(vec![home.clone(), school.clone()], 1_000_000))
+            .unwrap()
+            .goals;
+        assert_eq!(ample[0].id, "school");
+        assert!(ample[0].required_monthly < ample[1].required_monthly);
+
+        let budget = as_f64(&ample[0].required_monthly).ceil() as i64 + 10_000;
+        let plan = plan_goals(&certain(vec![home, school], budget)).unwrap();
+        let (school, home) = (&plan.goals[0], &plan.goals[1]);
+        assert_eq!(school.allocated_monthly, school.required_monthly);
+        assert_eq!(school.probability_of_success, 1.0);
+        close(
+            as_f64(&school.allocated_monthly) + as_f64(&home.allocated_monthly),
+            budget as f64,
+            0.01,
+        );
+        assert_eq!(home.probability_of_success, 0.0);
+        assert!(home.median_shortfall.nominal.is_positive());
+        assert_eq!(plan.unallocated_monthly, kes(0));
+        assert_eq!(
+            plan.total_required_monthly,
+            school
+                .required_monthly
+                .checked_add(&home.required_monthly)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn seeded_plans_are_reproducible() {
+        let mut input = certain(vec![goal("retire", 1, 5_000_000, "2044-01-15")], 8_000);
+        input.assumptions.growth = sleeve(0.14, 0.20);
+        input.assumptions.defensive = sleeve(0.10, 0.04);
+        input.assumptions.correlation = 0.1;
+        input.goals[0].glide_path = vec![
+            GlidePoint {
+                years_to_goal: 20.0,
+                growth_share: 0.9,
+            },
+            GlidePoint {
+                years_to_goal: 0.0,
+                growth_share: 0.2,
+            },
+        ];
+        input.paths = 1_000;
+        let a = plan_goals(&input).unwrap();
+        assert_eq!(a, plan_goals(&input).unwrap());
+        assert_eq!(a.seed, 1);
+        let g = &a.goals[0];
+        assert!(g.probability_of_success > 0.0 && g.probability_of_success < 1.0);
+        assert!(g.p10_outcome.nominal < g.median_outcome.nominal);
+        input.seed = Some(2);
+        assert_ne!(a, plan_goals(&input).unwrap());
+    }
+
+    #[test]
+    fn glide_path_interpolates_and_flattens() {
+        let path = [
+            GlidePoint {
+                years_to_goal: 0.0,
+                growth_share: 0.2,
+            },
+            GlidePoint {
+                years_to_goal: 10.0,
+                growth_share: 0.8,
+            },
+        ];
+        close(growth_share(&path, 5.0), 0.5, 1e-12);
+        close(growth_share(&path, 25.0), 0.8, 1e-12);
+        close(growth_share(&path, 0.0), 0.2, 1e-12);
+        assert_eq!(months_between(d("2024-01-31"), d("2024-02-29")), 0);
+        assert_eq!(months_between(d("2024-01-15"), d("2025-01-15")), 12);
+    }
+
+    #[test]
+    fn rejects_invalid_plans() {
+        assert_eq!(plan_goals(&certain(vec![], 1_000)), Err(GoalError::NoGoals));
+        let past = certain(vec![goal("old", 1, 1_000, "2024-02-01")], 1_000);
+        assert!(matches!(
+            plan_goals(&past),
+            Err(GoalError::GoalInPast { .. })
+        ));
+        let mut bad_glide = goal("g", 1, 1_000, "2030-01-01");
+        bad_glide.glide_path[0].growth_share = 1.5;
+        assert!(matches!(
+            plan_goals(&certain(vec![bad_glide], 1_000)),
+            Err(GoalError::InvalidGlidePath { .. })
+        ));
+        let mut too_many = certain(vec![goal("g", 1, 1_000, "2030-01-01")], 1_000);
+        too_many.paths = MAX_PATHS + 1;
+        assert_eq!(
+            plan_goals(&too_many),
+            Err(GoalError::InvalidPaths(MAX_PATHS + 1))
+        );
+
+        let mut usd = goal("g", 1, 1_000, "2030-01-01");
+        usd.target = Money::new(Decimal::from(1_000), Currency::Usd);
+        let err = plan_goals(&certain(vec![usd], 1_000)).unwrap_err();
+        assert!(matches!(
+            err,
+            GoalError::Money(MoneyError::CurrencyMismatch { .. })
+        ));
+        assert_eq!(err.to_string(), "currency mismatch: USD vs KES");
+    }
+
+    #[test]
+    fn rejects_non_finite_assumptions() {
+        let base = certain(vec![goal("g", 1, 1_000, "2030-01-01")], 1_000);
+        for bad in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            let mut input = base.clone();
+            input.assumptions.growth.expected_return = bad;
+            assert!(matches!(
+                plan_goals(&input),
+                Err(GoalError::InvalidAssumption(_))
+            ));
+            let mut input = base.clone();
+            input.assumptions.defensive.volatility = bad;
+            assert!(matches!(
+                plan_goals(&input),
+                Err(GoalError::InvalidAssumption(_))
+            ));
+            let mut input = base.clone();
+            input.assumptions.correlation = bad;
+            assert!(matches!(
+                plan_goals(&input),
+                Err(GoalError::InvalidAssumption(_))
+            ));
+            let mut input = base.clone();
+            input.assumptions.inflation = bad;
+            assert!(matches!(
+                plan_goals(&input),
+                Err(GoalError::InvalidAssumption(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn caps_paths_times_goal_months() {
+        // Two goals of 10 and 20 years: 360 months, so the cap falls at 33,333 paths.
+        let mut input = certain(
+            vec![
+                goal("school", 1, 1_000_000, "2034-01-15"),
+                goal("home", 2, 5_000_000, "2044-01-15"),
+            ],
+            50_000,
+        );
+        input.paths = (MAX_PATH_MONTHS / 360) as u32;
+        assert!(plan_goals(&input).is_ok());
+        input.paths += 1;
+        assert_eq!(
+            plan_goals(&input),
+            Err(GoalError::TooLarge {
+                paths: 33_334,
+                months: 360,
+            })
+        );
+    }
+}
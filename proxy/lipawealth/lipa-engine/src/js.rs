@@ -0,0 +1,22 @@
+//! Glue for passing plain JS objects across the wasm boundary.
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+/// Deserialises a JS object into a Rust input struct.
+pub(crate) fn from_js<T: DeserializeOwned>(value: JsValue) -> Result<T, JsError> {
+    serde_wasm_bindgen::from_value(value).map_err(|e| JsError::new(&e.to_string()))
+}
+
+/// Serialises a result as a plain JS object (maps become objects, not `Map`s).
+pub(crate) fn to_js<T: Serialize>(value: &T) -> Result<JsValue, JsError> {
+    value
+        .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+        .map_err(|e| JsError::new(&e.to_string()))
+}
+
+/// Converts any engine error into a thrown JS `Error`.
+pub(crate) fn js_err<E: std::fmt::Display>(err: E) -> JsError {
+    JsError::new(&err.to_string())
+}
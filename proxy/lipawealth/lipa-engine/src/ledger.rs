@@ -0,0 +1,716 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::js::{from_js, js_err, to_js};
+use crate::money::{Currency, Money, MoneyError};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AccountKind {
+    Asset,
+    Liability,
+    Equity,
+    Income,
+    Expense,
+}
+
+impl AccountKind {
+    fn debit_normal(self) -> bool {
+        matches!(self, AccountKind::Asset | AccountKind::Expense)
+    }
+}
+
+/// Which pot of money an account belongs to. Client money sits in trust and must
+/// balance on its own; it is never available to settle the firm's obligations.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Pool {
+    Client,
+    House,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Account {
+    pub code: String,
+    pub name: String,
+    pub kind: AccountKind,
+    pub pool: Pool,
+    pub currency: Currency,
+    /// Set on client sub-ledger accounts (what the firm owes one client).
+    #[serde(default)]
+    pub client_id: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Side {
+    Debit,
+    Credit,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Posting {
+    pub account: String,
+    pub side: Side,
+    pub amount: Money,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub id: String,
+    pub date: NaiveDate,
+    pub description: String,
+    pub postings: Vec<Posting>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum LedgerError {
+    DuplicateAccount(String),
+    UnknownAccount(String),
+    DuplicateEntry(String),
+    TooFewPostings(String),
+    NonPositiveAmount {
+        entry: String,
+        account: String,
+    },
+    CurrencyMismatch {
+        entry: String,
+        account: String,
+    },
+    ClientAccountWithoutClient(String),
+    HouseAccountWithClient(String),
+    Unbalanced {
+        entry: String,
+        pool: Pool,
+        currency: Currency,
+    },
+    Money(MoneyError),
+    Json(String),
+}
+
+impl fmt::Display for LedgerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LedgerError::DuplicateAccount(code) => write!(f, "account {} already exists", code),
+            LedgerError::UnknownAccount(code) => write!(f, "unknown account {}", code),
+            LedgerError::DuplicateEntry(id) => write!(f, "journal entry {} already posted", id),
+            LedgerError::TooFewPostings(id) => {
+                write!(f, "journal entry {} needs at least two postings", id)
+            }
+            LedgerError::NonPositiveAmount { entry, account } => write!(
+                f,
+                "posting to {} in entry {} must be a positive amount",
+                account, entry
+            ),
+            LedgerError::CurrencyMismatch { entry, account } => write!(
+                f,
+                "posting to {} in entry {} is not in the account's currency",
+                account, entry
+            ),
+            LedgerError::ClientAccountWithoutClient(code) => {
+                write!(f, "client liability account {} must name its client", code)
+            }
+            LedgerError::HouseAccountWithClient(code) => {
+                write!(f, "house account {} cannot belong to a client", code)
+            }
+            LedgerError::Unbalanced {
+                entry,
+                pool,
+                currency,
+            } => write!(
+                f,
+                "journal entry {} does not balance for {:?} money in {}",
+                entry, pool, currency
+            ),
+            LedgerError::Money(err) => err.fmt(f),
+            LedgerError::Json(msg) => write!(f, "invalid ledger JSON: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for LedgerError {}
+
+impl From<MoneyError> for LedgerError {
+    fn from(err: MoneyError) -> Self {
+        LedgerError::Money(err)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrialBalanceLine {
+    pub account: String,
+    pub name: String,
+    pub kind: AccountKind,
+    pub pool: Pool,
+    pub client_id: Option<String>,
+    pub debits: Money,
+    pub credits: Money,
+    /// Signed by the account's normal side: positive means a normal balance.
+    pub balance: Money,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrialBalanceTotal {
+    pub currency: Currency,
+    pub debits: Money,
+    pub credits: Money,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrialBalance {
+    pub lines: Vec<TrialBalanceLine>,
+    pub totals: Vec<TrialBalanceTotal>,
+    pub balanced: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SegregationLine {
+    pub currency: Currency,
+    /// Client money actually held (trust bank and custody cash).
+    pub client_assets: Money,
+    /// Client money owed (sum of every client's sub-ledger).
+    pub client_liabilities: Money,
+    /// Positive when trust holds more than it owes; negative is a shortfall.
+    pub difference: Money,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SegregationReport {
+    pub lines: Vec<SegregationLine>,
+    /// Client sub-ledger accounts in debit: one client's money funding another.
+    pub overdrawn_clients: Vec<String>,
+    pub ok: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClientBalance {
+    pub account: String,
+    pub name: String,
+    pub balance: Money,
+}
+
+/// Wire form of a ledger: accounts, then entries in posting order.
+#[derive(Serialize, Deserialize)]
+struct LedgerRecord {
+    accounts: Vec<Account>,
+    entries: Vec<JournalEntry>,
+}
+
+/// A double-entry book that rejects any entry mixing client and house money.
+#[wasm_bindgen]
+#[derive(Clone, Debug, Default)]
+pub struct Ledger {
+    accounts: BTreeMap<String, Account>,
+    entries: Vec<JournalEntry>,
+    entry_ids: HashSet<String>,
+}
+
+impl Ledger {
+    pub fn new() -> Ledger {
+        Ledger::default()
+    }
+
+    pub fn open_account(&mut self, account: Account) -> Result<(), LedgerError> {
+        if self.accounts.contains_key(&account.code) {
+            return Err(LedgerError::DuplicateAccount(account.code));
+        }
+        match (account.pool, &account.client_id) {
+            (Pool::House, Some(_)) => {
+                return Err(LedgerError::HouseAccountWithClient(account.code));
+            }
+            (Pool::Client, None) if account.kind == AccountKind::Liability => {
+                return Err(LedgerError::ClientAccountWithoutClient(account.code));
+            }
+            _ => {}
+        }
+        self.accounts.insert(account.code.clone(), account);
+        Ok(())
+    }
+
+    pub fn account(&self, code: &str) -> Option<&Account> {
+        self.accounts.get(code)
+    }
+
+    pub fn entries(&self) -> &[JournalEntry] {
+        &self.entries
+    }
+
+    /// Validates and appends an entry. Debits must equal credits separately for
+    /// each (pool, currency), so a client shilling can never offset a house one.
+    pub fn post(&mut self, entry: JournalEntry) -> Result<(), LedgerError> {
+        if self.entry_ids.contains(&entry.id) {
+            return Err(LedgerError::DuplicateEntry(entry.id));
+        }
+        if entry.postings.len() < 2 {
+            return Err(LedgerError::TooFewPostings(entry.id));
+        }
+
+        let mut nets: BTreeMap<(Pool, Currency), Money> = BTreeMap::new();
+        for p in &entry.postings {
+            let account = self
+                .accounts
+                .get(&p.account)
+                .ok_or_else(|| LedgerError::UnknownAccount(p.account.clone()))?;
+            if !p.amount.is_positive() {
+                return Err(LedgerError::NonPositiveAmount {
+                    entry: entry.id.clone(),
+                    account: p.account.clone(),
+                });
+            }
+            if p.amount.currency() != account.currency {
+                return Err(LedgerError::CurrencyMismatch {
+                    entry: entry.id.clone(),
+                    account: p.account.clone(),
+                });
+            }
+            let net = nets
+                .entry((account.pool, account.currency))
+                .or_insert(Money::zero(account.currency));
+            *net = match p.side {
+                Side::Debit => net.checked_add(&p.amount)?,
+                Side::Credit => net.checked_sub(&p.amount)?,
+            };
+        }
+        if let Some(((pool, currency), _)) = nets.iter().find(|(_, net)| !net.is_zero()) {
+            return Err(LedgerError::Unbalanced {
+                entry: entry.id,
+                pool: *pool,
+                currency: *currency,
+            });
+        }
+
+        self.entry_ids.insert(entry.id.clone());
+        self.entries.push(entry);
+        Ok(())
+    }
+
+    fn totals(&self) -> Result<BTreeMap<&str, (Money, Money)>, LedgerError> {
+        let mut totals: BTreeMap<&str, (Money, Money)> = self
+            .accounts
+            .values()
+            .map(|a| {
+                (
+                    a.code.as_str(),
+                    (Money::zero(a.currency), Money::zero(a.currency)),
+                )
+            })
+            .collect();
+        for p in self.entries.iter().flat_map(|e| &e.postings) {
+            let t = totals
+                .get_mut(p.account.as_str())
+                .ok_or_else(|| LedgerError::UnknownAccount(p.account.clone()))?;
+            match p.side {
+                Side::Debit => t.0 = t.0.checked_add(&p.amount)?,
+                Side::Credit => t.1 = t.1.checked_add(&p.amount)?,
+            }
+        }
+        Ok(totals)
+    }
+
+    /// Normal-side balance of one account.
+    pub fn balance(&self, code: &str) -> Result<Money, LedgerError> {
+        let account = self
+            .accounts
+            .get(code)
+            .ok_or_else(|| LedgerError::UnknownAccount(code.to_string()))?;
+        let totals = self.totals()?;
+        let (debits, credits) = totals[code];
+        normal_balance(account, &debits, &credits)
+    }
+
+    pub fn trial_balance(&self) -> Result<TrialBalance, LedgerError> {
+        let totals = self.totals()?;
+        let mut lines = Vec::with_capacity(self.accounts.len());
+        let mut by_currency: BTreeMap<Currency, (Money, Money)> = BTreeMap::new();
+        for account in self.accounts.values() {
+            let (debits, credits) = totals[account.code.as_str()];
+            let sum = by_currency
+                .entry(account.currency)
+                .or_insert((Money::zero(account.currency), Money::zero(account.currency)));
+            sum.0 = sum.0.checked_add(&debits)?;
+            sum.1 = sum.1.checked_add(&credits)?;
+            lines.push(TrialBalanceLine {
+                account: account.code.clone(),
+                name: account.name.clone(),
+                kind: account.kind,
+                pool: account.pool,
+                client_id: account.client_id.clone(),
+                debits,
+                credits,
+                balance: normal_balance(account, &debits, &credits)?,
+            });
+        }
+        let totals: Vec<TrialBalanceTotal> = by_currency
+            .into_iter()
+            .map(|(currency, (debits, credits))| TrialBalanceTotal {
+                currency,
+                debits,
+                credits,
+            })
+            .collect();
+        let balanced = totals.iter().all(|t| t.debits == t.credits);
+        Ok(TrialBalance {
+            lines,
+            totals,
+            balanced,
+        })
+    }
+
+    /// Every sub-ledger account held for `client_id`, with its balance.
+    pub fn client_balances(&self, client_id: &str) -> Result<Vec<ClientBalance>, LedgerError> {
+        let totals = self.totals()?;
+        self.accounts
+            .values()
+            .filter(|a| a.client_id.as_deref() == Some(client_id))
+            .map(|a| {
+                let (debits, credits) = totals[a.code.as_str()];
+                Ok(ClientBalance {
+                    account: a.code.clone(),
+                    name: a.name.clone(),
+                    balance: normal_balance(a, &debits, &credits)?,
+                })
+            })
+            .collect()
+    }
+
+    /// Reconciles client money held against client money owed, per currency.
+    pub fn segregation_check(&self) -> Result<SegregationReport, LedgerError> {
+        let totals = self.totals()?;
+        let mut sums: BTreeMap<Currency, (Money, Money)> = BTreeMap::new();
+        let mut overdrawn_clients = Vec::new();
+        for account in self.accounts.values().filter(|a| a.pool == Pool::Client) {
+            let (debits, credits) = totals[account.code.as_str()];
+            let balance = normal_balance(account, &debits, &credits)?;
+            let sum = sums
+                .entry(account.currency)
+                .or_insert((Money::zero(account.currency), Money::zero(account.currency)));
+            if account.kind.debit_normal() {
+                sum.0 = sum.0.checked_add(&balance)?;
+            } else {
+                sum.1 = sum.1.checked_add(&balance)?;
+                if account.client_id.is_some() && balance.is_negative() {
+                    overdrawn_clients.push(account.code.clone());
+                }
+            }
+        }
+        let lines = sums
+            .into_iter()
+            .map(|(currency, (client_assets, client_liabilities))| {
+                Ok(SegregationLine {
+                    currency,
+                    client_assets,
+                    client_liabilities,
+                    difference: client_assets.checked_sub(&client_liabilities)?,
+                })
+            })
+            .collect::<Result<Vec<_>, LedgerError>>()?;
+        let ok = overdrawn_clients.is_empty() && lines.iter().all(|l| !l.difference.is_negative());
+        Ok(SegregationReport {
+            lines,
+            overdrawn_clients,
+            ok,
+        })
+    }
+
+    pub fn to_json(&self) -> Result<String, LedgerError> {
+        let record = LedgerRecord {
+            accounts: self.accounts.values().cloned().collect(),
+            entries: self.entries.clone(),
+        };
+        serde_json::to_string(&record).map_err(|e| LedgerError::Json(e.to_string()))
+    }
+
+    /// Rebuilds a ledger by re-opening every account and re-posting every entry,
+    /// so a tampered or unbalanced file is rejected rather than loaded.
+    pub fn from_json(json: &str) -> Result<Ledger, LedgerError> {
+        let record: LedgerRecord =
+            serde_json::from_str(json).map_err(|e| LedgerError::Json(e.to_string()))?;
+        let mut ledger = Ledger::new();
+        for account in record.accounts {
+            ledger.open_account(account)?;
+        }
+        for entry in record.entries {
+            ledger.post(entry)?;
+        }
+        Ok(ledger)
+    }
+}
+
+fn normal_balance(
+    account: &Account,
+    debits: &Money,
+    credits: &Money,
+) -> Result<Money, LedgerError> {
+    Ok(if account.kind.debit_normal() {
+        debits.checked_sub(credits)?
+    } else {
+        credits.checked_sub(debits)?
+    })
+}
+
+#[wasm_bindgen]
+impl Ledger {
+    #[wasm_bindgen(constructor)]
+    pub fn js_new() -> Ledger {
+        Ledger::new()
+    }
+
+    #[wasm_bindgen(js_name = fromJSON)]
+    pub fn js_from_json(json: &str) -> Result<Ledger, JsError> {
+        Ledger::from_json(json).map_err(js_err)
+    }
+
+    #[wasm_bindgen(js_name = toJSON)]
+    pub fn js_to_json(&self) -> Result<String, JsError> {
+        self.to_json().map_err(js_err)
+    }
+
+    /// Opens a `{ code, name, kind, pool, currency, clientId? }` account.
+    #[wasm_bindgen(js_name = openAccount)]
+    pub fn js_open_account(&mut self, account: JsValue) -> Result<(), JsError> {
+        let account: Account = from_js(account)?;
+        self.open_account(account).map_err(js_err)
+    }
+
+    /// Posts a `{ id, date, description, postings: [{ account, side, amount }] }` entry.
+    #[wasm_bindgen(js_name = post)]
+    pub fn js_post(&mut self, entry: JsValue) -> Result<(), JsError> {
+        let entry: JournalEntry = from_js(entry)?;
+        self.post(entry).map_err(js_err)
+    }
+
+    #[wasm_bindgen(js_name = balance)]
+    pub fn js_balance(&self, code: &str) -> Result<Money, JsError> {
+        self.balance(code).map_err(js_err)
+    }
+
+    #[wasm_bindgen(js_name = trialBalance)]
+    pub fn js_trial_balance(&self) -> Result<JsValue, JsError> {
+        to_js(&self.trial_balance().map_err(js_err)?)
+    }
+
+    #[wasm_bindgen(js_name = clientBalances)]
+    pub fn js_client_balances(&self, client_id: &str) -> Result<JsValue, JsError> {
+        to_js(&self.client_balances(client_id).map_err(js_err)?)
+    }
+
+    #[wasm_bindgen(js_name = segregationCheck)]
+    pub fn js_segregation_check(&self) -> Result<JsValue, JsError> {
+        to_js(&self.segregation_check().map_err(js_err)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kes(s: &str) -> Money {
+        Money::parse(s, Currency::Kes).unwrap()
+    }
+
+    fn account(code: &str, kind: AccountKind, pool: Pool, client_id: Option<&str>) -> Account {
+        Account {
+            code: code.to_string(),
+            name: code.to_string(),
+            kind,
+            pool,
+            currency: Currency::Kes,
+            client_id: client_id.map(str::to_string),
+        }
+    }
+
+    fn entry(id: &str, postings: &[(&str, Side, &str)]) -> JournalEntry {
+        JournalEntry {
+            id: id.to_string(),
+            date: NaiveDate::from_ymd_opt(2024, 3, 1).unwrap(),
+            description: id.to_string(),
+            postings: postings
+                .iter()
+                .map(|(account, side, amount)| Posting {
+                    account: account.to_string(),
+                    side: *side,
+                    amount: kes(amount),
+                })
+                .collect(),
+        }
+    }
+
+    fn books() -> Ledger {
+        let mut ledger = Ledger::new();
+        for a in [
+            account("TRUST", AccountKind::Asset, Pool::Client, None),
+            account(
+                "CL:amina",
+                AccountKind::Liability,
+                Pool::Client,
+                Some("amina"),
+            ),
+            account(
+                "CL:otieno",
+                AccountKind::Liability,
+                Pool::Client,
+                Some("otieno"),
+            ),
+            account("BANK", AccountKind::Asset, Pool::House, None),
+            account("FEES", AccountKind::Income, Pool::House, None),
+        ] {
+            ledger.open_account(a).unwrap();
+        }
+        ledger
+            .post(entry(
+                "dep-1",
+                &[
+                    ("TRUST", Side::Debit, "10000"),
+                    ("CL:amina", Side::Credit, "6000"),
+                    ("CL:otieno", Side::Credit, "4000"),
+                ],
+            ))
+            .unwrap();
+        ledger
+    }
+
+    #[test]
+    fn unbalanced_entries_are_rejected() {
+        let mut ledger = books();
+        let err = ledger
+            .post(entry(
+                "bad",
+                &[
+                    ("TRUST", Side::Debit, "100"),
+                    ("CL:amina", Side::Credit, "99"),
+                ],
+            ))
+            .unwrap_err();
+        assert_eq!(
+            err,
+            LedgerError::Unbalanced {
+                entry: "bad".to_string(),
+                pool: Pool::Client,
+                currency: Currency::Kes,
+            }
+        );
+        assert_eq!(ledger.entries().len(), 1);
+        assert_eq!(
+            ledger.post(entry("dep-1", &[])),
+            Err(LedgerError::DuplicateEntry("dep-1".to_string()))
+        );
+        assert_eq!(
+            ledger.post(entry("one", &[("TRUST", Side::Debit, "1")])),
+            Err(LedgerError::TooFewPostings("one".to_string()))
+        );
+    }
+
+    #[test]
+    fn client_and_house_money_balance_separately() {
+        let mut ledger = books();
+        // Balanced overall, but client money would be paying the firm's fee.
+        let err = ledger
+            .post(entry(
+                "fee",
+                &[
+                    ("CL:amina", Side::Debit, "100"),
+                    ("FEES", Side::Credit, "100"),
+                ],
+            ))
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            LedgerError::Unbalanced {
+                pool: Pool::Client,
+                ..
+            }
+        ));
+        // Moving the fee out of trust is balanced in each pool.
+        ledger
+            .post(entry(
+                "fee",
+                &[
+                    ("CL:amina", Side::Debit, "100"),
+                    ("TRUST", Side::Credit, "100"),
+                    ("BANK", Side::Debit, "100"),
+                    ("FEES", Side::Credit, "100"),
+                ],
+            ))
+            .unwrap();
+        assert_eq!(ledger.balance("CL:amina").unwrap(), kes("5900"));
+        assert_eq!(ledger.balance("BANK").unwrap(), kes("100"));
+
+        assert_eq!(
+            ledger.open_account(account(
+                "HX",
+                AccountKind::Asset,
+                Pool::House,
+                Some("amina")
+            )),
+            Err(LedgerError::HouseAccountWithClient("HX".to_string()))
+        );
+        assert_eq!(
+            ledger.open_account(account("CX", AccountKind::Liability, Pool::Client, None)),
+            Err(LedgerError::ClientAccountWithoutClient("CX".to_string()))
+        );
+    }
+
+    #[test]
+    fn segregation_flags_one_client_funding_another() {
+        let mut ledger = books();
+        assert!(ledger.segregation_check().unwrap().ok);
+        ledger
+            .post(entry(
+                "move",
+                &[
+                    ("CL:otieno", Side::Debit, "5000"),
+                    ("CL:amina", Side::Credit, "5000"),
+                ],
+            ))
+            .unwrap();
+        let report = ledger.segregation_check().unwrap();
+        assert_eq!(report.overdrawn_clients, vec!["CL:otieno".to_string()]);
+        assert_eq!(report.lines[0].client_assets, kes("10000"));
+        assert_eq!(report.lines[0].difference, kes("0"));
+        assert!(!report.ok);
+        let amina = ledger.client_balances("amina").unwrap();
+        assert_eq!(amina[0].balance, kes("11000"));
+    }
+
+    #[test]
+    fn trial_balance_totals_match() {
+        let tb = books().trial_balance().unwrap();
+        assert!(tb.balanced);
+        assert_eq!(tb.totals.len(), 1);
+        assert_eq!(tb.totals[0].debits, kes("10000"));
+        assert_eq!(tb.totals[0].credits, kes("10000"));
+        let trust = tb.lines.iter().find(|l| l.account == "TRUST").unwrap();
+        assert_eq!(trust.balance, kes("10000"));
+        let amina = tb.lines.iter().find(|l| l.account == "CL:amina").unwrap();
+        assert_eq!(amina.balance, kes("6000"));
+    }
+
+    #[test]
+    fn json_round_trip_is_lossless() {
+        let ledger = books();
+        let json = ledger.to_json().unwrap();
+        let restored = Ledger::from_json(&json).unwrap();
+        assert_eq!(restored.entries(), ledger.entries());
+        assert_eq!(restored.account("CL:amina"), ledger.account("CL:amina"));
+        assert_eq!(restored.trial_balance(), ledger.trial_balance());
+        assert_eq!(restored.to_json().unwrap(), json);
+
+        let tampered = json.replace("\"6000.00\"", "\"6000.01\"");
+        assert_ne!(tampered, json);
+        assert!(matches!(
+            Ledger::from_json(&tampered),
+            Err(LedgerError::Unbalanced { .. })
+        ));
+    }
+}
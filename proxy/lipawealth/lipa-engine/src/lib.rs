@@ -1,5 +1,36 @@
 use wasm_bindgen::prelude::*;
 
+mod js;
+
+pub mod attribution;
+pub mod auction;
+pub mod calendar;
+pub mod chama;
+pub mod corporate_actions;
+pub mod fees;
+pub mod fixed_income;
+pub mod fund;
+pub mod fx;
+pub mod goals;
+pub mod ledger;
+pub mod linalg;
+pub mod loan;
+pub mod lots;
+pub mod mmf;
+pub mod money;
+pub mod montecarlo;
+pub mod mpesa;
+pub mod optimizer;
+pub mod portfolio;
+pub mod rebalance;
+pub mod returns;
+pub mod risk;
+pub mod rng;
+pub mod tax;
+pub mod trade_cost;
+
+pub use money::{Currency, Money, MoneyError};
+
 #[wasm_bindgen]
 extern "C" {
     fn alert(s: &str);
@@ -0,0 +1,148 @@
+//! Dense matrix helpers for the small (asset-count sized) problems the engine solves.
+
+/// Row-major square matrix.
+pub type Matrix = Vec<Vec<f64>>;
+
+/// Tolerance for treating a matrix as symmetric or a pivot as zero.
+const EPS: f64 = 1e-10;
+
+pub fn is_square(m: &[Vec<f64>]) -> bool {
+    m.iter().all(|row| row.len() == m.len())
+}
+
+pub fn is_symmetric(m: &[Vec<f64>]) -> bool {
+    is_square(m) && (0..m.len()).all(|i| (0..i).all(|j| (m[i][j] - m[j][i]).abs() <= EPS))
+}
+
+/// Lower-triangular `L` with `L·Lᵀ = m`, or `None` if `m` is not symmetric
+/// positive semi-definite. Zero pivots (perfectly correlated assets) are allowed.
+pub fn cholesky(m: &[Vec<f64>]) -> Option<Matrix> {
+    if !is_symmetric(m) {
+        return None;
+    }
+    let n = m.len();
+    let mut l = vec![vec![0.0; n]; n];
+    for i in 0..n {
+        for j in 0..=i {
+            let dot: f64 = (0..j).map(|k| l[i][k] * l[j][k]).sum();
+            if i == j {
+                let d = m[i][i] - dot;
+                if d < -EPS {
+                    return None;
+                }
+                l[i][i] = d.max(0.0).sqrt();
+            } else if l[j][j] > EPS {
+                l[i][j] = (m[i][j] - dot) / l[j][j];
+            } else if (m[i][j] - dot).abs() > EPS {
+                return None;
+            }
+        }
+    }
+    Some(l)
+}
+
+/// `l · z` for lower-triangular `l`.
+pub fn lower_mul(l: &[Vec<f64>], z: &[f64], out: &mut [f64]) {
+    for (i, row) in l.iter().enumerate() {
+        out[i] = row[..=i].iter().zip(z).map(|(a, b)| a * b).sum();
+    }
+}
+
+/// Solves `a · x = b` by Gaussian elimination with partial pivoting; `None` if singular.
+pub fn solve(a: &[Vec<f64>], b: &[f64]) -> Option<Vec<f64>> {
+    let n = b.len();
+    let mut m: Matrix = a
+        .iter()
+        .zip(b)
+        .map(|(row, rhs)| {
+            let mut r = row.clone();
+            r.push(*rhs);
+            r
+        })
+        .collect();
+    for col in 0..n {
+        let pivot = (col..n).max_by(|i, j| m[*i][col].abs().total_cmp(&m[*j][col].abs()))?;
+        if m[pivot][col].abs() <= EPS {
+            return None;
+        }
+        m.swap(col, pivot);
+        let (done, rest) = m.split_at_mut(col + 1);
+        let pivot_row = &done[col];
+        for row in rest.iter_mut() {
+            let factor = row[col] / pivot_row[col];
+            for (x, p) in row[col..].iter_mut().zip(&pivot_row[col..]) {
+                *x -= factor * p;
+            }
+        }
+    }
+    let mut x = vec![0.0; n];
+    for row in (0..n).rev() {
+        let tail: f64 = (row + 1..n).map(|k| m[row][k] * x[k]).sum();
+        x[row] = (m[row][n] - tail) / m[row][row];
+    }
+    Some(x)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64, tol: f64) {
+        assert!((a - b).abs() < tol, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn solve_pivots_past_a_zero_diagonal() {
+        let a = vec![
+            vec![0.0, 2.0, 1.0],
+            vec![1.0, 1.0, 1.0],
+            vec![4.0, -1.0, 2.0],
+        ];
+        let x = solve(&a, &[5.0, 4.0, 4.0]).unwrap();
+        for (row, rhs) in a.iter().zip([5.0, 4.0, 4.0]) {
+            close(row.iter().zip(&x).map(|(a, x)| a * x).sum(), rhs, 1e-12);
+        }
+        close(x[0], 1.0, 1e-12);
+        close(x[1], 2.0, 1e-12);
+        close(x[2], 1.0, 1e-12);
+    }
+
+    #[test]
+    fn solve_rejects_singular_systems() {
+        let a = vec![vec![1.0, 2.0], vec![2.0, 4.0]];
+        assert_eq!(solve(&a, &[1.0, 2.0]), None);
+    }
+
+    #[test]
+    fn cholesky_reproduces_the_matrix() {
+        let m = vec![
+            vec![4.0, 2.0, 0.4],
+            vec![2.0, 10.0, 1.0],
+            vec![0.4, 1.0, 1.0],
+        ];
+        let l = cholesky(&m).unwrap();
+        for i in 0..3 {
+            for j in 0..3 {
+                let llt: f64 = (0..3).map(|k| l[i][k] * l[j][k]).sum();
+                close(llt, m[i][j], 1e-12);
+            }
+            assert!(l[i][i + 1..].iter().all(|x| *x == 0.0));
+        }
+        let mut out = [0.0; 3];
+        lower_mul(&l, &[1.0, 0.0, 0.0], &mut out);
+        assert_eq!(out, [l[0][0], l[1][0], l[2][0]]);
+    }
+
+    #[test]
+    fn cholesky_allows_perfect_correlation_but_not_indefinite_matrices() {
+        let perfect = vec![vec![1.0, 1.0], vec![1.0, 1.0]];
+        assert_eq!(
+            cholesky(&perfect),
+            Some(vec![vec![1.0, 0.0], vec![1.0, 0.0]])
+        );
+        let indefinite = vec![vec![1.0, 2.0], vec![2.0, 1.0]];
+        assert_eq!(cholesky(&indefinite), None);
+        let asymmetric = vec![vec![1.0, 0.5], vec![0.0, 1.0]];
+        assert_eq!(cholesky(&asymmetric), None);
+    }
+}
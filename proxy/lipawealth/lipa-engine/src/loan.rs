@@ -0,0 +1,461 @@
+use std::fmt;
+
+use chrono::{Months, NaiveDate};
+use rust_decimal::prelude::*;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::js::{from_js, js_err, to_js};
+use crate::money::{Currency, Money, MoneyError};
+
+/// Employment Act s.19(3): total deductions may not exceed two-thirds of wages.
+pub fn max_deduction_share() -> Decimal {
+    Decimal::TWO / Decimal::from(3)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FeeTreatment {
+    /// Fee is netted off the disbursement.
+    Deducted,
+    /// Fee is added to the principal and amortised with it.
+    Capitalised,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoanRequest {
+    pub basic_pay: Money,
+    pub net_pay: Money,
+    /// Statutory and voluntary deductions already on the payslip.
+    pub existing_deductions: Money,
+    /// Amount asked for; when absent the quote is for the largest affordable loan.
+    #[serde(default)]
+    pub principal: Option<Money>,
+    pub tenor_months: u32,
+    /// Nominal annual interest rate, reducing balance (0.14 = 14%).
+    pub annual_rate: Decimal,
+    /// One-off processing fee as a fraction of principal.
+    #[serde(default)]
+    pub processing_fee_rate: Decimal,
+    #[serde(default = "default_fee_treatment")]
+    pub fee_treatment: FeeTreatment,
+    /// Annual credit-life premium as a fraction of the outstanding balance.
+    #[serde(default)]
+    pub insurance_rate: Decimal,
+    pub first_repayment: NaiveDate,
+}
+
+fn default_fee_treatment() -> FeeTreatment {
+    FeeTreatment::Deducted
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Installment {
+    pub period: u32,
+    pub due: NaiveDate,
+    pub opening_balance: Money,
+    pub interest: Money,
+    pub principal: Money,
+    pub insurance: Money,
+    pub payment: Money,
+    pub closing_balance: Money,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoanQuote {
+    /// Largest monthly payment the two-thirds rule and net pay allow.
+    pub max_installment: Money,
+    pub max_principal: Money,
+    pub principal: Money,
+    pub processing_fee: Money,
+    pub disbursed: Money,
+    pub amortised_amount: Money,
+    pub schedule: Vec<Installment>,
+    pub total_interest: Money,
+    pub total_insurance: Money,
+    pub total_repayable: Money,
+    /// Annualised cost of credit including fees and insurance (nominal, monthly compounding).
+    pub apr: f64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum LoanError {
+    InvalidTenor,
+    InvalidRate,
+    InvalidPrincipal,
+    NoCapacity {
+        max_installment: Money,
+    },
+    ExceedsDeductionLimit {
+        installment: Money,
+        max_installment: Money,
+    },
+    Money(MoneyError),
+}
+
+impl fmt::Display for LoanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoanError::InvalidTenor => f.write_str("tenor must be at least one month"),
+            LoanError::InvalidRate => f.write_str("rates must not be negative"),
+            LoanError::InvalidPrincipal => f.write_str("principal must be positive"),
+            LoanError::NoCapacity { max_installment } => write!(
+                f,
+                "no room for further deductions (headroom {})",
+                max_installment
+            ),
+            LoanError::ExceedsDeductionLimit {
+                installment,
+                max_installment,
+            } => write!(
+                f,
+                "installment {} exceeds the two-thirds deduction limit of {}",
+                installment, max_installment
+            ),
+            LoanError::Money(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for LoanError {}
+
+impl From<MoneyError> for LoanError {
+    fn from(err: MoneyError) -> Self {
+        LoanError::Money(err)
+    }
+}
+
+/// Level payment per unit of principal: r / (1 - (1 + r)^-n).
+fn annuity_factor(monthly_rate: Decimal, months: u32) -> Result<Decimal, LoanError> {
+    if monthly_rate.is_zero() {
+        return Ok(Decimal::ONE / Decimal::from(months));
+    }
+    let mut growth = Decimal::ONE;
+    for _ in 0..months {
+        growth = growth
+            .checked_mul(Decimal::ONE + monthly_rate)
+            .ok_or(MoneyError::Overflow)?;
+    }
+    monthly_rate
+        .checked_mul(growth)
+        .and_then(|scaled| scaled.checked_div(growth - Decimal::ONE))
+        .ok_or_else(|| MoneyError::Overflow.into())
+}
+
+/// The headroom left under the two-thirds rule, capped at what actually reaches the account.
+pub fn max_installment(
+    basic_pay: &Money,
+    net_pay: &Money,
+    existing_deductions: &Money,
+) -> Result<Money, MoneyError> {
+    let cap = basic_pay.checked_mul(max_deduction_share())?;
+    let headroom = cap.checked_sub(existing_deductions)?;
+    let headroom = if headroom.checked_sub(net_pay)?.is_positive() {
+        *net_pay
+    } else {
+        headroom
+    };
+    Ok(if headroom.is_negative() {
+        Money::zero(headroom.currency())
+    } else {
+        headroom
+    })
+}
+
+fn build_schedule(amount: &Money, req: &LoanRequest) -> Result<Vec<Installment>, LoanError> {
+    let currency = amount.currency();
+    let monthly_rate = req.annual_rate / Decimal::from(12);
+    let monthly_insurance = req.insurance_rate / Decimal::from(12);
+    let level = amount.checked_mul(annuity_factor(monthly_rate, req.tenor_months)?)?;
+
+    let mut balance = *amount;
+    let mut schedule = Vec::with_capacity(req.tenor_months as usize);
+    for period in 1..=req.tenor_months {
+        let interest = balance.checked_mul(monthly_rate)?;
+        let insurance = balance.checked_mul(monthly_insurance)?;
+        // The final payment clears whatever rounding has left on the balance.
+        let principal = if period == req.tenor_months {
+            balance
+        } else {
+            level.checked_sub(&interest)?
+        };
+        let closing = balance.checked_sub(&principal)?;
+        let payment = Money::sum([&interest, &principal, &insurance], currency)?;
+        let due = req
+            .first_repayment
+            .checked_add_months(Months::new(period - 1))
+            .ok_or(LoanError::InvalidTenor)?;
+        schedule.push(Installment {
+            period,
+            due,
+            opening_balance: balance,
+            interest,
+            principal,
+            insurance,
+            payment,
+            closing_balance: closing,
+        });
+        balance = closing;
+    }
+    Ok(schedule)
+}
+
+/// Monthly IRR of `outflow` against `payments`, found by bisection, then annualised ×12.
+fn nominal_apr(outflow: f64, payments: &[f64]) -> f64 {
+    let pv = |r: f64| -> f64 {
+        payments
+            .iter()
+            .enumerate()
+            .map(|(i, p)| p / (1.0 + r).powi(i as i32 + 1))
+            .sum::<f64>()
+            - outflow
+    };
+    let (mut lo, mut hi) = (0.0_f64, 1.0_f64);
+    if pv(lo) <= 0.0 {
+        return 0.0;
+    }
+    for _ in 0..200 {
+        let mid = (lo + hi) / 2.0;
+        if pv(mid) > 0.0 {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    (lo + hi) / 2.0 * 12.0
+}
+
+/// Prices a salary-backed loan and checks it against the two-thirds deduction rule.
+pub fn quote(req: &LoanRequest) -> Result<LoanQuote, LoanError> {
+    if req.tenor_months == 0 {
+        return Err(LoanError::InvalidTenor);
+    }
+    if req.annual_rate < Decimal::ZERO
+        || req.processing_fee_rate < Decimal::ZERO
+        || req.insurance_rate < Decimal::ZERO
+    {
+        return Err(LoanError::InvalidRate);
+    }
+    let currency: Currency = req.basic_pay.currency();
+    if let Some(principal) = &req.principal {
+        if principal.currency() != currency {
+            return Err(MoneyError::CurrencyMismatch {
+                left: currency,
+                right: principal.currency(),
+            }
+            .into());
+        }
+        if !principal.is_positive() {
+            return Err(LoanError::InvalidPrincipal);
+        }
+    }
+    let max_installment = max_installment(&req.basic_pay, &req.net_pay, &req.existing_deductions)?;
+    if max_installment.is_zero() {
+        return Err(LoanError::NoCapacity { max_installment });
+    }
+
+    // The first payment is the largest, so sizing on it keeps every payment under the cap.
+    let monthly_rate = req.annual_rate / Decimal::from(12);
+    let first_payment_factor = annuity_factor(monthly_rate, req.tenor_months)?
+        .checked_add(req.insurance_rate / Decimal::from(12))
+        .ok_or(MoneyError::Overflow)?;
+    let amortisable_per_principal = match req.fee_treatment {
+        FeeTreatment::Deducted => Decimal::ONE,
+        FeeTreatment::Capitalised => Decimal::ONE + req.processing_fee_rate,
+    };
+    let max_principal = Money::new(
+        (max_installment.amount() / (first_payment_factor * amortisable_per_principal)).floor(),
+        currency,
+    );
+
+    let principal = req.principal.unwrap_or(max_principal);
+    let processing_fee = principal.checked_mul(req.processing_fee_rate)?;
+    let (disbursed, amortised_amount) = match req.fee_treatment {
+        FeeTreatment::Deducted => (principal.checked_sub(&processing_fee)?, principal),
+        FeeTreatment::Capitalised => (principal, principal.checked_add(&processing_fee)?),
+    };
+
+    let schedule = build_schedule(&amortised_amount, req)?;
+    let largest = schedule
+        .iter()
+        .map(|i| i.payment)
+        .fold(Money::zero(currency), |a, b| if b > a { b } else { a });
+    if largest > max_installment {
+        return Err(LoanError::ExceedsDeductionLimit {
+            installment: largest,
+            max_installment,
+        });
+    }
+
+    let total_interest = Money::sum(schedule.iter().map(|i| &i.interest), currency)?;
+    let total_insurance = Money::sum(schedule.iter().map(|i| &i.insurance), currency)?;
+    let total_repayable = Money::sum(schedule.iter().map(|i| &i.payment), currency)?;
+    let payments: Vec<f64> = schedule
+        .iter()
+        .map(|i| i.payment.amount().to_f64().unwrap_or(0.0))
+        .collect();
+    let apr = nominal_apr(disbursed.amount().to_f64().unwrap_or(0.0), &payments);
+
+    Ok(LoanQuote {
+        max_installment,
+        max_principal,
+        principal,
+        processing_fee,
+        disbursed,
+        amortised_amount,
+        schedule,
+        total_interest,
+        total_insurance,
+        total_repayable,
+        apr,
+    })
+}
+
+/// JS entry point: takes a `LoanRequest`-shaped object and returns a `LoanQuote`.
+#[wasm_bindgen(js_name = quoteSalaryLoan)]
+pub fn quote_js(input: JsValue) -> Result<JsValue, JsError> {
+    let req: LoanRequest = from_js(input)?;
+    let quote = quote(&req).map_err(js_err)?;
+    to_js(&quote)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kes(s: &str) -> Money {
+        Money::parse(s, Currency::Kes).unwrap()
+    }
+
+    fn request(principal: Option<&str>, tenor_months: u32, annual_rate: &str) -> LoanRequest {
+        LoanRequest {
+            basic_pay: kes("60000"),
+            net_pay: kes("30000"),
+            existing_deductions: kes("30000"),
+            principal: principal.map(kes),
+            tenor_months,
+            annual_rate: annual_rate.parse().unwrap(),
+            processing_fee_rate: Decimal::ZERO,
+            fee_treatment: FeeTreatment::Deducted,
+            insurance_rate: Decimal::ZERO,
+            first_repayment: NaiveDate::from_ymd_opt(2024, 1, 31).unwrap(),
+        }
+    }
+
+    #[test]
+    fn two_thirds_rule_caps_the_installment() {
+        // 2/3 of 60,000 is 40,000; 30,000 already deducted leaves 10,000.
+        let headroom = max_installment(&kes("60000"), &kes("30000"), &kes("30000")).unwrap();
+        assert_eq!(headroom, kes("10000"));
+        // Headroom never exceeds what actually reaches the account.
+        let headroom = max_installment(&kes("60000"), &kes("4000"), &kes("30000")).unwrap();
+        assert_eq!(headroom, kes("4000"));
+        let headroom = max_installment(&kes("60000"), &kes("30000"), &kes("45000")).unwrap();
+        assert_eq!(headroom, kes("0"));
+
+        let mut req = request(None, 12, "0.12");
+        req.existing_deductions = kes("40000");
+        assert!(matches!(quote(&req), Err(LoanError::NoCapacity { .. })));
+    }
+
+    #[test]
+    fn level_payments_amortise_to_zero() {
+        let q = quote(&request(Some("100000"), 12, "0.12")).unwrap();
+        assert_eq!(q.schedule.len(), 12);
+        assert_eq!(q.schedule[0].interest, kes("1000"));
+        assert_eq!(q.schedule[0].payment, kes("8884.88"));
+        assert_eq!(q.schedule[11].closing_balance, kes("0"));
+        assert_eq!(
+            q.total_repayable,
+            q.principal.checked_add(&q.total_interest).unwrap()
+        );
+        assert_eq!(
+            q.schedule[11].due,
+            NaiveDate::from_ymd_opt(2024, 12, 31).unwrap()
+        );
+        assert!((q.apr - 0.12).abs() < 1e-6);
+    }
+
+    #[test]
+    fn largest_loan_fits_the_headroom() {
+        let q = quote(&request(None, 10, "0")).unwrap();
+        assert_eq!(q.max_principal, kes("100000"));
+        assert!(q.schedule.iter().all(|i| i.payment == kes("10000")));
+
+        let mut req = request(None, 12, "0.14");
+        req.processing_fee_rate = "0.02".parse().unwrap();
+        req.fee_treatment = FeeTreatment::Capitalised;
+        req.insurance_rate = "0.01".parse().unwrap();
+        let q = quote(&req).unwrap();
+        assert!(q.schedule.iter().all(|i| i.payment <= q.max_installment));
+        assert!(q.apr > 0.14);
+    }
+
+    #[test]
+    fn deducted_fee_raises_the_apr() {
+        let mut req = request(Some("50000"), 10, "0");
+        req.processing_fee_rate = "0.02".parse().unwrap();
+        let q = quote(&req).unwrap();
+        assert_eq!(q.processing_fee, kes("1000"));
+        assert_eq!(q.disbursed, kes("49000"));
+        assert_eq!(q.amortised_amount, kes("50000"));
+        assert!(q.apr > 0.0);
+    }
+
+    #[test]
+    fn oversize_loan_breaches_the_deduction_limit() {
+        assert!(matches!(
+            quote(&request(Some("150000"), 12, "0.12")),
+            Err(LoanError::ExceedsDeductionLimit { .. })
+        ));
+    }
+
+    #[test]
+    fn rejects_bad_principal_tenor_and_rate() {
+        assert_eq!(
+            quote(&request(Some("0"), 12, "0.12")),
+            Err(LoanError::InvalidPrincipal)
+        );
+        assert_eq!(
+            quote(&request(Some("-5000"), 12, "0.12")),
+            Err(LoanError::InvalidPrincipal)
+        );
+        let mut req = request(None, 12, "0.12");
+        req.principal = Some(Money::parse("1000", Currency::Usd).unwrap());
+        assert_eq!(
+            quote(&req),
+            Err(LoanError::Money(MoneyError::CurrencyMismatch {
+                left: Currency::Kes,
+                right: Currency::Usd,
+            }))
+        );
+        assert_eq!(
+            quote(&request(None, 0, "0.12")),
+            Err(LoanError::InvalidTenor)
+        );
+        assert_eq!(
+            quote(&request(None, 12, "-0.01")),
+            Err(LoanError::InvalidRate)
+        );
+    }
+
+    #[test]
+    fn compounding_overflow_is_an_error() {
+        assert_eq!(
+            quote(&request(None, 100_000, "12")),
+            Err(LoanError::Money(MoneyError::Overflow))
+        );
+        // 500% a month: the growth term fits, but scaling it by the rate does not.
+        assert_eq!(
+            annuity_factor(Decimal::from(5), 37),
+            Err(LoanError::Money(MoneyError::Overflow))
+        );
+        assert_eq!(
+            quote(&request(None, 37, "60")),
+            Err(LoanError::Money(MoneyError::Overflow))
+        );
+    }
+}
@@ -0,0 +1,1055 @@
+//! Tax-lot cost basis: buys open lots, sells and transfers out relieve them by FIFO,
+//! weighted average or specific identification, and every disposal reports its gain
+//! in the trade currency and in KES.
+//!
+//! KES figures use the rate carried on each event, so a lot's KES cost stays at the
+//! rate of the day it was acquired and the KES gain includes the currency move.
+
+use std::fmt;
+
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::js::{from_js, js_err, to_js};
+use crate::money::{Currency, Money, MoneyError};
+
+/// Decimal places kept on share quantities split between lots.
+const QUANTITY_DP: u32 = 8;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum LotError {
+    InvalidEvent {
+        event: String,
+        why: &'static str,
+    },
+    /// A non-KES event or price without the KES rate for its date.
+    MissingKesRate(String),
+    InsufficientQuantity {
+        event: String,
+        instrument: String,
+    },
+    UnknownLot {
+        event: String,
+        lot: String,
+    },
+    /// A lot with this id is already in the book.
+    DuplicateLot(String),
+    /// Specific identification needs the lots to relieve on every sell and transfer out.
+    MissingLotSelection(String),
+    MissingPrice(String),
+    Money(MoneyError),
+}
+
+impl fmt::Display for LotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LotError::InvalidEvent { event, why } => write!(f, "invalid event {}: {}", event, why),
+            LotError::MissingKesRate(event) => {
+                write!(f, "{} is not in KES and has no KES rate", event)
+            }
+            LotError::InsufficientQuantity { event, instrument } => {
+                write!(
+                    f,
+                    "event {} relieves more {} than is held",
+                    event, instrument
+                )
+            }
+            LotError::UnknownLot { event, lot } => {
+                write!(f, "event {} selects lot {}, which is not open", event, lot)
+            }
+            LotError::DuplicateLot(lot) => write!(f, "lot {} is already in the book", lot),
+            LotError::MissingLotSelection(event) => {
+                write!(f, "event {} must name the lots to relieve", event)
+            }
+            LotError::MissingPrice(instrument) => write!(f, "no price for {}", instrument),
+            LotError::Money(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for LotError {}
+
+impl From<MoneyError> for LotError {
+    fn from(err: MoneyError) -> Self {
+        LotError::Money(err)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ReliefMethod {
+    #[default]
+    Fifo,
+    /// Every lot of an instrument carries the pooled average cost per share.
+    AverageCost,
+    SpecificLot,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LotSelection {
+    pub lot: String,
+    pub quantity: Decimal,
+}
+
+/// Something that changes the lots held. `kes_rate` is KES per unit of the trade
+/// currency on the event date and may be left out for KES trades.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase",
+    tag = "kind"
+)]
+pub enum LotEvent {
+    Buy {
+        id: String,
+        date: NaiveDate,
+        instrument: String,
+        quantity: Decimal,
+        price: Decimal,
+        #[serde(default)]
+        fees: Decimal,
+        currency: Currency,
+        #[serde(default)]
+        kes_rate: Option<Decimal>,
+    },
+    Sell {
+        id: String,
+        date: NaiveDate,
+        instrument: String,
+        quantity: Decimal,
+        price: Decimal,
+        #[serde(default)]
+        fees: Decimal,
+        currency: Currency,
+        #[serde(default)]
+        kes_rate: Option<Decimal>,
+        /// Lots to relieve; only read under specific identification.
+        #[serde(default)]
+        lots: Option<Vec<LotSelection>>,
+    },
+    /// Shares moved in from another custodian with their original cost and date.
+    TransferIn {
+        id: String,
+        date: NaiveDate,
+        instrument: String,
+        quantity: Decimal,
+        /// Total cost basis, fees included.
+        cost: Decimal,
+        currency: Currency,
+        acquired: NaiveDate,
+        /// KES rate on the original acquisition date.
+        #[serde(default)]
+        kes_rate: Option<Decimal>,
+    },
+    /// Shares moved out at cost; no gain is realised.
+    TransferOut {
+        id: String,
+        date: NaiveDate,
+        instrument: String,
+        quantity: Decimal,
+        #[serde(default)]
+        lots: Option<Vec<LotSelection>>,
+    },
+    /// Multiplies every open lot's quantity by `ratio` at unchanged cost: a split
+    /// (2 for a 2-for-1), a bonus issue (1.1 for 1-for-10) or a consolidation (0.1).
+    Split {
+        id: String,
+        date: NaiveDate,
+        instrument: String,
+        ratio: Decimal,
+    },
+}
+
+impl LotEvent {
+    pub fn id(&self) -> &str {
+        match self {
+            LotEvent::Buy { id, .. }
+            | LotEvent::Sell { id, .. }
+            | LotEvent::TransferIn { id, .. }
+            | LotEvent::TransferOut { id, .. }
+            | LotEvent::Split { id, .. } => id,
+        }
+    }
+
+    pub fn date(&self) -> NaiveDate {
+        match self {
+            LotEvent::Buy { date, .. }
+            | LotEvent::Sell { date, .. }
+            | LotEvent::TransferIn { date, .. }
+            | LotEvent::TransferOut { date, .. }
+            | LotEvent::Split { date, .. } => *date,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Lot {
+    /// Id of the event that opened the lot.
+    pub id: String,
+    pub instrument: String,
+    pub acquired: NaiveDate,
+    pub quantity: Decimal,
+    /// Remaining cost basis in the trade currency, fees included.
+    pub cost: Money,
+    /// The same cost at the KES rate on the acquisition date.
+    pub cost_kes: Money,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Disposal {
+    pub event: String,
+    pub date: NaiveDate,
+    pub instrument: String,
+    pub lot: String,
+    pub acquired: NaiveDate,
+    pub quantity: Decimal,
+    /// Proceeds net of this lot's share of the selling fees.
+    pub proceeds: Money,
+    pub cost: Money,
+    pub gain: Money,
+    pub proceeds_kes: Money,
+    pub cost_kes: Money,
+    pub gain_kes: Money,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Transfer {
+    pub event: String,
+    pub date: NaiveDate,
+    pub lot: Lot,
+}
+
+fn invalid(event: &str, why: &'static str) -> LotError {
+    LotError::InvalidEvent {
+        event: event.to_string(),
+        why,
+    }
+}
+
+pub(crate) fn kes_rate(
+    event: &str,
+    currency: Currency,
+    rate: Option<Decimal>,
+) -> Result<Decimal, LotError> {
+    if currency == Currency::Kes {
+        return Ok(Decimal::ONE);
+    }
+    match rate {
+        Some(rate) if rate > Decimal::ZERO => Ok(rate),
+        Some(_) => Err(invalid(event, "KES rate must be positive")),
+        None => Err(LotError::MissingKesRate(event.to_string())),
+    }
+}
+
+/// Open lots plus everything relieved from them so far.
+#[derive(Clone, Debug, Default)]
+pub struct LotBook {
+    method: ReliefMethod,
+    lots: Vec<Lot>,
+    disposals: Vec<Disposal>,
+    transfers: Vec<Transfer>,
+}
+
+impl LotBook {
+    pub fn new(method: ReliefMethod) -> LotBook {
+        LotBook {
+            method,
+            ..LotBook::default()
+        }
+    }
+
+    pub fn method(&self) -> ReliefMethod {
+        self.method
+    }
+
+    /// Open lots, in the order they were opened.
+    pub fn lots(&self) -> &[Lot] {
+        &self.lots
+    }
+
+    pub fn disposals(&self) -> &[Disposal] {
+        &self.disposals
+    }
+
+    pub fn transfers(&self) -> &[Transfer] {
+        &self.transfers
+    }
+
+    pub fn quantity(&self, instrument: &str) -> Decimal {
+        self.lots
+            .iter()
+            .filter(|l| l.instrument == instrument)
+            .map(|l| l.quantity)
+            .sum()
+    }
+
+    pub fn apply(&mut self, event: &LotEvent) -> Result<(), LotError> {
+        match event {
+            LotEvent::Buy {
+                id,
+                date,
+                instrument,
+                quantity,
+                price,
+                fees,
+                currency,
+                kes_rate: rate,
+            } => {
+                if *quantity <= Decimal::ZERO || *price < Decimal::ZERO || *fees < Decimal::ZERO {
+                    return Err(invalid(
+                        id,
+                        "quantity must be positive, price and fees must not be negative",
+                    ));
+                }
+                let rate = kes_rate(id, *currency, *rate)?;
+                let cost = price
+                    .checked_mul(*quantity)
+                    .and_then(|c| c.checked_add(*fees))
+                    .ok_or(MoneyError::Overflow)?;
+                self.open(id, instrument, *date, *quantity, cost, *currency, rate)
+            }
+            LotEvent::TransferIn {
+                id,
+                instrument,
+                quantity,
+                cost,
+                currency,
+                acquired,
+                kes_rate: rate,
+                ..
+            } => {
+                if *quantity <= Decimal::ZERO || *cost < Decimal::ZERO {
+                    return Err(invalid(
+                        id,
+                        "quantity must be positive and cost must not be negative",
+                    ));
+                }
+                let rate = kes_rate(id, *currency, *rate)?;
+                self.open(id, instrument, *acquired, *quantity, *cost, *currency, rate)
+            }
+            LotEvent::Sell {
+                id,
+                date,
+                instrument,
+                quantity,
+                price,
+                fees,
+                currency,
+                kes_rate: rate,
+                lots,
+            } => {
+                if *quantity <= Decimal::ZERO || *price < Decimal::ZERO || *fees < Decimal::ZERO {
+                    return Err(invalid(
+                        id,
+                        "quantity must be positive, price and fees must not be negative",
+                    ));
+                }
+                let rate = kes_rate(id, *currency, *rate)?;
+                let net = price
+                    .checked_mul(*quantity)
+                    .and_then(|p| p.checked_sub(*fees))
+                    .ok_or(MoneyError::Overflow)?;
+                let (remaining, relieved) =
+                    self.relieve(id, instrument, *quantity, lots.as_deref())?;
+                let ratios: Vec<Decimal> = relieved.iter().map(|l| l.quantity).collect();
+                let shares = Money::new(net, *currency).allocate(&ratios)?;
+                let mut disposals = Vec::with_capacity(relieved.len());
+                for (lot, proceeds) in relieved.into_iter().zip(shares) {
+                    let proceeds_kes = Money::new(
+                        proceeds
+                            .amount()
+                            .checked_mul(rate)
+                            .ok_or(MoneyError::Overflow)?,
+                        Currency::Kes,
+                    );
+                    disposals.push(Disposal {
+                        event: id.clone(),
+                        date: *date,
+                        instrument: instrument.clone(),
+                        gain: proceeds.checked_sub(&lot.cost)?,
+                        gain_kes: proceeds_kes.checked_sub(&lot.cost_kes)?,
+                        lot: lot.id,
+                        acquired: lot.acquired,
+                        quantity: lot.quantity,
+                        proceeds,
+                        cost: lot.cost,
+                        proceeds_kes,
+                        cost_kes: lot.cost_kes,
+                    });
+                }
+                // Nothing above touched the book, so a failed sell leaves it as it was.
+                self.lots = remaining;
+                self.disposals.extend(disposals);
+                Ok(())
+            }
+            LotEvent::TransferOut {
+                id,
+                date,
+                instrument,
+                quantity,
+                lots,
+            } => {
+                if *quantity <= Decimal::ZERO {
+                    return Err(invalid(id, "quantity must be positive"));
+                }
+                let (remaining, relieved) =
+                    self.relieve(id, instrument, *quantity, lots.as_deref())?;
+                self.lots = remaining;
+                for lot in relieved {
+                    self.transfers.push(Transfer {
+                        event: id.clone(),
+                        date: *date,
+                        lot,
+                    });
+                }
+                Ok(())
+            }
+            LotEvent::Split {
+                id,
+                instrument,
+                ratio,
+                ..
+            } => {
+                if *ratio <= Decimal::ZERO {
+                    return Err(invalid(id, "ratio must be positive"));
+                }
+                self.scale(instrument, *ratio)
+            }
+        }
+    }
+
+    /// Multiplies the quantity of every open lot of `instrument`, leaving cost alone.
+    pub fn scale(&mut self, instrument: &str, ratio: Decimal) -> Result<(), LotError> {
+        for lot in self.lots.iter_mut().filter(|l| l.instrument == instrument) {
+            lot.quantity = lot
+                .quantity
+                .checked_mul(ratio)
+                .ok_or(MoneyError::Overflow)?;
+        }
+        Ok(())
+    }
+
+    /// Spreads `extra` shares (a bonus issue) over the open lots of `instrument` in
+    /// proportion to their quantity, at no extra cost. The last lot takes the
+    /// rounding remainder so the total comes out exact.
+    pub fn add_shares(&mut self, instrument: &str, extra: Decimal) -> Result<(), LotError> {
+        let held = self.quantity(instrument);
+        if held.is_zero() {
+            return Ok(());
+        }
+        let indices: Vec<usize> = (0..self.lots.len())
+            .filter(|&i| self.lots[i].instrument == instrument)
+            .collect();
+        let mut left = extra;
+        for (n, &i) in indices.iter().enumerate() {
+            let lot = &mut self.lots[i];
+            let share = if n + 1 == indices.len() {
+                left
+            } else {
+                (extra * lot.quantity / held)
+                    .round_dp(QUANTITY_DP)
+                    .normalize()
+            };
+            lot.quantity += share;
+            left -= share;
+        }
+        Ok(())
+    }
+
+    /// Adds a lot opened outside the usual events (a rights take-up, say). A lot
+    /// that is refused leaves the book as it was.
+    pub fn add_lot(&mut self, lot: Lot) -> Result<(), LotError> {
+        if lot.quantity <= Decimal::ZERO || lot.cost.is_negative() {
+            return Err(invalid(
+                &lot.id,
+                "quantity must be positive and cost must not be negative",
+            ));
+        }
+        if lot.cost_kes.currency() != Currency::Kes {
+            return Err(invalid(&lot.id, "KES cost must be in KES"));
+        }
+        let taken = self.lots.iter().any(|l| l.id == lot.id)
+            || self.disposals.iter().any(|x| x.lot == lot.id)
+            || self.transfers.iter().any(|t| t.lot.id == lot.id);
+        if taken {
+            return Err(LotError::DuplicateLot(lot.id));
+        }
+        let instrument = lot.instrument.clone();
+        self.lots.push(lot);
+        // Repooling fails before it writes anything, so dropping the new lot is enough.
+        if let Err(err) = self.repool(&instrument) {
+            self.lots.pop();
+            return Err(err);
+        }
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn open(
+        &mut self,
+        id: &str,
+        instrument: &str,
+        acquired: NaiveDate,
+        quantity: Decimal,
+        cost: Decimal,
+        currency: Currency,
+        rate: Decimal,
+    ) -> Result<(), LotError> {
+        let cost = Money::new(cost, currency);
+        let cost_kes = Money::new(
+            cost.amount()
+                .checked_mul(rate)
+                .ok_or(MoneyError::Overflow)?,
+            Currency::Kes,
+        );
+        self.add_lot(Lot {
+            id: id.to_string(),
+            instrument: instrument.to_string(),
+            acquired,
+            quantity,
+            cost,
+            cost_kes,
+        })
+    }
+
+    /// Under average cost, spreads the instrument's pooled cost over its lots by quantity.
+    fn repool(&mut self, instrument: &str) -> Result<(), LotError> {
+        if self.method != ReliefMethod::AverageCost {
+            return Ok(());
+        }
+        let pooled: Vec<usize> = (0..self.lots.len())
+            .filter(|&i| self.lots[i].instrument == instrument)
+            .collect();
+        let Some(&first) = pooled.first() else {
+            return Ok(());
+        };
+        let currency = self.lots[first].cost.currency();
+        let cost = Money::sum(pooled.iter().map(|&i| &self.lots[i].cost), currency)?;
+        let cost_kes = Money::sum(
+            pooled.iter().map(|&i| &self.lots[i].cost_kes),
+            Currency::Kes,
+        )?;
+        let ratios: Vec<Decimal> = pooled.iter().map(|&i| self.lots[i].quantity).collect();
+        let costs = cost.allocate(&ratios)?;
+        let costs_kes = cost_kes.allocate(&ratios)?;
+        for ((&i, cost), cost_kes) in pooled.iter().zip(costs).zip(costs_kes) {
+            self.lots[i].cost = cost;
+            self.lots[i].cost_kes = cost_kes;
+        }
+        Ok(())
+    }
+
+    /// Works out taking `quantity` of `instrument` out of the open lots without
+    /// touching the book: returns the lots that would remain and the pieces removed,
+    /// each carrying its share of cost.
+    fn relieve(
+        &self,
+        event: &str,
+        instrument: &str,
+        quantity: Decimal,
+        selection: Option<&[LotSelection]>,
+    ) -> Result<(Vec<Lot>, Vec<Lot>), LotError> {
+        if self.quantity(instrument) < quantity {
+            return Err(LotError::InsufficientQuantity {
+                event: event.to_string(),
+                instrument: instrument.to_string(),
+            });
+        }
+        let plan: Vec<(usize, Decimal)> = match self.method {
+            ReliefMethod::SpecificLot => {
+                let selection =
+                    selection.ok_or_else(|| LotError::MissingLotSelection(event.to_string()))?;
+                let selected: Decimal = selection.iter().map(|s| s.quantity).sum();
+                if selected != quantity {
+                    return Err(invalid(event, "selected lots must add up to the quantity"));
+                }
+                let mut plan: Vec<(usize, Decimal)> = Vec::with_capacity(selection.len());
+                for s in selection {
+                    let unknown = || LotError::UnknownLot {
+                        event: event.to_string(),
+                        lot: s.lot.clone(),
+                    };
+                    let i = self
+                        .lots
+                        .iter()
+                        .position(|l| l.id == s.lot && l.instrument == instrument)
+                        .ok_or_else(unknown)?;
+                    let taken: Decimal = plan.iter().filter(|p| p.0 == i).map(|p| p.1).sum();
+                    if s.quantity <= Decimal::ZERO || taken + s.quantity > self.lots[i].quantity {
+                        return Err(invalid(event, "selected quantity exceeds the lot"));
+                    }
+                    plan.push((i, s.quantity));
+                }
+                plan
+            }
+            // Average cost lots share one unit cost, so taking them oldest first
+            // relieves the pooled average while keeping acquisition dates honest.
+            ReliefMethod::Fifo | ReliefMethod::AverageCost => {
+                let mut order: Vec<usize> = (0..self.lots.len())
+                    .filter(|&i| self.lots[i].instrument == instrument)
+                    .collect();
+                order.sort_by_key(|&i| self.lots[i].acquired);
+                let mut left = quantity;
+                let mut plan = Vec::new();
+                for i in order {
+                    if left.is_zero() {
+                        break;
+                    }
+                    let take = left.min(self.lots[i].quantity);
+                    plan.push((i, take));
+                    left -= take;
+                }
+                plan
+            }
+        };
+
+        let mut remaining = self.lots.clone();
+        let mut relieved = Vec::with_capacity(plan.len());
+        for (i, take) in plan {
+            let lot = &mut remaining[i];
+            let (cost, cost_kes) = if take == lot.quantity {
+                (lot.cost, lot.cost_kes)
+            } else {
+                let share = take / lot.quantity;
+                (
+                    lot.cost.checked_mul(share)?,
+                    lot.cost_kes.checked_mul(share)?,
+                )
+            };
+            lot.quantity -= take;
+            lot.cost = lot.cost.checked_sub(&cost)?;
+            lot.cost_kes = lot.cost_kes.checked_sub(&cost_kes)?;
+            relieved.push(Lot {
+                id: lot.id.clone(),
+                instrument: lot.instrument.clone(),
+                acquired: lot.acquired,
+                quantity: take,
+                cost,
+                cost_kes,
+            });
+        }
+        remaining.retain(|l| !l.quantity.is_zero());
+        Ok((remaining, relieved))
+    }
+}
+
+/// Price of an instrument on the valuation date, with the KES rate for that date.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MarketPrice {
+    pub instrument: String,
+    pub price: Decimal,
+    #[serde(default)]
+    pub kes_rate: Option<Decimal>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LotValuation {
+    pub lot: Lot,
+    pub market_value: Money,
+    pub unrealised_gain: Money,
+    pub market_value_kes: Money,
+    pub unrealised_gain_kes: Money,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LotReport {
+    pub method: ReliefMethod,
+    pub open_lots: Vec<LotValuation>,
+    pub disposals: Vec<Disposal>,
+    pub transfers: Vec<Transfer>,
+    pub realised_gain_kes: Money,
+    pub unrealised_gain_kes: Money,
+}
+
+/// Values the open lots of `book` at `prices`.
+pub fn value_lots(book: &LotBook, prices: &[MarketPrice]) -> Result<Vec<LotValuation>, LotError> {
+    book.lots()
+        .iter()
+        .map(|lot| {
+            let mark = prices
+                .iter()
+                .find(|p| p.instrument == lot.instrument)
+                .ok_or_else(|| LotError::MissingPrice(lot.instrument.clone()))?;
+            let currency = lot.cost.currency();
+            let rate = kes_rate(&mark.instrument, currency, mark.kes_rate)?;
+            let value = mark
+                .price
+                .checked_mul(lot.quantity)
+                .ok_or(MoneyError::Overflow)?;
+            let market_value = Money::new(value, currency);
+            let market_value_kes = Money::new(
+                value.checked_mul(rate).ok_or(MoneyError::Overflow)?,
+                Currency::Kes,
+            );
+            Ok(LotValuation {
+                unrealised_gain: market_value.checked_sub(&lot.cost)?,
+                unrealised_gain_kes: market_value_kes.checked_sub(&lot.cost_kes)?,
+                lot: lot.clone(),
+                market_value,
+                market_value_kes,
+            })
+        })
+        .collect()
+}
+
+/// Applies `events` in date order (ties keep their given order) and values what is left.
+pub fn track_lots(
+    method: ReliefMethod,
+    events: &[LotEvent],
+    prices: &[MarketPrice],
+) -> Result<LotReport, LotError> {
+    let mut ordered: Vec<&LotEvent> = events.iter().collect();
+    ordered.sort_by_key(|e| e.date());
+    let mut book = LotBook::new(method);
+    for event in ordered {
+        book.apply(event)?;
+    }
+    let open_lots = value_lots(&book, prices)?;
+    let realised_gain_kes =
+        Money::sum(book.disposals().iter().map(|d| &d.gain_kes), Currency::Kes)?;
+    let unrealised_gain_kes = Money::sum(
+        open_lots.iter().map(|v| &v.unrealised_gain_kes),
+        Currency::Kes,
+    )?;
+    Ok(LotReport {
+        method,
+        open_lots,
+        disposals: book.disposals().to_vec(),
+        transfers: book.transfers().to_vec(),
+        realised_gain_kes,
+        unrealised_gain_kes,
+    })
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TrackLotsInput {
+    #[serde(default)]
+    method: ReliefMethod,
+    events: Vec<LotEvent>,
+    #[serde(default)]
+    prices: Vec<MarketPrice>,
+}
+
+/// JS entry point: `{ method?, events: [{ kind, … }], prices? }` in, `LotReport` out.
+#[wasm_bindgen(js_name = trackLots)]
+pub fn track_lots_js(input: JsValue) -> Result<JsValue, JsError> {
+    let input: TrackLotsInput = from_js(input)?;
+    let report = track_lots(input.method, &input.events, &input.prices).map_err(js_err)?;
+    to_js(&report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn d(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn dec(s: &str) -> Decimal {
+        Decimal::from_str(s).unwrap()
+    }
+
+    fn kes(s: &str) -> Money {
+        Money::parse(s, Currency::Kes).unwrap()
+    }
+
+    fn buy(id: &str, date: &str, quantity: &str, price: &str) -> LotEvent {
+        LotEvent::Buy {
+            id: id.to_string(),
+            date: d(date),
+            instrument: "SCOM".to_string(),
+            quantity: dec(quantity),
+            price: dec(price),
+            fees: Decimal::ZERO,
+            currency: Currency::Kes,
+            kes_rate: None,
+        }
+    }
+
+    fn sell(id: &str, date: &str, quantity: &str, price: &str) -> LotEvent {
+        LotEvent::Sell {
+            id: id.to_string(),
+            date: d(date),
+            instrument: "SCOM".to_string(),
+            quantity: dec(quantity),
+            price: dec(price),
+            fees: Decimal::ZERO,
+            currency: Currency::Kes,
+            kes_rate: None,
+            lots: None,
+        }
+    }
+
+    fn select(lot: &str, quantity: &str) -> LotSelection {
+        LotSelection {
+            lot: lot.to_string(),
+            quantity: dec(quantity),
+        }
+    }
+
+    fn two_buys(method: ReliefMethod) -> LotBook {
+        let mut book = LotBook::new(method);
+        book.apply(&buy("B1", "2024-01-10", "100", "10")).unwrap();
+        book.apply(&buy("B2", "2024-03-10", "100", "20")).unwrap();
+        book
+    }
+
+    fn gains(book: &LotBook) -> Vec<(&str, Decimal, Money, Money)> {
+        book.disposals()
+            .iter()
+            .map(|x| (x.lot.as_str(), x.quantity, x.cost, x.gain))
+            .collect()
+    }
+
+    #[test]
+    fn fifo_relieves_the_oldest_lot_first() {
+        let mut book = two_buys(ReliefMethod::Fifo);
+        book.apply(&sell("S1", "2024-06-10", "150", "30")).unwrap();
+        assert_eq!(
+            gains(&book),
+            vec![
+                ("B1", dec("100"), kes("1000"), kes("2000")),
+                ("B2", dec("50"), kes("1000"), kes("500")),
+            ]
+        );
+        assert_eq!(book.quantity("SCOM"), dec("50"));
+        assert_eq!(book.lots()[0].cost, kes("1000"));
+    }
+
+    #[test]
+    fn average_cost_relieves_the_pooled_cost() {
+        let mut book = two_buys(ReliefMethod::AverageCost);
+        book.apply(&sell("S1", "2024-06-10", "150", "30")).unwrap();
+        // 3,000 over 200 shares is 15 a share whichever lot goes.
+        assert_eq!(
+            gains(&book),
+            vec![
+                ("B1", dec("100"), kes("1500"), kes("1500")),
+                ("B2", dec("50"), kes("750"), kes("750")),
+            ]
+        );
+        assert_eq!(book.lots()[0].cost, kes("750"));
+    }
+
+    #[test]
+    fn specific_lot_relieves_the_named_lots() {
+        let mut book = two_buys(ReliefMethod::SpecificLot);
+        assert_eq!(
+            book.apply(&sell("S0", "2024-06-10", "150", "30")),
+            Err(LotError::MissingLotSelection("S0".to_string()))
+        );
+        let mut event = sell("S1", "2024-06-10", "150", "30");
+        if let LotEvent::Sell { lots, .. } = &mut event {
+            *lots = Some(vec![select("B2", "100"), select("B1", "50")]);
+        }
+        book.apply(&event).unwrap();
+        assert_eq!(
+            gains(&book),
+            vec![
+                ("B2", dec("100"), kes("2000"), kes("1000")),
+                ("B1", dec("50"), kes("500"), kes("1000")),
+            ]
+        );
+        assert_eq!(book.lots().len(), 1);
+        assert_eq!(book.lots()[0].id, "B1");
+    }
+
+    #[test]
+    fn kes_gain_uses_the_rate_on_each_date() {
+        let mut book = LotBook::new(ReliefMethod::Fifo);
+        book.apply(&LotEvent::Buy {
+            id: "B1".to_string(),
+            date: d("2022-01-10"),
+            instrument: "AAPL".to_string(),
+            quantity: dec("10"),
+            price: dec("100"),
+            fees: Decimal::ZERO,
+            currency: Currency::Usd,
+            kes_rate: Some(dec("113.50")),
+        })
+        .unwrap();
+        book.apply(&LotEvent::Sell {
+            id: "S1".to_string(),
+            date: d("2024-01-10"),
+            instrument: "AAPL".to_string(),
+            quantity: dec("10"),
+            price: dec("110"),
+            fees: dec("10"),
+            currency: Currency::Usd,
+            kes_rate: Some(dec("158.00")),
+            lots: None,
+        })
+        .unwrap();
+        let sold = &book.disposals()[0];
+        assert_eq!(sold.gain, Money::parse("90", Currency::Usd).unwrap());
+        assert_eq!(sold.cost_kes, kes("113500"));
+        assert_eq!(sold.proceeds_kes, kes("172220"));
+        // 90 dollars of gain plus the shilling's fall on the 1,000 cost.
+        assert_eq!(sold.gain_kes, kes("58720"));
+
+        let missing = LotEvent::Buy {
+            id: "B2".to_string(),
+            date: d("2024-02-10"),
+            instrument: "AAPL".to_string(),
+            quantity: dec("1"),
+            price: dec("100"),
+            fees: Decimal::ZERO,
+            currency: Currency::Usd,
+            kes_rate: None,
+        };
+        assert_eq!(
+            book.apply(&missing),
+            Err(LotError::MissingKesRate("B2".to_string()))
+        );
+    }
+
+    #[test]
+    fn transfers_carry_cost_and_acquisition_date() {
+        let mut book = LotBook::new(ReliefMethod::Fifo);
+        book.apply(&buy("B1", "2024-01-10", "100", "10")).unwrap();
+        book.apply(&LotEvent::TransferIn {
+            id: "T1".to_string(),
+            date: d("2024-02-01"),
+            instrument: "SCOM".to_string(),
+            quantity: dec("100"),
+            cost: dec("500"),
+            currency: Currency::Kes,
+            acquired: d("2020-05-04"),
+            kes_rate: None,
+        })
+        .unwrap();
+        book.apply(&LotEvent::TransferOut {
+            id: "T2".to_string(),
+            date: d("2024-03-01"),
+            instrument: "SCOM".to_string(),
+            quantity: dec("60"),
+            lots: None,
+        })
+        .unwrap();
+        // The transferred-in lot was acquired first, so FIFO moves it out first.
+        let moved = &book.transfers()[0];
+        assert_eq!(moved.lot.id, "T1");
+        assert_eq!(moved.lot.acquired, d("2020-05-04"));
+        assert_eq!(moved.lot.cost, kes("300"));
+        assert!(book.disposals().is_empty());
+        assert_eq!(book.quantity("SCOM"), dec("140"));
+    }
+
+    #[test]
+    fn splits_keep_the_cost_basis() {
+        let mut book = LotBook::new(ReliefMethod::Fifo);
+        book.apply(&buy("B1", "2024-01-10", "100", "10")).unwrap();
+        book.apply(&LotEvent::Split {
+            id: "X1".to_string(),
+            date: d("2024-02-01"),
+            instrument: "SCOM".to_string(),
+            ratio: dec("2"),
+        })
+        .unwrap();
+        assert_eq!(book.quantity("SCOM"), dec("200"));
+        assert_eq!(book.lots()[0].cost, kes("1000"));
+        book.apply(&sell("S1", "2024-03-01", "200", "6")).unwrap();
+        assert_eq!(book.disposals()[0].gain, kes("200"));
+    }
+
+    #[test]
+    fn failed_sells_leave_the_book_untouched() {
+        let mut book = two_buys(ReliefMethod::SpecificLot);
+        let before = book.lots().to_vec();
+        let mut unknown = sell("S1", "2024-06-10", "50", "30");
+        if let LotEvent::Sell { lots, .. } = &mut unknown {
+            *lots = Some(vec![select("B9", "50")]);
+        }
+        assert_eq!(
+            book.apply(&unknown),
+            Err(LotError::UnknownLot {
+                event: "S1".to_string(),
+                lot: "B9".to_string(),
+            })
+        );
+
+        assert_eq!(book.lots(), &before[..]);
+
+        let mut book = two_buys(ReliefMethod::Fifo);
+        assert_eq!(
+            book.apply(&sell("S2", "2024-06-10", "201", "30")),
+            Err(LotError::InsufficientQuantity {
+                event: "S2".to_string(),
+                instrument: "SCOM".to_string(),
+            })
+        );
+        // Selling KES lots in dollars fails only once the gain is worked out.
+        let wrong_currency = LotEvent::Sell {
+            id: "S3".to_string(),
+            date: d("2024-06-10"),
+            instrument: "SCOM".to_string(),
+            quantity: dec("150"),
+            price: dec("0.25"),
+            fees: Decimal::ZERO,
+            currency: Currency::Usd,
+            kes_rate: Some(dec("130")),
+            lots: None,
+        };
+        assert!(matches!(
+            book.apply(&wrong_currency),
+            Err(LotError::Money(MoneyError::CurrencyMismatch { .. }))
+        ));
+        assert_eq!(book.lots(), &before[..]);
+        assert!(book.disposals().is_empty());
+    }
+
+    #[test]
+    fn refused_lots_leave_the_book_untouched() {
+        let mut book = two_buys(ReliefMethod::AverageCost);
+        let before = book.lots().to_vec();
+        // Dollar shares cannot join a shilling pool.
+        let usd = LotEvent::Buy {
+            id: "B3".to_string(),
+            date: d("2024-04-10"),
+            instrument: "SCOM".to_string(),
+            quantity: dec("10"),
+            price: dec("0.2"),
+            fees: Decimal::ZERO,
+            currency: Currency::Usd,
+            kes_rate: Some(dec("130")),
+        };
+        assert!(matches!(
+            book.apply(&usd),
+            Err(LotError::Money(MoneyError::CurrencyMismatch { .. }))
+        ));
+        assert_eq!(book.lots(), &before[..]);
+
+        assert_eq!(
+            book.apply(&buy("B1", "2024-04-10", "10", "15")),
+            Err(LotError::DuplicateLot("B1".to_string()))
+        );
+        book.apply(&sell("S1", "2024-06-10", "200", "30")).unwrap();
+        assert!(book.lots().is_empty());
+        // A sold lot's id still names its disposals, so it cannot be reused either.
+        assert_eq!(
+            book.apply(&buy("B2", "2024-07-10", "10", "15")),
+            Err(LotError::DuplicateLot("B2".to_string()))
+        );
+        assert!(book.lots().is_empty());
+
+        assert_eq!(
+            book.apply(&buy("B4", "2024-07-10", "10", "-1")),
+            Err(LotError::InvalidEvent {
+                event: "B4".to_string(),
+                why: "quantity must be positive, price and fees must not be negative",
+            })
+        );
+    }
+}
@@ -0,0 +1,448 @@
+use std::fmt;
+
+use chrono::{Datelike, Days, NaiveDate};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::js::{from_js, js_err, to_js};
+use crate::money::{parse_decimal, Money, MoneyError};
+
+/// Actual/365 fixed: every day earns 1/365 of the annual rate, leap years included.
+pub const DAYS_IN_YEAR: u32 = 365;
+
+/// The annual rate (a fraction, 0.155 = 15.5%) in force from `effective` until the next point.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RatePoint {
+    pub effective: NaiveDate,
+    pub annual_rate: Decimal,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FlowKind {
+    Deposit,
+    Withdrawal,
+}
+
+/// A deposit or withdrawal, applied at the start of `date` so it earns (or stops
+/// earning) interest that same day. A partial withdrawal may not exceed the balance
+/// already credited; interest accrued but not yet capitalised is only paid out early
+/// by a full redemption, i.e. a withdrawal of exactly the balance plus the accrual
+/// rounded to the minor unit.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MmfFlow {
+    pub date: NaiveDate,
+    pub kind: FlowKind,
+    pub amount: Money,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Capitalisation {
+    /// Interest joins the balance every day.
+    Daily,
+    /// Interest accrues daily and is credited on the last calendar day of each month.
+    Monthly,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MmfSimulation {
+    pub opening_balance: Money,
+    pub start: NaiveDate,
+    /// Last day that accrues interest (inclusive).
+    pub end: NaiveDate,
+    pub rates: Vec<RatePoint>,
+    #[serde(default)]
+    pub flows: Vec<MmfFlow>,
+    pub capitalisation: Capitalisation,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LedgerDay {
+    pub date: NaiveDate,
+    pub annual_rate: Decimal,
+    pub opening_balance: Money,
+    pub deposits: Money,
+    pub withdrawals: Money,
+    /// Interest earned on the day, before rounding to the minor unit.
+    pub interest: Decimal,
+    /// Interest accrued but not yet credited, at full precision.
+    pub accrued: Decimal,
+    pub capitalised: Money,
+    pub closing_balance: Money,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MmfResult {
+    pub days: Vec<LedgerDay>,
+    pub total_deposits: Money,
+    pub total_withdrawals: Money,
+    pub total_interest_credited: Money,
+    /// Accrued interest still to be credited after the last day.
+    pub accrued_uncredited: Decimal,
+    pub closing_balance: Money,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum MmfError {
+    InvalidPeriod,
+    NoRate(NaiveDate),
+    FlowOutsidePeriod(NaiveDate),
+    NonPositiveFlow(NaiveDate),
+    InsufficientBalance {
+        date: NaiveDate,
+        requested: Money,
+        available: Money,
+    },
+    Money(MoneyError),
+}
+
+impl fmt::Display for MmfError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MmfError::InvalidPeriod => f.write_str("simulation end is before its start"),
+            MmfError::NoRate(date) => write!(f, "no rate in force on {}", date),
+            MmfError::FlowOutsidePeriod(date) => {
+                write!(f, "cash flow on {} is outside the simulation period", date)
+            }
+            MmfError::NonPositiveFlow(date) => {
+                write!(f, "cash flow on {} must be a positive amount", date)
+            }
+            MmfError::InsufficientBalance {
+                date,
+                requested,
+                available,
+            } => write!(
+                f,
+                "withdrawal of {} on {} exceeds available {}",
+                requested, date, available
+            ),
+            MmfError::Money(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for MmfError {}
+
+impl From<MoneyError> for MmfError {
+    fn from(err: MoneyError) -> Self {
+        MmfError::Money(err)
+    }
+}
+
+fn rate_on(rates: &[RatePoint], date: NaiveDate) -> Result<Decimal, MmfError> {
+    rates
+        .iter()
+        .filter(|p| p.effective <= date)
+        .max_by_key(|p| p.effective)
+        .map(|p| p.annual_rate)
+        .ok_or(MmfError::NoRate(date))
+}
+
+fn is_month_end(date: NaiveDate) -> bool {
+    date.succ_opt()
+        .is_none_or(|next| next.month() != date.month())
+}
+
+/// Simple actual/365 interest on a constant balance, e.g. for a "monthly yield" estimate.
+pub fn estimate_interest(
+    balance: &Money,
+    annual_rate: Decimal,
+    days: u32,
+) -> Result<Money, MoneyError> {
+    balance.checked_mul(annual_rate * Decimal::from(days) / Decimal::from(DAYS_IN_YEAR))
+}
+
+/// Runs the fund day by day from `start` to `end`, producing one ledger row per day.
+///
+/// Interest is accrued at full precision and only rounded when credited; any
+/// sub-cent residue stays in the accrual so nothing is lost across months.
+pub fn simulate(sim: &MmfSimulation) -> Result<MmfResult, MmfError> {
+    if sim.end < sim.start {
+        return Err(MmfError::InvalidPeriod);
+    }
+    let currency = sim.opening_balance.currency();
+    for flow in &sim.flows {
+        if flow.date < sim.start || flow.date > sim.end {
+            return Err(MmfError::FlowOutsidePeriod(flow.date));
+        }
+        if !flow.amount.is_positive() {
+            return Err(MmfError::NonPositiveFlow(flow.date));
+        }
+    }
+
+    let mut balance = sim.opening_balance;
+    let mut accrued = Decimal::ZERO;
+    let mut total_deposits = Money::zero(currency);
+    let mut total_withdrawals = Money::zero(currency);
+    let mut total_credited = Money::zero(currency);
+    let mut days = Vec::new();
+
+    let mut date = sim.start;
+    loop {
+        let annual_rate = rate_on(&sim.rates, date)?;
+        let opening_balance = balance;
+        let mut deposits = Money::zero(currency);
+        let mut withdrawals = Money::zero(currency);
+        let mut capitalised = Money::zero(currency);
+
+        for flow in sim.flows.iter().filter(|f| f.date == date) {
+            match flow.kind {
+                FlowKind::Deposit => {
+                    balance = balance.checked_add(&flow.amount)?;
+                    deposits = deposits.checked_add(&flow.amount)?;
+                }
+                FlowKind::Withdrawal => {
+                    // A full redemption closes the holding, so the interest accrued
+                    // to date is paid out with it. Partial withdrawals leave the
+                    // accrual for the next capitalisation.
+                    let credit = Money::new(accrued, currency);
+                    if credit.is_positive() && flow.amount == balance.checked_add(&credit)? {
+                        accrued -= credit.amount();
+                        balance = balance.checked_add(&credit)?;
+                        capitalised = capitalised.checked_add(&credit)?;
+                    }
+                    if flow.amount > balance {
+                        return Err(MmfError::InsufficientBalance {
+                            date,
+                            requested: flow.amount,
+                            available: balance,
+                        });
+                    }
+                    balance = balance.checked_sub(&flow.amount)?;
+                    withdrawals = withdrawals.checked_add(&flow.amount)?;
+                }
+            }
+        }
+
+        let interest = balance
+            .amount()
+            .checked_mul(annual_rate)
+            .and_then(|daily| daily.checked_div(Decimal::from(DAYS_IN_YEAR)))
+            .ok_or(MoneyError::Overflow)?;
+        accrued = accrued.checked_add(interest).ok_or(MoneyError::Overflow)?;
+
+        let credit_today = match sim.capitalisation {
+            Capitalisation::Daily => true,
+            Capitalisation::Monthly => is_month_end(date),
+        };
+        if credit_today {
+            let credit = Money::new(accrued, currency);
+            accrued -= credit.amount();
+            balance = balance.checked_add(&credit)?;
+            capitalised = capitalised.checked_add(&credit)?;
+        }
+
+        total_deposits = total_deposits.checked_add(&deposits)?;
+        total_withdrawals = total_withdrawals.checked_add(&withdrawals)?;
+        total_credited = total_credited.checked_add(&capitalised)?;
+        days.push(LedgerDay {
+            date,
+            annual_rate,
+            opening_balance,
+            deposits,
+            withdrawals,
+            interest,
+            accrued,
+            capitalised,
+            closing_balance: balance,
+        });
+
+        if date == sim.end {
+            break;
+        }
+        date = date
+            .checked_add_days(Days::new(1))
+            .ok_or(MmfError::InvalidPeriod)?;
+    }
+
+    Ok(MmfResult {
+        days,
+        total_deposits,
+        total_withdrawals,
+        total_interest_credited: total_credited,
+        accrued_uncredited: accrued,
+        closing_balance: balance,
+    })
+}
+
+/// JS entry point: takes an `MmfSimulation`-shaped object and returns the day-by-day ledger.
+#[wasm_bindgen(js_name = simulateMmf)]
+pub fn simulate_js(input: JsValue) -> Result<JsValue, JsError> {
+    let sim: MmfSimulation = from_js(input)?;
+    let result = simulate(&sim).map_err(js_err)?;
+    to_js(&result)
+}
+
+/// Estimated interest on `balance` over `days` at `annualRate`, actual/365.
+#[wasm_bindgen(js_name = estimateMmfInterest)]
+pub fn estimate_interest_js(
+    balance: &Money,
+    annual_rate: &str,
+    days: u32,
+) -> Result<Money, JsError> {
+    let rate = parse_decimal(annual_rate).map_err(js_err)?;
+    estimate_interest(balance, rate, days).map_err(js_err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    use crate::money::Currency;
+
+    fn d(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn dec(s: &str) -> Decimal {
+        Decimal::from_str(s).unwrap()
+    }
+
+    fn kes(s: &str) -> Money {
+        Money::parse(s, Currency::Kes).unwrap()
+    }
+
+    fn sim(start: &str, end: &str, capitalisation: Capitalisation) -> MmfSimulation {
+        MmfSimulation {
+            opening_balance: kes("100000"),
+            start: d(start),
+            end: d(end),
+            rates: vec![RatePoint {
+                effective: d("2024-01-01"),
+                annual_rate: dec("0.1460"),
+            }],
+            flows: vec![],
+            capitalisation,
+        }
+    }
+
+    #[test]
+    fn accrues_actual_365_even_in_a_leap_year() {
+        // 100,000 x 14.6% / 365 = 40.00 a day, including 29 February.
+        let r = simulate(&sim("2024-02-01", "2024-02-29", Capitalisation::Monthly)).unwrap();
+        assert_eq!(r.days.len(), 29);
+        assert_eq!(r.days[0].interest, dec("40"));
+        assert!(r.days[..28].iter().all(|day| day.capitalised.is_zero()));
+        assert_eq!(r.days[28].capitalised, kes("1160"));
+        assert_eq!(r.closing_balance, kes("101160"));
+        assert_eq!(r.accrued_uncredited, Decimal::ZERO);
+        assert_eq!(
+            estimate_interest(&kes("100000"), dec("0.146"), 30).unwrap(),
+            kes("1200")
+        );
+    }
+
+    #[test]
+    fn monthly_capitalisation_compounds_from_the_next_month() {
+        let r = simulate(&sim("2024-01-31", "2024-02-01", Capitalisation::Monthly)).unwrap();
+        assert_eq!(r.days[0].capitalised, kes("40"));
+        // February earns on 100,040.
+        assert_eq!(
+            r.days[1].interest,
+            dec("100040") * dec("0.146") / dec("365")
+        );
+        assert_eq!(r.total_interest_credited, kes("40"));
+    }
+
+    #[test]
+    fn daily_capitalisation_keeps_the_sub_cent_residue() {
+        let mut s = sim("2024-03-01", "2024-03-03", Capitalisation::Daily);
+        s.opening_balance = kes("1000");
+        s.rates[0].annual_rate = dec("0.10");
+        let r = simulate(&s).unwrap();
+        // 1000 x 0.10 / 365 = 0.27397..., credited as 0.27 with the rest carried.
+        assert_eq!(r.days[0].capitalised, kes("0.27"));
+        assert!(r.days[0].accrued > Decimal::ZERO && r.days[0].accrued < dec("0.01"));
+        let credited = Money::sum(r.days.iter().map(|d| &d.capitalised), Currency::Kes).unwrap();
+        assert_eq!(credited, r.total_interest_credited);
+    }
+
+    #[test]
+    fn rate_changes_take_effect_on_their_date() {
+        let mut s = sim("2024-06-01", "2024-06-02", Capitalisation::Monthly);
+        s.rates.push(RatePoint {
+            effective: d("2024-06-02"),
+            annual_rate: dec("0.0730"),
+        });
+        let r = simulate(&s).unwrap();
+        assert_eq!(r.days[0].interest, dec("40"));
+        assert_eq!(r.days[1].interest, dec("20"));
+    }
+
+    #[test]
+    fn withdrawals_are_limited_to_the_credited_balance() {
+        let mut s = sim("2024-04-01", "2024-04-30", Capitalisation::Monthly);
+        s.flows.push(MmfFlow {
+            date: d("2024-04-11"),
+            kind: FlowKind::Withdrawal,
+            amount: kes("100000.01"),
+        });
+        // Ten days of accrual (400.00) are not yet credited, so they cannot be drawn.
+        assert_eq!(
+            simulate(&s),
+            Err(MmfError::InsufficientBalance {
+                date: d("2024-04-11"),
+                requested: kes("100000.01"),
+                available: kes("100000"),
+            })
+        );
+
+        // Withdrawing the credited balance leaves the accrual to be credited at month end.
+        s.flows[0].amount = kes("100000");
+        let r = simulate(&s).unwrap();
+        assert_eq!(r.days[10].closing_balance, Money::zero(Currency::Kes));
+        assert_eq!(r.days[29].capitalised, kes("400"));
+        assert_eq!(r.closing_balance, kes("400"));
+
+        // A full redemption pays the accrual out with the balance and closes the holding.
+        s.flows[0].amount = kes("100400");
+        let r = simulate(&s).unwrap();
+        assert_eq!(r.days[10].capitalised, kes("400"));
+        assert_eq!(r.days[10].withdrawals, kes("100400"));
+        assert_eq!(r.days[10].closing_balance, Money::zero(Currency::Kes));
+        assert_eq!(r.total_interest_credited, kes("400"));
+        assert_eq!(r.accrued_uncredited, Decimal::ZERO);
+        assert_eq!(r.closing_balance, Money::zero(Currency::Kes));
+
+        // Anything between the credited balance and a full redemption is still refused.
+        s.flows[0].amount = kes("100200");
+        assert!(matches!(
+            simulate(&s),
+            Err(MmfError::InsufficientBalance { .. })
+        ));
+    }
+
+    #[test]
+    fn an_absurd_balance_overflows_instead_of_panicking() {
+        let mut s = sim("2024-04-01", "2024-04-02", Capitalisation::Daily);
+        s.opening_balance = Money::new(Decimal::MAX, Currency::Kes);
+        s.rates[0].annual_rate = dec("1000");
+        assert_eq!(simulate(&s), Err(MmfError::Money(MoneyError::Overflow)));
+    }
+
+    #[test]
+    fn rejects_bad_inputs() {
+        let mut s = sim("2024-04-01", "2024-03-01", Capitalisation::Daily);
+        assert_eq!(simulate(&s), Err(MmfError::InvalidPeriod));
+        s.end = d("2024-04-30");
+        s.flows.push(MmfFlow {
+            date: d("2024-05-01"),
+            kind: FlowKind::Deposit,
+            amount: kes("1"),
+        });
+        assert_eq!(
+            simulate(&s),
+            Err(MmfError::FlowOutsidePeriod(d("2024-05-01")))
+        );
+        s.rates[0].effective = d("2024-04-02");
+        s.flows.clear();
+        assert_eq!(simulate(&s), Err(MmfError::NoRate(d("2024-04-01"))));
+    }
+}
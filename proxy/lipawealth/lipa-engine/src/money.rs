@@ -0,0 +1,596 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+use rust_decimal::prelude::*;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use wasm_bindgen::prelude::*;
+
+use crate::js::js_err;
+
+/// ISO 4217 currencies the engine knows how to book.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Currency {
+    Kes,
+    Usd,
+    Gbp,
+    Eur,
+    Ugx,
+    Tzs,
+}
+
+impl Currency {
+    pub const ALL: [Currency; 6] = [
+        Currency::Kes,
+        Currency::Usd,
+        Currency::Gbp,
+        Currency::Eur,
+        Currency::Ugx,
+        Currency::Tzs,
+    ];
+
+    /// Three-letter ISO 4217 code.
+    pub fn code(self) -> &'static str {
+        match self {
+            Currency::Kes => "KES",
+            Currency::Usd => "USD",
+            Currency::Gbp => "GBP",
+            Currency::Eur => "EUR",
+            Currency::Ugx => "UGX",
+            Currency::Tzs => "TZS",
+        }
+    }
+
+    /// Number of decimal places in the currency's minor unit (ISO 4217 exponent).
+    pub fn minor_units(self) -> u32 {
+        match self {
+            Currency::Ugx => 0,
+            _ => 2,
+        }
+    }
+}
+
+impl fmt::Display for Currency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+impl Serialize for Currency {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.code())
+    }
+}
+
+impl<'de> Deserialize<'de> for Currency {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let code = String::deserialize(deserializer)?;
+        Currency::from_str(&code).map_err(serde::de::Error::custom)
+    }
+}
+
+impl FromStr for Currency {
+    type Err = MoneyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let code = s.trim().to_ascii_uppercase();
+        Currency::ALL
+            .into_iter()
+            .find(|c| c.code() == code)
+            .ok_or(MoneyError::UnknownCurrency(s.to_string()))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MoneyError {
+    CurrencyMismatch { left: Currency, right: Currency },
+    Overflow,
+    DivisionByZero,
+    UnknownCurrency(String),
+    InvalidAmount(String),
+    InvalidAllocation(&'static str),
+}
+
+impl fmt::Display for MoneyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MoneyError::CurrencyMismatch { left, right } => {
+                write!(f, "currency mismatch: {} vs {}", left, right)
+            }
+            MoneyError::Overflow => f.write_str("arithmetic overflow"),
+            MoneyError::DivisionByZero => f.write_str("division by zero"),
+            MoneyError::UnknownCurrency(code) => write!(f, "unknown currency: {}", code),
+            MoneyError::InvalidAmount(raw) => write!(f, "invalid amount: {}", raw),
+            MoneyError::InvalidAllocation(why) => write!(f, "invalid allocation: {}", why),
+        }
+    }
+}
+
+impl std::error::Error for MoneyError {}
+
+/// Rounds to `dp` places, sending midpoints to the even neighbour.
+pub fn round_bankers(value: Decimal, dp: u32) -> Decimal {
+    value.round_dp_with_strategy(dp, RoundingStrategy::MidpointNearestEven)
+}
+
+/// A decimal amount in a single currency, always held at the currency's minor unit.
+///
+/// Every constructor and arithmetic result is rounded half-to-even, so two
+/// computations that agree on paper agree to the cent.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "MoneyRepr")]
+pub struct Money {
+    amount: Decimal,
+    currency: Currency,
+}
+
+/// Wire shape for [`Money`]; deserialising goes through [`Money::new`] so the
+/// amount is re-rounded to the currency's minor unit.
+#[derive(Deserialize)]
+struct MoneyRepr {
+    amount: Decimal,
+    currency: Currency,
+}
+
+impl From<MoneyRepr> for Money {
+    fn from(repr: MoneyRepr) -> Money {
+        Money::new(repr.amount, repr.currency)
+    }
+}
+
+impl Money {
+    pub fn new(amount: Decimal, currency: Currency) -> Money {
+        let mut amount = round_bankers(amount, currency.minor_units());
+        // A fixed scale keeps serialised amounts canonical ("1000.00", never "1000").
+        amount.rescale(currency.minor_units());
+        Money { amount, currency }
+    }
+
+    pub fn zero(currency: Currency) -> Money {
+        Money::new(Decimal::ZERO, currency)
+    }
+
+    pub fn from_minor(minor: i64, currency: Currency) -> Money {
+        Money {
+            amount: Decimal::new(minor, currency.minor_units()),
+            currency,
+        }
+    }
+
+    /// Parses a plain decimal string such as `"148200000.50"`.
+    pub fn parse(amount: &str, currency: Currency) -> Result<Money, MoneyError> {
+        let cleaned: String = amount.trim().chars().filter(|c| *c != ',').collect();
+        let value = Decimal::from_str(&cleaned)
+            .map_err(|_| MoneyError::InvalidAmount(amount.to_string()))?;
+        Ok(Money::new(value, currency))
+    }
+
+    pub fn amount(&self) -> Decimal {
+        self.amount
+    }
+
+    pub fn currency(&self) -> Currency {
+        self.currency
+    }
+
+    /// The amount expressed as an integer count of minor units (cents, for KES).
+    pub fn to_minor(&self) -> Result<i64, MoneyError> {
+        let scaled = self
+            .amount
+            .checked_mul(Decimal::from(10i64.pow(self.currency.minor_units())))
+            .ok_or(MoneyError::Overflow)?;
+        scaled.to_i64().ok_or(MoneyError::Overflow)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.amount.is_zero()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.amount.is_sign_negative() && !self.amount.is_zero()
+    }
+
+    pub fn is_positive(&self) -> bool {
+        self.amount.is_sign_positive() && !self.amount.is_zero()
+    }
+
+    pub fn abs(&self) -> Money {
+        Money {
+            amount: self.amount.abs(),
+            currency: self.currency,
+        }
+    }
+
+    pub fn negate(&self) -> Money {
+        Money {
+            amount: -self.amount,
+            currency: self.currency,
+        }
+    }
+
+    fn same_currency(&self, other: &Money) -> Result<(), MoneyError> {
+        if self.currency == other.currency {
+            Ok(())
+        } else {
+            Err(MoneyError::CurrencyMismatch {
+                left: self.currency,
+                right: other.currency,
+            })
+        }
+    }
+
+    pub fn checked_add(&self, other: &Money) -> Result<Money, MoneyError> {
+        self.same_currency(other)?;
+        let amount = self
+            .amount
+            .checked_add(other.amount)
+            .ok_or(MoneyError::Overflow)?;
+        Ok(Money::new(amount, self.currency))
+    }
+
+    pub fn checked_sub(&self, other: &Money) -> Result<Money, MoneyError> {
+        self.same_currency(other)?;
+        let amount = self
+            .amount
+            .checked_sub(other.amount)
+            .ok_or(MoneyError::Overflow)?;
+        Ok(Money::new(amount, self.currency))
+    }
+
+    /// Multiplies by a scalar (a rate, a quantity) and rounds half-to-even.
+    pub fn checked_mul(&self, factor: Decimal) -> Result<Money, MoneyError> {
+        let amount = self
+            .amount
+            .checked_mul(factor)
+            .ok_or(MoneyError::Overflow)?;
+        Ok(Money::new(amount, self.currency))
+    }
+
+    pub fn checked_div(&self, divisor: Decimal) -> Result<Money, MoneyError> {
+        if divisor.is_zero() {
+            return Err(MoneyError::DivisionByZero);
+        }
+        let amount = self
+            .amount
+            .checked_div(divisor)
+            .ok_or(MoneyError::Overflow)?;
+        Ok(Money::new(amount, self.currency))
+    }
+
+    /// Sums a sequence of amounts that must all share `currency`.
+    pub fn sum<'a, I>(items: I, currency: Currency) -> Result<Money, MoneyError>
+    where
+        I: IntoIterator<Item = &'a Money>,
+    {
+        items
+            .into_iter()
+            .try_fold(Money::zero(currency), |acc, m| acc.checked_add(m))
+    }
+
+    /// Splits the amount in proportion to `ratios` without losing a single minor unit.
+    ///
+    /// Each share is floored to the minor unit and the leftover units go, one at a
+    /// time, to the shares with the largest truncated fraction (earliest index on ties).
+    pub fn allocate(&self, ratios: &[Decimal]) -> Result<Vec<Money>, MoneyError> {
+        if ratios.is_empty() {
+            return Err(MoneyError::InvalidAllocation("no ratios given"));
+        }
+        if ratios.iter().any(|r| r.is_sign_negative() && !r.is_zero()) {
+            return Err(MoneyError::InvalidAllocation("ratios must not be negative"));
+        }
+        let total_ratio = ratios
+            .iter()
+            .try_fold(Decimal::ZERO, |acc, r| acc.checked_add(*r))
+            .ok_or(MoneyError::Overflow)?;
+        if total_ratio.is_zero() {
+            return Err(MoneyError::InvalidAllocation("ratios sum to zero"));
+        }
+
+        let minor = self.to_minor()?;
+        let units = Decimal::from(minor.unsigned_abs());
+        let mut shares = Vec::with_capacity(ratios.len());
+        let mut fractions = Vec::with_capacity(ratios.len());
+        let mut allocated: u64 = 0;
+        for ratio in ratios {
+            let exact = units
+                .checked_mul(*ratio)
+                .and_then(|v| v.checked_div(total_ratio))
+                .ok_or(MoneyError::Overflow)?;
+            let floor = exact.floor();
+            let share = floor.to_u64().ok_or(MoneyError::Overflow)?;
+            allocated += share;
+            shares.push(share);
+            fractions.push(exact - floor);
+        }
+
+        let mut order: Vec<usize> = (0..ratios.len()).collect();
+        order.sort_by(|a, b| fractions[*b].cmp(&fractions[*a]).then(a.cmp(b)));
+        let remainder = minor.unsigned_abs() - allocated;
+        for idx in order.into_iter().take(remainder as usize) {
+            shares[idx] += 1;
+        }
+
+        let sign = if minor < 0 { -1 } else { 1 };
+        shares
+            .into_iter()
+            .map(|s| {
+                let s = i64::try_from(s).map_err(|_| MoneyError::Overflow)?;
+                Ok(Money::from_minor(sign * s, self.currency))
+            })
+            .collect()
+    }
+
+    /// Splits the amount into `parts` equal shares, spreading any remainder from the front.
+    pub fn split(&self, parts: usize) -> Result<Vec<Money>, MoneyError> {
+        if parts == 0 {
+            return Err(MoneyError::InvalidAllocation(
+                "cannot split into zero parts",
+            ));
+        }
+        self.allocate(&vec![Decimal::ONE; parts])
+    }
+}
+
+impl PartialOrd for Money {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        if self.currency == other.currency {
+            Some(self.amount.cmp(&other.amount))
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let dp = self.currency.minor_units();
+        let fixed = format!("{:.*}", dp as usize, self.amount.abs());
+        let (int_part, frac_part) = match fixed.split_once('.') {
+            Some((i, fr)) => (i, Some(fr)),
+            None => (fixed.as_str(), None),
+        };
+        let mut grouped = String::with_capacity(int_part.len() + int_part.len() / 3);
+        for (i, ch) in int_part.chars().enumerate() {
+            if i > 0 && (int_part.len() - i) % 3 == 0 {
+                grouped.push(',');
+            }
+            grouped.push(ch);
+        }
+        let sign = if self.is_negative() { "-" } else { "" };
+        match frac_part {
+            Some(fr) => write!(f, "{} {}{}.{}", self.currency, sign, grouped, fr),
+            None => write!(f, "{} {}{}", self.currency, sign, grouped),
+        }
+    }
+}
+
+pub(crate) fn parse_decimal(raw: &str) -> Result<Decimal, MoneyError> {
+    Decimal::from_str(raw.trim()).map_err(|_| MoneyError::InvalidAmount(raw.to_string()))
+}
+
+#[wasm_bindgen]
+impl Money {
+    #[wasm_bindgen(constructor)]
+    pub fn js_new(amount: &str, currency: &str) -> Result<Money, JsError> {
+        let currency = Currency::from_str(currency).map_err(js_err)?;
+        Money::parse(amount, currency).map_err(js_err)
+    }
+
+    #[wasm_bindgen(js_name = fromMinor)]
+    pub fn js_from_minor(minor: i64, currency: &str) -> Result<Money, JsError> {
+        let currency = Currency::from_str(currency).map_err(js_err)?;
+        Ok(Money::from_minor(minor, currency))
+    }
+
+    /// Amount as a decimal string, e.g. `"148200000.00"`.
+    #[wasm_bindgen(getter = amount)]
+    pub fn js_amount(&self) -> String {
+        format!("{:.*}", self.currency.minor_units() as usize, self.amount)
+    }
+
+    #[wasm_bindgen(getter = currency)]
+    pub fn js_currency(&self) -> String {
+        self.currency.code().to_string()
+    }
+
+    #[wasm_bindgen(getter = minorUnits)]
+    pub fn js_minor_units(&self) -> u32 {
+        self.currency.minor_units()
+    }
+
+    #[wasm_bindgen(js_name = toMinor)]
+    pub fn js_to_minor(&self) -> Result<i64, JsError> {
+        self.to_minor().map_err(js_err)
+    }
+
+    #[wasm_bindgen(js_name = add)]
+    pub fn js_add(&self, other: &Money) -> Result<Money, JsError> {
+        self.checked_add(other).map_err(js_err)
+    }
+
+    #[wasm_bindgen(js_name = sub)]
+    pub fn js_sub(&self, other: &Money) -> Result<Money, JsError> {
+        self.checked_sub(other).map_err(js_err)
+    }
+
+    #[wasm_bindgen(js_name = mul)]
+    pub fn js_mul(&self, factor: &str) -> Result<Money, JsError> {
+        let factor = parse_decimal(factor).map_err(js_err)?;
+        self.checked_mul(factor).map_err(js_err)
+    }
+
+    #[wasm_bindgen(js_name = div)]
+    pub fn js_div(&self, divisor: &str) -> Result<Money, JsError> {
+        let divisor = parse_decimal(divisor).map_err(js_err)?;
+        self.checked_div(divisor).map_err(js_err)
+    }
+
+    #[wasm_bindgen(js_name = allocate)]
+    pub fn js_allocate(&self, ratios: Vec<String>) -> Result<Vec<Money>, JsError> {
+        let ratios = ratios
+            .iter()
+            .map(|r| parse_decimal(r))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(js_err)?;
+        self.allocate(&ratios).map_err(js_err)
+    }
+
+    #[wasm_bindgen(js_name = split)]
+    pub fn js_split(&self, parts: usize) -> Result<Vec<Money>, JsError> {
+        self.split(parts).map_err(js_err)
+    }
+
+    #[wasm_bindgen(js_name = isZero)]
+    pub fn js_is_zero(&self) -> bool {
+        self.is_zero()
+    }
+
+    #[wasm_bindgen(js_name = isNegative)]
+    pub fn js_is_negative(&self) -> bool {
+        self.is_negative()
+    }
+
+    #[wasm_bindgen(js_name = equals)]
+    pub fn js_equals(&self, other: &Money) -> bool {
+        self == other
+    }
+
+    #[wasm_bindgen(js_name = toString)]
+    pub fn js_to_string(&self) -> String {
+        self.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dec(s: &str) -> Decimal {
+        Decimal::from_str(s).unwrap()
+    }
+
+    fn kes(s: &str) -> Money {
+        Money::parse(s, Currency::Kes).unwrap()
+    }
+
+    #[test]
+    fn rounds_half_to_even_at_the_minor_unit() {
+        assert_eq!(kes("10.005").amount(), dec("10.00"));
+        assert_eq!(kes("10.015").amount(), dec("10.02"));
+        assert_eq!(kes("10.0051").amount(), dec("10.01"));
+        assert_eq!(kes("-10.005").amount(), dec("-10.00"));
+        assert_eq!(kes("-10.025").amount(), dec("-10.02"));
+        // UGX has no minor unit, so the rounding happens at the shilling.
+        let ugx = Money::new(dec("2.5"), Currency::Ugx);
+        assert_eq!(ugx.amount(), dec("2"));
+        assert_eq!(Money::new(dec("3.5"), Currency::Ugx).amount(), dec("4"));
+        // Arithmetic results are rounded the same way.
+        assert_eq!(kes("0.25").checked_mul(dec("0.5")).unwrap(), kes("0.12"));
+        assert_eq!(kes("0.35").checked_mul(dec("0.5")).unwrap(), kes("0.18"));
+    }
+
+    #[test]
+    fn amounts_serialise_at_a_fixed_scale() {
+        assert_eq!(kes("1000").amount().to_string(), "1000.00");
+        let json = serde_json::to_string(&kes("12.5")).unwrap();
+        assert_eq!(json, r#"{"amount":"12.50","currency":"KES"}"#);
+    }
+
+    #[test]
+    fn allocate_never_loses_a_minor_unit() {
+        let thirds = kes("100").allocate(&[Decimal::ONE; 3]).unwrap();
+        assert_eq!(thirds, vec![kes("33.34"), kes("33.33"), kes("33.33")]);
+
+        let awkward = kes("0.05")
+            .allocate(&[dec("1"), dec("1"), dec("1")])
+            .unwrap();
+        assert_eq!(awkward, vec![kes("0.02"), kes("0.02"), kes("0.01")]);
+
+        // Largest fractional remainder wins the spare cent, not the first share.
+        let weighted = kes("10").allocate(&[dec("0.3"), dec("0.7")]).unwrap();
+        assert_eq!(weighted, vec![kes("3"), kes("7")]);
+        let odd = kes("1").allocate(&[dec("1"), dec("2"), dec("4")]).unwrap();
+        assert_eq!(odd, vec![kes("0.14"), kes("0.29"), kes("0.57")]);
+
+        let negative = kes("-100").split(3).unwrap();
+        assert_eq!(negative, vec![kes("-33.34"), kes("-33.33"), kes("-33.33")]);
+
+        for total in ["0.01", "99.99", "1234567.89", "-0.07"] {
+            let total = kes(total);
+            for ratios in [
+                vec![dec("1"), dec("1"), dec("1")],
+                vec![dec("0.1"), dec("0.2"), dec("0.3"), dec("0.4")],
+                vec![dec("7"), dec("0"), dec("13")],
+            ] {
+                let shares = total.allocate(&ratios).unwrap();
+                assert_eq!(Money::sum(&shares, Currency::Kes).unwrap(), total);
+            }
+        }
+    }
+
+    #[test]
+    fn allocate_rejects_bad_ratios() {
+        let total = kes("10");
+        assert!(matches!(
+            total.allocate(&[]),
+            Err(MoneyError::InvalidAllocation(_))
+        ));
+        assert!(matches!(
+            total.allocate(&[dec("0"), dec("0")]),
+            Err(MoneyError::InvalidAllocation(_))
+        ));
+        assert!(matches!(
+            total.allocate(&[dec("1"), dec("-1")]),
+            Err(MoneyError::InvalidAllocation(_))
+        ));
+        assert!(matches!(
+            total.split(0),
+            Err(MoneyError::InvalidAllocation(_))
+        ));
+    }
+
+    #[test]
+    fn mixing_currencies_is_an_error() {
+        let usd = Money::parse("10", Currency::Usd).unwrap();
+        let mismatch = MoneyError::CurrencyMismatch {
+            left: Currency::Kes,
+            right: Currency::Usd,
+        };
+        assert_eq!(kes("10").checked_add(&usd), Err(mismatch.clone()));
+        assert_eq!(kes("10").checked_sub(&usd), Err(mismatch));
+        assert_eq!(kes("10").partial_cmp(&usd), None);
+        assert!(Money::sum(&[kes("1"), usd], Currency::Kes).is_err());
+    }
+
+    #[test]
+    fn overflow_is_reported_not_wrapped() {
+        let max = Money::new(Decimal::MAX.trunc_with_scale(2), Currency::Kes);
+        assert_eq!(max.checked_add(&kes("1")), Err(MoneyError::Overflow));
+        assert_eq!(
+            max.negate().checked_sub(&kes("1")),
+            Err(MoneyError::Overflow)
+        );
+        assert_eq!(max.checked_mul(dec("2")), Err(MoneyError::Overflow));
+        assert_eq!(max.to_minor(), Err(MoneyError::Overflow));
+        assert_eq!(
+            kes("1").checked_div(dec("0")),
+            Err(MoneyError::DivisionByZero)
+        );
+    }
+
+    #[test]
+    fn parses_grouped_amounts_and_rejects_garbage() {
+        assert_eq!(kes("1,234,567.891").amount(), dec("1234567.89"));
+        assert!(matches!(
+            Money::parse("12a", Currency::Kes),
+            Err(MoneyError::InvalidAmount(_))
+        ));
+        assert_eq!(
+            Currency::from_str("xyz"),
+            Err(MoneyError::UnknownCurrency("xyz".to_string()))
+        );
+        assert_eq!(Currency::from_str(" kes "), Ok(Currency::Kes));
+    }
+}
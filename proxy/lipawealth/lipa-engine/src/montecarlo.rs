@@ -0,0 +1,740 @@
+//! Seeded Monte Carlo wealth projection: correlated multi-asset returns, planned
+//! contributions and withdrawals, inflation and a KES/USD exchange-rate path.
+
+use std::fmt;
+
+use rust_decimal::prelude::*;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::js::{from_js, js_err, to_js};
+use crate::linalg::{self, Matrix};
+use crate::money::{Currency, Money, MoneyError};
+use crate::rng::{self, Rng};
+
+pub const MAX_PATHS: u32 = 50_000;
+pub const MAX_YEARS: u32 = 100;
+/// Every path-year keeps three `f64` outcomes (nominal, real and FX), so capping
+/// paths × years bounds a single call's working set at about 24 MB.
+pub const MAX_PATH_YEARS: u64 = 1_000_000;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum MonteCarloError {
+    NoAssets,
+    InvalidWeights {
+        total: f64,
+    },
+    InvalidAssumption {
+        asset: String,
+    },
+    /// KES/USD spot must be positive, drift above -100% and volatility non-negative.
+    InvalidFx,
+    /// Inflation must average above -100% with non-negative volatility.
+    InvalidInflation,
+    InvalidCorrelations,
+    /// The correlation matrix has no Cholesky factor (not positive semi-definite).
+    NotPositiveSemiDefinite,
+    /// A foreign-currency asset needs a KES/USD path, and only KES and USD are modelled.
+    UnsupportedCurrency(Currency),
+    MissingFx,
+    FlowCurrency {
+        expected: Currency,
+        found: Currency,
+    },
+    TooLarge {
+        paths: u32,
+        years: u32,
+    },
+    Money(MoneyError),
+}
+
+impl fmt::Display for MonteCarloError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MonteCarloError::NoAssets => f.write_str("projection needs at least one asset"),
+            MonteCarloError::InvalidWeights { total } => {
+                write!(f, "asset weights sum to {}, not 1", total)
+            }
+            MonteCarloError::InvalidAssumption { asset } => {
+                write!(f, "return or volatility for {} is out of range", asset)
+            }
+            MonteCarloError::InvalidFx => f.write_str(
+                "KES/USD spot must be positive, drift above -100% and volatility non-negative",
+            ),
+            MonteCarloError::InvalidInflation => {
+                f.write_str("inflation must average above -100% with non-negative volatility")
+            }
+            MonteCarloError::InvalidCorrelations => {
+                f.write_str("correlations must be a symmetric matrix with ones on the diagonal")
+            }
+            MonteCarloError::NotPositiveSemiDefinite => {
+                f.write_str("correlation matrix is not positive semi-definite")
+            }
+            MonteCarloError::UnsupportedCurrency(c) => {
+                write!(
+                    f,
+                    "{} assets cannot be projected; only KES and USD are modelled",
+                    c
+                )
+            }
+            MonteCarloError::MissingFx => {
+                f.write_str("foreign-currency assets need a KES/USD exchange-rate assumption")
+            }
+            MonteCarloError::FlowCurrency { expected, found } => write!(
+                f,
+                "planned flow in {} but the projection is in {}",
+                found, expected
+            ),
+            MonteCarloError::TooLarge { paths, years } => write!(
+                f,
+                "{} paths over {} years is too large (limits {} paths, {} years, {} path-years)",
+                paths, years, MAX_PATHS, MAX_YEARS, MAX_PATH_YEARS
+            ),
+            MonteCarloError::Money(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for MonteCarloError {}
+
+impl From<MoneyError> for MonteCarloError {
+    fn from(err: MoneyError) -> Self {
+        MonteCarloError::Money(err)
+    }
+}
+
+/// Annual expectations for one asset class. Returns are lognormal with the given
+/// arithmetic mean, in the asset's own currency.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AssetAssumption {
+    pub name: String,
+    /// Target weight; the portfolio is rebalanced to it every year.
+    pub weight: f64,
+    pub expected_return: f64,
+    pub volatility: f64,
+    pub currency: Currency,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FxAssumption {
+    /// KES per USD today.
+    pub spot: f64,
+    /// Expected annual change in KES per USD (positive means the shilling weakens).
+    pub drift: f64,
+    pub volatility: f64,
+    /// Correlation of the rate's moves with each asset, in asset order.
+    #[serde(default)]
+    pub correlations: Vec<f64>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InflationAssumption {
+    pub mean: f64,
+    #[serde(default)]
+    pub volatility: f64,
+}
+
+/// A yearly contribution (positive) or withdrawal (negative), paid at the start
+/// of each year from `start_year` (1 = the first projected year) to `end_year`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlannedFlow {
+    pub start_year: u32,
+    #[serde(default)]
+    pub end_year: Option<u32>,
+    /// In today's money when `indexed`, otherwise nominal.
+    pub amount: Money,
+    #[serde(default = "default_indexed")]
+    pub indexed: bool,
+}
+
+fn default_indexed() -> bool {
+    true
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Projection {
+    /// Today's portfolio value; its currency is the reporting currency.
+    pub initial_value: Money,
+    pub years: u32,
+    pub paths: u32,
+    /// Omit for a fresh random seed; the seed used is returned either way.
+    #[serde(default)]
+    pub seed: Option<u64>,
+    pub assets: Vec<AssetAssumption>,
+    /// Asset return correlations; identity when omitted.
+    #[serde(default)]
+    pub correlations: Option<Matrix>,
+    #[serde(default)]
+    pub fx: Option<FxAssumption>,
+    #[serde(default)]
+    pub inflation: InflationAssumption,
+    #[serde(default)]
+    pub flows: Vec<PlannedFlow>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FxBand {
+    pub p5: f64,
+    pub p50: f64,
+    pub p95: f64,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct YearBand {
+    /// 0 is today.
+    pub year: u32,
+    pub p5: Money,
+    pub p50: Money,
+    pub p95: Money,
+    /// The same percentiles deflated to today's money.
+    pub real_p5: Money,
+    pub real_p50: Money,
+    pub real_p95: Money,
+    /// Share of paths that have run out of money at some point by this year.
+    pub depleted_share: f64,
+    pub fx: Option<FxBand>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectionResult {
+    pub seed: u64,
+    pub paths: u32,
+    pub currency: Currency,
+    pub bands: Vec<YearBand>,
+}
+
+/// Linear interpolation between order statistics of an ascending slice.
+pub(crate) fn percentile(sorted: &[f64], p: f64) -> f64 {
+    let h = (sorted.len() - 1) as f64 * p;
+    let lo = h.floor() as usize;
+    let hi = h.ceil() as usize;
+    sorted[lo] + (sorted[hi] - sorted[lo]) * (h - lo as f64)
+}
+
+/// A simulated amount as money; NaN, infinities and values beyond `Decimal` are errors.
+pub(crate) fn to_money(value: f64, currency: Currency) -> Result<Money, MoneyError> {
+    let amount = Decimal::from_f64(value).ok_or(MoneyError::Overflow)?;
+    Ok(Money::new(amount, currency))
+}
+
+pub(crate) fn as_f64(m: &Money) -> f64 {
+    m.amount().to_f64().unwrap_or(0.0)
+}
+
+/// How an asset's local return translates into the reporting currency.
+#[derive(Clone, Copy, PartialEq)]
+enum FxExposure {
+    None,
+    /// A USD asset reported in KES: gains when KES per USD rises.
+    UsdInKes,
+    /// A KES asset reported in USD: loses when KES per USD rises.
+    KesInUsd,
+}
+
+fn exposure(asset: Currency, reporting: Currency) -> Result<FxExposure, MonteCarloError> {
+    match (asset, reporting) {
+        (a, r) if a == r => Ok(FxExposure::None),
+        (Currency::Usd, Currency::Kes) => Ok(FxExposure::UsdInKes),
+        (Currency::Kes, Currency::Usd) => Ok(FxExposure::KesInUsd),
+        (Currency::Kes | Currency::Usd, other) | (other, _) => {
+            Err(MonteCarloError::UnsupportedCurrency(other))
+        }
+    }
+}
+
+/// Correlation matrix over the asset shocks and, when modelled, the FX shock.
+fn factor_correlations(p: &Projection) -> Result<Matrix, MonteCarloError> {
+    let n = p.assets.len();
+    let mut m = match &p.correlations {
+        Some(c) => {
+            let valid = c.len() == n
+                && linalg::is_symmetric(c)
+                && (0..n).all(|i| (c[i][i] - 1.0).abs() < 1e-9)
+                && c.iter().flatten().all(|r| r.abs() <= 1.0);
+            if !valid {
+                return Err(MonteCarloError::InvalidCorrelations);
+            }
+            c.clone()
+        }
+        None => (0..n)
+            .map(|i| (0..n).map(|j| if i == j { 1.0 } else { 0.0 }).collect())
+            .collect(),
+    };
+    if let Some(fx) = &p.fx {
+        let rho = if fx.correlations.is_empty() {
+            vec![0.0; n]
+        } else if fx.correlations.len() == n && fx.correlations.iter().all(|r| r.abs() <= 1.0) {
+            fx.correlations.clone()
+        } else {
+            return Err(MonteCarloError::InvalidCorrelations);
+        };
+        for (row, r) in m.iter_mut().zip(&rho) {
+            row.push(*r);
+        }
+        let mut last = rho;
+        last.push(1.0);
+        m.push(last);
+    }
+    Ok(m)
+}
+
+/// Parameters of `ln(1 + r)` for a lognormal return with arithmetic mean `mean`.
+pub(crate) fn log_params(mean: f64, volatility: f64) -> (f64, f64) {
+    let variance = (1.0 + (volatility / (1.0 + mean)).powi(2)).ln();
+    ((1.0 + mean).ln() - variance / 2.0, variance.sqrt())
+}
+
+/// Runs the projection. Paths are simulated in annual steps: flows land at the
+/// start of each year, then the rebalanced portfolio earns that year's return.
+pub fn project(p: &Projection) -> Result<ProjectionResult, MonteCarloError> {
+    if p.assets.is_empty() {
+        return Err(MonteCarloError::NoAssets);
+    }
+    if p.paths == 0
+        || p.paths > MAX_PATHS
+        || p.years > MAX_YEARS
+        || u64::from(p.paths) * u64::from(p.years + 1) > MAX_PATH_YEARS
+    {
+        return Err(MonteCarloError::TooLarge {
+            paths: p.paths,
+            years: p.years,
+        });
+    }
+    let total: f64 = p.assets.iter().map(|a| a.weight).sum();
+    if (total - 1.0).abs() > 1e-6 {
+        return Err(MonteCarloError::InvalidWeights { total });
+    }
+    for a in &p.assets {
+        let sane = a.expected_return > -1.0
+            && a.expected_return.is_finite()
+            && a.volatility >= 0.0
+            && a.volatility.is_finite();
+        if !sane {
+            return Err(MonteCarloError::InvalidAssumption {
+                asset: a.name.clone(),
+            });
+        }
+    }
+    if let Some(fx) = &p.fx {
+        let sane = fx.spot > 0.0
+            && fx.spot.is_finite()
+            && fx.drift > -1.0
+            && fx.drift.is_finite()
+            && fx.volatility >= 0.0
+            && fx.volatility.is_finite();
+        if !sane {
+            return Err(MonteCarloError::InvalidFx);
+        }
+    }
+    let inflation = &p.inflation;
+    let sane = inflation.mean > -1.0
+        && inflation.mean.is_finite()
+        && inflation.volatility >= 0.0
+        && inflation.volatility.is_finite();
+    if !sane {
+        return Err(MonteCarloError::InvalidInflation);
+    }
+    let currency = p.initial_value.currency();
+    let exposures = p
+        .assets
+        .iter()
+        .map(|a| exposure(a.currency, currency))
+        .collect::<Result<Vec<_>, _>>()?;
+    if p.fx.is_none() && exposures.iter().any(|e| *e != FxExposure::None) {
+        return Err(MonteCarloError::MissingFx);
+    }
+    if let Some(f) = p.flows.iter().find(|f| f.amount.currency() != currency) {
+        return Err(MonteCarloError::FlowCurrency {
+            expected: currency,
+            found: f.amount.currency(),
+        });
+    }
+    let chol = linalg::cholesky(&factor_correlations(p)?)
+        .ok_or(MonteCarloError::NotPositiveSemiDefinite)?;
+
+    let seed = p.seed.unwrap_or_else(rng::random_seed);
+    let mut rng = Rng::seed_from_u64(seed);
+    let n = p.assets.len();
+    let factors = chol.len();
+    let asset_params: Vec<(f64, f64)> = p
+        .assets
+        .iter()
+        .map(|a| log_params(a.expected_return, a.volatility))
+        .collect();
+    let fx_params = p.fx.as_ref().map(|fx| log_params(fx.drift, fx.volatility));
+    let years = p.years as usize;
+    let paths = p.paths as usize;
+    let initial = as_f64(&p.initial_value);
+    let flows: Vec<(u32, u32, f64, bool)> = p
+        .flows
+        .iter()
+        .map(|f| {
+            (
+                f.start_year,
+                f.end_year.unwrap_or(u32::MAX),
+                as_f64(&f.amount),
+                f.indexed,
+            )
+        })
+        .collect();
+
+    // Year-major so each year's outcomes can be sorted in place.
+    let mut nominal = vec![vec![0.0; paths]; years + 1];
+    let mut real = vec![vec![0.0; paths]; years + 1];
+    let mut fx_rates = vec![vec![0.0; paths]; if p.fx.is_some() { years + 1 } else { 0 }];
+    let mut depleted_by_year = vec![0u32; years + 1];
+
+    let mut z = vec![0.0; factors];
+    let mut shocks = vec![0.0; factors];
+    for path in 0..paths {
+        let mut wealth = initial;
+        let mut prices = 1.0;
+        let mut fx_rate = p.fx.as_ref().map_or(0.0, |fx| fx.spot);
+        let mut depleted = wealth <= 0.0;
+        nominal[0][path] = wealth;
+        real[0][path] = wealth;
+        if let Some(rates) = fx_rates.first_mut() {
+            rates[path] = fx_rate;
+        }
+        depleted_by_year[0] += depleted as u32;
+
+        for year in 1..=years {
+            let y = year as u32;
+            let planned: f64 = flows
+                .iter()
+                .filter(|(start, end, _, _)| y >= *start && y <= *end)
+                .map(|(_, _, amount, indexed)| if *indexed { amount * prices } else { *amount })
+                .sum();
+            wealth += planned;
+            if wealth <= 0.0 {
+                wealth = 0.0;
+                depleted = true;
+            }
+
+            for v in z.iter_mut() {
+                *v = rng.standard_normal();
+            }
+            linalg::lower_mul(&chol, &z, &mut shocks);
+            let fx_growth = match fx_params {
+                Some((mu, sigma)) => (mu + sigma * shocks[n]).exp(),
+                None => 1.0,
+            };
+            fx_rate *= fx_growth;
+            let growth: f64 = p
+                .assets
+                .iter()
+                .zip(&asset_params)
+                .zip(&exposures)
+                .zip(&shocks)
+                .map(|(((asset, (mu, sigma)), fx), shock)| {
+                    let local = (mu + sigma * shock).exp();
+                    let translated = match fx {
+                        FxExposure::None => local,
+                        FxExposure::UsdInKes => local * fx_growth,
+                        FxExposure::KesInUsd => local / fx_growth,
+                    };
+                    asset.weight * translated
+                })
+                .sum();
+            wealth *= growth;
+            prices *= 1.0 + p.inflation.mean + p.inflation.volatility * rng.standard_normal();
+
+            nominal[year][path] = wealth;
+            real[year][path] = wealth / prices;
+            if let Some(rates) = fx_rates.get_mut(year) {
+                rates[path] = fx_rate;
+            }
+            depleted_by_year[year] += depleted as u32;
+        }
+    }
+
+    let band = |values: &mut Vec<f64>| {
+        values.sort_by(|a, b| a.total_cmp(b));
+        (
+            percentile(values, 0.05),
+            percentile(values, 0.50),
+            percentile(values, 0.95),
+        )
+    };
+    let mut bands = Vec::with_capacity(years + 1);
+    for year in 0..=years {
+        let (p5, p50, p95) = band(&mut nominal[year]);
+        let (r5, r50, r95) = band(&mut real[year]);
+        let fx = fx_rates.get_mut(year).map(|rates| {
+            let (p5, p50, p95) = band(rates);
+            FxBand { p5, p50, p95 }
+        });
+        bands.push(YearBand {
+            year: year as u32,
+            p5: to_money(p5, currency)?,
+            p50: to_money(p50, currency)?,
+            p95: to_money(p95, currency)?,
+            real_p5: to_money(r5, currency)?,
+            real_p50: to_money(r50, currency)?,
+            real_p95: to_money(r95, currency)?,
+            depleted_share: depleted_by_year[year] as f64 / paths as f64,
+            fx,
+        });
+    }
+    Ok(ProjectionResult {
+        seed,
+        paths: p.paths,
+        currency,
+        bands,
+    })
+}
+
+/// JS entry point: a `Projection`-shaped object in, `ProjectionResult` out.
+#[wasm_bindgen(js_name = projectWealth)]
+pub fn project_js(input: JsValue) -> Result<JsValue, JsError> {
+    let projection: Projection = from_js(input)?;
+    to_js(&project(&projection).map_err(js_err)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kes(v: i64) -> Money {
+        Money::new(Decimal::from(v), Currency::Kes)
+    }
+
+    fn asset(name: &str, weight: f64, expected_return: f64, volatility: f64) -> AssetAssumption {
+        AssetAssumption {
+            name: name.to_string(),
+            weight,
+            expected_return,
+            volatility,
+            currency: Currency::Kes,
+        }
+    }
+
+    fn balanced(seed: u64) -> Projection {
+        Projection {
+            initial_value: kes(1_000_000),
+            years: 30,
+            paths: 2_000,
+            seed: Some(seed),
+            assets: vec![
+                asset("NSE equities", 0.6, 0.12, 0.22),
+                asset("T-bonds", 0.4, 0.14, 0.06),
+            ],
+            correlations: Some(vec![vec![1.0, 0.2], vec![0.2, 1.0]]),
+            fx: None,
+            inflation: InflationAssumption {
+                mean: 0.06,
+                volatility: 0.02,
+            },
+            flows: vec![PlannedFlow {
+                start_year: 1,
+                end_year: Some(20),
+                amount: kes(120_000),
+                indexed: true,
+            }],
+        }
+    }
+
+    #[test]
+    fn same_seed_reproduces_the_projection() {
+        let a = project(&balanced(42)).unwrap();
+        let b = project(&balanced(42)).unwrap();
+        assert_eq!(a, b);
+        assert_eq!(a.seed, 42);
+        let c = project(&balanced(43)).unwrap();
+        assert_ne!(a.bands[30].p50, c.bands[30].p50);
+    }
+
+    #[test]
+    fn bands_are_ordered() {
+        let r = project(&balanced(7)).unwrap();
+        assert_eq!(r.bands.len(), 31);
+        assert_eq!(r.bands[0].p50, kes(1_000_000));
+        for b in &r.bands[1..] {
+            assert!(b.p5 < b.p50 && b.p50 < b.p95);
+            assert!(b.real_p50 < b.p50);
+        }
+    }
+
+    #[test]
+    fn zero_volatility_matches_closed_form() {
+        let p = Projection {
+            initial_value: kes(100_000),
+            years: 2,
+            paths: 10,
+            seed: Some(1),
+            assets: vec![asset("Cash", 1.0, 0.10, 0.0)],
+            correlations: None,
+            fx: None,
+            inflation: InflationAssumption {
+                mean: 0.05,
+                volatility: 0.0,
+            },
+            flows: vec![PlannedFlow {
+                start_year: 1,
+                end_year: None,
+                amount: kes(10_000),
+                indexed: false,
+            }],
+        };
+        let r = project(&p).unwrap();
+        assert_eq!(r.bands[2].p5, kes(144_100));
+        assert_eq!(r.bands[2].p95, kes(144_100));
+        assert_eq!(
+            r.bands[2].real_p50,
+            Money::new(Decimal::new(13_070_295, 2), Currency::Kes)
+        );
+    }
+
+    #[test]
+    fn withdrawals_can_deplete_the_portfolio() {
+        let mut p = balanced(9);
+        p.flows = vec![PlannedFlow {
+            start_year: 1,
+            end_year: None,
+            amount: kes(-150_000),
+            indexed: true,
+        }];
+        let r = project(&p).unwrap();
+        assert_eq!(r.bands[1].depleted_share, 0.0);
+        assert!(r.bands[30].depleted_share > 0.5);
+        assert!(r
+            .bands
+            .windows(2)
+            .all(|w| w[0].depleted_share <= w[1].depleted_share));
+    }
+
+    #[test]
+    fn usd_assets_follow_the_exchange_rate() {
+        let p = Projection {
+            initial_value: kes(1_000_000),
+            years: 3,
+            paths: 5,
+            seed: Some(3),
+            assets: vec![AssetAssumption {
+                currency: Currency::Usd,
+                ..asset("US treasuries", 1.0, 0.0, 0.0)
+            }],
+            correlations: None,
+            fx: Some(FxAssumption {
+                spot: 130.0,
+                drift: 0.10,
+                volatility: 0.0,
+                correlations: vec![],
+            }),
+            inflation: InflationAssumption::default(),
+            flows: vec![],
+        };
+        let r = project(&p).unwrap();
+        assert_eq!(r.bands[3].p50, kes(1_331_000));
+        let fx = r.bands[3].fx.unwrap();
+        assert!((fx.p50 - 130.0 * 1.331).abs() < 1e-9);
+
+        let mut no_fx = p.clone();
+        no_fx.fx = None;
+        assert_eq!(project(&no_fx), Err(MonteCarloError::MissingFx));
+    }
+
+    #[test]
+    fn rejects_invalid_correlations() {
+        let mut p = balanced(1);
+        p.correlations = Some(vec![vec![1.0, 1.5], vec![1.5, 1.0]]);
+        assert_eq!(project(&p), Err(MonteCarloError::InvalidCorrelations));
+        p.assets.push(asset("REIT", 0.0, 0.1, 0.2));
+        p.correlations = Some(vec![
+            vec![1.0, 0.9, -0.9],
+            vec![0.9, 1.0, 0.9],
+            vec![-0.9, 0.9, 1.0],
+        ]);
+        assert_eq!(project(&p), Err(MonteCarloError::NotPositiveSemiDefinite));
+    }
+
+    #[test]
+    fn caps_paths_times_years() {
+        // 99 years keeps 100 path-years per path, so the cap falls at 10,000 paths.
+        let mut p = balanced(1);
+        p.years = 99;
+        p.paths = (MAX_PATH_YEARS / 100) as u32;
+        let r = project(&p).unwrap();
+        assert_eq!(r.paths, 10_000);
+        assert_eq!(r.bands.len(), 100);
+        p.paths += 1;
+        assert_eq!(
+            project(&p),
+            Err(MonteCarloError::TooLarge {
+                paths: 10_001,
+                years: 99
+            })
+        );
+        p.paths = MAX_PATHS + 1;
+        p.years = 1;
+        assert!(matches!(project(&p), Err(MonteCarloError::TooLarge { .. })));
+        p.paths = 0;
+        assert!(matches!(project(&p), Err(MonteCarloError::TooLarge { .. })));
+    }
+
+    #[test]
+    fn rejects_out_of_range_fx_and_inflation() {
+        let mut p = balanced(1);
+        p.fx = Some(FxAssumption {
+            spot: 130.0,
+            drift: 0.05,
+            volatility: 0.1,
+            correlations: vec![],
+        });
+        assert!(project(&p).is_ok());
+        for (spot, drift, volatility) in [
+            (0.0, 0.05, 0.1),
+            (f64::NAN, 0.05, 0.1),
+            (130.0, -1.0, 0.1),
+            (130.0, f64::INFINITY, 0.1),
+            (130.0, 0.05, -0.1),
+            (130.0, 0.05, f64::NAN),
+        ] {
+            p.fx = Some(FxAssumption {
+                spot,
+                drift,
+                volatility,
+                correlations: vec![],
+            });
+            assert_eq!(project(&p), Err(MonteCarloError::InvalidFx));
+        }
+        p.fx = None;
+        for (mean, volatility) in [
+            (-1.0, 0.0),
+            (f64::NAN, 0.0),
+            (0.05, -0.01),
+            (0.05, f64::INFINITY),
+        ] {
+            p.inflation = InflationAssumption { mean, volatility };
+            assert_eq!(project(&p), Err(MonteCarloError::InvalidInflation));
+        }
+    }
+
+    #[test]
+    fn unrepresentable_outcomes_are_errors() {
+        assert_eq!(to_money(f64::NAN, Currency::Kes), Err(MoneyError::Overflow));
+        assert_eq!(to_money(1e300, Currency::Kes), Err(MoneyError::Overflow));
+        // A thousandfold year takes 10^27 past what a Decimal can hold.
+        let mut p = balanced(1);
+        p.initial_value = Money::new(
+            Decimal::from_i128_with_scale(10i128.pow(27), 0),
+            Currency::Kes,
+        );
+        p.assets = vec![asset("Moonshot", 1.0, 1_000.0, 0.0)];
+        p.correlations = None;
+        p.years = 1;
+        p.flows.clear();
+        assert_eq!(
+            project(&p),
+            Err(MonteCarloError::Money(MoneyError::Overflow))
+        );
+    }
+}
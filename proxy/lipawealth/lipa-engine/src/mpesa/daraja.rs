@@ -0,0 +1,656 @@
+//! Typed parsers for Safaricom Daraja callbacks: STK Push results, C2B
+//! validation/confirmation requests and B2C results.
+
+use std::collections::HashSet;
+use std::str::FromStr;
+
+use chrono::NaiveDateTime;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use serde_json::Value;
+use wasm_bindgen::prelude::*;
+
+use super::{MpesaError, Msisdn};
+use crate::js::{js_err, to_js};
+use crate::money::{Currency, Money};
+
+fn at<'a>(root: &'a Value, path: &[&str]) -> Result<&'a Value, MpesaError> {
+    path.iter()
+        .try_fold(root, |v, key| v.get(key))
+        .filter(|v| !v.is_null())
+        .ok_or_else(|| MpesaError::MissingField(path.join(".")))
+}
+
+/// Daraja mixes strings and numbers for the same fields; both are read as text.
+fn text(value: &Value, field: &str) -> Result<String, MpesaError> {
+    match value {
+        Value::String(s) => Ok(s.trim().to_string()),
+        Value::Number(n) => Ok(n.to_string()),
+        other => Err(MpesaError::InvalidField {
+            field: field.to_string(),
+            value: other.to_string(),
+        }),
+    }
+}
+
+fn text_at(root: &Value, path: &[&str]) -> Result<String, MpesaError> {
+    text(at(root, path)?, &path.join("."))
+}
+
+fn optional_text_at(root: &Value, path: &[&str]) -> Result<Option<String>, MpesaError> {
+    match at(root, path) {
+        Ok(v) => Ok(Some(text(v, &path.join("."))?).filter(|s| !s.is_empty())),
+        Err(MpesaError::MissingField(_)) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+fn int_at(root: &Value, path: &[&str]) -> Result<i64, MpesaError> {
+    let raw = text_at(root, path)?;
+    raw.parse().map_err(|_| MpesaError::InvalidField {
+        field: path.join("."),
+        value: raw,
+    })
+}
+
+/// A transaction amount: unlike account balances it must be strictly positive.
+fn amount(value: &Value, field: &str) -> Result<Money, MpesaError> {
+    let money = kes(value, field)?;
+    if !money.is_positive() {
+        return Err(MpesaError::InvalidAmount {
+            field: field.to_string(),
+            value: money.amount().to_string(),
+        });
+    }
+    Ok(money)
+}
+
+fn kes(value: &Value, field: &str) -> Result<Money, MpesaError> {
+    let raw = text(value, field).map_err(|_| MpesaError::InvalidAmount {
+        field: field.to_string(),
+        value: value.to_string(),
+    })?;
+    Decimal::from_str(&raw)
+        .map(|d| Money::new(d, Currency::Kes))
+        .map_err(|_| MpesaError::InvalidAmount {
+            field: field.to_string(),
+            value: raw,
+        })
+}
+
+fn timestamp(raw: &str, format: &str, field: &str) -> Result<NaiveDateTime, MpesaError> {
+    NaiveDateTime::parse_from_str(raw, format).map_err(|_| MpesaError::InvalidField {
+        field: field.to_string(),
+        value: raw.to_string(),
+    })
+}
+
+/// A `[{ <key_name>: k, "Value": v }]` list (STK metadata, B2C parameters). Errors
+/// name the entry by its full path, e.g. `Body.stkCallback.CallbackMetadata.Item[Amount]`.
+struct Items<'a> {
+    items: &'a [Value],
+    key_name: &'static str,
+    prefix: &'static str,
+}
+
+impl<'a> Items<'a> {
+    fn read(
+        root: &'a Value,
+        path: &[&str],
+        key_name: &'static str,
+        prefix: &'static str,
+    ) -> Result<Items<'a>, MpesaError> {
+        let items = at(root, path)?
+            .as_array()
+            .ok_or_else(|| MpesaError::MissingField(prefix.to_string()))?;
+        Ok(Items {
+            items,
+            key_name,
+            prefix,
+        })
+    }
+
+    fn path(&self, key: &str) -> String {
+        format!("{}[{}]", self.prefix, key)
+    }
+
+    fn get(&self, key: &str) -> Option<&'a Value> {
+        self.items
+            .iter()
+            .find(|item| item.get(self.key_name).and_then(Value::as_str) == Some(key))
+            .and_then(|item| item.get("Value"))
+            .filter(|v| !v.is_null())
+    }
+
+    fn require(&self, key: &str) -> Result<&'a Value, MpesaError> {
+        self.get(key)
+            .ok_or_else(|| MpesaError::MissingField(self.path(key)))
+    }
+
+    fn text(&self, key: &str) -> Result<String, MpesaError> {
+        text(self.require(key)?, &self.path(key))
+    }
+
+    fn amount(&self, key: &str) -> Result<Money, MpesaError> {
+        amount(self.require(key)?, &self.path(key))
+    }
+
+    fn balance(&self, key: &str) -> Result<Option<Money>, MpesaError> {
+        self.get(key).map(|v| kes(v, &self.path(key))).transpose()
+    }
+
+    fn timestamp(&self, key: &str, format: &str) -> Result<NaiveDateTime, MpesaError> {
+        timestamp(&self.text(key)?, format, &self.path(key))
+    }
+}
+
+fn parse_json(raw: &str) -> Result<Value, MpesaError> {
+    serde_json::from_str(raw).map_err(|e| MpesaError::Json(e.to_string()))
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StkPayment {
+    pub amount: Money,
+    pub receipt: String,
+    pub transaction_time: NaiveDateTime,
+    pub msisdn: Msisdn,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StkPushResult {
+    pub merchant_request_id: String,
+    pub checkout_request_id: String,
+    pub result_code: i64,
+    pub result_desc: String,
+    /// Present only when `result_code` is 0.
+    pub payment: Option<StkPayment>,
+}
+
+impl StkPushResult {
+    pub fn dedupe_key(&self) -> &str {
+        self.payment
+            .as_ref()
+            .map_or(&self.checkout_request_id, |p| &p.receipt)
+    }
+}
+
+pub fn parse_stk_callback(raw: &str) -> Result<StkPushResult, MpesaError> {
+    let root = parse_json(raw)?;
+    let result_code = int_at(&root, &["Body", "stkCallback", "ResultCode"])?;
+    let payment = if result_code == 0 {
+        let items = Items::read(
+            &root,
+            &["Body", "stkCallback", "CallbackMetadata", "Item"],
+            "Name",
+            "Body.stkCallback.CallbackMetadata.Item",
+        )?;
+        Some(StkPayment {
+            amount: items.amount("Amount")?,
+            receipt: items.text("MpesaReceiptNumber")?,
+            transaction_time: items.timestamp("TransactionDate", "%Y%m%d%H%M%S")?,
+            msisdn: Msisdn::parse(&items.text("PhoneNumber")?)?,
+        })
+    } else {
+        None
+    };
+    Ok(StkPushResult {
+        merchant_request_id: text_at(&root, &["Body", "stkCallback", "MerchantRequestID"])?,
+        checkout_request_id: text_at(&root, &["Body", "stkCallback", "CheckoutRequestID"])?,
+        result_code,
+        result_desc: text_at(&root, &["Body", "stkCallback", "ResultDesc"])?,
+        payment,
+    })
+}
+
+/// A C2B validation or confirmation request (Daraja sends the same shape for both).
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct C2bTransaction {
+    pub transaction_type: String,
+    pub trans_id: String,
+    pub trans_time: NaiveDateTime,
+    pub amount: Money,
+    pub short_code: String,
+    pub bill_ref_number: Option<String>,
+    pub invoice_number: Option<String>,
+    pub org_account_balance: Option<Money>,
+    pub third_party_trans_id: Option<String>,
+    /// `None` when Safaricom masks the payer's number.
+    pub msisdn: Option<Msisdn>,
+    pub payer_name: String,
+}
+
+pub fn parse_c2b(raw: &str) -> Result<C2bTransaction, MpesaError> {
+    let root = parse_json(raw)?;
+    let msisdn_raw = text_at(&root, &["MSISDN"])?;
+    let msisdn = if msisdn_raw.contains('*') {
+        None
+    } else {
+        Some(Msisdn::parse(&msisdn_raw)?)
+    };
+    let org_account_balance = match optional_text_at(&root, &["OrgAccountBalance"])? {
+        Some(_) => Some(kes(
+            at(&root, &["OrgAccountBalance"])?,
+            "OrgAccountBalance",
+        )?),
+        None => None,
+    };
+    let payer_name = ["FirstName", "MiddleName", "LastName"]
+        .iter()
+        .filter_map(|k| optional_text_at(&root, &[k]).ok().flatten())
+        .collect::<Vec<_>>()
+        .join(" ");
+    let when = text_at(&root, &["TransTime"])?;
+    Ok(C2bTransaction {
+        transaction_type: text_at(&root, &["TransactionType"])?,
+        trans_id: text_at(&root, &["TransID"])?,
+        trans_time: timestamp(&when, "%Y%m%d%H%M%S", "TransTime")?,
+        amount: amount(at(&root, &["TransAmount"])?, "TransAmount")?,
+        short_code: text_at(&root, &["BusinessShortCode"])?,
+        bill_ref_number: optional_text_at(&root, &["BillRefNumber"])?,
+        invoice_number: optional_text_at(&root, &["InvoiceNumber"])?,
+        org_account_balance,
+        third_party_trans_id: optional_text_at(&root, &["ThirdPartyTransID"])?,
+        msisdn,
+        payer_name,
+    })
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct B2cPayment {
+    pub amount: Money,
+    pub receipt: String,
+    pub recipient_registered: bool,
+    pub receiver: Option<Msisdn>,
+    pub receiver_name: String,
+    pub completed_at: NaiveDateTime,
+    pub charges_paid_account_balance: Option<Money>,
+    pub utility_account_balance: Option<Money>,
+    pub working_account_balance: Option<Money>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct B2cResult {
+    pub result_code: i64,
+    pub result_desc: String,
+    pub originator_conversation_id: String,
+    pub conversation_id: String,
+    pub transaction_id: String,
+    /// Present only when `result_code` is 0.
+    pub payment: Option<B2cPayment>,
+}
+
+impl B2cResult {
+    pub fn dedupe_key(&self) -> &str {
+        &self.conversation_id
+    }
+}
+
+pub fn parse_b2c_result(raw: &str) -> Result<B2cResult, MpesaError> {
+    let root = parse_json(raw)?;
+    let result_code = int_at(&root, &["Result", "ResultCode"])?;
+    let payment = if result_code == 0 {
+        let params = Items::read(
+            &root,
+            &["Result", "ResultParameters", "ResultParameter"],
+            "Key",
+            "Result.ResultParameters.ResultParameter",
+        )?;
+        // "254708374149 - John Doe"
+        let public_name = params.text("ReceiverPartyPublicName")?;
+        let (receiver, receiver_name) = match public_name.split_once(" - ") {
+            Some((number, name)) => {
+                let receiver = Msisdn::parse(number).map_err(|_| MpesaError::InvalidField {
+                    field: params.path("ReceiverPartyPublicName"),
+                    value: public_name.clone(),
+                })?;
+                (Some(receiver), name.trim().to_string())
+            }
+            None => (None, public_name.clone()),
+        };
+        let registered = params.text("B2CRecipientIsRegisteredCustomer")?;
+        Some(B2cPayment {
+            amount: params.amount("TransactionAmount")?,
+            receipt: params.text("TransactionReceipt")?,
+            recipient_registered: registered.eq_ignore_ascii_case("Y"),
+            receiver,
+            receiver_name,
+            completed_at: params.timestamp("TransactionCompletedDateTime", "%d.%m.%Y %H:%M:%S")?,
+            charges_paid_account_balance: params.balance("B2CChargesPaidAccountAvailableFunds")?,
+            utility_account_balance: params.balance("B2CUtilityAccountAvailableFunds")?,
+            working_account_balance: params.balance("B2CWorkingAccountAvailableFunds")?,
+        })
+    } else {
+        None
+    };
+    Ok(B2cResult {
+        result_code,
+        result_desc: text_at(&root, &["Result", "ResultDesc"])?,
+        originator_conversation_id: text_at(&root, &["Result", "OriginatorConversationID"])?,
+        conversation_id: text_at(&root, &["Result", "ConversationID"])?,
+        transaction_id: text_at(&root, &["Result", "TransactionID"])?,
+        payment,
+    })
+}
+
+/// Which Daraja endpoint a callback arrived on. C2B validation and confirmation
+/// carry the same TransID, so each kind is deduplicated on its own.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Callback {
+    Stk,
+    C2bValidation,
+    C2bConfirmation,
+    B2c,
+}
+
+/// Parses callbacks and refuses any transaction it has already seen, since
+/// Daraja retries deliveries it thinks were not acknowledged.
+#[wasm_bindgen]
+#[derive(Clone, Debug, Default)]
+pub struct MpesaInbox {
+    seen: HashSet<(Callback, String)>,
+}
+
+impl MpesaInbox {
+    pub fn new() -> MpesaInbox {
+        MpesaInbox::default()
+    }
+
+    fn claim(&mut self, kind: Callback, key: &str) -> Result<(), MpesaError> {
+        if !self.seen.insert((kind, key.to_string())) {
+            return Err(MpesaError::Duplicate(key.to_string()));
+        }
+        Ok(())
+    }
+
+    pub fn stk_callback(&mut self, raw: &str) -> Result<StkPushResult, MpesaError> {
+        let result = parse_stk_callback(raw)?;
+        self.claim(Callback::Stk, result.dedupe_key())?;
+        Ok(result)
+    }
+
+    /// A C2B validation request; the payment has not been taken yet.
+    pub fn c2b_validation(&mut self, raw: &str) -> Result<C2bTransaction, MpesaError> {
+        let txn = parse_c2b(raw)?;
+        self.claim(Callback::C2bValidation, &txn.trans_id)?;
+        Ok(txn)
+    }
+
+    /// A C2B confirmation: the money has moved and should be credited.
+    pub fn c2b_confirmation(&mut self, raw: &str) -> Result<C2bTransaction, MpesaError> {
+        let txn = parse_c2b(raw)?;
+        self.claim(Callback::C2bConfirmation, &txn.trans_id)?;
+        Ok(txn)
+    }
+
+    pub fn b2c_result(&mut self, raw: &str) -> Result<B2cResult, MpesaError> {
+        let result = parse_b2c_result(raw)?;
+        self.claim(Callback::B2c, result.dedupe_key())?;
+        Ok(result)
+    }
+}
+
+#[wasm_bindgen]
+impl MpesaInbox {
+    #[wasm_bindgen(constructor)]
+    pub fn js_new() -> MpesaInbox {
+        MpesaInbox::new()
+    }
+
+    #[wasm_bindgen(js_name = stkCallback)]
+    pub fn js_stk_callback(&mut self, raw: &str) -> Result<JsValue, JsError> {
+        to_js(&self.stk_callback(raw).map_err(js_err)?)
+    }
+
+    #[wasm_bindgen(js_name = c2bValidation)]
+    pub fn js_c2b_validation(&mut self, raw: &str) -> Result<JsValue, JsError> {
+        to_js(&self.c2b_validation(raw).map_err(js_err)?)
+    }
+
+    #[wasm_bindgen(js_name = c2bConfirmation)]
+    pub fn js_c2b_confirmation(&mut self, raw: &str) -> Result<JsValue, JsError> {
+        to_js(&self.c2b_confirmation(raw).map_err(js_err)?)
+    }
+
+    #[wasm_bindgen(js_name = b2cResult)]
+    pub fn js_b2c_result(&mut self, raw: &str) -> Result<JsValue, JsError> {
+        to_js(&self.b2c_result(raw).map_err(js_err)?)
+    }
+}
+
+/// Normalises a phone number to `2547XXXXXXXX` / `2541XXXXXXXX`.
+#[wasm_bindgen(js_name = normaliseMsisdn)]
+pub fn normalise_msisdn_js(raw: &str) -> Result<String, JsError> {
+    Msisdn::parse(raw).map(|m| m.0).map_err(js_err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> &'static str {
+        match name {
+            "stk_success" => include_str!("../../tests/fixtures/mpesa/stk_success.json"),
+            "stk_cancelled" => include_str!("../../tests/fixtures/mpesa/stk_cancelled.json"),
+            "stk_bad_amount" => include_str!("../../tests/fixtures/mpesa/stk_bad_amount.json"),
+            "c2b_confirmation" => {
+                include_str!("../../tests/fixtures/mpesa/c2b_confirmation.json")
+            }
+            "c2b_validation" => include_str!("../../tests/fixtures/mpesa/c2b_validation.json"),
+            "c2b_masked_msisdn" => {
+                include_str!("../../tests/fixtures/mpesa/c2b_masked_msisdn.json")
+            }
+            "b2c_result" => include_str!("../../tests/fixtures/mpesa/b2c_result.json"),
+            "b2c_failed" => include_str!("../../tests/fixtures/mpesa/b2c_failed.json"),
+            _ => unreachable!("unknown fixture {}", name),
+        }
+    }
+
+    fn kes(s: &str) -> Money {
+        Money::parse(s, Currency::Kes).unwrap()
+    }
+
+    #[test]
+    fn msisdn_formats_normalise() {
+        for raw in [
+            "0712345678",
+            "712345678",
+            "+254712345678",
+            "254 712 345 678",
+        ] {
+            assert_eq!(Msisdn::parse(raw).unwrap().as_str(), "254712345678");
+        }
+        assert_eq!(
+            Msisdn::parse("0110374149").unwrap().as_str(),
+            "254110374149"
+        );
+        for raw in ["0812345678", "07123456", "2547123456789", "07a2345678"] {
+            assert!(matches!(
+                Msisdn::parse(raw),
+                Err(MpesaError::InvalidMsisdn(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn stk_success_parses_metadata() {
+        let r = parse_stk_callback(fixture("stk_success")).unwrap();
+        let p = r.payment.unwrap();
+        assert_eq!(r.checkout_request_id, "ws_CO_191220191020363925");
+        assert_eq!(p.amount, kes("1500"));
+        assert_eq!(p.receipt, "NLJ7RT61SV");
+        assert_eq!(p.msisdn.as_str(), "254708374149");
+        assert_eq!(p.transaction_time.to_string(), "2019-12-19 10:21:15");
+    }
+
+    #[test]
+    fn stk_cancelled_has_no_payment() {
+        let r = parse_stk_callback(fixture("stk_cancelled")).unwrap();
+        assert_eq!(r.result_code, 1032);
+        assert!(r.payment.is_none());
+        assert_eq!(r.dedupe_key(), "ws_CO_191220191020363926");
+    }
+
+    #[test]
+    fn stk_bad_amount_names_the_field() {
+        let err = parse_stk_callback(fixture("stk_bad_amount")).unwrap_err();
+        assert_eq!(
+            err,
+            MpesaError::InvalidAmount {
+                field: "Body.stkCallback.CallbackMetadata.Item[Amount]".into(),
+                value: "one thousand".into()
+            }
+        );
+    }
+
+    #[test]
+    fn stk_missing_metadata_reports_path() {
+        let raw = r#"{"Body":{"stkCallback":{"MerchantRequestID":"1","CheckoutRequestID":"2","ResultCode":0,"ResultDesc":"ok"}}}"#;
+        assert_eq!(
+            parse_stk_callback(raw).unwrap_err(),
+            MpesaError::MissingField("Body.stkCallback.CallbackMetadata.Item".into())
+        );
+        assert!(matches!(
+            parse_stk_callback("{not json"),
+            Err(MpesaError::Json(_))
+        ));
+    }
+
+    #[test]
+    fn c2b_confirmation_parses() {
+        let t = parse_c2b(fixture("c2b_confirmation")).unwrap();
+        assert_eq!(t.trans_id, "RKTQDM7W6S");
+        assert_eq!(t.amount, kes("25000"));
+        assert_eq!(t.bill_ref_number.as_deref(), Some("LW-00042"));
+        assert_eq!(t.invoice_number, None);
+        assert_eq!(t.org_account_balance, Some(kes("49197")));
+        assert_eq!(t.msisdn.unwrap().as_str(), "254110374149");
+        assert_eq!(t.payer_name, "Wanjiru Kamau");
+    }
+
+    #[test]
+    fn c2b_masked_msisdn_is_none() {
+        let t = parse_c2b(fixture("c2b_masked_msisdn")).unwrap();
+        assert_eq!(t.msisdn, None);
+        assert_eq!(t.org_account_balance, None);
+        assert_eq!(t.amount, kes("500"));
+    }
+
+    #[test]
+    fn b2c_result_parses_parameters() {
+        let r = parse_b2c_result(fixture("b2c_result")).unwrap();
+        let p = r.payment.unwrap();
+        assert_eq!(p.amount, kes("10"));
+        assert_eq!(p.receipt, "NLJ41HAY6Q");
+        assert!(p.recipient_registered);
+        assert_eq!(p.receiver.unwrap().as_str(), "254708374149");
+        assert_eq!(p.receiver_name, "John Doe");
+        assert_eq!(p.completed_at.to_string(), "2019-12-19 11:45:50");
+        assert_eq!(p.charges_paid_account_balance, Some(kes("-4510")));
+    }
+
+    #[test]
+    fn b2c_failure_has_no_payment() {
+        let r = parse_b2c_result(fixture("b2c_failed")).unwrap();
+        assert_eq!(r.result_code, 2001);
+        assert!(r.payment.is_none());
+    }
+
+    #[test]
+    fn inbox_rejects_redelivered_transactions() {
+        let mut inbox = MpesaInbox::new();
+        inbox.c2b_confirmation(fixture("c2b_confirmation")).unwrap();
+        assert_eq!(
+            inbox
+                .c2b_confirmation(fixture("c2b_confirmation"))
+                .unwrap_err(),
+            MpesaError::Duplicate("RKTQDM7W6S".into())
+        );
+        inbox.stk_callback(fixture("stk_success")).unwrap();
+        assert!(inbox.stk_callback(fixture("stk_success")).is_err());
+    }
+
+    #[test]
+    fn c2b_validation_then_confirmation_are_both_accepted() {
+        let mut inbox = MpesaInbox::new();
+        let validation = inbox.c2b_validation(fixture("c2b_validation")).unwrap();
+        assert_eq!(validation.org_account_balance, None);
+        let confirmation = inbox.c2b_confirmation(fixture("c2b_confirmation")).unwrap();
+        assert_eq!(validation.trans_id, confirmation.trans_id);
+        assert_eq!(
+            inbox.c2b_validation(fixture("c2b_validation")).unwrap_err(),
+            MpesaError::Duplicate("RKTQDM7W6S".into())
+        );
+        assert_eq!(
+            inbox
+                .c2b_confirmation(fixture("c2b_confirmation"))
+                .unwrap_err(),
+            MpesaError::Duplicate("RKTQDM7W6S".into())
+        );
+    }
+
+    #[test]
+    fn zero_and_negative_amounts_are_rejected() {
+        for bad in ["0.00", "-25000.00"] {
+            let raw = fixture("c2b_confirmation").replace("25000.00", bad);
+            assert!(matches!(
+                parse_c2b(&raw),
+                Err(MpesaError::InvalidAmount { field, .. }) if field == "TransAmount"
+            ));
+        }
+        let raw = fixture("stk_success").replace("1500", "0");
+        assert!(matches!(
+            parse_stk_callback(&raw),
+            Err(MpesaError::InvalidAmount { field, .. })
+                if field == "Body.stkCallback.CallbackMetadata.Item[Amount]"
+        ));
+        let raw = fixture("b2c_result").replace(r#""Value": 10"#, r#""Value": -10"#);
+        assert!(matches!(
+            parse_b2c_result(&raw),
+            Err(MpesaError::InvalidAmount { field, .. })
+                if field == "Result.ResultParameters.ResultParameter[TransactionAmount]"
+        ));
+    }
+
+    #[test]
+    fn missing_fields_report_the_full_path() {
+        let raw = r#"{"Body":{"stkCallback":{"MerchantRequestID":"1","CheckoutRequestID":"2","ResultDesc":"ok"}}}"#;
+        assert_eq!(
+            parse_stk_callback(raw).unwrap_err(),
+            MpesaError::MissingField("Body.stkCallback.ResultCode".into())
+        );
+        let raw = r#"{"Result":{"ResultCode":2001,"ResultDesc":"bad"}}"#;
+        assert_eq!(
+            parse_b2c_result(raw).unwrap_err(),
+            MpesaError::MissingField("Result.OriginatorConversationID".into())
+        );
+        let raw = fixture("stk_success").replace("\"MpesaReceiptNumber\"", "\"Receipt\"");
+        assert_eq!(
+            parse_stk_callback(&raw).unwrap_err(),
+            MpesaError::MissingField(
+                "Body.stkCallback.CallbackMetadata.Item[MpesaReceiptNumber]".into()
+            )
+        );
+    }
+
+    #[test]
+    fn b2c_bad_receiver_number_is_an_error() {
+        let raw = fixture("b2c_result").replace("254708374149 - John Doe", "0812345678 - John Doe");
+        assert_eq!(
+            parse_b2c_result(&raw).unwrap_err(),
+            MpesaError::InvalidField {
+                field: "Result.ResultParameters.ResultParameter[ReceiverPartyPublicName]".into(),
+                value: "0812345678 - John Doe".into(),
+            }
+        );
+        // A name with no number is kept as-is.
+        let raw = fixture("b2c_result").replace("254708374149 - John Doe", "John Doe");
+        let p = parse_b2c_result(&raw).unwrap().payment.unwrap();
+        assert_eq!(p.receiver, None);
+        assert_eq!(p.receiver_name, "John Doe");
+    }
+}
@@ -0,0 +1,86 @@
+//! M-PESA integration: Daraja callbacks, statement import and deposit reconciliation.
+
+use std::fmt;
+
+use serde::{Serialize, Serializer};
+
+pub mod daraja;
+pub mod reconcile;
+pub mod statement;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum MpesaError {
+    Json(String),
+    MissingField(String),
+    InvalidField { field: String, value: String },
+    InvalidMsisdn(String),
+    InvalidAmount { field: String, value: String },
+    Duplicate(String),
+}
+
+impl fmt::Display for MpesaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MpesaError::Json(msg) => write!(f, "malformed JSON: {}", msg),
+            MpesaError::MissingField(field) => write!(f, "missing field {}", field),
+            MpesaError::InvalidField { field, value } => {
+                write!(f, "invalid value for {}: {}", field, value)
+            }
+            MpesaError::InvalidMsisdn(raw) => write!(f, "invalid Safaricom MSISDN: {}", raw),
+            MpesaError::InvalidAmount { field, value } => {
+                write!(f, "invalid amount in {}: {}", field, value)
+            }
+            MpesaError::Duplicate(receipt) => {
+                write!(f, "transaction {} has already been processed", receipt)
+            }
+        }
+    }
+}
+
+impl std::error::Error for MpesaError {}
+
+/// A Safaricom mobile number in international form: `2547XXXXXXXX` or `2541XXXXXXXX`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Msisdn(String);
+
+impl Msisdn {
+    /// Accepts `07…`, `01…`, `7…`, `1…`, `254…` and `+254…`, with spaces or dashes.
+    pub fn parse(raw: &str) -> Result<Msisdn, MpesaError> {
+        let digits: String = raw
+            .trim()
+            .trim_start_matches('+')
+            .chars()
+            .filter(|c| !matches!(c, ' ' | '-'))
+            .collect();
+        if !digits.chars().all(|c| c.is_ascii_digit()) {
+            return Err(MpesaError::InvalidMsisdn(raw.to_string()));
+        }
+        let national = if let Some(rest) = digits.strip_prefix("254") {
+            rest
+        } else if let Some(rest) = digits.strip_prefix('0') {
+            rest
+        } else {
+            digits.as_str()
+        };
+        if national.len() != 9 || !(national.starts_with('7') || national.starts_with('1')) {
+            return Err(MpesaError::InvalidMsisdn(raw.to_string()));
+        }
+        Ok(Msisdn(format!("254{}", national)))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for Msisdn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Serialize for Msisdn {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
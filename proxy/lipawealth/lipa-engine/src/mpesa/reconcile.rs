@@ -0,0 +1,577 @@
+//! Matches imported M-PESA statement lines against the deposits we expect clients to make.
+
+use std::collections::{HashMap, HashSet};
+
+use chrono::{Duration, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use super::statement::{parse_csv, parse_pdf_text, StatementTransaction};
+use super::MpesaError;
+use crate::js::{from_js, js_err, to_js};
+use crate::ledger::{JournalEntry, Posting, Side};
+use crate::money::Money;
+
+/// A deposit a client has been asked to make, e.g. to fund an order.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExpectedDeposit {
+    pub id: String,
+    pub amount: Money,
+    /// Account number the client was told to quote (e.g. `LW-00042`).
+    pub reference: String,
+    pub expected_at: NaiveDateTime,
+    /// Client sub-ledger account to credit once the deposit is matched.
+    #[serde(default)]
+    pub client_account: Option<String>,
+}
+
+/// How far either side of `expected_at` a payment may land; neither side may be negative.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MatchWindow {
+    /// How early a payment may land before it was expected.
+    pub before_minutes: i64,
+    /// How late a payment may land after it was expected.
+    pub after_minutes: i64,
+}
+
+impl Default for MatchWindow {
+    fn default() -> Self {
+        MatchWindow {
+            before_minutes: 60,
+            after_minutes: 3 * 24 * 60,
+        }
+    }
+}
+
+impl MatchWindow {
+    fn validate(&self) -> Result<(), MpesaError> {
+        for (field, minutes) in [
+            ("window.beforeMinutes", self.before_minutes),
+            ("window.afterMinutes", self.after_minutes),
+        ] {
+            if minutes < 0 {
+                return Err(MpesaError::InvalidField {
+                    field: field.to_string(),
+                    value: minutes.to_string(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// A bound too wide for chrono to represent leaves that side of the window open.
+    fn contains(&self, expected_at: NaiveDateTime, at: NaiveDateTime) -> bool {
+        let earliest = Duration::try_minutes(self.before_minutes)
+            .and_then(|before| expected_at.checked_sub_signed(before));
+        let latest = Duration::try_minutes(self.after_minutes)
+            .and_then(|after| expected_at.checked_add_signed(after));
+        earliest.is_none_or(|earliest| at >= earliest) && latest.is_none_or(|latest| at <= latest)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MatchRule {
+    /// Quoted our reference, paid the exact amount, inside the window.
+    ReferenceAndAmount,
+    /// No usable reference, but the only exact-amount payment inside the window.
+    AmountAndTime,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReconciledDeposit {
+    pub expected_id: String,
+    pub receipt: String,
+    pub amount: Money,
+    pub rule: MatchRule,
+    /// Minutes between when the deposit was expected and when it completed.
+    pub lag_minutes: i64,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+pub enum SuspiciousReason {
+    DuplicateReceipt,
+    AmountMismatch { expected: Money, received: Money },
+    OutsideWindow,
+    NotCompleted { status: String },
+    AmbiguousAmount { candidates: Vec<String> },
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SuspiciousEntry {
+    pub receipt: Option<String>,
+    pub expected_id: Option<String>,
+    pub reason: SuspiciousReason,
+}
+
+#[derive(Clone, Debug, PartialEq, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReconciliationReport {
+    pub matched: Vec<ReconciledDeposit>,
+    pub unmatched_expected: Vec<String>,
+    /// Money received that nothing explains.
+    pub unmatched_transactions: Vec<String>,
+    pub suspicious: Vec<SuspiciousEntry>,
+}
+
+/// True when `token` appears in `text` with no letter or digit either side, so
+/// `LW-0004` is not found inside `LW-00042`. Case-insensitive.
+fn contains_token(text: &str, token: &str) -> bool {
+    if token.is_empty() {
+        return false;
+    }
+    let text = text.to_ascii_lowercase();
+    let token = token.to_ascii_lowercase();
+    text.match_indices(&token).any(|(at, _)| {
+        let before = text[..at].chars().next_back();
+        let after = text[at + token.len()..].chars().next();
+        !before.is_some_and(char::is_alphanumeric) && !after.is_some_and(char::is_alphanumeric)
+    })
+}
+
+fn quotes_reference(txn: &StatementTransaction, reference: &str) -> bool {
+    txn.account_ref
+        .as_deref()
+        .is_some_and(|r| r.trim().eq_ignore_ascii_case(reference.trim()))
+        || contains_token(&txn.details, reference.trim())
+}
+
+/// Reconciles statement lines against expected deposits.
+///
+/// Matching is deterministic: expected deposits are taken in `expected_at` order
+/// and, among equally good candidates, the payment closest in time wins.
+pub fn reconcile(
+    transactions: &[StatementTransaction],
+    expected: &[ExpectedDeposit],
+    window: &MatchWindow,
+) -> Result<ReconciliationReport, MpesaError> {
+    window.validate()?;
+    let mut report = ReconciliationReport::default();
+    let mut used: HashSet<usize> = HashSet::new();
+
+    let mut first_seen: HashMap<&str, usize> = HashMap::new();
+    for (i, t) in transactions.iter().enumerate() {
+        if first_seen.insert(t.receipt.as_str(), i).is_some() {
+            used.insert(i);
+            report.suspicious.push(SuspiciousEntry {
+                receipt: Some(t.receipt.clone()),
+                expected_id: None,
+                reason: SuspiciousReason::DuplicateReceipt,
+            });
+        }
+    }
+    let deposits: Vec<usize> = (0..transactions.len())
+        .filter(|i| !used.contains(i))
+        .filter(|i| transactions[*i].paid_in.is_positive())
+        .collect();
+    let is_candidate =
+        |i: usize, used: &HashSet<usize>| !used.contains(&i) && transactions[i].is_completed();
+
+    let mut order: Vec<&ExpectedDeposit> = expected.iter().collect();
+    order.sort_by(|a, b| a.expected_at.cmp(&b.expected_at).then(a.id.cmp(&b.id)));
+    let closest = |e: &ExpectedDeposit, pool: &[usize]| -> Option<usize> {
+        pool.iter().copied().min_by_key(|i| {
+            (
+                (transactions[*i].completed_at - e.expected_at)
+                    .num_seconds()
+                    .abs(),
+                *i,
+            )
+        })
+    };
+
+    let mut pending = Vec::new();
+    for e in &order {
+        let hits: Vec<usize> = deposits
+            .iter()
+            .copied()
+            .filter(|i| is_candidate(*i, &used))
+            .filter(|i| {
+                let t = &transactions[*i];
+                t.paid_in == e.amount
+                    && quotes_reference(t, &e.reference)
+                    && window.contains(e.expected_at, t.completed_at)
+            })
+            .collect();
+        match closest(e, &hits) {
+            Some(i) => {
+                used.insert(i);
+                report
+                    .matched
+                    .push(matched(e, &transactions[i], MatchRule::ReferenceAndAmount));
+            }
+            None => pending.push(*e),
+        }
+    }
+
+    // A payment quoting some other deposit's reference is never matched on amount alone.
+    let quotes_any =
+        |t: &StatementTransaction| expected.iter().any(|e| quotes_reference(t, &e.reference));
+    let mut still_pending = Vec::new();
+    for e in pending {
+        let hits: Vec<usize> = deposits
+            .iter()
+            .copied()
+            .filter(|i| is_candidate(*i, &used))
+            .filter(|i| {
+                let t = &transactions[*i];
+                t.paid_in == e.amount
+                    && !quotes_any(t)
+                    && window.contains(e.expected_at, t.completed_at)
+            })
+            .collect();
+        match hits.as_slice() {
+            [i] => {
+                used.insert(*i);
+                report
+                    .matched
+                    .push(matched(e, &transactions[*i], MatchRule::AmountAndTime));
+            }
+            [] => still_pending.push(e),
+            many => {
+                report.suspicious.push(SuspiciousEntry {
+                    receipt: None,
+                    expected_id: Some(e.id.clone()),
+                    reason: SuspiciousReason::AmbiguousAmount {
+                        candidates: many
+                            .iter()
+                            .map(|i| transactions[*i].receipt.clone())
+                            .collect(),
+                    },
+                });
+                still_pending.push(e);
+            }
+        }
+    }
+
+    // Leftover payments that quote a reference point at a specific problem.
+    for &i in &deposits {
+        if used.contains(&i) {
+            continue;
+        }
+        let t = &transactions[i];
+        let Some(e) = still_pending
+            .iter()
+            .find(|e| quotes_reference(t, &e.reference))
+        else {
+            continue;
+        };
+        let reason = if !t.is_completed() {
+            SuspiciousReason::NotCompleted {
+                status: t.status.clone().unwrap_or_default(),
+            }
+        } else if t.paid_in != e.amount {
+            SuspiciousReason::AmountMismatch {
+                expected: e.amount,
+                received: t.paid_in,
+            }
+        } else {
+            SuspiciousReason::OutsideWindow
+        };
+        used.insert(i);
+        report.suspicious.push(SuspiciousEntry {
+            receipt: Some(t.receipt.clone()),
+            expected_id: Some(e.id.clone()),
+            reason,
+        });
+    }
+
+    let matched_ids: HashSet<&str> = report
+        .matched
+        .iter()
+        .map(|m| m.expected_id.as_str())
+        .collect();
+    report.unmatched_expected = order
+        .iter()
+        .filter(|e| !matched_ids.contains(e.id.as_str()))
+        .map(|e| e.id.clone())
+        .collect();
+    report.unmatched_transactions = deposits
+        .iter()
+        .filter(|i| !used.contains(i) && transactions[**i].is_completed())
+        .map(|i| transactions[*i].receipt.clone())
+        .collect();
+    Ok(report)
+}
+
+fn matched(e: &ExpectedDeposit, t: &StatementTransaction, rule: MatchRule) -> ReconciledDeposit {
+    ReconciledDeposit {
+        expected_id: e.id.clone(),
+        receipt: t.receipt.clone(),
+        amount: t.paid_in,
+        rule,
+        lag_minutes: (t.completed_at - e.expected_at).num_minutes(),
+    }
+}
+
+/// Journal entries crediting each matched client's sub-ledger from the trust account.
+///
+/// Deposits without a `client_account` are left for manual posting.
+pub fn deposit_entries(
+    report: &ReconciliationReport,
+    transactions: &[StatementTransaction],
+    expected: &[ExpectedDeposit],
+    trust_account: &str,
+) -> Vec<JournalEntry> {
+    report
+        .matched
+        .iter()
+        .filter_map(|m| {
+            let e = expected.iter().find(|e| e.id == m.expected_id)?;
+            let client_account = e.client_account.as_ref()?;
+            let t = transactions.iter().find(|t| t.receipt == m.receipt)?;
+            Some(JournalEntry {
+                id: format!("mpesa-{}", m.receipt),
+                date: t.completed_at.date(),
+                description: format!("M-PESA deposit {} for {}", m.receipt, e.reference),
+                postings: vec![
+                    Posting {
+                        account: trust_account.to_string(),
+                        side: Side::Debit,
+                        amount: m.amount,
+                    },
+                    Posting {
+                        account: client_account.clone(),
+                        side: Side::Credit,
+                        amount: m.amount,
+                    },
+                ],
+            })
+        })
+        .collect()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum StatementFormat {
+    Csv,
+    PdfText,
+}
+
+pub fn parse_statement(
+    format: StatementFormat,
+    content: &str,
+) -> Result<Vec<StatementTransaction>, MpesaError> {
+    match format {
+        StatementFormat::Csv => parse_csv(content),
+        StatementFormat::PdfText => parse_pdf_text(content),
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ReconcileInput {
+    format: StatementFormat,
+    content: String,
+    expected: Vec<ExpectedDeposit>,
+    #[serde(default)]
+    window: Option<MatchWindow>,
+    #[serde(default)]
+    trust_account: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ReconcileOutput {
+    transactions: Vec<StatementTransaction>,
+    report: ReconciliationReport,
+    journal: Vec<JournalEntry>,
+}
+
+/// Parses an M-PESA statement (`format` is `"csv"` or `"pdfText"`) into transactions.
+#[wasm_bindgen(js_name = parseMpesaStatement)]
+pub fn parse_statement_js(format: &str, content: &str) -> Result<JsValue, JsError> {
+    let format: StatementFormat = from_js(JsValue::from_str(format))?;
+    to_js(&parse_statement(format, content).map_err(js_err)?)
+}
+
+/// JS entry point: `{ format, content, expected, window?, trustAccount? }` in,
+/// `{ transactions, report, journal }` out.
+#[wasm_bindgen(js_name = reconcileMpesa)]
+pub fn reconcile_js(input: JsValue) -> Result<JsValue, JsError> {
+    let input: ReconcileInput = from_js(input)?;
+    let transactions = parse_statement(input.format, &input.content).map_err(js_err)?;
+    let report = reconcile(
+        &transactions,
+        &input.expected,
+        &input.window.unwrap_or_default(),
+    )
+    .map_err(js_err)?;
+    let journal = match &input.trust_account {
+        Some(trust) => deposit_entries(&report, &transactions, &input.expected, trust),
+        None => Vec::new(),
+    };
+    to_js(&ReconcileOutput {
+        transactions,
+        report,
+        journal,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::money::Currency;
+
+    const CSV: &str = include_str!("../../tests/fixtures/mpesa/statement.csv");
+    const PDF_TEXT: &str = include_str!("../../tests/fixtures/mpesa/statement_pdf.txt");
+
+    fn kes(s: &str) -> Money {
+        Money::parse(s, Currency::Kes).unwrap()
+    }
+
+    fn at(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    fn expect(id: &str, amount: &str, reference: &str, expected_at: &str) -> ExpectedDeposit {
+        ExpectedDeposit {
+            id: id.to_string(),
+            amount: kes(amount),
+            reference: reference.to_string(),
+            expected_at: at(expected_at),
+            client_account: Some(format!("CL:{}", id)),
+        }
+    }
+
+    fn expected() -> Vec<ExpectedDeposit> {
+        vec![
+            expect("amina", "25000", "LW-00042", "2024-03-01 09:00"),
+            // A prefix of the reference John quoted; must not be taken for it.
+            expect("otieno", "10000", "LW-0004", "2024-03-02 10:00"),
+            expect("njeri", "5000", "LW-00051", "2024-03-03 08:00"),
+            expect("mutua", "7000", "LW-00060", "2024-03-04 09:00"),
+        ]
+    }
+
+    fn check(transactions: &[StatementTransaction]) {
+        assert_eq!(transactions.len(), 6);
+        let report = reconcile(transactions, &expected(), &MatchWindow::default()).unwrap();
+
+        let rules: Vec<(&str, &str, MatchRule)> = report
+            .matched
+            .iter()
+            .map(|m| (m.expected_id.as_str(), m.receipt.as_str(), m.rule))
+            .collect();
+        assert_eq!(
+            rules,
+            vec![
+                ("amina", "SBK1A2B3C4", MatchRule::ReferenceAndAmount),
+                ("otieno", "SBL2D4E6F8", MatchRule::AmountAndTime),
+            ]
+        );
+        assert_eq!(report.matched[0].lag_minutes, 15);
+
+        assert_eq!(report.suspicious.len(), 1);
+        assert_eq!(report.suspicious[0].expected_id.as_deref(), Some("njeri"));
+        assert_eq!(
+            report.suspicious[0].reason,
+            SuspiciousReason::AmbiguousAmount {
+                candidates: vec!["SBM3G5H7J9".to_string(), "SBM4K6L8M0".to_string()],
+            }
+        );
+        assert_eq!(report.unmatched_expected, vec!["njeri", "mutua"]);
+        assert_eq!(
+            report.unmatched_transactions,
+            vec!["SBM3G5H7J9", "SBM4K6L8M0", "SBN5P7Q9R1"]
+        );
+
+        let journal = deposit_entries(&report, transactions, &expected(), "TRUST");
+        assert_eq!(journal.len(), 2);
+        assert_eq!(journal[0].id, "mpesa-SBK1A2B3C4");
+        assert_eq!(journal[0].postings[1].account, "CL:amina");
+    }
+
+    #[test]
+    fn csv_statement_reconciles() {
+        let transactions = parse_statement(StatementFormat::Csv, CSV).unwrap();
+        assert_eq!(transactions[0].account_ref.as_deref(), Some("LW-00042"));
+        assert_eq!(transactions[0].paid_in, kes("25000"));
+        assert_eq!(transactions[5].withdrawn, kes("300"));
+        assert_eq!(transactions[5].balance, Some(kes("145900")));
+        check(&transactions);
+    }
+
+    #[test]
+    fn pdf_text_statement_reconciles() {
+        let transactions = parse_statement(StatementFormat::PdfText, PDF_TEXT).unwrap();
+        assert_eq!(
+            transactions[0].details,
+            "Funds received from 254712345678 - AMINA WANJIRU Acc. LW-00042"
+        );
+        assert_eq!(transactions[0].status.as_deref(), Some("Completed"));
+        assert_eq!(
+            transactions[5].details,
+            "Business Payment Charges to SAFARICOM"
+        );
+        assert_eq!(transactions[5].withdrawn, kes("300"));
+        check(&transactions);
+    }
+
+    #[test]
+    fn references_match_as_whole_tokens() {
+        let txn = |details: &str| StatementTransaction {
+            receipt: "SBK1A2B3C4".to_string(),
+            completed_at: at("2024-03-01 09:00"),
+            details: details.to_string(),
+            status: None,
+            paid_in: kes("100"),
+            withdrawn: kes("0"),
+            balance: None,
+            account_ref: None,
+            other_party: None,
+        };
+        assert!(quotes_reference(&txn("Acc. LW-0004"), "LW-0004"));
+        assert!(quotes_reference(&txn("Acc.(lw-0004)."), "LW-0004"));
+        assert!(!quotes_reference(&txn("Acc. LW-00042"), "LW-0004"));
+        assert!(!quotes_reference(&txn("Acc. XLW-0004"), "LW-0004"));
+        assert!(!quotes_reference(&txn("Acc. LW-0004"), ""));
+    }
+
+    #[test]
+    fn reference_payments_outside_the_rules_are_flagged() {
+        let mut transactions = parse_statement(StatementFormat::Csv, CSV).unwrap();
+        // Amina underpays, and a redelivered row repeats her receipt.
+        transactions[0].paid_in = kes("20000");
+        transactions.push(transactions[0].clone());
+        let report = reconcile(&transactions, &expected(), &MatchWindow::default()).unwrap();
+        let reasons: Vec<&SuspiciousReason> = report.suspicious.iter().map(|s| &s.reason).collect();
+        assert!(reasons.contains(&&SuspiciousReason::DuplicateReceipt));
+        assert!(reasons.contains(&&SuspiciousReason::AmountMismatch {
+            expected: kes("25000"),
+            received: kes("20000"),
+        }));
+        assert!(report.unmatched_expected.contains(&"amina".to_string()));
+    }
+
+    #[test]
+    fn match_windows_must_not_be_negative_and_may_be_unbounded() {
+        let transactions = parse_statement(StatementFormat::Csv, CSV).unwrap();
+        let window = MatchWindow {
+            before_minutes: -1,
+            after_minutes: 60,
+        };
+        assert_eq!(
+            reconcile(&transactions, &expected(), &window),
+            Err(MpesaError::InvalidField {
+                field: "window.beforeMinutes".into(),
+                value: "-1".into(),
+            })
+        );
+        // Bounds past what chrono can represent leave the window open instead of panicking.
+        let window = MatchWindow {
+            before_minutes: i64::MAX,
+            after_minutes: i64::MAX,
+        };
+        let at = NaiveDateTime::parse_from_str("2024-03-01 09:00", "%Y-%m-%d %H:%M").unwrap();
+        assert!(window.contains(at, NaiveDateTime::MIN));
+        assert!(window.contains(at, NaiveDateTime::MAX));
+        let report = reconcile(&transactions, &expected(), &window).unwrap();
+        assert!(!report.matched.is_empty());
+    }
+}
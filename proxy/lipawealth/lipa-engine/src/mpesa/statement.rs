@@ -0,0 +1,264 @@
+//! M-PESA statement import: the CSV export and the text layer of the PDF statement.
+
+use std::str::FromStr;
+
+use chrono::NaiveDateTime;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use super::MpesaError;
+use crate::money::{Currency, Money};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatementTransaction {
+    pub receipt: String,
+    pub completed_at: NaiveDateTime,
+    pub details: String,
+    #[serde(default)]
+    pub status: Option<String>,
+    pub paid_in: Money,
+    pub withdrawn: Money,
+    #[serde(default)]
+    pub balance: Option<Money>,
+    /// The payer's account number / bill reference, where the export carries one.
+    #[serde(default)]
+    pub account_ref: Option<String>,
+    #[serde(default)]
+    pub other_party: Option<String>,
+}
+
+impl StatementTransaction {
+    /// Failed and reversed rows are listed on statements but moved no money.
+    pub fn is_completed(&self) -> bool {
+        self.status
+            .as_deref()
+            .is_none_or(|s| s.eq_ignore_ascii_case("completed"))
+    }
+}
+
+const TIME_FORMATS: [&str; 4] = [
+    "%Y-%m-%d %H:%M:%S",
+    "%d-%m-%Y %H:%M:%S",
+    "%d/%m/%Y %H:%M:%S",
+    "%Y-%m-%d %H:%M",
+];
+
+fn parse_time(raw: &str, line: usize) -> Result<NaiveDateTime, MpesaError> {
+    TIME_FORMATS
+        .iter()
+        .find_map(|f| NaiveDateTime::parse_from_str(raw.trim(), f).ok())
+        .ok_or_else(|| MpesaError::InvalidField {
+            field: format!("line {} Completion Time", line),
+            value: raw.to_string(),
+        })
+}
+
+/// Reads "1,000.00", "-500.00" or an empty cell (zero) as KES.
+fn parse_amount(raw: &str, field: &str, line: usize) -> Result<Money, MpesaError> {
+    let cleaned: String = raw.trim().chars().filter(|c| *c != ',').collect();
+    if cleaned.is_empty() {
+        return Ok(Money::zero(Currency::Kes));
+    }
+    Decimal::from_str(&cleaned)
+        .map(|d| Money::new(d, Currency::Kes))
+        .map_err(|_| MpesaError::InvalidAmount {
+            field: format!("line {} {}", line, field),
+            value: raw.to_string(),
+        })
+}
+
+/// Splits one CSV record, honouring double-quoted cells and `""` escapes.
+fn split_csv(line: &str) -> Vec<String> {
+    let mut cells = Vec::new();
+    let mut cell = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, quoted) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                cell.push('"');
+                chars.next();
+            }
+            ('"', _) => quoted = !quoted,
+            (',', false) => cells.push(std::mem::take(&mut cell)),
+            _ => cell.push(c),
+        }
+    }
+    cells.push(cell);
+    cells.into_iter().map(|c| c.trim().to_string()).collect()
+}
+
+fn optional(cell: Option<&String>) -> Option<String> {
+    cell.map(|c| c.trim().to_string()).filter(|c| !c.is_empty())
+}
+
+/// Parses a statement CSV, locating columns by header name so both the personal
+/// and the business (org portal) exports load. Quoted cells may not span lines.
+pub fn parse_csv(raw: &str) -> Result<Vec<StatementTransaction>, MpesaError> {
+    let mut lines = raw
+        .lines()
+        .enumerate()
+        .filter(|(_, l)| !l.trim().is_empty());
+    let (_, header) = lines
+        .next()
+        .ok_or_else(|| MpesaError::MissingField("CSV header".to_string()))?;
+    let headers: Vec<String> = split_csv(header.trim_start_matches('\u{feff}'))
+        .into_iter()
+        .map(|h| h.to_ascii_lowercase())
+        .collect();
+    let column = |names: &[&str]| headers.iter().position(|h| names.contains(&h.as_str()));
+    let required = |names: &[&str]| {
+        column(names).ok_or_else(|| MpesaError::MissingField(format!("CSV column {}", names[0])))
+    };
+
+    let receipt = required(&["receipt no.", "receipt no", "receipt"])?;
+    let completed = required(&["completion time", "completed time"])?;
+    let details = required(&["details", "transaction details"])?;
+    let paid_in = required(&["paid in", "paid in (kes)"])?;
+    let withdrawn = required(&["withdrawn", "withdrawn (kes)"])?;
+    let status = column(&["transaction status", "status"]);
+    let balance = column(&["balance", "balance (kes)"]);
+    let account = column(&["a/c no.", "account no.", "account"]);
+    let other_party = column(&["other party info", "other party"]);
+
+    let mut out = Vec::new();
+    for (idx, line) in lines {
+        let line_no = idx + 1;
+        let cells = split_csv(line);
+        let cell = |i: usize| cells.get(i).map(String::as_str).unwrap_or("");
+        let receipt_no = cell(receipt).to_string();
+        if receipt_no.is_empty() {
+            return Err(MpesaError::MissingField(format!(
+                "line {} Receipt No.",
+                line_no
+            )));
+        }
+        // The business export shows withdrawals as negative figures; store magnitudes.
+        let withdrawn_amount = parse_amount(cell(withdrawn), "Withdrawn", line_no)?.abs();
+        out.push(StatementTransaction {
+            receipt: receipt_no,
+            completed_at: parse_time(cell(completed), line_no)?,
+            details: cell(details).to_string(),
+            status: status.and_then(|i| optional(cells.get(i))),
+            paid_in: parse_amount(cell(paid_in), "Paid In", line_no)?,
+            withdrawn: withdrawn_amount,
+            balance: match balance.and_then(|i| optional(cells.get(i))) {
+                Some(b) => Some(parse_amount(&b, "Balance", line_no)?),
+                None => None,
+            },
+            account_ref: account.and_then(|i| optional(cells.get(i))),
+            other_party: other_party.and_then(|i| optional(cells.get(i))),
+        });
+    }
+    Ok(out)
+}
+
+fn looks_like_receipt(token: &str) -> bool {
+    token.len() == 10
+        && token
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+        && token.chars().any(|c| c.is_ascii_uppercase())
+        && token.chars().any(|c| c.is_ascii_digit())
+}
+
+fn looks_like_amount(token: &str) -> bool {
+    let t = token.trim_start_matches('-');
+    !t.is_empty()
+        && t.contains('.')
+        && t.chars()
+            .all(|c| c.is_ascii_digit() || c == ',' || c == '.')
+}
+
+const STATUSES: [&str; 4] = ["Completed", "Failed", "Reversed", "Cancelled"];
+
+fn is_page_furniture(line: &str) -> bool {
+    line.starts_with("Page ")
+        || line.starts_with("Receipt No")
+        || line.starts_with("Disclaimer")
+        || line.starts_with("M-PESA STATEMENT")
+}
+
+fn ends_with_amounts(line: &str) -> bool {
+    let mut tail = line.split_whitespace().rev();
+    matches!((tail.next(), tail.next()), (Some(a), Some(b)) if looks_like_amount(a) && looks_like_amount(b))
+}
+
+/// Parses the text pulled out of an M-PESA PDF statement.
+///
+/// Each transaction starts on a line beginning `<receipt> <date> <time>` and
+/// carries `<status> <amount> <balance>` at the end, where withdrawals have a
+/// minus sign. The details column often wraps onto following lines; those are
+/// folded back into the transaction's details. Page headers and footers are skipped.
+pub fn parse_pdf_text(raw: &str) -> Result<Vec<StatementTransaction>, MpesaError> {
+    // (line number, row text up to and including the amounts, wrapped detail lines)
+    let mut rows: Vec<(usize, String, Vec<String>)> = Vec::new();
+    for (idx, line) in raw.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || is_page_furniture(line) {
+            continue;
+        }
+        let first = line.split_whitespace().next().unwrap_or("");
+        if looks_like_receipt(first) {
+            rows.push((idx + 1, line.to_string(), Vec::new()));
+        } else if let Some((_, head, wrapped)) = rows.last_mut() {
+            if ends_with_amounts(head) {
+                wrapped.push(line.to_string());
+            } else {
+                head.push(' ');
+                head.push_str(line);
+            }
+        }
+    }
+
+    rows.into_iter()
+        .map(|(line_no, row, wrapped)| {
+            let tokens: Vec<&str> = row.split_whitespace().collect();
+            if tokens.len() < 6 {
+                return Err(MpesaError::InvalidField {
+                    field: format!("line {}", line_no),
+                    value: row.clone(),
+                });
+            }
+            let completed_at = parse_time(&format!("{} {}", tokens[1], tokens[2]), line_no)?;
+            if !ends_with_amounts(&row) {
+                return Err(MpesaError::InvalidAmount {
+                    field: format!("line {}", line_no),
+                    value: row.clone(),
+                });
+            }
+            let balance_token = tokens[tokens.len() - 1];
+            let amount_token = tokens[tokens.len() - 2];
+            let mut detail_end = tokens.len() - 2;
+            let status = if STATUSES.contains(&tokens[detail_end - 1]) {
+                detail_end -= 1;
+                Some(tokens[detail_end].to_string())
+            } else {
+                None
+            };
+            let amount = parse_amount(amount_token, "Amount", line_no)?;
+            let (paid_in, withdrawn) = if amount.is_negative() {
+                (Money::zero(Currency::Kes), amount.abs())
+            } else {
+                (amount, Money::zero(Currency::Kes))
+            };
+            let mut details = tokens[3..detail_end].join(" ");
+            for extra in wrapped {
+                details.push(' ');
+                details.push_str(&extra);
+            }
+            Ok(StatementTransaction {
+                receipt: tokens[0].to_string(),
+                completed_at,
+                details,
+                status,
+                paid_in,
+                withdrawn,
+                balance: Some(parse_amount(balance_token, "Balance", line_no)?),
+                account_ref: None,
+                other_party: None,
+            })
+        })
+        .collect()
+}
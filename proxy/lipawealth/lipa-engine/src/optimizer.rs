@@ -0,0 +1,922 @@
+//! Allocation optimiser: minimum variance, mean-variance (and its efficient frontier)
+//! and equal risk contribution, under long-only, per-asset, offshore and liquidity
+//! constraints.
+//!
+//! Everything is solved by projected gradient descent, with an exact projection
+//! onto the feasible set computed through the dual of the projection problem.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::js::{from_js, js_err, to_js};
+use crate::linalg::{self, Matrix};
+
+const MAX_ITERATIONS: usize = 5_000;
+const TOLERANCE: f64 = 1e-10;
+/// Largest constraint violation accepted in a solution.
+const FEASIBILITY_TOLERANCE: f64 = 1e-7;
+/// Each frontier point is a full solve, so a single call is capped at this many.
+pub const MAX_FRONTIER_POINTS: u32 = 200;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum OptimiserError {
+    NoAssets,
+    InvalidAsset {
+        asset: String,
+    },
+    InvalidCorrelations,
+    /// The covariance matrix is not positive semi-definite.
+    NotPositiveSemiDefinite,
+    Infeasible(&'static str),
+    InvalidObjective(&'static str),
+    /// The solver stopped before reaching a feasible optimum (e.g. extreme inputs).
+    NoConvergence,
+}
+
+impl fmt::Display for OptimiserError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OptimiserError::NoAssets => f.write_str("nothing to allocate: no assets given"),
+            OptimiserError::InvalidAsset { asset } => {
+                write!(
+                    f,
+                    "asset {} has an invalid volatility or weight bounds",
+                    asset
+                )
+            }
+            OptimiserError::InvalidCorrelations => {
+                f.write_str("correlations must be a symmetric matrix with ones on the diagonal")
+            }
+            OptimiserError::NotPositiveSemiDefinite => {
+                f.write_str("covariance matrix is not positive semi-definite")
+            }
+            OptimiserError::Infeasible(why) => write!(f, "constraints cannot be met: {}", why),
+            OptimiserError::InvalidObjective(why) => write!(f, "invalid objective: {}", why),
+            OptimiserError::NoConvergence => f.write_str("optimiser did not converge"),
+        }
+    }
+}
+
+impl std::error::Error for OptimiserError {}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OptimiserAsset {
+    pub name: String,
+    /// Annual expected return (only used by the mean-variance objectives).
+    #[serde(default)]
+    pub expected_return: f64,
+    pub volatility: f64,
+    #[serde(default)]
+    pub min_weight: f64,
+    #[serde(default = "default_max_weight")]
+    pub max_weight: f64,
+    /// Counts towards the offshore exposure limit.
+    #[serde(default)]
+    pub offshore: bool,
+    /// Counts towards the minimum KES liquidity (cash, money market, T-bills).
+    #[serde(default)]
+    pub kes_liquid: bool,
+}
+
+fn default_max_weight() -> f64 {
+    1.0
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Constraints {
+    /// When set, negative minimum weights are raised to zero.
+    #[serde(default = "default_long_only")]
+    pub long_only: bool,
+    #[serde(default)]
+    pub max_offshore: Option<f64>,
+    #[serde(default)]
+    pub min_kes_liquidity: Option<f64>,
+}
+
+fn default_long_only() -> bool {
+    true
+}
+
+impl Default for Constraints {
+    fn default() -> Self {
+        Constraints {
+            long_only: true,
+            max_offshore: None,
+            min_kes_liquidity: None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+pub enum Objective {
+    MinVariance,
+    /// Maximise `μᵀw − aversion/2 · wᵀΣw`.
+    MeanVariance {
+        aversion: f64,
+    },
+    /// `points` portfolios (2 to `MAX_FRONTIER_POINTS`) from minimum variance up to
+    /// the highest attainable return.
+    Frontier {
+        points: u32,
+    },
+    EqualRiskContribution,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AssetWeight {
+    pub name: String,
+    pub weight: f64,
+    /// Contribution to portfolio volatility; these sum to the volatility.
+    pub risk_contribution: f64,
+    /// Share of portfolio variance; these sum to one.
+    pub risk_share: f64,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Allocation {
+    pub weights: Vec<AssetWeight>,
+    pub expected_return: f64,
+    pub volatility: f64,
+}
+
+/// A linear constraint `aᵀw ≤ b`, or `= b` when `equality` is set.
+struct Linear {
+    a: Vec<f64>,
+    b: f64,
+    equality: bool,
+}
+
+impl Linear {
+    fn violation(&self, w: &[f64]) -> f64 {
+        let lhs: f64 = self.a.iter().zip(w).map(|(a, x)| a * x).sum();
+        if self.equality {
+            (lhs - self.b).abs()
+        } else {
+            (lhs - self.b).max(0.0)
+        }
+    }
+}
+
+struct FeasibleSet {
+    lower: Vec<f64>,
+    upper: Vec<f64>,
+    linear: Vec<Linear>,
+}
+
+impl FeasibleSet {
+    fn violation(&self, w: &[f64]) -> f64 {
+        let bounds = w
+            .iter()
+            .zip(self.lower.iter().zip(&self.upper))
+            .map(|(x, (lo, hi))| (lo - x).max(x - hi).max(0.0))
+            .fold(0.0, f64::max);
+        self.linear
+            .iter()
+            .map(|c| c.violation(w))
+            .fold(bounds, f64::max)
+    }
+
+    /// Box-clamped point for dual multipliers `lambda`: the inner minimiser of the
+    /// projection's Lagrangian.
+    fn primal(&self, point: &[f64], lambda: &[f64]) -> Vec<f64> {
+        (0..point.len())
+            .map(|j| {
+                let shift: f64 = self
+                    .linear
+                    .iter()
+                    .zip(lambda)
+                    .map(|(c, l)| c.a[j] * l)
+                    .sum();
+                (point[j] - shift).clamp(self.lower[j], self.upper[j])
+            })
+            .collect()
+    }
+
+    fn dual_value(&self, point: &[f64], lambda: &[f64]) -> f64 {
+        let x = self.primal(point, lambda);
+        let distance: f64 = x.iter().zip(point).map(|(a, b)| (a - b).powi(2)).sum();
+        let penalty: f64 = self
+            .linear
+            .iter()
+            .zip(lambda)
+            .map(|(c, l)| l * (dot(&c.a, &x) - c.b))
+            .sum();
+        distance / 2.0 + penalty
+    }
+
+    /// Euclidean projection onto the feasible set.
+    ///
+    /// The box is handled in closed form and the few linear constraints through their
+    /// multipliers, which are found by a projected semi-smooth Newton ascent on the
+    /// (concave, piecewise-quadratic) dual. That converges in a handful of steps even
+    /// when the answer sits on a vertex, where alternating projections crawl.
+    fn project(&self, point: &[f64]) -> Vec<f64> {
+        let m = self.linear.len();
+        let mut lambda = vec![0.0; m];
+        for _ in 0..MAX_NEWTON_STEPS {
+            let x = self.primal(point, &lambda);
+            let grad: Vec<f64> = self.linear.iter().map(|c| dot(&c.a, &x) - c.b).collect();
+            // Inequality multipliers stuck at zero with a satisfied constraint stay put.
+            let active: Vec<usize> = (0..m)
+                .filter(|i| self.linear[*i].equality || lambda[*i] > 0.0 || grad[*i] > 0.0)
+                .collect();
+            if active.iter().all(|i| grad[*i].abs() < TOLERANCE * 1e-3) {
+                break;
+            }
+            let free: Vec<usize> = (0..point.len())
+                .filter(|j| x[*j] > self.lower[*j] && x[*j] < self.upper[*j])
+                .collect();
+            let hessian: Matrix = active
+                .iter()
+                .map(|i| {
+                    active
+                        .iter()
+                        .map(|k| {
+                            let h: f64 = free
+                                .iter()
+                                .map(|j| self.linear[*i].a[*j] * self.linear[*k].a[*j])
+                                .sum();
+                            if i == k {
+                                h + 1e-12
+                            } else {
+                                h
+                            }
+                        })
+                        .collect()
+                })
+                .collect();
+            let rhs: Vec<f64> = active.iter().map(|i| grad[*i]).collect();
+            let direction = linalg::solve(&hessian, &rhs).unwrap_or(rhs);
+
+            let current = self.dual_value(point, &lambda);
+            let mut step = 1.0;
+            let mut improved = false;
+            for _ in 0..60 {
+                let mut trial = lambda.clone();
+                for (i, d) in active.iter().zip(&direction) {
+                    trial[*i] += step * d;
+                    if !self.linear[*i].equality {
+                        trial[*i] = trial[*i].max(0.0);
+                    }
+                }
+                if self.dual_value(point, &trial) > current {
+                    lambda = trial;
+                    improved = true;
+                    break;
+                }
+                step /= 2.0;
+            }
+            if !improved {
+                break;
+            }
+        }
+        self.primal(point, &lambda)
+    }
+}
+
+/// Newton steps allowed per projection; convergence normally takes a few.
+const MAX_NEWTON_STEPS: usize = 200;
+
+fn covariance(
+    assets: &[OptimiserAsset],
+    correlations: Option<&Matrix>,
+) -> Result<Matrix, OptimiserError> {
+    let n = assets.len();
+    let corr = match correlations {
+        Some(c) => {
+            let valid = c.len() == n
+                && linalg::is_symmetric(c)
+                && (0..n).all(|i| (c[i][i] - 1.0).abs() < 1e-9)
+                && c.iter().flatten().all(|r| r.abs() <= 1.0);
+            if !valid {
+                return Err(OptimiserError::InvalidCorrelations);
+            }
+            c.clone()
+        }
+        None => (0..n)
+            .map(|i| (0..n).map(|j| if i == j { 1.0 } else { 0.0 }).collect())
+            .collect(),
+    };
+    let cov: Matrix = (0..n)
+        .map(|i| {
+            (0..n)
+                .map(|j| corr[i][j] * assets[i].volatility * assets[j].volatility)
+                .collect()
+        })
+        .collect();
+    linalg::cholesky(&cov).ok_or(OptimiserError::NotPositiveSemiDefinite)?;
+    Ok(cov)
+}
+
+fn feasible_set(
+    assets: &[OptimiserAsset],
+    constraints: &Constraints,
+) -> Result<FeasibleSet, OptimiserError> {
+    let lower: Vec<f64> = assets
+        .iter()
+        .map(|a| {
+            if constraints.long_only {
+                a.min_weight.max(0.0)
+            } else {
+                a.min_weight
+            }
+        })
+        .collect();
+    let upper: Vec<f64> = assets.iter().map(|a| a.max_weight).collect();
+    if lower.iter().sum::<f64>() > 1.0 + FEASIBILITY_TOLERANCE {
+        return Err(OptimiserError::Infeasible("minimum weights exceed 100%"));
+    }
+    if upper.iter().sum::<f64>() < 1.0 - FEASIBILITY_TOLERANCE {
+        return Err(OptimiserError::Infeasible("maximum weights are below 100%"));
+    }
+
+    let mut linear = vec![Linear {
+        a: vec![1.0; assets.len()],
+        b: 1.0,
+        equality: true,
+    }];
+    if let Some(limit) = constraints.max_offshore {
+        linear.push(Linear {
+            a: assets.iter().map(|a| a.offshore as u8 as f64).collect(),
+            b: limit,
+            equality: false,
+        });
+    }
+    if let Some(floor) = constraints.min_kes_liquidity {
+        linear.push(Linear {
+            a: assets
+                .iter()
+                .map(|a| -(a.kes_liquid as u8 as f64))
+                .collect(),
+            b: -floor,
+            equality: false,
+        });
+    }
+    Ok(FeasibleSet {
+        lower,
+        upper,
+        linear,
+    })
+}
+
+fn mat_vec(m: &[Vec<f64>], v: &[f64]) -> Vec<f64> {
+    m.iter()
+        .map(|row| row.iter().zip(v).map(|(a, b)| a * b).sum())
+        .collect()
+}
+
+fn dot(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+/// Upper bound on the largest eigenvalue (maximum absolute row sum).
+fn spectral_bound(m: &[Vec<f64>]) -> f64 {
+    m.iter()
+        .map(|row| row.iter().map(|x| x.abs()).sum::<f64>())
+        .fold(0.0, f64::max)
+}
+
+/// Minimises `½·wᵀQw − cᵀw` over the feasible set (FISTA).
+fn solve_qp(q: &[Vec<f64>], c: &[f64], set: &FeasibleSet) -> Result<Vec<f64>, OptimiserError> {
+    let n = c.len();
+    let step = 1.0 / spectral_bound(q).max(1e-12);
+    let mut w = set.project(&vec![1.0 / n as f64; n]);
+    if set.violation(&w) > FEASIBILITY_TOLERANCE {
+        return Err(OptimiserError::Infeasible(
+            "no allocation satisfies every constraint",
+        ));
+    }
+    let mut y = w.clone();
+    let mut t = 1.0_f64;
+    for _ in 0..MAX_ITERATIONS {
+        let grad: Vec<f64> = mat_vec(q, &y).iter().zip(c).map(|(g, c)| g - c).collect();
+        let trial: Vec<f64> = y.iter().zip(&grad).map(|(y, g)| y - step * g).collect();
+        let next = set.project(&trial);
+        let t_next = (1.0 + (1.0 + 4.0 * t * t).sqrt()) / 2.0;
+        let momentum = (t - 1.0) / t_next;
+        y = next
+            .iter()
+            .zip(&w)
+            .map(|(a, b)| a + momentum * (a - b))
+            .collect();
+        let moved = max_change(&next, &w);
+        w = next;
+        t = t_next;
+        if moved < TOLERANCE {
+            // Momentum can park on a vertex for a step; only stop if a plain
+            // projected-gradient step from `w` stays put too, else restart.
+            let grad: Vec<f64> = mat_vec(q, &w).iter().zip(c).map(|(g, c)| g - c).collect();
+            let trial: Vec<f64> = w.iter().zip(&grad).map(|(w, g)| w - step * g).collect();
+            if max_change(&set.project(&trial), &w) < TOLERANCE {
+                break;
+            }
+            y = w.clone();
+            t = 1.0;
+        }
+    }
+    if set.violation(&w) > FEASIBILITY_TOLERANCE {
+        return Err(OptimiserError::NoConvergence);
+    }
+    Ok(w)
+}
+
+fn max_change(a: &[f64], b: &[f64]) -> f64 {
+    a.iter()
+        .zip(b)
+        .map(|(x, y)| (x - y).abs())
+        .fold(0.0, f64::max)
+}
+
+/// Highest attainable `μᵀw`, by projected ascent: the iteration's fixed points are
+/// exactly the linear programme's optima.
+fn max_return(mu: &[f64], set: &FeasibleSet) -> Result<f64, OptimiserError> {
+    let scale = mu.iter().map(|m| m.abs()).fold(0.0, f64::max);
+    if scale == 0.0 {
+        return Ok(0.0);
+    }
+    let step = 0.1 / scale;
+    let mut w = set.project(&vec![1.0 / mu.len() as f64; mu.len()]);
+    for _ in 0..MAX_ITERATIONS {
+        let trial: Vec<f64> = w.iter().zip(mu).map(|(w, m)| w + step * m).collect();
+        let next = set.project(&trial);
+        let moved = max_change(&next, &w);
+        w = next;
+        if moved < TOLERANCE {
+            break;
+        }
+    }
+    if set.violation(&w) > FEASIBILITY_TOLERANCE {
+        return Err(OptimiserError::NoConvergence);
+    }
+    Ok(dot(mu, &w))
+}
+
+/// Unconstrained equal-risk-contribution weights by cyclical coordinate descent on
+/// `½·yᵀΣy − Σ ln(y)/n`, whose minimiser, rescaled to sum to one, equalises risk.
+fn unconstrained_erc(cov: &[Vec<f64>]) -> Vec<f64> {
+    let n = cov.len();
+    let budget = 1.0 / n as f64;
+    let mut y: Vec<f64> = cov
+        .iter()
+        .enumerate()
+        .map(|(i, r)| 1.0 / r[i].sqrt())
+        .collect();
+    for _ in 0..MAX_ITERATIONS {
+        let mut moved = 0.0_f64;
+        for i in 0..n {
+            let a: f64 = (0..n).filter(|j| *j != i).map(|j| cov[i][j] * y[j]).sum();
+            let next = (-a + (a * a + 4.0 * cov[i][i] * budget).sqrt()) / (2.0 * cov[i][i]);
+            moved = moved.max((next - y[i]).abs());
+            y[i] = next;
+        }
+        if moved < TOLERANCE {
+            break;
+        }
+    }
+    let total: f64 = y.iter().sum();
+    y.iter().map(|v| v / total).collect()
+}
+
+/// Risk-contribution dispersion `Σ (wᵢ(Σw)ᵢ − wᵀΣw/n)²` and its gradient.
+fn erc_dispersion(cov: &[Vec<f64>], w: &[f64]) -> (f64, Vec<f64>) {
+    let v = mat_vec(cov, w);
+    let variance = dot(w, &v);
+    let mean = variance / w.len() as f64;
+    let e: Vec<f64> = w.iter().zip(&v).map(|(w, v)| w * v - mean).collect();
+    let ew: Vec<f64> = e.iter().zip(w).map(|(e, w)| e * w).collect();
+    let cov_ew = mat_vec(cov, &ew);
+    // The mean's derivative drops out because the deviations sum to zero.
+    let grad = (0..w.len())
+        .map(|k| 2.0 * (e[k] * v[k] + cov_ew[k]))
+        .collect();
+    (e.iter().map(|x| x * x).sum(), grad)
+}
+
+/// Equal risk contribution. Exact when the constraints do not bind; otherwise the
+/// feasible allocation whose risk contributions are as even as possible.
+fn equal_risk_contribution(
+    cov: &[Vec<f64>],
+    set: &FeasibleSet,
+) -> Result<Vec<f64>, OptimiserError> {
+    if cov.iter().enumerate().any(|(i, r)| r[i] <= 0.0) {
+        return Err(OptimiserError::InvalidObjective(
+            "risk parity needs every asset to have positive volatility",
+        ));
+    }
+    let erc = unconstrained_erc(cov);
+    if set.violation(&erc) <= FEASIBILITY_TOLERANCE {
+        return Ok(erc);
+    }
+    let mut w = set.project(&erc);
+    if set.violation(&w) > FEASIBILITY_TOLERANCE {
+        return Err(OptimiserError::Infeasible(
+            "no allocation satisfies every constraint",
+        ));
+    }
+    let (mut value, mut grad) = erc_dispersion(cov, &w);
+    let mut step = 1.0 / spectral_bound(cov).powi(2).max(1e-12);
+    for _ in 0..MAX_ITERATIONS {
+        // Backtracking: shrink the step until the projected move improves.
+        let mut improved = None;
+        for _ in 0..50 {
+            let trial: Vec<f64> = w.iter().zip(&grad).map(|(w, g)| w - step * g).collect();
+            let candidate = set.project(&trial);
+            let (v, g) = erc_dispersion(cov, &candidate);
+            if v < value {
+                improved = Some((candidate, v, g));
+                break;
+            }
+            step /= 2.0;
+        }
+        let Some((candidate, v, g)) = improved else {
+            break;
+        };
+        let moved = max_change(&candidate, &w);
+        w = candidate;
+        value = v;
+        grad = g;
+        step *= 2.0;
+        if moved < TOLERANCE {
+            break;
+        }
+    }
+    Ok(w)
+}
+
+fn describe(assets: &[OptimiserAsset], cov: &[Vec<f64>], w: &[f64]) -> Allocation {
+    let v = mat_vec(cov, w);
+    let variance = dot(w, &v).max(0.0);
+    let volatility = variance.sqrt();
+    let weights = assets
+        .iter()
+        .enumerate()
+        .map(|(i, a)| {
+            let marginal = w[i] * v[i];
+            AssetWeight {
+                name: a.name.clone(),
+                weight: w[i],
+                risk_contribution: if volatility > 0.0 {
+                    marginal / volatility
+                } else {
+                    0.0
+                },
+                risk_share: if variance > 0.0 {
+                    marginal / variance
+                } else {
+                    0.0
+                },
+            }
+        })
+        .collect();
+    Allocation {
+        weights,
+        expected_return: assets
+            .iter()
+            .zip(w)
+            .map(|(a, w)| a.expected_return * w)
+            .sum(),
+        volatility,
+    }
+}
+
+/// Solves `objective` and returns one allocation, or `points` for the frontier.
+pub fn optimise(
+    assets: &[OptimiserAsset],
+    correlations: Option<&Matrix>,
+    constraints: &Constraints,
+    objective: Objective,
+) -> Result<Vec<Allocation>, OptimiserError> {
+    if assets.is_empty() {
+        return Err(OptimiserError::NoAssets);
+    }
+    for a in assets {
+        let valid = a.volatility >= 0.0
+            && a.volatility.is_finite()
+            && a.expected_return.is_finite()
+            && a.min_weight <= a.max_weight;
+        if !valid {
+            return Err(OptimiserError::InvalidAsset {
+                asset: a.name.clone(),
+            });
+        }
+    }
+    let cov = covariance(assets, correlations)?;
+    let mut set = feasible_set(assets, constraints)?;
+    let n = assets.len();
+    let mu: Vec<f64> = assets.iter().map(|a| a.expected_return).collect();
+    let scaled = |k: f64| -> Matrix {
+        cov.iter()
+            .map(|r| r.iter().map(|x| x * k).collect())
+            .collect()
+    };
+
+    let weights = match objective {
+        Objective::MinVariance => vec![solve_qp(&scaled(2.0), &vec![0.0; n], &set)?],
+        Objective::MeanVariance { aversion } => {
+            if aversion.is_nan() || aversion <= 0.0 {
+                return Err(OptimiserError::InvalidObjective(
+                    "risk aversion must be positive",
+                ));
+            }
+            vec![solve_qp(&scaled(aversion), &mu, &set)?]
+        }
+        Objective::EqualRiskContribution => vec![equal_risk_contribution(&cov, &set)?],
+        Objective::Frontier { points } => {
+            if points < 2 {
+                return Err(OptimiserError::InvalidObjective(
+                    "a frontier needs at least two points",
+                ));
+            }
+            if points > MAX_FRONTIER_POINTS {
+                return Err(OptimiserError::InvalidObjective(
+                    "a frontier has at most 200 points",
+                ));
+            }
+            let low = solve_qp(&scaled(2.0), &vec![0.0; n], &set)?;
+            let r_low = dot(&mu, &low);
+            // Slightly inside the maximum so the last point stays strictly feasible.
+            let r_high = max_return(&mu, &set)? - FEASIBILITY_TOLERANCE;
+            let mut frontier = vec![low];
+            for k in 1..points {
+                let target = r_low + (r_high - r_low).max(0.0) * k as f64 / (points - 1) as f64;
+                set.linear.push(Linear {
+                    a: mu.iter().map(|m| -m).collect(),
+                    b: -target,
+                    equality: false,
+                });
+                frontier.push(solve_qp(&scaled(2.0), &vec![0.0; n], &set)?);
+                set.linear.pop();
+            }
+            frontier
+        }
+    };
+    Ok(weights.iter().map(|w| describe(assets, &cov, w)).collect())
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OptimiseInput {
+    assets: Vec<OptimiserAsset>,
+    #[serde(default)]
+    correlations: Option<Matrix>,
+    #[serde(default)]
+    constraints: Constraints,
+    objective: Objective,
+}
+
+/// JS entry point: `{ assets, correlations?, constraints?, objective: { kind, … } }` in,
+/// an array of allocations out (one, or one per frontier point).
+#[wasm_bindgen(js_name = optimiseAllocation)]
+pub fn optimise_js(input: JsValue) -> Result<JsValue, JsError> {
+    let input: OptimiseInput = from_js(input)?;
+    let allocations = optimise(
+        &input.assets,
+        input.correlations.as_ref(),
+        &input.constraints,
+        input.objective,
+    )
+    .map_err(js_err)?;
+    to_js(&allocations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64, tol: f64) {
+        assert!((a - b).abs() < tol, "{} != {}", a, b);
+    }
+
+    fn asset(name: &str, expected_return: f64, volatility: f64) -> OptimiserAsset {
+        OptimiserAsset {
+            name: name.to_string(),
+            expected_return,
+            volatility,
+            min_weight: 0.0,
+            max_weight: 1.0,
+            offshore: false,
+            kes_liquid: false,
+        }
+    }
+
+    fn universe() -> (Vec<OptimiserAsset>, Matrix) {
+        let mut assets = vec![
+            asset("NSE equities", 0.14, 0.22),
+            asset("Kenya bonds", 0.15, 0.07),
+            asset("Money market", 0.11, 0.01),
+            asset("S&P 500 (KES)", 0.12, 0.18),
+        ];
+        assets[0].max_weight = 0.5;
+        assets[1].min_weight = 0.1;
+        assets[2].kes_liquid = true;
+        assets[3].offshore = true;
+        let corr = vec![
+            vec![1.0, 0.2, 0.0, 0.3],
+            vec![0.2, 1.0, 0.1, 0.0],
+            vec![0.0, 0.1, 1.0, 0.0],
+            vec![0.3, 0.0, 0.0, 1.0],
+        ];
+        (assets, corr)
+    }
+
+    fn weights(a: &Allocation) -> Vec<f64> {
+        a.weights.iter().map(|w| w.weight).collect()
+    }
+
+    #[test]
+    fn min_variance_matches_the_two_asset_closed_form() {
+        let assets = [asset("A", 0.1, 0.2), asset("B", 0.05, 0.1)];
+        let corr = vec![vec![1.0, 0.3], vec![0.3, 1.0]];
+        let a = &optimise(
+            &assets,
+            Some(&corr),
+            &Constraints::default(),
+            Objective::MinVariance,
+        )
+        .unwrap()[0];
+        // w_A = (σB² − ρσAσB) / (σA² + σB² − 2ρσAσB)
+        let w_a = (0.01 - 0.3 * 0.02) / (0.04 + 0.01 - 2.0 * 0.3 * 0.02);
+        close(a.weights[0].weight, w_a, 1e-6);
+        close(a.weights[1].weight, 1.0 - w_a, 1e-6);
+        let variance =
+            w_a.powi(2) * 0.04 + (1.0 - w_a).powi(2) * 0.01 + 2.0 * w_a * (1.0 - w_a) * 0.3 * 0.02;
+        close(a.volatility, variance.sqrt(), 1e-8);
+    }
+
+    #[test]
+    fn every_objective_sums_to_one_within_its_bounds() {
+        let (assets, corr) = universe();
+        let constraints = Constraints {
+            long_only: true,
+            max_offshore: Some(0.15),
+            min_kes_liquidity: Some(0.2),
+        };
+        for objective in [
+            Objective::MinVariance,
+            Objective::MeanVariance { aversion: 3.0 },
+            Objective::EqualRiskContribution,
+            Objective::Frontier { points: 5 },
+        ] {
+            for allocation in optimise(&assets, Some(&corr), &constraints, objective).unwrap() {
+                let w = weights(&allocation);
+                close(w.iter().sum(), 1.0, 1e-7);
+                for (x, a) in w.iter().zip(&assets) {
+                    assert!(
+                        *x >= a.min_weight - 1e-7 && *x <= a.max_weight + 1e-7,
+                        "{:?}",
+                        w
+                    );
+                }
+                assert!(w[3] <= 0.15 + 1e-7, "{:?}", objective);
+                assert!(w[2] >= 0.2 - 1e-7, "{:?}", objective);
+                let shares: f64 = allocation.weights.iter().map(|w| w.risk_share).sum();
+                close(shares, 1.0, 1e-9);
+                let contributions: f64 =
+                    allocation.weights.iter().map(|w| w.risk_contribution).sum();
+                close(contributions, allocation.volatility, 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn frontier_climbs_from_minimum_variance() {
+        let (assets, corr) = universe();
+        let frontier = optimise(
+            &assets,
+            Some(&corr),
+            &Constraints::default(),
+            Objective::Frontier { points: 4 },
+        )
+        .unwrap();
+        assert_eq!(frontier.len(), 4);
+        for pair in frontier.windows(2) {
+            assert!(pair[1].expected_return >= pair[0].expected_return - 1e-9);
+            assert!(pair[1].volatility >= pair[0].volatility - 1e-9);
+        }
+        // The top point is (just inside) all in the highest-returning asset.
+        close(frontier[3].weights[1].weight, 1.0, 1e-4);
+    }
+
+    #[test]
+    fn frontier_point_count_is_bounded() {
+        let (assets, corr) = universe();
+        let frontier = |points| {
+            optimise(
+                &assets,
+                Some(&corr),
+                &Constraints::default(),
+                Objective::Frontier { points },
+            )
+        };
+        assert_eq!(
+            frontier(MAX_FRONTIER_POINTS).unwrap().len(),
+            MAX_FRONTIER_POINTS as usize
+        );
+        assert_eq!(
+            frontier(MAX_FRONTIER_POINTS + 1),
+            Err(OptimiserError::InvalidObjective(
+                "a frontier has at most 200 points"
+            ))
+        );
+        assert_eq!(
+            frontier(1),
+            Err(OptimiserError::InvalidObjective(
+                "a frontier needs at least two points"
+            ))
+        );
+    }
+
+    #[test]
+    fn risk_parity_equalises_contributions() {
+        let (mut assets, corr) = universe();
+        for a in &mut assets {
+            a.min_weight = 0.0;
+            a.max_weight = 1.0;
+        }
+        let a = &optimise(
+            &assets,
+            Some(&corr),
+            &Constraints::default(),
+            Objective::EqualRiskContribution,
+        )
+        .unwrap()[0];
+        for w in &a.weights {
+            close(w.risk_share, 0.25, 1e-6);
+        }
+
+        // Uncorrelated: weights are proportional to 1/σ.
+        let pair = [asset("A", 0.0, 0.2), asset("B", 0.0, 0.1)];
+        let a = &optimise(
+            &pair,
+            None,
+            &Constraints::default(),
+            Objective::EqualRiskContribution,
+        )
+        .unwrap()[0];
+        close(a.weights[0].weight, 1.0 / 3.0, 1e-8);
+    }
+
+    #[test]
+    fn infeasible_bounds_are_errors() {
+        let (mut assets, corr) = universe();
+        assets[1].min_weight = 0.95;
+        assets[2].min_weight = 0.1;
+        assert_eq!(
+            optimise(
+                &assets,
+                Some(&corr),
+                &Constraints::default(),
+                Objective::MinVariance
+            ),
+            Err(OptimiserError::Infeasible("minimum weights exceed 100%"))
+        );
+
+        let (mut assets, corr) = universe();
+        for a in &mut assets {
+            a.max_weight = 0.2;
+        }
+        assert_eq!(
+            optimise(
+                &assets,
+                Some(&corr),
+                &Constraints::default(),
+                Objective::MinVariance
+            ),
+            Err(OptimiserError::Infeasible("maximum weights are below 100%"))
+        );
+
+        let (mut assets, corr) = universe();
+        assets[3].min_weight = 0.3;
+        let constraints = Constraints {
+            max_offshore: Some(0.2),
+            ..Constraints::default()
+        };
+        for objective in [Objective::MinVariance, Objective::EqualRiskContribution] {
+            assert_eq!(
+                optimise(&assets, Some(&corr), &constraints, objective),
+                Err(OptimiserError::Infeasible(
+                    "no allocation satisfies every constraint"
+                ))
+            );
+        }
+
+        let (mut assets, corr) = universe();
+        assets[0].min_weight = 0.6;
+        assert!(matches!(
+            optimise(
+                &assets,
+                Some(&corr),
+                &Constraints::default(),
+                Objective::MinVariance
+            ),
+            Err(OptimiserError::InvalidAsset { .. })
+        ));
+    }
+}
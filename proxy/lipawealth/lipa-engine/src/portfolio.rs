@@ -0,0 +1,288 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::fx::{FxError, FxQuote, FxTable};
+use crate::js::{from_js, js_err, to_js};
+use crate::money::{round_bankers, Currency, Money, MoneyError};
+
+/// A position in a single instrument, priced in `currency`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Holding {
+    pub instrument: String,
+    pub quantity: Decimal,
+    pub currency: Currency,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HoldingValuation {
+    pub instrument: String,
+    pub quantity: Decimal,
+    pub price: Decimal,
+    pub local_value: Money,
+    pub reporting_value: Money,
+    /// Share of net worth, in percent to two decimals.
+    pub allocation_pct: Decimal,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CurrencyExposure {
+    pub currency: Currency,
+    pub local_value: Money,
+    pub reporting_value: Money,
+    pub allocation_pct: Decimal,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PortfolioValuation {
+    pub reporting_currency: Currency,
+    pub holdings: Vec<HoldingValuation>,
+    pub by_currency: Vec<CurrencyExposure>,
+    pub net_worth: Money,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ValuationError {
+    MissingPrice(String),
+    Fx(FxError),
+    Money(MoneyError),
+}
+
+impl fmt::Display for ValuationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValuationError::MissingPrice(instrument) => write!(f, "no price for {}", instrument),
+            ValuationError::Fx(err) => err.fmt(f),
+            ValuationError::Money(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for ValuationError {}
+
+impl From<FxError> for ValuationError {
+    fn from(err: FxError) -> Self {
+        ValuationError::Fx(err)
+    }
+}
+
+impl From<MoneyError> for ValuationError {
+    fn from(err: MoneyError) -> Self {
+        ValuationError::Money(err)
+    }
+}
+
+fn pct_of(part: &Money, total: &Money) -> Decimal {
+    if total.is_zero() {
+        return Decimal::ZERO;
+    }
+    round_bankers(part.amount() * Decimal::ONE_HUNDRED / total.amount(), 2)
+}
+
+/// Values each holding at `prices` (keyed by instrument, quoted in the holding's
+/// currency) and consolidates everything into `reporting` at mid rates as of `now`.
+pub fn value_portfolio(
+    holdings: &[Holding],
+    prices: &HashMap<String, Decimal>,
+    fx: &FxTable,
+    reporting: Currency,
+    now: i64,
+) -> Result<PortfolioValuation, ValuationError> {
+    let mut rows = Vec::with_capacity(holdings.len());
+    let mut net_worth = Money::zero(reporting);
+    let mut by_currency: BTreeMap<Currency, (Money, Money)> = BTreeMap::new();
+
+    for holding in holdings {
+        let price = *prices
+            .get(&holding.instrument)
+            .ok_or_else(|| ValuationError::MissingPrice(holding.instrument.clone()))?;
+        let gross = holding
+            .quantity
+            .checked_mul(price)
+            .ok_or(MoneyError::Overflow)?;
+        let local_value = Money::new(gross, holding.currency);
+        let reporting_value = fx.convert_mid(&local_value, reporting, now)?;
+        net_worth = net_worth.checked_add(&reporting_value)?;
+
+        let entry = by_currency
+            .entry(holding.currency)
+            .or_insert((Money::zero(holding.currency), Money::zero(reporting)));
+        entry.0 = entry.0.checked_add(&local_value)?;
+        entry.1 = entry.1.checked_add(&reporting_value)?;
+
+        rows.push(HoldingValuation {
+            instrument: holding.instrument.clone(),
+            quantity: holding.quantity,
+            price,
+            local_value,
+            reporting_value,
+            allocation_pct: Decimal::ZERO,
+        });
+    }
+
+    for row in &mut rows {
+        row.allocation_pct = pct_of(&row.reporting_value, &net_worth);
+    }
+    let by_currency = by_currency
+        .into_iter()
+        .map(
+            |(currency, (local_value, reporting_value))| CurrencyExposure {
+                currency,
+                local_value,
+                reporting_value,
+                allocation_pct: pct_of(&reporting_value, &net_worth),
+            },
+        )
+        .collect();
+
+    Ok(PortfolioValuation {
+        reporting_currency: reporting,
+        holdings: rows,
+        by_currency,
+        net_worth,
+    })
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ValuationInput {
+    holdings: Vec<Holding>,
+    prices: HashMap<String, Decimal>,
+    fx_quotes: Vec<FxQuote>,
+    reporting_currency: Currency,
+    as_of: i64,
+    #[serde(default)]
+    max_rate_age_ms: Option<i64>,
+}
+
+/// JS entry point: `{ holdings, prices, fxQuotes, reportingCurrency, asOf, maxRateAgeMs? }`
+/// in, `PortfolioValuation` out.
+#[wasm_bindgen(js_name = valuePortfolio)]
+pub fn value_portfolio_js(input: JsValue) -> Result<JsValue, JsError> {
+    let input: ValuationInput = from_js(input)?;
+    let mut fx = FxTable::from_quotes(&input.fx_quotes).map_err(js_err)?;
+    fx.set_max_age_ms(input.max_rate_age_ms);
+    let valuation = value_portfolio(
+        &input.holdings,
+        &input.prices,
+        &fx,
+        input.reporting_currency,
+        input.as_of,
+    )
+    .map_err(js_err)?;
+    to_js(&valuation)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn dec(s: &str) -> Decimal {
+        Decimal::from_str(s).unwrap()
+    }
+
+    fn holding(instrument: &str, quantity: &str, currency: Currency) -> Holding {
+        Holding {
+            instrument: instrument.to_string(),
+            quantity: dec(quantity),
+            currency,
+        }
+    }
+
+    fn fx() -> FxTable {
+        FxTable::from_quotes(&[FxQuote {
+            base: Currency::Usd,
+            quote: Currency::Kes,
+            bid: dec("129"),
+            ask: dec("130"),
+            timestamp: 0,
+        }])
+        .unwrap()
+    }
+
+    #[test]
+    fn consolidates_holdings_into_the_reporting_currency() {
+        let holdings = [
+            holding("SCOM", "1000", Currency::Kes),
+            holding("EABL", "50", Currency::Kes),
+            holding("VOO", "2.5", Currency::Usd),
+        ];
+        let prices: HashMap<String, Decimal> = [
+            ("SCOM".to_string(), dec("17.35")),
+            ("EABL".to_string(), dec("161.50")),
+            ("VOO".to_string(), dec("480.10")),
+        ]
+        .into_iter()
+        .collect();
+        let v = value_portfolio(&holdings, &prices, &fx(), Currency::Kes, 0).unwrap();
+
+        let kes = |s: &str| Money::parse(s, Currency::Kes).unwrap();
+        // 2.5 x 480.10 = USD 1,200.25 at the 129.50 mid.
+        assert_eq!(
+            v.holdings[2].local_value,
+            Money::parse("1200.25", Currency::Usd).unwrap()
+        );
+        assert_eq!(v.holdings[2].reporting_value, kes("155432.38"));
+        assert_eq!(
+            v.net_worth,
+            kes("17350")
+                .checked_add(&kes("8075"))
+                .unwrap()
+                .checked_add(&kes("155432.38"))
+                .unwrap()
+        );
+        assert_eq!(v.holdings[0].allocation_pct, dec("9.59"));
+        assert_eq!(v.holdings[2].allocation_pct, dec("85.94"));
+
+        assert_eq!(v.by_currency.len(), 2);
+        assert_eq!(v.by_currency[0].currency, Currency::Kes);
+        assert_eq!(v.by_currency[0].reporting_value, kes("25425"));
+        assert_eq!(
+            v.by_currency[1].local_value,
+            Money::parse("1200.25", Currency::Usd).unwrap()
+        );
+    }
+
+    #[test]
+    fn reports_missing_prices_and_rates() {
+        let prices: HashMap<String, Decimal> =
+            [("VOO".to_string(), dec("480"))].into_iter().collect();
+        let missing = value_portfolio(
+            &[holding("SCOM", "1", Currency::Kes)],
+            &prices,
+            &fx(),
+            Currency::Kes,
+            0,
+        );
+        assert_eq!(
+            missing,
+            Err(ValuationError::MissingPrice("SCOM".to_string()))
+        );
+
+        let no_rate = value_portfolio(
+            &[holding("VOO", "1", Currency::Usd)],
+            &prices,
+            &fx(),
+            Currency::Gbp,
+            0,
+        );
+        assert!(matches!(
+            no_rate,
+            Err(ValuationError::Fx(FxError::MissingRate { .. }))
+        ));
+    }
+
+    #[test]
+    fn empty_portfolio_is_worth_zero() {
+        let v = value_portfolio(&[], &HashMap::new(), &fx(), Currency::Usd, 0).unwrap();
+        assert_eq!(v.net_worth, Money::zero(Currency::Usd));
+        assert!(v.holdings.is_empty() && v.by_currency.is_empty());
+    }
+}
@@ -0,0 +1,847 @@
+//! Rebalancing: turns target weights and drift bands into a lot-sized NSE order list.
+//!
+//! Only positions that have drifted outside their band trade. Sells run first and
+//! fund the buys; a position sitting on a gain is trimmed back to the edge of its band,
+//! while one at a loss goes all the way to target. If cash still falls short of the
+//! buffer, the shortfall is raised from the holdings whose sale realises the least
+//! gain per shilling, and whatever cannot be raised is reported. Every step walks the positions in a fixed order, so the same
+//! inputs always produce the same orders.
+
+use std::collections::HashSet;
+use std::fmt;
+
+use chrono::NaiveDate;
+use rust_decimal::prelude::*;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::js::{from_js, js_err, to_js};
+use crate::money::{round_bankers, Money, MoneyError};
+use crate::trade_cost::{
+    schedule_for, trade_cost, FeeSchedule, TradeCost, TradeCostError, TradeSide,
+};
+
+/// Shares per board lot on the NSE main board.
+pub const NSE_BOARD_LOT: u64 = 100;
+
+/// Decimal places reported on weights.
+const WEIGHT_DP: u32 = 6;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum RebalanceError {
+    DuplicateInstrument(String),
+    InvalidPosition {
+        instrument: String,
+        why: &'static str,
+    },
+    /// Target weights add up to more than the whole portfolio.
+    TargetsExceedOne,
+    InvalidParams(&'static str),
+    Cost(TradeCostError),
+    Money(MoneyError),
+}
+
+impl fmt::Display for RebalanceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RebalanceError::DuplicateInstrument(instrument) => {
+                write!(f, "{} is listed more than once", instrument)
+            }
+            RebalanceError::InvalidPosition { instrument, why } => {
+                write!(f, "invalid position {}: {}", instrument, why)
+            }
+            RebalanceError::TargetsExceedOne => f.write_str("target weights sum to more than 1"),
+            RebalanceError::InvalidParams(why) => {
+                write!(f, "invalid rebalance parameters: {}", why)
+            }
+            RebalanceError::Cost(err) => err.fmt(f),
+            RebalanceError::Money(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for RebalanceError {}
+
+impl From<TradeCostError> for RebalanceError {
+    fn from(err: TradeCostError) -> Self {
+        RebalanceError::Cost(err)
+    }
+}
+
+impl From<MoneyError> for RebalanceError {
+    fn from(err: MoneyError) -> Self {
+        RebalanceError::Money(err)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RebalancePosition {
+    pub instrument: String,
+    /// Shares held.
+    pub quantity: u64,
+    pub price: Decimal,
+    /// Average cost per share, for the gain realised by a sell.
+    pub average_cost: Decimal,
+    /// Share of total value (holdings plus cash) to aim for; the rest stays in cash.
+    pub target_weight: Decimal,
+    /// Tolerance either side of the target; defaults to the plan's band.
+    #[serde(default)]
+    pub drift_band: Option<Decimal>,
+    /// Defaults to the plan's board lot.
+    #[serde(default)]
+    pub board_lot: Option<u64>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RebalanceParams {
+    /// Uninvested cash, in the fee schedule's currency.
+    pub cash: Money,
+    /// Cash that must remain after every order settles.
+    #[serde(default)]
+    pub cash_buffer: Option<Money>,
+    #[serde(default = "default_drift_band")]
+    pub drift_band: Decimal,
+    #[serde(default = "default_board_lot")]
+    pub board_lot: u64,
+    /// Orders with a smaller consideration are dropped, except full exits.
+    #[serde(default)]
+    pub min_order_value: Decimal,
+}
+
+fn default_drift_band() -> Decimal {
+    Decimal::new(5, 2)
+}
+
+fn default_board_lot() -> u64 {
+    NSE_BOARD_LOT
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OrderReason {
+    /// Brings a position back inside its drift band.
+    Drift,
+    /// Raises cash to restore the buffer.
+    CashBuffer,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RebalanceOrder {
+    pub instrument: String,
+    pub reason: OrderReason,
+    pub cost: TradeCost,
+    /// Net proceeds less cost basis; sells only.
+    pub realised_gain: Option<Money>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SkipReason {
+    BelowBoardLot,
+    BelowMinimumOrder,
+    InsufficientCash,
+}
+
+/// A trade the drift called for that was not placed.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SkippedTrade {
+    pub instrument: String,
+    pub side: TradeSide,
+    pub reason: SkipReason,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PositionDrift {
+    pub instrument: String,
+    pub target_weight: Decimal,
+    pub weight_before: Decimal,
+    pub weight_after: Decimal,
+    pub quantity_before: u64,
+    pub quantity_after: u64,
+    /// Whether the position started outside its drift band.
+    pub breached: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RebalancePlan {
+    pub schedule_version: String,
+    pub value_before: Money,
+    pub value_after: Money,
+    /// Sells first, then buys in order of priority.
+    pub orders: Vec<RebalanceOrder>,
+    pub skipped: Vec<SkippedTrade>,
+    pub positions: Vec<PositionDrift>,
+    pub cash_before: Money,
+    pub cash_after: Money,
+    pub total_fees: Money,
+    pub realised_gain: Money,
+    /// Cash still missing from the buffer once every available sale is made; zero
+    /// when the buffer is met.
+    pub buffer_shortfall: Money,
+}
+
+/// Rounds a share count to whole lots.
+fn to_lots(shares: Decimal, lot: u64, strategy: RoundingStrategy) -> u64 {
+    let lots = (shares / Decimal::from(lot)).round_dp_with_strategy(0, strategy);
+    lots.to_u64().unwrap_or(0).saturating_mul(lot)
+}
+
+/// The part of a holding that can be sold in whole lots.
+fn whole_lots(quantity: u64, lot: u64) -> u64 {
+    quantity - quantity % lot
+}
+
+/// Shares whose value is `weight` of `total` at `price`.
+fn shares_for(weight: Decimal, total: Decimal, price: Decimal) -> Result<Decimal, MoneyError> {
+    weight
+        .checked_mul(total)
+        .and_then(|value| value.checked_div(price))
+        .ok_or(MoneyError::Overflow)
+}
+
+struct Prepared<'a> {
+    position: &'a RebalancePosition,
+    band: Decimal,
+    lot: u64,
+}
+
+fn prepare<'a>(
+    positions: &'a [RebalancePosition],
+    params: &RebalanceParams,
+) -> Result<Vec<Prepared<'a>>, RebalanceError> {
+    if params.drift_band < Decimal::ZERO {
+        return Err(RebalanceError::InvalidParams(
+            "drift band must not be negative",
+        ));
+    }
+    if params.board_lot == 0 {
+        return Err(RebalanceError::InvalidParams(
+            "board lot must be at least one share",
+        ));
+    }
+    if params.min_order_value < Decimal::ZERO {
+        return Err(RebalanceError::InvalidParams(
+            "minimum order value must not be negative",
+        ));
+    }
+    let mut seen = HashSet::new();
+    let mut prepared = Vec::with_capacity(positions.len());
+    for p in positions {
+        let invalid = |why| RebalanceError::InvalidPosition {
+            instrument: p.instrument.clone(),
+            why,
+        };
+        if !seen.insert(p.instrument.as_str()) {
+            return Err(RebalanceError::DuplicateInstrument(p.instrument.clone()));
+        }
+        if p.price <= Decimal::ZERO {
+            return Err(invalid("price must be positive"));
+        }
+        if p.average_cost < Decimal::ZERO {
+            return Err(invalid("average cost must not be negative"));
+        }
+        if p.target_weight < Decimal::ZERO || p.target_weight > Decimal::ONE {
+            return Err(invalid("target weight must be between 0 and 1"));
+        }
+        let band = p.drift_band.unwrap_or(params.drift_band);
+        if band < Decimal::ZERO {
+            return Err(invalid("drift band must not be negative"));
+        }
+        let lot = p.board_lot.unwrap_or(params.board_lot);
+        if lot == 0 {
+            return Err(invalid("board lot must be at least one share"));
+        }
+        prepared.push(Prepared {
+            position: p,
+            band,
+            lot,
+        });
+    }
+    let targets: Decimal = positions.iter().map(|p| p.target_weight).sum();
+    if targets > Decimal::ONE {
+        return Err(RebalanceError::TargetsExceedOne);
+    }
+    // Instrument order makes every later pass deterministic.
+    prepared.sort_by(|a, b| a.position.instrument.cmp(&b.position.instrument));
+    Ok(prepared)
+}
+
+fn market_value(p: &RebalancePosition, quantity: u64) -> Result<Decimal, MoneyError> {
+    p.price
+        .checked_mul(Decimal::from(quantity))
+        .ok_or(MoneyError::Overflow)
+}
+
+/// Net settlement of a trade, or zero for no trade.
+fn settlement(
+    side: TradeSide,
+    p: &RebalancePosition,
+    quantity: u64,
+    schedule: &FeeSchedule,
+) -> Result<Decimal, TradeCostError> {
+    if quantity == 0 {
+        return Ok(Decimal::ZERO);
+    }
+    Ok(trade_cost(side, p.price, quantity, schedule)?
+        .net_settlement
+        .amount())
+}
+
+/// Combined percentage fees a buy pays on top of its consideration.
+fn buy_fee_rate(schedule: &FeeSchedule) -> Decimal {
+    schedule
+        .components
+        .iter()
+        .filter(|c| c.side.is_none_or(|side| side == TradeSide::Buy))
+        .map(|c| c.rate)
+        .sum()
+}
+
+/// Compares holdings against targets and drift bands and produces the cheapest
+/// lot-sized order list that restores them, priced with `schedule`.
+pub fn rebalance(
+    positions: &[RebalancePosition],
+    params: &RebalanceParams,
+    schedule: &FeeSchedule,
+) -> Result<RebalancePlan, RebalanceError> {
+    let currency = schedule.currency;
+    let zero = Money::zero(currency);
+    // Adding zero checks the currency against the schedule's.
+    let cash_before = zero.checked_add(&params.cash)?;
+    let buffer = zero
+        .checked_add(params.cash_buffer.as_ref().unwrap_or(&zero))?
+        .amount();
+    if buffer < Decimal::ZERO {
+        return Err(RebalanceError::InvalidParams(
+            "cash buffer must not be negative",
+        ));
+    }
+    let book = prepare(positions, params)?;
+
+    let mut total = cash_before.amount();
+    for entry in &book {
+        total = total
+            .checked_add(market_value(entry.position, entry.position.quantity)?)
+            .ok_or(MoneyError::Overflow)?;
+    }
+    if total <= Decimal::ZERO {
+        return Err(RebalanceError::InvalidParams(
+            "portfolio has no value to rebalance",
+        ));
+    }
+    let weights: Vec<Decimal> = book
+        .iter()
+        .map(|e| {
+            market_value(e.position, e.position.quantity)?
+                .checked_div(total)
+                .ok_or(MoneyError::Overflow)
+        })
+        .collect::<Result<_, _>>()?;
+
+    let mut sells = vec![0u64; book.len()];
+    let mut buys = vec![0u64; book.len()];
+    let mut reasons = vec![OrderReason::Drift; book.len()];
+    let mut skipped = Vec::new();
+    let mut skip = |entry: &Prepared, side, reason| {
+        skipped.push(SkippedTrade {
+            instrument: entry.position.instrument.clone(),
+            side,
+            reason,
+        })
+    };
+
+    // Overweight positions: losses go back to target, gains only to the band edge.
+    let mut cash = cash_before.amount();
+    for (k, entry) in book.iter().enumerate() {
+        let p = entry.position;
+        let excess = weights[k] - p.target_weight;
+        if excess <= entry.band {
+            continue;
+        }
+        // Partial sells stay in whole lots; only a full exit clears an odd lot.
+        let sellable = whole_lots(p.quantity, entry.lot);
+        let quantity = if p.target_weight.is_zero() {
+            p.quantity
+        } else if p.price > p.average_cost {
+            let shares = shares_for(excess - entry.band, total, p.price)?;
+            to_lots(shares, entry.lot, RoundingStrategy::AwayFromZero).min(sellable)
+        } else {
+            let shares = shares_for(excess, total, p.price)?;
+            to_lots(shares, entry.lot, RoundingStrategy::MidpointNearestEven).min(sellable)
+        };
+        if quantity == 0 {
+            skip(entry, TradeSide::Sell, SkipReason::BelowBoardLot);
+            continue;
+        }
+        if quantity < p.quantity && market_value(p, quantity)? < params.min_order_value {
+            skip(entry, TradeSide::Sell, SkipReason::BelowMinimumOrder);
+            continue;
+        }
+        sells[k] = quantity;
+        cash = cash
+            .checked_add(settlement(TradeSide::Sell, p, quantity, schedule)?)
+            .ok_or(MoneyError::Overflow)?;
+    }
+
+    let underweight = |k: usize| book[k].position.target_weight - weights[k] > book[k].band;
+
+    // Top the buffer back up from whatever realises the least gain per shilling sold.
+    if cash < buffer {
+        let mut donors: Vec<usize> = (0..book.len())
+            .filter(|&k| {
+                !underweight(k) && sells[k] < whole_lots(book[k].position.quantity, book[k].lot)
+            })
+            .collect();
+        donors.sort_by(|&a, &b| {
+            let ratio = |k: usize| {
+                let p = book[k].position;
+                (p.price - p.average_cost) / p.price
+            };
+            ratio(a).cmp(&ratio(b)).then(a.cmp(&b))
+        });
+        for k in donors {
+            let shortfall = buffer - cash;
+            if shortfall <= Decimal::ZERO {
+                break;
+            }
+            let entry = &book[k];
+            let p = entry.position;
+            let sellable = whole_lots(p.quantity, entry.lot);
+            let already = settlement(TradeSide::Sell, p, sells[k], schedule)?;
+            let mut quantity = sells[k]
+                .saturating_add(to_lots(
+                    shortfall.checked_div(p.price).ok_or(MoneyError::Overflow)?,
+                    entry.lot,
+                    RoundingStrategy::AwayFromZero,
+                ))
+                .min(sellable);
+            // Fees (and minimum brokerage) can leave the estimate a lot or two short.
+            let mut raised = settlement(TradeSide::Sell, p, quantity, schedule)? - already;
+            while raised < shortfall && quantity < sellable {
+                quantity = (quantity + entry.lot).min(sellable);
+                raised = settlement(TradeSide::Sell, p, quantity, schedule)? - already;
+            }
+            if sells[k] == 0 {
+                reasons[k] = OrderReason::CashBuffer;
+            }
+            sells[k] = quantity;
+            cash = cash.checked_add(raised).ok_or(MoneyError::Overflow)?;
+        }
+    }
+
+    // Underweight positions, largest shortfall first, within the cash above the buffer.
+    let mut wanted: Vec<usize> = (0..book.len()).filter(|&k| underweight(k)).collect();
+    wanted.sort_by(|&a, &b| {
+        let gap = |k: usize| book[k].position.target_weight - weights[k];
+        gap(b).cmp(&gap(a)).then(a.cmp(&b))
+    });
+    let mut budget = cash - buffer;
+    let fee_rate = buy_fee_rate(schedule);
+    for &k in &wanted {
+        let entry = &book[k];
+        let p = entry.position;
+        let gap = p.target_weight - weights[k];
+        let shares = shares_for(gap, total, p.price)?;
+        let mut quantity = to_lots(shares, entry.lot, RoundingStrategy::ToZero);
+        if quantity == 0 {
+            skip(entry, TradeSide::Buy, SkipReason::BelowBoardLot);
+            continue;
+        }
+        if market_value(p, quantity)? < params.min_order_value {
+            skip(entry, TradeSide::Buy, SkipReason::BelowMinimumOrder);
+            continue;
+        }
+        // Fees come out of the same budget, so size against the all-in price per share.
+        let all_in = p
+            .price
+            .checked_mul(Decimal::ONE + fee_rate)
+            .ok_or(MoneyError::Overflow)?;
+        let affordable = to_lots(
+            budget
+                .max(Decimal::ZERO)
+                .checked_div(all_in)
+                .ok_or(MoneyError::Overflow)?,
+            entry.lot,
+            RoundingStrategy::ToZero,
+        );
+        quantity = quantity.min(affordable);
+        let mut paid = settlement(TradeSide::Buy, p, quantity, schedule)?;
+        while quantity > 0 && paid > budget {
+            quantity -= entry.lot;
+            paid = settlement(TradeSide::Buy, p, quantity, schedule)?;
+        }
+        if quantity == 0 || market_value(p, quantity)? < params.min_order_value {
+            skip(entry, TradeSide::Buy, SkipReason::InsufficientCash);
+            continue;
+        }
+        buys[k] = quantity;
+        budget -= paid;
+    }
+
+    let mut orders = Vec::new();
+    let mut total_fees = zero;
+    let mut realised = zero;
+    let mut cash_after = cash_before;
+    let sell_order = (0..book.len()).filter(|&k| sells[k] > 0);
+    let buy_order = wanted.iter().copied().filter(|&k| buys[k] > 0);
+    for (k, side) in sell_order
+        .map(|k| (k, TradeSide::Sell))
+        .chain(buy_order.map(|k| (k, TradeSide::Buy)))
+    {
+        let p = book[k].position;
+        let quantity = match side {
+            TradeSide::Sell => sells[k],
+            TradeSide::Buy => buys[k],
+        };
+        let cost = trade_cost(side, p.price, quantity, schedule)?;
+        total_fees = total_fees.checked_add(&cost.total_fees)?;
+        let realised_gain = match side {
+            TradeSide::Sell => {
+                cash_after = cash_after.checked_add(&cost.net_settlement)?;
+                let basis = Money::new(
+                    p.average_cost
+                        .checked_mul(Decimal::from(quantity))
+                        .ok_or(MoneyError::Overflow)?,
+                    currency,
+                );
+                let gain = cost.net_settlement.checked_sub(&basis)?;
+                realised = realised.checked_add(&gain)?;
+                Some(gain)
+            }
+            TradeSide::Buy => {
+                cash_after = cash_after.checked_sub(&cost.net_settlement)?;
+                None
+            }
+        };
+        orders.push(RebalanceOrder {
+            instrument: p.instrument.clone(),
+            reason: reasons[k],
+            cost,
+            realised_gain,
+        });
+    }
+
+    let mut value_after = cash_after.amount();
+    let mut held_after = Vec::with_capacity(book.len());
+    for (k, entry) in book.iter().enumerate() {
+        let quantity = entry.position.quantity - sells[k] + buys[k];
+        value_after = value_after
+            .checked_add(market_value(entry.position, quantity)?)
+            .ok_or(MoneyError::Overflow)?;
+        held_after.push(quantity);
+    }
+    let weight_of = |value: Decimal, of: Decimal| {
+        if of.is_zero() {
+            Decimal::ZERO
+        } else {
+            round_bankers(value / of, WEIGHT_DP)
+        }
+    };
+    let mut drift = Vec::with_capacity(book.len());
+    for (k, entry) in book.iter().enumerate() {
+        let p = entry.position;
+        drift.push(PositionDrift {
+            instrument: p.instrument.clone(),
+            target_weight: p.target_weight,
+            weight_before: round_bankers(weights[k], WEIGHT_DP),
+            weight_after: weight_of(market_value(p, held_after[k])?, value_after),
+            quantity_before: p.quantity,
+            quantity_after: held_after[k],
+            breached: (weights[k] - p.target_weight).abs() > entry.band,
+        });
+    }
+
+    let buffer_shortfall = Money::new((buffer - cash_after.amount()).max(Decimal::ZERO), currency);
+    Ok(RebalancePlan {
+        schedule_version: schedule.version.clone(),
+        value_before: Money::new(total, currency),
+        value_after: Money::new(value_after, currency),
+        orders,
+        skipped,
+        positions: drift,
+        cash_before,
+        cash_after,
+        total_fees,
+        realised_gain: realised,
+        buffer_shortfall,
+    })
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RebalanceInput {
+    positions: Vec<RebalancePosition>,
+    params: RebalanceParams,
+    trade_date: NaiveDate,
+    /// Versioned schedules to pick from; defaults to the built-in NSE schedule.
+    #[serde(default)]
+    schedules: Option<Vec<FeeSchedule>>,
+}
+
+/// JS entry point: `{ positions, params, tradeDate, schedules? }` in, `RebalancePlan` out.
+#[wasm_bindgen(js_name = rebalancePortfolio)]
+pub fn rebalance_js(input: JsValue) -> Result<JsValue, JsError> {
+    let input: RebalanceInput = from_js(input)?;
+    let schedules = input
+        .schedules
+        .unwrap_or_else(|| vec![FeeSchedule::nse_standard()]);
+    let schedule = schedule_for(&schedules, input.trade_date)
+        .ok_or(TradeCostError::NoSchedule(input.trade_date))
+        .map_err(js_err)?;
+    let plan = rebalance(&input.positions, &input.params, schedule).map_err(js_err)?;
+    to_js(&plan)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::money::Currency;
+
+    fn dec(s: &str) -> Decimal {
+        Decimal::from_str(s).unwrap()
+    }
+
+    fn kes(s: &str) -> Money {
+        Money::parse(s, Currency::Kes).unwrap()
+    }
+
+    fn position(
+        instrument: &str,
+        quantity: u64,
+        price: &str,
+        cost: &str,
+        target: &str,
+    ) -> RebalancePosition {
+        RebalancePosition {
+            instrument: instrument.to_string(),
+            quantity,
+            price: dec(price),
+            average_cost: dec(cost),
+            target_weight: dec(target),
+            drift_band: None,
+            board_lot: None,
+        }
+    }
+
+    fn params(cash: &str) -> RebalanceParams {
+        RebalanceParams {
+            cash: kes(cash),
+            cash_buffer: None,
+            drift_band: default_drift_band(),
+            board_lot: NSE_BOARD_LOT,
+            min_order_value: Decimal::ZERO,
+        }
+    }
+
+    fn order<'a>(plan: &'a RebalancePlan, instrument: &str) -> Option<&'a RebalanceOrder> {
+        plan.orders.iter().find(|o| o.instrument == instrument)
+    }
+
+    fn book() -> Vec<RebalancePosition> {
+        vec![
+            position("EQTY", 1_000, "100", "50", "0.3"),
+            position("KCB", 1_000, "100", "150", "0.3"),
+            position("SCOM", 0, "10", "0", "0.3"),
+            position("EABL", 0, "150", "0", "0.05"),
+        ]
+    }
+
+    #[test]
+    fn gains_trim_to_the_band_edge_and_losses_go_to_target() {
+        let plan = rebalance(&book(), &params("0"), &FeeSchedule::nse_standard()).unwrap();
+        // EQTY sits on a gain: 20 points over target, trimmed 15 back to the band edge.
+        let eqty = order(&plan, "EQTY").unwrap();
+        assert_eq!(eqty.cost.side, TradeSide::Sell);
+        assert_eq!(eqty.cost.quantity, 300);
+        assert!(eqty.realised_gain.unwrap().is_positive());
+        // KCB is at a loss, so the full 20 points go.
+        let kcb = order(&plan, "KCB").unwrap();
+        assert_eq!(kcb.cost.quantity, 400);
+        assert!(kcb.realised_gain.unwrap().is_negative());
+        // EABL is 5 points under target, exactly on its band, so it does not trade.
+        assert!(order(&plan, "EABL").is_none());
+        assert!(
+            !plan
+                .positions
+                .iter()
+                .find(|p| p.instrument == "EABL")
+                .unwrap()
+                .breached
+        );
+        // Sells come before buys.
+        let sides: Vec<TradeSide> = plan.orders.iter().map(|o| o.cost.side).collect();
+        assert_eq!(
+            sides,
+            vec![TradeSide::Sell, TradeSide::Sell, TradeSide::Buy]
+        );
+        assert_eq!(order(&plan, "SCOM").unwrap().cost.quantity, 6_000);
+    }
+
+    #[test]
+    fn orders_are_whole_board_lots() {
+        let mut positions = book();
+        positions[2].price = dec("13");
+        positions[2].board_lot = Some(250);
+        let plan = rebalance(&positions, &params("0"), &FeeSchedule::nse_standard()).unwrap();
+        // 60,000 / 13 = 4,615.4 shares, rounded down to whole lots of 250.
+        assert_eq!(order(&plan, "SCOM").unwrap().cost.quantity, 4_500);
+        for o in &plan.orders {
+            let lot = positions
+                .iter()
+                .find(|p| p.instrument == o.instrument)
+                .and_then(|p| p.board_lot)
+                .unwrap_or(NSE_BOARD_LOT);
+            assert_eq!(o.cost.quantity % lot, 0, "{}", o.instrument);
+        }
+    }
+
+    #[test]
+    fn same_inputs_in_any_order_give_the_same_plan() {
+        let schedule = FeeSchedule::nse_standard();
+        let plan = rebalance(&book(), &params("2500"), &schedule).unwrap();
+        let mut reversed = book();
+        reversed.reverse();
+        assert_eq!(
+            rebalance(&reversed, &params("2500"), &schedule).unwrap(),
+            plan
+        );
+        assert_eq!(
+            rebalance(&book(), &params("2500"), &schedule).unwrap(),
+            plan
+        );
+    }
+
+    #[test]
+    fn buys_net_their_fees_out_of_the_budget() {
+        let mut params = params("10000");
+        params.board_lot = 1;
+        let positions = [position("SCOM", 0, "10", "0", "1")];
+        let plan = rebalance(&positions, &params, &FeeSchedule::nse_standard()).unwrap();
+        let buy = order(&plan, "SCOM").unwrap();
+        // 982 shares cost 9,820.00 plus 179.70 in fees; 983 would overdraw.
+        assert_eq!(buy.cost.quantity, 982);
+        assert_eq!(buy.cost.net_settlement, kes("9999.70"));
+        assert_eq!(plan.cash_after, kes("0.30"));
+        assert_eq!(plan.total_fees, kes("179.70"));
+    }
+
+    #[test]
+    fn cash_buffer_is_raised_from_the_smallest_gain_first() {
+        let positions = [
+            position("BAT", 1_000, "100", "90", "0.33"),
+            position("COOP", 1_000, "100", "99", "0.33"),
+            position("NCBA", 1_000, "100", "120", "0.33"),
+        ];
+        let mut params = params("0");
+        params.cash_buffer = Some(kes("5000"));
+        let plan = rebalance(&positions, &params, &FeeSchedule::nse_standard()).unwrap();
+        assert_eq!(plan.orders.len(), 1);
+        let sold = &plan.orders[0];
+        assert_eq!(sold.instrument, "NCBA");
+        assert_eq!(sold.reason, OrderReason::CashBuffer);
+        assert_eq!(sold.cost.quantity, 100);
+        assert!(plan.cash_after.amount() >= dec("5000"));
+        assert!(plan.buffer_shortfall.is_zero());
+
+        // A bigger buffer works up the list to the next-smallest gain.
+        params.cash_buffer = Some(kes("150000"));
+        let plan = rebalance(&positions, &params, &FeeSchedule::nse_standard()).unwrap();
+        let sold: Vec<&str> = plan.orders.iter().map(|o| o.instrument.as_str()).collect();
+        assert_eq!(sold, vec!["COOP", "NCBA"]);
+        assert_eq!(order(&plan, "NCBA").unwrap().cost.quantity, 1_000);
+        assert!(plan.cash_after.amount() >= dec("150000"));
+    }
+
+    #[test]
+    fn partial_sells_leave_odd_lots_and_an_unmet_buffer_is_reported() {
+        // 1,050 shares on a gain, trimmed almost to nothing: the odd 50 stay.
+        let positions = [position("BAT", 1_050, "100", "90", "0.0001")];
+        let mut params = params("0");
+        params.drift_band = Decimal::ZERO;
+        let plan = rebalance(&positions, &params, &FeeSchedule::nse_standard()).unwrap();
+        assert_eq!(order(&plan, "BAT").unwrap().cost.quantity, 1_000);
+        assert!(plan.buffer_shortfall.is_zero());
+
+        // A buffer bigger than the whole book can only be part met.
+        params.cash_buffer = Some(kes("1000000"));
+        let plan = rebalance(&positions, &params, &FeeSchedule::nse_standard()).unwrap();
+        assert_eq!(order(&plan, "BAT").unwrap().cost.quantity, 1_000);
+        assert!(plan.buffer_shortfall.is_positive());
+        assert_eq!(
+            plan.buffer_shortfall,
+            kes("1000000").checked_sub(&plan.cash_after).unwrap()
+        );
+    }
+
+    #[test]
+    fn extreme_prices_overflow_instead_of_panicking() {
+        let positions = [position(
+            "PENNY",
+            0,
+            "0.0000000000000000000000000001",
+            "0",
+            "0.5",
+        )];
+        assert_eq!(
+            rebalance(
+                &positions,
+                &params("100000000000000000000000000"),
+                &FeeSchedule::nse_standard()
+            ),
+            Err(RebalanceError::Money(MoneyError::Overflow))
+        );
+    }
+
+    #[test]
+    fn small_orders_are_skipped_but_full_exits_are_not() {
+        let mut positions = book();
+        positions[3] = position("EABL", 100, "150", "200", "0");
+        let mut params = params("0");
+        params.min_order_value = dec("50000");
+        let plan = rebalance(&positions, &params, &FeeSchedule::nse_standard()).unwrap();
+        let skipped: Vec<(&str, SkipReason)> = plan
+            .skipped
+            .iter()
+            .map(|s| (s.instrument.as_str(), s.reason))
+            .collect();
+        assert_eq!(
+            skipped,
+            vec![
+                ("EQTY", SkipReason::BelowMinimumOrder),
+                ("KCB", SkipReason::BelowMinimumOrder),
+                ("SCOM", SkipReason::InsufficientCash),
+            ]
+        );
+        // The 15,000 exit is under the minimum but still goes.
+        assert_eq!(order(&plan, "EABL").unwrap().cost.quantity, 100);
+    }
+
+    #[test]
+    fn invalid_inputs_are_rejected() {
+        let schedule = FeeSchedule::nse_standard();
+        let mut twice = book();
+        twice.push(position("KCB", 1, "1", "1", "0"));
+        assert_eq!(
+            rebalance(&twice, &params("0"), &schedule),
+            Err(RebalanceError::DuplicateInstrument("KCB".to_string()))
+        );
+        let mut heavy = book();
+        heavy[3].target_weight = dec("0.2");
+        assert_eq!(
+            rebalance(&heavy, &params("0"), &schedule),
+            Err(RebalanceError::TargetsExceedOne)
+        );
+        let mut usd = params("0");
+        usd.cash = Money::zero(Currency::Usd);
+        assert!(matches!(
+            rebalance(&book(), &usd, &schedule),
+            Err(RebalanceError::Money(MoneyError::CurrencyMismatch { .. }))
+        ));
+    }
+}
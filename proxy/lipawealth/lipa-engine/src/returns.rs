@@ -0,0 +1,637 @@
+//! Portfolio performance: time-weighted return linked across external cash flows,
+//! and money-weighted return (XIRR).
+
+use std::fmt;
+
+use chrono::NaiveDate;
+use rust_decimal::prelude::*;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::js::{from_js, js_err, to_js};
+use crate::money::{Money, MoneyError};
+
+/// Day-count basis for annualisation and XIRR discounting (actual/365).
+pub const DAYS_IN_YEAR: f64 = 365.0;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ReturnError {
+    NotEnoughValuations,
+    /// Valuation dates must be strictly increasing.
+    UnorderedValuations {
+        date: NaiveDate,
+    },
+    FlowOutsidePeriod {
+        date: NaiveDate,
+    },
+    /// The sub-period's capital base is zero or negative, so no return is defined.
+    NonPositiveBase {
+        start: NaiveDate,
+        end: NaiveDate,
+    },
+    /// XIRR needs at least one inflow and one outflow.
+    NoSignChange,
+    /// No rate in the search range sets NPV to zero.
+    NoSolution,
+    Money(MoneyError),
+}
+
+impl fmt::Display for ReturnError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReturnError::NotEnoughValuations => {
+                f.write_str("at least two valuations are needed to measure a return")
+            }
+            ReturnError::UnorderedValuations { date } => {
+                write!(f, "valuation on {} is out of order or repeated", date)
+            }
+            ReturnError::FlowOutsidePeriod { date } => {
+                write!(
+                    f,
+                    "cash flow on {} falls outside the valuation period",
+                    date
+                )
+            }
+            ReturnError::NonPositiveBase { start, end } => write!(
+                f,
+                "capital base for {} to {} is not positive; return is undefined",
+                start, end
+            ),
+            ReturnError::NoSignChange => {
+                f.write_str("cash flows must include both contributions and withdrawals")
+            }
+            ReturnError::NoSolution => f.write_str("no internal rate of return found"),
+            ReturnError::Money(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for ReturnError {}
+
+impl From<MoneyError> for ReturnError {
+    fn from(err: MoneyError) -> Self {
+        ReturnError::Money(err)
+    }
+}
+
+/// Market value at the close of `date`, after that day's flows.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Valuation {
+    pub date: NaiveDate,
+    pub value: Money,
+}
+
+/// Money the client put in (positive) or took out (negative).
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExternalFlow {
+    pub date: NaiveDate,
+    pub amount: Money,
+}
+
+/// When during its day an external flow becomes available for investment.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FlowTiming {
+    StartOfDay,
+    #[default]
+    EndOfDay,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubPeriodReturn {
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+    pub begin_value: Money,
+    pub end_value: Money,
+    pub net_flow: Money,
+    pub r#return: f64,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeWeightedReturn {
+    pub sub_periods: Vec<SubPeriodReturn>,
+    pub cumulative: f64,
+    pub days: i64,
+    /// Only given for periods of a year or more; shorter returns are not annualised.
+    pub annualised: Option<f64>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MoneyWeightedReturn {
+    /// Annual effective internal rate of return.
+    pub irr: f64,
+    /// The IRR compounded over the period itself.
+    pub cumulative: f64,
+    pub days: i64,
+    pub annualised: Option<f64>,
+}
+
+/// Compounds a cumulative return to an annual rate over `days` (actual/365).
+pub fn annualise(cumulative: f64, days: i64) -> f64 {
+    (1.0 + cumulative).powf(DAYS_IN_YEAR / days as f64) - 1.0
+}
+
+fn annualised_if_long(cumulative: f64, days: i64) -> Option<f64> {
+    (days as f64 >= DAYS_IN_YEAR).then(|| annualise(cumulative, days))
+}
+
+fn as_f64(m: &Money) -> f64 {
+    m.amount().to_f64().unwrap_or(0.0)
+}
+
+fn check_valuations(valuations: &[Valuation]) -> Result<(), ReturnError> {
+    if valuations.len() < 2 {
+        return Err(ReturnError::NotEnoughValuations);
+    }
+    for pair in valuations.windows(2) {
+        if pair[1].date <= pair[0].date {
+            return Err(ReturnError::UnorderedValuations { date: pair[1].date });
+        }
+    }
+    Ok(())
+}
+
+/// Time-weighted return, geometrically linking one return per pair of valuations.
+///
+/// With a valuation on every flow date (or daily valuations) each sub-period
+/// return is exact. Flows that fall between valuations are day-weighted within
+/// their sub-period (Modified Dietz), which is the usual fallback when a
+/// portfolio cannot be revalued at every flow.
+pub fn time_weighted_return(
+    valuations: &[Valuation],
+    flows: &[ExternalFlow],
+    timing: FlowTiming,
+) -> Result<TimeWeightedReturn, ReturnError> {
+    check_valuations(valuations)?;
+    let first = valuations[0].date;
+    let last = valuations[valuations.len() - 1].date;
+    if let Some(f) = flows.iter().find(|f| f.date <= first || f.date > last) {
+        return Err(ReturnError::FlowOutsidePeriod { date: f.date });
+    }
+
+    let currency = valuations[0].value.currency();
+    let mut sub_periods = Vec::with_capacity(valuations.len() - 1);
+    let mut growth = 1.0;
+    for pair in valuations.windows(2) {
+        let (begin, end) = (&pair[0], &pair[1]);
+        let length = (end.date - begin.date).num_days() as f64;
+        let inside: Vec<&ExternalFlow> = flows
+            .iter()
+            .filter(|f| f.date > begin.date && f.date <= end.date)
+            .collect();
+        let net_flow = Money::sum(inside.iter().map(|f| &f.amount), currency)?;
+        let weighted: f64 = inside
+            .iter()
+            .map(|f| {
+                let mut remaining = (end.date - f.date).num_days() as f64;
+                if timing == FlowTiming::StartOfDay {
+                    remaining += 1.0;
+                }
+                as_f64(&f.amount) * remaining / length
+            })
+            .sum();
+
+        let gain = end
+            .value
+            .checked_sub(&begin.value)?
+            .checked_sub(&net_flow)?;
+        let base = as_f64(&begin.value) + weighted;
+        if base <= 0.0 {
+            return Err(ReturnError::NonPositiveBase {
+                start: begin.date,
+                end: end.date,
+            });
+        }
+        let r = as_f64(&gain) / base;
+        growth *= 1.0 + r;
+        sub_periods.push(SubPeriodReturn {
+            start: begin.date,
+            end: end.date,
+            begin_value: begin.value,
+            end_value: end.value,
+            net_flow,
+            r#return: r,
+        });
+    }
+
+    let days = (last - first).num_days();
+    let cumulative = growth - 1.0;
+    Ok(TimeWeightedReturn {
+        sub_periods,
+        cumulative,
+        days,
+        annualised: annualised_if_long(cumulative, days),
+    })
+}
+
+/// A dated cash flow from the investor's side: negative is money paid in.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DatedAmount {
+    pub date: NaiveDate,
+    pub amount: f64,
+}
+
+/// Finds a root of `f` in `[lo, hi]`, where `f(lo)` and `f(hi)` differ in sign,
+/// by regula falsi with the Illinois modification (always converges, and fast).
+pub(crate) fn solve_bracketed(f: impl Fn(f64) -> f64, lo: f64, hi: f64) -> Option<f64> {
+    let (mut a, mut b) = (lo, hi);
+    let (mut fa, mut fb) = (f(a), f(b));
+    if fa == 0.0 {
+        return Some(a);
+    }
+    if fb == 0.0 {
+        return Some(b);
+    }
+    if fa.signum() == fb.signum() || !fa.is_finite() || !fb.is_finite() {
+        return None;
+    }
+    let mut side = 0;
+    for _ in 0..200 {
+        let c = (a * fb - b * fa) / (fb - fa);
+        let fc = f(c);
+        if fc == 0.0 || (b - a).abs() < 1e-14 * (1.0 + c.abs()) {
+            return Some(c);
+        }
+        if fc.signum() == fb.signum() {
+            b = c;
+            fb = fc;
+            if side == -1 {
+                fa /= 2.0;
+            }
+            side = -1;
+        } else {
+            a = c;
+            fa = fc;
+            if side == 1 {
+                fb /= 2.0;
+            }
+            side = 1;
+        }
+        if fc.abs() < 1e-12 {
+            return Some(c);
+        }
+    }
+    Some((a * fb - b * fa) / (fb - fa))
+}
+
+/// Candidate rates for bracketing the IRR: 1% steps out to ±99%, then geometric
+/// steps up to 100,000%.
+fn xirr_grid() -> Vec<f64> {
+    let mut grid: Vec<f64> = (-99..=100).map(|i| i as f64 / 100.0).collect();
+    grid.extend([-0.999, -0.9999, -0.999_999]);
+    let mut r = 1.0;
+    while r < 1000.0 {
+        r = (r * 1.1_f64).min(1000.0);
+        grid.push(r);
+    }
+    grid.sort_by(f64::total_cmp);
+    grid
+}
+
+/// Annual rate at which the flows' net present value is zero (Excel `XIRR` convention:
+/// actual/365 from the earliest flow). Every sign change on the scan grid is solved
+/// and, where several rates qualify, the one nearest zero is returned. Two roots
+/// closer together than a grid step cancel out and are not seen.
+pub fn xirr(flows: &[DatedAmount]) -> Result<f64, ReturnError> {
+    if !(flows.iter().any(|f| f.amount > 0.0) && flows.iter().any(|f| f.amount < 0.0)) {
+        return Err(ReturnError::NoSignChange);
+    }
+    let origin = flows.iter().map(|f| f.date).min().unwrap();
+    let npv = |r: f64| -> f64 {
+        flows
+            .iter()
+            .map(|f| {
+                let years = (f.date - origin).num_days() as f64 / DAYS_IN_YEAR;
+                f.amount / (1.0 + r).powf(years)
+            })
+            .sum()
+    };
+
+    xirr_grid()
+        .windows(2)
+        .filter_map(|pair| solve_bracketed(npv, pair[0], pair[1]).filter(|r| r.is_finite()))
+        .min_by(|a, b| a.abs().total_cmp(&b.abs()))
+        .ok_or(ReturnError::NoSolution)
+}
+
+/// Money-weighted return over the span of `valuations`: the opening value and every
+/// contribution are invested, withdrawals and the closing value are received.
+pub fn money_weighted_return(
+    valuations: &[Valuation],
+    flows: &[ExternalFlow],
+) -> Result<MoneyWeightedReturn, ReturnError> {
+    check_valuations(valuations)?;
+    let open = &valuations[0];
+    let close = &valuations[valuations.len() - 1];
+    if let Some(f) = flows
+        .iter()
+        .find(|f| f.date <= open.date || f.date > close.date)
+    {
+        return Err(ReturnError::FlowOutsidePeriod { date: f.date });
+    }
+    let currency = open.value.currency();
+    let mut cash = vec![DatedAmount {
+        date: open.date,
+        amount: -as_f64(&open.value),
+    }];
+    for f in flows {
+        // Fails on a currency mismatch rather than mixing units.
+        f.amount.checked_add(&Money::zero(currency))?;
+        cash.push(DatedAmount {
+            date: f.date,
+            amount: -as_f64(&f.amount),
+        });
+    }
+    close.value.checked_add(&Money::zero(currency))?;
+    cash.push(DatedAmount {
+        date: close.date,
+        amount: as_f64(&close.value),
+    });
+
+    let irr = xirr(&cash)?;
+    let days = (close.date - open.date).num_days();
+    let cumulative = (1.0 + irr).powf(days as f64 / DAYS_IN_YEAR) - 1.0;
+    Ok(MoneyWeightedReturn {
+        irr,
+        cumulative,
+        days,
+        annualised: annualised_if_long(cumulative, days),
+    })
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PerformanceInput {
+    valuations: Vec<Valuation>,
+    #[serde(default)]
+    flows: Vec<ExternalFlow>,
+    #[serde(default)]
+    timing: FlowTiming,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PerformanceOutput {
+    time_weighted: TimeWeightedReturn,
+    money_weighted: Option<MoneyWeightedReturn>,
+}
+
+/// JS entry point: `{ valuations, flows?, timing? }` in, `{ timeWeighted, moneyWeighted }` out.
+///
+/// `moneyWeighted` is null when no IRR exists for the flows (e.g. a total loss).
+#[wasm_bindgen(js_name = portfolioPerformance)]
+pub fn performance_js(input: JsValue) -> Result<JsValue, JsError> {
+    let input: PerformanceInput = from_js(input)?;
+    let time_weighted =
+        time_weighted_return(&input.valuations, &input.flows, input.timing).map_err(js_err)?;
+    let money_weighted = match money_weighted_return(&input.valuations, &input.flows) {
+        Ok(m) => Some(m),
+        Err(ReturnError::NoSignChange | ReturnError::NoSolution) => None,
+        Err(e) => return Err(js_err(e)),
+    };
+    to_js(&PerformanceOutput {
+        time_weighted,
+        money_weighted,
+    })
+}
+
+/// JS entry point: `[{ date, amount }]` in, annual XIRR out.
+#[wasm_bindgen(js_name = xirr)]
+pub fn xirr_js(flows: JsValue) -> Result<f64, JsError> {
+    let flows: Vec<DatedAmount> = from_js(flows)?;
+    xirr(&flows).map_err(js_err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::money::Currency;
+
+    fn d(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn kes(v: i64) -> Money {
+        Money::new(Decimal::from(v), Currency::Kes)
+    }
+
+    fn val(date: &str, v: i64) -> Valuation {
+        Valuation {
+            date: d(date),
+            value: kes(v),
+        }
+    }
+
+    fn flow(date: &str, v: i64) -> ExternalFlow {
+        ExternalFlow {
+            date: d(date),
+            amount: kes(v),
+        }
+    }
+
+    fn close(a: f64, b: f64, tol: f64) {
+        assert!((a - b).abs() < tol, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn twr_links_sub_periods_valued_at_the_flow() {
+        // Valued at 1,070,000 on the day a 50,000 contribution arrives.
+        let vals = [
+            val("2024-01-31", 1_000_000),
+            val("2024-02-15", 1_070_000),
+            val("2024-02-29", 1_100_000),
+        ];
+        let r = time_weighted_return(&vals, &[flow("2024-02-15", 50_000)], FlowTiming::EndOfDay)
+            .unwrap();
+        close(r.sub_periods[0].r#return, 0.02, 1e-12);
+        close(r.sub_periods[1].r#return, 30_000.0 / 1_070_000.0, 1e-12);
+        close(r.cumulative, 1.02 * 1_100_000.0 / 1_070_000.0 - 1.0, 1e-12);
+        assert_eq!(r.annualised, None);
+    }
+
+    #[test]
+    fn twr_day_weights_flows_between_valuations() {
+        // Modified Dietz: 30-day month, 10,000 in at the close of day 11.
+        let vals = [val("2023-03-31", 100_000), val("2023-04-30", 115_000)];
+        let flows = [flow("2023-04-11", 10_000)];
+        let r = time_weighted_return(&vals, &flows, FlowTiming::EndOfDay).unwrap();
+        close(
+            r.cumulative,
+            5_000.0 / (100_000.0 + 10_000.0 * 19.0 / 30.0),
+            1e-12,
+        );
+        let r = time_weighted_return(&vals, &flows, FlowTiming::StartOfDay).unwrap();
+        close(
+            r.cumulative,
+            5_000.0 / (100_000.0 + 10_000.0 * 20.0 / 30.0),
+            1e-12,
+        );
+    }
+
+    #[test]
+    fn twr_with_start_of_day_daily_flows() {
+        let vals = [val("2024-03-01", 1_000), val("2024-03-02", 1_650)];
+        let r = time_weighted_return(&vals, &[flow("2024-03-02", 500)], FlowTiming::StartOfDay)
+            .unwrap();
+        close(r.cumulative, 1_650.0 / 1_500.0 - 1.0, 1e-12);
+    }
+
+    #[test]
+    fn twr_annualises_only_beyond_a_year() {
+        let vals = [
+            val("2021-01-01", 100_000),
+            val("2022-01-01", 110_000),
+            val("2023-01-01", 121_000),
+        ];
+        let r = time_weighted_return(&vals, &[], FlowTiming::EndOfDay).unwrap();
+        close(r.cumulative, 0.21, 1e-12);
+        close(r.annualised.unwrap(), 0.10, 1e-12);
+    }
+
+    #[test]
+    fn twr_rejects_bad_input() {
+        let vals = [val("2024-01-31", 1_000), val("2024-02-29", 1_100)];
+        assert_eq!(
+            time_weighted_return(&vals, &[flow("2024-03-01", 5)], FlowTiming::EndOfDay),
+            Err(ReturnError::FlowOutsidePeriod {
+                date: d("2024-03-01")
+            })
+        );
+        assert_eq!(
+            time_weighted_return(&vals[..1], &[], FlowTiming::EndOfDay),
+            Err(ReturnError::NotEnoughValuations)
+        );
+        let emptied = [val("2024-01-31", 0), val("2024-02-29", 0)];
+        assert!(matches!(
+            time_weighted_return(&emptied, &[], FlowTiming::EndOfDay),
+            Err(ReturnError::NonPositiveBase { .. })
+        ));
+    }
+
+    #[test]
+    fn xirr_matches_the_spreadsheet_reference() {
+        let flows = [
+            ("2008-01-01", -10_000.0),
+            ("2008-03-01", 2_750.0),
+            ("2008-10-30", 4_250.0),
+            ("2008-02-15", 0.0),
+            ("2009-02-15", 3_250.0),
+            ("2009-04-01", 2_750.0),
+        ]
+        .map(|(date, amount)| DatedAmount {
+            date: d(date),
+            amount,
+        });
+        close(xirr(&flows).unwrap(), 0.373_362_535, 1e-8);
+    }
+
+    #[test]
+    fn xirr_handles_losses_and_reports_no_solution() {
+        let loss = [
+            DatedAmount {
+                date: d("2023-01-01"),
+                amount: -1_000.0,
+            },
+            DatedAmount {
+                date: d("2024-01-01"),
+                amount: 400.0,
+            },
+        ];
+        close(xirr(&loss).unwrap(), -0.6, 1e-10);
+        assert_eq!(xirr(&loss[..1]), Err(ReturnError::NoSignChange));
+        let gains = [loss[1].clone(), loss[1].clone()];
+        assert_eq!(xirr(&gains), Err(ReturnError::NoSignChange));
+
+        // -1000 + 2300/x - 1400/x² has no real root: the flows change sign but
+        // no rate prices them at zero.
+        let flows = [
+            ("2021-01-01", -1_000.0),
+            ("2022-01-01", 2_300.0),
+            ("2023-01-01", -1_400.0),
+        ]
+        .map(|(date, amount)| DatedAmount {
+            date: d(date),
+            amount,
+        });
+        assert_eq!(xirr(&flows), Err(ReturnError::NoSolution));
+    }
+
+    #[test]
+    fn xirr_returns_the_root_nearest_zero() {
+        // -1000 + 2300/x - 1321.6/x² is zero at 12% and 18%. The two roots fall in
+        // different grid steps, so both are solved and the one nearer zero wins.
+        let flows = [
+            ("2021-01-01", -1_000.0),
+            ("2022-01-01", 2_300.0),
+            ("2023-01-01", -1_321.6),
+        ]
+        .map(|(date, amount)| DatedAmount {
+            date: d(date),
+            amount,
+        });
+        close(xirr(&flows).unwrap(), 0.12, 1e-9);
+
+        // Roots at 12.2% and 12.8% share the [12%, 13%] step: the NPV is negative at
+        // both ends, so the pair cancels out as documented and nothing is found.
+        let flows = [
+            ("2021-01-01", -1_000.0),
+            ("2022-01-01", 2_250.0),
+            ("2023-01-01", -1_265.616),
+        ]
+        .map(|(date, amount)| DatedAmount {
+            date: d(date),
+            amount,
+        });
+        assert_eq!(xirr(&flows), Err(ReturnError::NoSolution));
+    }
+
+    #[test]
+    fn matches_the_cfa_institute_twr_and_mwr_worked_example() {
+        // CFA Institute, "Portfolio Risk and Return: Part I" (CFA Program Level I),
+        // time- and money-weighted return example: one share bought at 100, a second
+        // at 120 a year later, a dividend of 2 a share paid at each year end and both
+        // shares worth 130 after two years. Published answers: holding-period returns
+        // of 22% and 10%, TWR 15.84% a year, MWR 13.86%.
+        let vals = [
+            val("2021-01-01", 100),
+            val("2022-01-01", 240),
+            val("2023-01-01", 260),
+        ];
+        let flows = [
+            flow("2022-01-01", -2),
+            flow("2022-01-01", 120),
+            flow("2023-01-01", -4),
+        ];
+        let twr = time_weighted_return(&vals, &flows, FlowTiming::EndOfDay).unwrap();
+        close(twr.sub_periods[0].r#return, 0.22, 1e-12);
+        close(twr.sub_periods[1].r#return, 0.10, 1e-12);
+        close(twr.annualised.unwrap(), 0.1584, 5e-5);
+        let mwr = money_weighted_return(&vals, &flows).unwrap();
+        close(mwr.irr, 0.1386, 5e-5);
+    }
+
+    #[test]
+    fn mwr_differs_from_twr_when_flows_are_badly_timed() {
+        // A large top-up just before a fall drags the MWR below the TWR.
+        let vals = [
+            val("2023-01-01", 100_000),
+            val("2023-07-01", 220_000),
+            val("2024-01-01", 198_000),
+        ];
+        let flows = [flow("2023-07-01", 100_000)];
+        let twr = time_weighted_return(&vals, &flows, FlowTiming::EndOfDay).unwrap();
+        close(twr.cumulative, 1.2 * 0.9 - 1.0, 1e-12);
+        let mwr = money_weighted_return(&vals, &flows).unwrap();
+        assert_eq!(mwr.days, 365);
+        assert!(mwr.irr < twr.cumulative);
+        let npv = -100_000.0 - 100_000.0 / (1.0 + mwr.irr).powf(181.0 / 365.0)
+            + 198_000.0 / (1.0 + mwr.irr);
+        close(npv, 0.0, 1e-6);
+    }
+}
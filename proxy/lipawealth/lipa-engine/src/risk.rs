@@ -0,0 +1,488 @@
+//! Risk analytics over a periodic return series: volatility, drawdown,
+//! risk-adjusted ratios and value at risk.
+
+use std::fmt;
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::js::{from_js, js_err, to_js};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum RiskError {
+    NotEnoughObservations {
+        needed: usize,
+        got: usize,
+    },
+    NonFiniteReturn {
+        date: NaiveDate,
+    },
+    /// Returns of -100% or worse leave nothing to compound.
+    TotalLoss {
+        date: NaiveDate,
+    },
+    InvalidConfidence(f64),
+    /// Observations must be in strictly increasing date order.
+    UnorderedDates {
+        date: NaiveDate,
+    },
+}
+
+impl fmt::Display for RiskError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RiskError::NotEnoughObservations { needed, got } => write!(
+                f,
+                "need at least {} returns for risk metrics, got {}",
+                needed, got
+            ),
+            RiskError::NonFiniteReturn { date } => {
+                write!(f, "return on {} is not a finite number", date)
+            }
+            RiskError::TotalLoss { date } => {
+                write!(f, "return on {} is a loss of 100% or more", date)
+            }
+            RiskError::InvalidConfidence(c) => {
+                write!(
+                    f,
+                    "confidence level {} must lie strictly between 0 and 1",
+                    c
+                )
+            }
+            RiskError::UnorderedDates { date } => {
+                write!(f, "return on {} is out of date order", date)
+            }
+        }
+    }
+}
+
+impl std::error::Error for RiskError {}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Quarterly,
+}
+
+impl Frequency {
+    pub fn periods_per_year(self) -> f64 {
+        match self {
+            Frequency::Daily => 252.0,
+            Frequency::Weekly => 52.0,
+            Frequency::Monthly => 12.0,
+            Frequency::Quarterly => 4.0,
+        }
+    }
+}
+
+/// Simple return for the period ending on `date` (0.01 = 1%).
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReturnObservation {
+    pub date: NaiveDate,
+    pub r#return: f64,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RiskParams {
+    pub frequency: Frequency,
+    /// Annual risk-free rate, e.g. the 91-day T-bill yield.
+    #[serde(default)]
+    pub risk_free_rate: f64,
+    #[serde(default = "default_confidence_levels")]
+    pub confidence_levels: Vec<f64>,
+}
+
+fn default_confidence_levels() -> Vec<f64> {
+    vec![0.95, 0.99]
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Drawdown {
+    pub max_drawdown: f64,
+    /// `None` when the peak is the start of the series.
+    pub peak_date: Option<NaiveDate>,
+    pub trough_date: NaiveDate,
+    /// First date the previous peak was regained; `None` if it has not been.
+    pub recovery_date: Option<NaiveDate>,
+}
+
+/// One-period value at risk, as positive loss fractions.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValueAtRisk {
+    pub confidence: f64,
+    pub historical_var: f64,
+    pub historical_cvar: f64,
+    pub parametric_var: f64,
+    pub parametric_cvar: f64,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RiskReport {
+    pub observations: usize,
+    pub mean_return: f64,
+    pub annualised_return: f64,
+    pub annualised_volatility: f64,
+    pub downside_deviation: f64,
+    /// `None` when the series has no variability to divide by.
+    pub sharpe_ratio: Option<f64>,
+    pub sortino_ratio: Option<f64>,
+    /// `None` when the series never falls below a previous high.
+    pub drawdown: Option<Drawdown>,
+    pub value_at_risk: Vec<ValueAtRisk>,
+}
+
+fn mean(xs: &[f64]) -> f64 {
+    xs.iter().sum::<f64>() / xs.len() as f64
+}
+
+/// Sample standard deviation (n − 1).
+fn std_dev(xs: &[f64]) -> f64 {
+    let m = mean(xs);
+    (xs.iter().map(|x| (x - m).powi(2)).sum::<f64>() / (xs.len() - 1) as f64).sqrt()
+}
+
+/// Standard normal density.
+pub fn normal_pdf(x: f64) -> f64 {
+    (-0.5 * x * x).exp() / (2.0 * std::f64::consts::PI).sqrt()
+}
+
+/// Inverse standard normal CDF (Acklam's rational approximation, relative error < 1.2e-9).
+pub fn normal_quantile(p: f64) -> f64 {
+    const A: [f64; 6] = [
+        -3.969_683_028_665_376e1,
+        2.209_460_984_245_205e2,
+        -2.759_285_104_469_687e2,
+        1.383_577_518_672_69e2,
+        -3.066_479_806_614_716e1,
+        2.506_628_277_459_239,
+    ];
+    const B: [f64; 5] = [
+        -5.447_609_879_822_406e1,
+        1.615_858_368_580_409e2,
+        -1.556_989_798_598_866e2,
+        6.680_131_188_771_972e1,
+        -1.328_068_155_288_572e1,
+    ];
+    const C: [f64; 6] = [
+        -7.784_894_002_430_293e-3,
+        -3.223_964_580_411_365e-1,
+        -2.400_758_277_161_838,
+        -2.549_732_539_343_734,
+        4.374_664_141_464_968,
+        2.938_163_982_698_783,
+    ];
+    const D: [f64; 4] = [
+        7.784_695_709_041_462e-3,
+        3.224_671_290_700_398e-1,
+        2.445_134_137_142_996,
+        3.754_408_661_907_416,
+    ];
+    const LOW: f64 = 0.024_25;
+
+    let tail = |q: f64| {
+        (((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5])
+            / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.0)
+    };
+    if p < LOW {
+        tail((-2.0 * p.ln()).sqrt())
+    } else if p > 1.0 - LOW {
+        -tail((-2.0 * (1.0 - p).ln()).sqrt())
+    } else {
+        let q = p - 0.5;
+        let r = q * q;
+        (((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q
+            / (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1.0)
+    }
+}
+
+fn drawdown(series: &[ReturnObservation]) -> Option<Drawdown> {
+    let mut wealth = 1.0;
+    let mut peak = 1.0;
+    let mut peak_date: Option<NaiveDate> = None;
+    let mut worst: Option<Drawdown> = None;
+    let mut worst_peak = 0.0;
+    for obs in series {
+        wealth *= 1.0 + obs.r#return;
+        if wealth >= peak {
+            if let Some(w) = worst.as_mut() {
+                if w.recovery_date.is_none() && wealth >= worst_peak {
+                    w.recovery_date = Some(obs.date);
+                }
+            }
+            peak = wealth;
+            peak_date = Some(obs.date);
+            continue;
+        }
+        let dd = wealth / peak - 1.0;
+        if worst.as_ref().is_none_or(|w| dd < w.max_drawdown) {
+            worst_peak = peak;
+            worst = Some(Drawdown {
+                max_drawdown: dd,
+                peak_date,
+                trough_date: obs.date,
+                recovery_date: None,
+            });
+        }
+    }
+    worst
+}
+
+fn value_at_risk(returns: &[f64], confidence: f64) -> ValueAtRisk {
+    let mut sorted = returns.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    // The k worst outcomes make up the (1 − confidence) tail. The nudge keeps
+    // (1 − 0.95) × 20 = 1.0000000000000009 from rounding up to two.
+    let k = (((1.0 - confidence) * sorted.len() as f64 - 1e-9).ceil() as usize).max(1);
+    let historical_var = -sorted[k - 1];
+    let historical_cvar = -mean(&sorted[..k]);
+
+    let (mu, sigma) = (mean(returns), std_dev(returns));
+    let z = normal_quantile(1.0 - confidence);
+    ValueAtRisk {
+        confidence,
+        historical_var,
+        historical_cvar,
+        parametric_var: -(mu + sigma * z),
+        parametric_cvar: -(mu - sigma * normal_pdf(z) / (1.0 - confidence)),
+    }
+}
+
+/// Computes the risk report for `series`, which must be in strictly increasing date order.
+pub fn risk_metrics(
+    series: &[ReturnObservation],
+    params: &RiskParams,
+) -> Result<RiskReport, RiskError> {
+    if series.len() < 2 {
+        return Err(RiskError::NotEnoughObservations {
+            needed: 2,
+            got: series.len(),
+        });
+    }
+    if let Some(pair) = series.windows(2).find(|w| w[1].date <= w[0].date) {
+        return Err(RiskError::UnorderedDates { date: pair[1].date });
+    }
+    for obs in series {
+        if !obs.r#return.is_finite() {
+            return Err(RiskError::NonFiniteReturn { date: obs.date });
+        }
+        if obs.r#return <= -1.0 {
+            return Err(RiskError::TotalLoss { date: obs.date });
+        }
+    }
+    if let Some(c) = params
+        .confidence_levels
+        .iter()
+        .find(|c| !(**c > 0.0 && **c < 1.0))
+    {
+        return Err(RiskError::InvalidConfidence(*c));
+    }
+
+    let ppy = params.frequency.periods_per_year();
+    let returns: Vec<f64> = series.iter().map(|o| o.r#return).collect();
+    let n = returns.len() as f64;
+    let rf = (1.0 + params.risk_free_rate).powf(1.0 / ppy) - 1.0;
+    let excess: Vec<f64> = returns.iter().map(|r| r - rf).collect();
+
+    let growth: f64 = returns.iter().map(|r| 1.0 + r).product();
+    let volatility = std_dev(&returns);
+    let downside = (excess.iter().map(|x| x.min(0.0).powi(2)).sum::<f64>() / n).sqrt();
+    let excess_sd = std_dev(&excess);
+    let ratio =
+        |denominator: f64| (denominator > 0.0).then(|| mean(&excess) / denominator * ppy.sqrt());
+
+    Ok(RiskReport {
+        observations: returns.len(),
+        mean_return: mean(&returns),
+        annualised_return: growth.powf(ppy / n) - 1.0,
+        annualised_volatility: volatility * ppy.sqrt(),
+        downside_deviation: downside * ppy.sqrt(),
+        sharpe_ratio: ratio(excess_sd),
+        sortino_ratio: ratio(downside),
+        drawdown: drawdown(series),
+        value_at_risk: params
+            .confidence_levels
+            .iter()
+            .map(|c| value_at_risk(&returns, *c))
+            .collect(),
+    })
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RiskInput {
+    returns: Vec<ReturnObservation>,
+    frequency: Frequency,
+    #[serde(default)]
+    risk_free_rate: f64,
+    #[serde(default = "default_confidence_levels")]
+    confidence_levels: Vec<f64>,
+}
+
+/// JS entry point: `{ returns: [{ date, return }], frequency, riskFreeRate?,
+/// confidenceLevels? }` in, `RiskReport` out.
+#[wasm_bindgen(js_name = riskMetrics)]
+pub fn risk_metrics_js(input: JsValue) -> Result<JsValue, JsError> {
+    let input: RiskInput = from_js(input)?;
+    let params = RiskParams {
+        frequency: input.frequency,
+        risk_free_rate: input.risk_free_rate,
+        confidence_levels: input.confidence_levels,
+    };
+    to_js(&risk_metrics(&input.returns, &params).map_err(js_err)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64, tol: f64) {
+        assert!((a - b).abs() < tol, "{} != {}", a, b);
+    }
+
+    fn monthly(returns: &[f64]) -> Vec<ReturnObservation> {
+        returns
+            .iter()
+            .enumerate()
+            .map(|(i, r)| ReturnObservation {
+                date: NaiveDate::from_ymd_opt(2023, 1, 31)
+                    .unwrap()
+                    .checked_add_months(chrono::Months::new(i as u32))
+                    .unwrap(),
+                r#return: *r,
+            })
+            .collect()
+    }
+
+    fn params(confidence_levels: Vec<f64>) -> RiskParams {
+        RiskParams {
+            frequency: Frequency::Monthly,
+            risk_free_rate: 0.0,
+            confidence_levels,
+        }
+    }
+
+    #[test]
+    fn volatility_is_the_annualised_sample_deviation() {
+        let report = risk_metrics(&monthly(&[0.01, -0.02, 0.03, 0.0]), &params(vec![])).unwrap();
+        close(report.mean_return, 0.005, 1e-15);
+        let sd = (0.0013_f64 / 3.0).sqrt();
+        close(report.annualised_volatility, sd * 12f64.sqrt(), 1e-12);
+        close(
+            report.sharpe_ratio.unwrap(),
+            0.005 / sd * 12f64.sqrt(),
+            1e-12,
+        );
+        // Downside deviation over all n periods, not just the losing ones.
+        close(
+            report.downside_deviation,
+            (0.0004_f64 / 4.0).sqrt() * 12f64.sqrt(),
+            1e-12,
+        );
+    }
+
+    #[test]
+    fn drawdown_reports_peak_trough_and_recovery() {
+        let series = monthly(&[0.10, -0.20, 0.05, 0.20, -0.01]);
+        let dd = risk_metrics(&series, &params(vec![]))
+            .unwrap()
+            .drawdown
+            .unwrap();
+        close(dd.max_drawdown, -0.2, 1e-12);
+        assert_eq!(dd.peak_date, Some(series[0].date));
+        assert_eq!(dd.trough_date, series[1].date);
+        // 1.1 × 0.8 × 1.05 × 1.2 = 1.1088 regains the 1.1 peak.
+        assert_eq!(dd.recovery_date, Some(series[3].date));
+
+        let unrecovered = monthly(&[-0.05, -0.05, 0.02]);
+        let dd = risk_metrics(&unrecovered, &params(vec![]))
+            .unwrap()
+            .drawdown
+            .unwrap();
+        assert_eq!(dd.peak_date, None);
+        assert_eq!(dd.trough_date, unrecovered[1].date);
+        assert_eq!(dd.recovery_date, None);
+        close(dd.max_drawdown, 0.95 * 0.95 - 1.0, 1e-12);
+
+        let rising = monthly(&[0.01, 0.02]);
+        assert_eq!(
+            risk_metrics(&rising, &params(vec![])).unwrap().drawdown,
+            None
+        );
+    }
+
+    #[test]
+    fn historical_and_parametric_var() {
+        // -10%, -9%, ..., +9%.
+        let returns: Vec<f64> = (0..20).map(|i| (i as f64 - 10.0) / 100.0).collect();
+        let report = risk_metrics(&monthly(&returns), &params(vec![0.9, 0.95])).unwrap();
+        let v90 = &report.value_at_risk[0];
+        close(v90.historical_var, 0.09, 1e-12);
+        close(v90.historical_cvar, 0.095, 1e-12);
+        let v95 = &report.value_at_risk[1];
+        close(v95.historical_var, 0.10, 1e-12);
+        close(v95.historical_cvar, 0.10, 1e-12);
+
+        let (mu, sigma) = (-0.005, std_dev(&returns));
+        let z = -1.644_853_626_951_472;
+        close(v95.parametric_var, -(mu + sigma * z), 1e-8);
+        close(
+            v95.parametric_cvar,
+            -(mu - sigma * normal_pdf(z) / 0.05),
+            1e-8,
+        );
+        assert!(v95.parametric_cvar > v95.parametric_var);
+    }
+
+    #[test]
+    fn normal_quantile_matches_tables() {
+        for (p, z) in [
+            (0.5, 0.0),
+            (0.975, 1.959_963_984_540_054),
+            (0.95, 1.644_853_626_951_472),
+            (0.01, -2.326_347_874_040_841),
+            (0.001, -3.090_232_306_167_813),
+            (1e-6, -4.753_424_308_822_899),
+        ] {
+            close(normal_quantile(p), z, 1e-8 * (1.0 + z.abs()));
+        }
+        close(normal_quantile(0.2), -normal_quantile(0.8), 1e-12);
+    }
+
+    #[test]
+    fn rejects_unordered_and_invalid_series() {
+        let mut series = monthly(&[0.01, 0.02, 0.03]);
+        series.swap(1, 2);
+        assert_eq!(
+            risk_metrics(&series, &params(vec![])),
+            Err(RiskError::UnorderedDates {
+                date: series[2].date
+            })
+        );
+        series[2].date = series[1].date;
+        assert!(matches!(
+            risk_metrics(&series, &params(vec![])),
+            Err(RiskError::UnorderedDates { .. })
+        ));
+        assert_eq!(
+            risk_metrics(&monthly(&[0.01]), &params(vec![])),
+            Err(RiskError::NotEnoughObservations { needed: 2, got: 1 })
+        );
+        assert!(matches!(
+            risk_metrics(&monthly(&[0.01, -1.0]), &params(vec![])),
+            Err(RiskError::TotalLoss { .. })
+        ));
+        assert_eq!(
+            risk_metrics(&monthly(&[0.01, 0.02]), &params(vec![1.0])),
+            Err(RiskError::InvalidConfidence(1.0))
+        );
+    }
+}
@@ -0,0 +1,457 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use chrono::{Datelike, NaiveDate};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::js::{from_js, js_err, to_js};
+use crate::money::{Currency, Money, MoneyError};
+
+/// Kinds of investment income that attract withholding tax at source in Kenya.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum IncomeType {
+    MmfInterest,
+    BankInterest,
+    Dividend,
+    TreasuryBillInterest,
+    /// Coupon on a Treasury bond with under ten years to maturity at issue.
+    BondCoupon,
+    /// Coupon on a Treasury bond of ten years or more at issue.
+    LongBondCoupon,
+    InfrastructureBondCoupon,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Residency {
+    Resident,
+    NonResident,
+}
+
+/// One row of the rules table: `rate` applies to `income_type` for `residency`
+/// on payments dated from `effective_from` up to and including `effective_to`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaxRule {
+    pub income_type: IncomeType,
+    pub residency: Residency,
+    pub rate: Decimal,
+    pub effective_from: NaiveDate,
+    #[serde(default)]
+    pub effective_to: Option<NaiveDate>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TaxError {
+    NoRule {
+        income_type: IncomeType,
+        residency: Residency,
+        date: NaiveDate,
+    },
+    OverlappingRules {
+        income_type: IncomeType,
+        residency: Residency,
+        date: NaiveDate,
+    },
+    InvalidRate(Decimal),
+    Money(MoneyError),
+}
+
+impl fmt::Display for TaxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TaxError::NoRule {
+                income_type,
+                residency,
+                date,
+            } => write!(
+                f,
+                "no withholding rule for {:?} ({:?}) on {}",
+                income_type, residency, date
+            ),
+            TaxError::OverlappingRules {
+                income_type,
+                residency,
+                date,
+            } => write!(
+                f,
+                "more than one withholding rule for {:?} ({:?}) on {}",
+                income_type, residency, date
+            ),
+            TaxError::InvalidRate(rate) => write!(f, "withholding rate {} is outside [0, 1]", rate),
+            TaxError::Money(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for TaxError {}
+
+impl From<MoneyError> for TaxError {
+    fn from(err: MoneyError) -> Self {
+        TaxError::Money(err)
+    }
+}
+
+fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(y, m, d).expect("valid calendar date")
+}
+
+fn pct(p: i64) -> Decimal {
+    Decimal::new(p, 2)
+}
+
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct TaxTable {
+    rules: Vec<TaxRule>,
+}
+
+impl TaxTable {
+    pub fn new(rules: Vec<TaxRule>) -> Result<TaxTable, TaxError> {
+        if let Some(bad) = rules
+            .iter()
+            .find(|r| r.rate < Decimal::ZERO || r.rate > Decimal::ONE)
+        {
+            return Err(TaxError::InvalidRate(bad.rate));
+        }
+        Ok(TaxTable { rules })
+    }
+
+    /// Withholding rates under the Kenyan Income Tax Act as we apply them today.
+    ///
+    /// Interest is a final tax at 15%; long-dated bonds get the 10% concession and
+    /// infrastructure bonds are exempt. Non-resident dividends moved from 10% to 15%
+    /// with the Finance Act 2023.
+    pub fn kenya() -> TaxTable {
+        use IncomeType::*;
+        use Residency::*;
+        let since = date(2010, 1, 1);
+        let mut rules = Vec::new();
+        for residency in [Resident, NonResident] {
+            for (income_type, rate) in [
+                (MmfInterest, pct(15)),
+                (BankInterest, pct(15)),
+                (TreasuryBillInterest, pct(15)),
+                (BondCoupon, pct(15)),
+                (LongBondCoupon, pct(10)),
+                (InfrastructureBondCoupon, Decimal::ZERO),
+            ] {
+                rules.push(TaxRule {
+                    income_type,
+                    residency,
+                    rate,
+                    effective_from: since,
+                    effective_to: None,
+                });
+            }
+        }
+        rules.push(TaxRule {
+            income_type: Dividend,
+            residency: Resident,
+            rate: pct(5),
+            effective_from: since,
+            effective_to: None,
+        });
+        rules.push(TaxRule {
+            income_type: Dividend,
+            residency: NonResident,
+            rate: pct(10),
+            effective_from: since,
+            effective_to: Some(date(2023, 6, 30)),
+        });
+        rules.push(TaxRule {
+            income_type: Dividend,
+            residency: NonResident,
+            rate: pct(15),
+            effective_from: date(2023, 7, 1),
+            effective_to: None,
+        });
+        TaxTable { rules }
+    }
+
+    pub fn rules(&self) -> &[TaxRule] {
+        &self.rules
+    }
+
+    /// The single rule in force for a payment on `on`.
+    pub fn rule_for(
+        &self,
+        income_type: IncomeType,
+        residency: Residency,
+        on: NaiveDate,
+    ) -> Result<&TaxRule, TaxError> {
+        let mut matching = self.rules.iter().filter(|r| {
+            r.income_type == income_type
+                && r.residency == residency
+                && r.effective_from <= on
+                && r.effective_to.is_none_or(|to| on <= to)
+        });
+        let rule = matching.next().ok_or(TaxError::NoRule {
+            income_type,
+            residency,
+            date: on,
+        })?;
+        if matching.next().is_some() {
+            return Err(TaxError::OverlappingRules {
+                income_type,
+                residency,
+                date: on,
+            });
+        }
+        Ok(rule)
+    }
+}
+
+/// A single gross income payment to a client.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IncomeItem {
+    pub date: NaiveDate,
+    pub income_type: IncomeType,
+    pub gross: Money,
+    #[serde(default)]
+    pub reference: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaxedIncome {
+    pub date: NaiveDate,
+    pub income_type: IncomeType,
+    pub reference: Option<String>,
+    pub gross: Money,
+    pub rate: Decimal,
+    pub withheld: Money,
+    pub net: Money,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WithholdingLine {
+    pub year: i32,
+    pub currency: Currency,
+    pub income_type: IncomeType,
+    pub gross: Money,
+    pub withheld: Money,
+    pub net: Money,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WithholdingStatement {
+    pub residency: Residency,
+    pub items: Vec<TaxedIncome>,
+    /// One line per tax year, currency and income type, in that order.
+    pub summary: Vec<WithholdingLine>,
+}
+
+pub fn apply_withholding(
+    table: &TaxTable,
+    residency: Residency,
+    item: &IncomeItem,
+) -> Result<TaxedIncome, TaxError> {
+    let rule = table.rule_for(item.income_type, residency, item.date)?;
+    let withheld = item.gross.checked_mul(rule.rate)?;
+    let net = item.gross.checked_sub(&withheld)?;
+    Ok(TaxedIncome {
+        date: item.date,
+        income_type: item.income_type,
+        reference: item.reference.clone(),
+        gross: item.gross,
+        rate: rule.rate,
+        withheld,
+        net,
+    })
+}
+
+/// Nets every payment of tax and rolls the results up into a per-year summary.
+pub fn withholding_statement(
+    table: &TaxTable,
+    residency: Residency,
+    items: &[IncomeItem],
+) -> Result<WithholdingStatement, TaxError> {
+    let taxed = items
+        .iter()
+        .map(|item| apply_withholding(table, residency, item))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut lines: BTreeMap<(i32, Currency, IncomeType), WithholdingLine> = BTreeMap::new();
+    for t in &taxed {
+        let currency = t.gross.currency();
+        let line = lines
+            .entry((t.date.year(), currency, t.income_type))
+            .or_insert_with(|| WithholdingLine {
+                year: t.date.year(),
+                currency,
+                income_type: t.income_type,
+                gross: Money::zero(currency),
+                withheld: Money::zero(currency),
+                net: Money::zero(currency),
+            });
+        line.gross = line.gross.checked_add(&t.gross)?;
+        line.withheld = line.withheld.checked_add(&t.withheld)?;
+        line.net = line.net.checked_add(&t.net)?;
+    }
+
+    Ok(WithholdingStatement {
+        residency,
+        items: taxed,
+        summary: lines.into_values().collect(),
+    })
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StatementInput {
+    residency: Residency,
+    items: Vec<IncomeItem>,
+    /// Overrides the built-in Kenyan table when present.
+    #[serde(default)]
+    rules: Option<Vec<TaxRule>>,
+}
+
+/// JS entry point: `{ residency, items, rules? }` in, `WithholdingStatement` out.
+#[wasm_bindgen(js_name = withholdingStatement)]
+pub fn withholding_statement_js(input: JsValue) -> Result<JsValue, JsError> {
+    let input: StatementInput = from_js(input)?;
+    let table = match input.rules {
+        Some(rules) => TaxTable::new(rules).map_err(js_err)?,
+        None => TaxTable::kenya(),
+    };
+    let statement = withholding_statement(&table, input.residency, &input.items).map_err(js_err)?;
+    to_js(&statement)
+}
+
+/// The built-in Kenyan withholding rules, for display or as a base to edit.
+#[wasm_bindgen(js_name = kenyaWithholdingRules)]
+pub fn kenya_rules_js() -> Result<JsValue, JsError> {
+    to_js(&TaxTable::kenya().rules)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn dec(s: &str) -> Decimal {
+        Decimal::from_str(s).unwrap()
+    }
+
+    fn kes(s: &str) -> Money {
+        Money::parse(s, Currency::Kes).unwrap()
+    }
+
+    fn item(on: NaiveDate, income_type: IncomeType, gross: &str) -> IncomeItem {
+        IncomeItem {
+            date: on,
+            income_type,
+            gross: kes(gross),
+            reference: None,
+        }
+    }
+
+    #[test]
+    fn kenyan_rates_by_income_type_and_residency() {
+        use IncomeType::*;
+        use Residency::*;
+        let table = TaxTable::kenya();
+        let on = date(2024, 3, 31);
+        for (income_type, resident, non_resident) in [
+            (MmfInterest, "0.15", "0.15"),
+            (BankInterest, "0.15", "0.15"),
+            (TreasuryBillInterest, "0.15", "0.15"),
+            (BondCoupon, "0.15", "0.15"),
+            (LongBondCoupon, "0.10", "0.10"),
+            (InfrastructureBondCoupon, "0", "0"),
+            (Dividend, "0.05", "0.15"),
+        ] {
+            let rate = |residency| table.rule_for(income_type, residency, on).unwrap().rate;
+            assert_eq!(rate(Resident), dec(resident), "{:?} resident", income_type);
+            assert_eq!(
+                rate(NonResident),
+                dec(non_resident),
+                "{:?} non-resident",
+                income_type
+            );
+        }
+    }
+
+    #[test]
+    fn non_resident_dividend_rate_follows_the_finance_act_2023() {
+        let table = TaxTable::kenya();
+        let before = item(date(2023, 6, 30), IncomeType::Dividend, "10000");
+        let after = item(date(2023, 7, 1), IncomeType::Dividend, "10000");
+        let taxed = apply_withholding(&table, Residency::NonResident, &before).unwrap();
+        assert_eq!((taxed.withheld, taxed.net), (kes("1000"), kes("9000")));
+        let taxed = apply_withholding(&table, Residency::NonResident, &after).unwrap();
+        assert_eq!((taxed.withheld, taxed.net), (kes("1500"), kes("8500")));
+    }
+
+    #[test]
+    fn statement_totals_per_year_and_income_type() {
+        let items = [
+            item(date(2023, 12, 31), IncomeType::MmfInterest, "1234.57"),
+            item(date(2024, 1, 31), IncomeType::MmfInterest, "1000"),
+            item(date(2024, 2, 29), IncomeType::MmfInterest, "1000.10"),
+            item(date(2024, 5, 20), IncomeType::Dividend, "5000"),
+        ];
+        let s = withholding_statement(&TaxTable::kenya(), Residency::Resident, &items).unwrap();
+        assert_eq!(s.items[0].withheld, kes("185.19"));
+        assert_eq!(s.items[2].withheld, kes("150.02"));
+        assert_eq!(s.summary.len(), 3);
+
+        let line = |year, income_type| {
+            s.summary
+                .iter()
+                .find(|l| l.year == year && l.income_type == income_type)
+                .unwrap()
+        };
+        let mmf = line(2024, IncomeType::MmfInterest);
+        assert_eq!(mmf.gross, kes("2000.10"));
+        assert_eq!(mmf.withheld, kes("300.02"));
+        assert_eq!(mmf.net, kes("1700.08"));
+        assert_eq!(line(2023, IncomeType::MmfInterest).withheld, kes("185.19"));
+        assert_eq!(line(2024, IncomeType::Dividend).withheld, kes("250"));
+        for l in &s.summary {
+            assert_eq!(l.gross, l.withheld.checked_add(&l.net).unwrap());
+        }
+    }
+
+    #[test]
+    fn missing_overlapping_and_invalid_rules_are_errors() {
+        let rule = |from, to, rate: &str| TaxRule {
+            income_type: IncomeType::BankInterest,
+            residency: Residency::Resident,
+            rate: dec(rate),
+            effective_from: from,
+            effective_to: to,
+        };
+        let table = TaxTable::new(vec![
+            rule(date(2020, 1, 1), Some(date(2022, 12, 31)), "0.15"),
+            rule(date(2022, 1, 1), None, "0.20"),
+        ])
+        .unwrap();
+        assert!(matches!(
+            table.rule_for(
+                IncomeType::BankInterest,
+                Residency::Resident,
+                date(2019, 1, 1)
+            ),
+            Err(TaxError::NoRule { .. })
+        ));
+        assert!(matches!(
+            table.rule_for(
+                IncomeType::BankInterest,
+                Residency::Resident,
+                date(2022, 6, 1)
+            ),
+            Err(TaxError::OverlappingRules { .. })
+        ));
+        assert_eq!(
+            TaxTable::new(vec![rule(date(2020, 1, 1), None, "1.5")]),
+            Err(TaxError::InvalidRate(dec("1.5")))
+        );
+    }
+}
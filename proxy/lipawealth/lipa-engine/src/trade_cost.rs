@@ -0,0 +1,380 @@
+use std::fmt;
+
+use chrono::NaiveDate;
+use rust_decimal::prelude::*;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::js::{from_js, js_err, to_js};
+use crate::money::{Currency, Money, MoneyError};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TradeSide {
+    Buy,
+    Sell,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FeeKind {
+    Brokerage,
+    CmaLevy,
+    NseLevy,
+    CdscFee,
+    InvestorCompensationFund,
+    StampDuty,
+}
+
+/// How each fee line is rounded to the minor unit before it is summed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LineRounding {
+    HalfEven,
+    HalfUp,
+}
+
+impl LineRounding {
+    fn strategy(self) -> RoundingStrategy {
+        match self {
+            LineRounding::HalfEven => RoundingStrategy::MidpointNearestEven,
+            LineRounding::HalfUp => RoundingStrategy::MidpointAwayFromZero,
+        }
+    }
+}
+
+/// A rate charged on consideration, with an optional floor, for one or both sides.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeeComponent {
+    pub kind: FeeKind,
+    pub rate: Decimal,
+    #[serde(default)]
+    pub minimum: Option<Decimal>,
+    /// Restricts the charge to one side of the trade; `None` charges both.
+    #[serde(default)]
+    pub side: Option<TradeSide>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeeSchedule {
+    pub version: String,
+    pub effective_from: NaiveDate,
+    pub currency: Currency,
+    pub rounding: LineRounding,
+    pub components: Vec<FeeComponent>,
+}
+
+impl FeeSchedule {
+    /// Statutory NSE equity charges with a 1.5% brokerage cap.
+    ///
+    /// Stamp duty on listed share transfers is currently zero-rated but kept as a
+    /// line so contract notes that print it still reconcile.
+    pub fn nse_standard() -> FeeSchedule {
+        let component = |kind, bp: i64, minimum: Option<Decimal>| FeeComponent {
+            kind,
+            rate: Decimal::new(bp, 4),
+            minimum,
+            side: None,
+        };
+        FeeSchedule {
+            version: "NSE-2023.1".to_string(),
+            effective_from: NaiveDate::from_ymd_opt(2023, 1, 1).expect("valid calendar date"),
+            currency: Currency::Kes,
+            rounding: LineRounding::HalfUp,
+            components: vec![
+                component(FeeKind::Brokerage, 150, Some(Decimal::ONE_HUNDRED)),
+                component(FeeKind::CmaLevy, 12, None),
+                component(FeeKind::NseLevy, 12, None),
+                component(FeeKind::CdscFee, 8, None),
+                component(FeeKind::InvestorCompensationFund, 1, None),
+                component(FeeKind::StampDuty, 0, None),
+            ],
+        }
+    }
+}
+
+/// Picks the latest schedule already in force on `trade_date`.
+pub fn schedule_for(schedules: &[FeeSchedule], trade_date: NaiveDate) -> Option<&FeeSchedule> {
+    schedules
+        .iter()
+        .filter(|s| s.effective_from <= trade_date)
+        .max_by_key(|s| s.effective_from)
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeeLine {
+    pub kind: FeeKind,
+    pub rate: Decimal,
+    pub amount: Money,
+    /// True when the minimum charge, not the rate, set the amount.
+    pub minimum_applied: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TradeCost {
+    pub schedule_version: String,
+    pub side: TradeSide,
+    pub price: Decimal,
+    pub quantity: u64,
+    pub consideration: Money,
+    pub fees: Vec<FeeLine>,
+    pub total_fees: Money,
+    /// Cash paid on a buy, or received on a sell.
+    pub net_settlement: Money,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TradeCostError {
+    InvalidPrice,
+    ZeroQuantity,
+    NoSchedule(NaiveDate),
+    Money(MoneyError),
+}
+
+impl fmt::Display for TradeCostError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TradeCostError::InvalidPrice => f.write_str("price must be positive"),
+            TradeCostError::ZeroQuantity => f.write_str("quantity must be at least one share"),
+            TradeCostError::NoSchedule(date) => write!(f, "no fee schedule in force on {}", date),
+            TradeCostError::Money(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for TradeCostError {}
+
+impl From<MoneyError> for TradeCostError {
+    fn from(err: MoneyError) -> Self {
+        TradeCostError::Money(err)
+    }
+}
+
+/// Itemises every charge on an equity trade and the resulting settlement amount.
+pub fn trade_cost(
+    side: TradeSide,
+    price: Decimal,
+    quantity: u64,
+    schedule: &FeeSchedule,
+) -> Result<TradeCost, TradeCostError> {
+    if price <= Decimal::ZERO {
+        return Err(TradeCostError::InvalidPrice);
+    }
+    if quantity == 0 {
+        return Err(TradeCostError::ZeroQuantity);
+    }
+    let currency = schedule.currency;
+    let dp = currency.minor_units();
+    let gross = price
+        .checked_mul(Decimal::from(quantity))
+        .ok_or(MoneyError::Overflow)?;
+    let consideration = Money::new(gross, currency);
+
+    let mut fees = Vec::new();
+    for c in &schedule.components {
+        if c.side.is_some_and(|s| s != side) {
+            continue;
+        }
+        let raw = consideration
+            .amount()
+            .checked_mul(c.rate)
+            .ok_or(MoneyError::Overflow)?
+            .round_dp_with_strategy(dp, schedule.rounding.strategy());
+        let (amount, minimum_applied) = match c.minimum {
+            Some(min) if raw < min => (min, true),
+            _ => (raw, false),
+        };
+        fees.push(FeeLine {
+            kind: c.kind,
+            rate: c.rate,
+            amount: Money::new(amount, currency),
+            minimum_applied,
+        });
+    }
+
+    let total_fees = Money::sum(fees.iter().map(|l| &l.amount), currency)?;
+    let net_settlement = match side {
+        TradeSide::Buy => consideration.checked_add(&total_fees)?,
+        TradeSide::Sell => consideration.checked_sub(&total_fees)?,
+    };
+    Ok(TradeCost {
+        schedule_version: schedule.version.clone(),
+        side,
+        price,
+        quantity,
+        consideration,
+        fees,
+        total_fees,
+        net_settlement,
+    })
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TradeCostInput {
+    side: TradeSide,
+    price: Decimal,
+    quantity: u64,
+    trade_date: NaiveDate,
+    /// Versioned schedules to pick from; defaults to the built-in NSE schedule.
+    #[serde(default)]
+    schedules: Option<Vec<FeeSchedule>>,
+}
+
+/// JS entry point: `{ side, price, quantity, tradeDate, schedules? }` in, `TradeCost` out.
+#[wasm_bindgen(js_name = nseTradeCost)]
+pub fn trade_cost_js(input: JsValue) -> Result<JsValue, JsError> {
+    let input: TradeCostInput = from_js(input)?;
+    let schedules = input
+        .schedules
+        .unwrap_or_else(|| vec![FeeSchedule::nse_standard()]);
+    let schedule = schedule_for(&schedules, input.trade_date)
+        .ok_or(TradeCostError::NoSchedule(input.trade_date))
+        .map_err(js_err)?;
+    let cost = trade_cost(input.side, input.price, input.quantity, schedule).map_err(js_err)?;
+    to_js(&cost)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dec(s: &str) -> Decimal {
+        Decimal::from_str(s).unwrap()
+    }
+
+    fn kes(s: &str) -> Money {
+        Money::parse(s, Currency::Kes).unwrap()
+    }
+
+    fn d(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn line(cost: &TradeCost, kind: FeeKind) -> &FeeLine {
+        cost.fees.iter().find(|l| l.kind == kind).unwrap()
+    }
+
+    #[test]
+    fn buy_contract_note() {
+        // BUY 1,000 SCOM @ 16.50
+        let cost = trade_cost(
+            TradeSide::Buy,
+            dec("16.50"),
+            1000,
+            &FeeSchedule::nse_standard(),
+        )
+        .unwrap();
+        assert_eq!(cost.consideration, kes("16500.00"));
+        let amounts: Vec<Money> = cost.fees.iter().map(|l| l.amount).collect();
+        assert_eq!(
+            amounts,
+            ["247.50", "19.80", "19.80", "13.20", "1.65", "0"].map(kes)
+        );
+        assert_eq!(cost.total_fees, kes("301.95"));
+        assert_eq!(cost.net_settlement, kes("16801.95"));
+    }
+
+    #[test]
+    fn sell_contract_note() {
+        // SELL 300 EQTY @ 42.35: brokerage of 190.575 rounds half up to 190.58.
+        let cost = trade_cost(
+            TradeSide::Sell,
+            dec("42.35"),
+            300,
+            &FeeSchedule::nse_standard(),
+        )
+        .unwrap();
+        assert_eq!(cost.consideration, kes("12705.00"));
+        assert_eq!(line(&cost, FeeKind::Brokerage).amount, kes("190.58"));
+        assert_eq!(line(&cost, FeeKind::CmaLevy).amount, kes("15.25"));
+        assert_eq!(line(&cost, FeeKind::NseLevy).amount, kes("15.25"));
+        assert_eq!(line(&cost, FeeKind::CdscFee).amount, kes("10.16"));
+        assert_eq!(
+            line(&cost, FeeKind::InvestorCompensationFund).amount,
+            kes("1.27")
+        );
+        assert_eq!(cost.total_fees, kes("232.51"));
+        assert_eq!(cost.net_settlement, kes("12472.49"));
+    }
+
+    #[test]
+    fn small_trades_pay_the_brokerage_minimum() {
+        let cost = trade_cost(TradeSide::Buy, dec("5"), 100, &FeeSchedule::nse_standard()).unwrap();
+        let brokerage = line(&cost, FeeKind::Brokerage);
+        assert!(brokerage.minimum_applied);
+        assert_eq!(brokerage.amount, kes("100"));
+        assert!(!line(&cost, FeeKind::CmaLevy).minimum_applied);
+        assert_eq!(cost.total_fees, kes("101.65"));
+        assert_eq!(cost.net_settlement, kes("601.65"));
+    }
+
+    #[test]
+    fn levies_round_per_line_with_the_schedule_strategy() {
+        // 0.01% of 1,050.00 is exactly 0.105.
+        let mut schedule = FeeSchedule::nse_standard();
+        let cost = trade_cost(TradeSide::Buy, dec("10.50"), 100, &schedule).unwrap();
+        assert_eq!(
+            line(&cost, FeeKind::InvestorCompensationFund).amount,
+            kes("0.11")
+        );
+        schedule.rounding = LineRounding::HalfEven;
+        let cost = trade_cost(TradeSide::Buy, dec("10.50"), 100, &schedule).unwrap();
+        assert_eq!(
+            line(&cost, FeeKind::InvestorCompensationFund).amount,
+            kes("0.10")
+        );
+    }
+
+    #[test]
+    fn one_sided_components_skip_the_other_side() {
+        let mut schedule = FeeSchedule::nse_standard();
+        schedule.components.push(FeeComponent {
+            kind: FeeKind::StampDuty,
+            rate: dec("0.001"),
+            minimum: None,
+            side: Some(TradeSide::Buy),
+        });
+        let buy = trade_cost(TradeSide::Buy, dec("10"), 1000, &schedule).unwrap();
+        let sell = trade_cost(TradeSide::Sell, dec("10"), 1000, &schedule).unwrap();
+        assert_eq!(buy.fees.len(), sell.fees.len() + 1);
+    }
+
+    #[test]
+    fn schedule_is_chosen_by_trade_date() {
+        let mut revised = FeeSchedule::nse_standard();
+        revised.version = "NSE-2025.1".to_string();
+        revised.effective_from = d("2025-01-01");
+        let schedules = [revised, FeeSchedule::nse_standard()];
+        assert!(schedule_for(&schedules, d("2022-12-30")).is_none());
+        assert_eq!(
+            schedule_for(&schedules, d("2023-01-01")).unwrap().version,
+            "NSE-2023.1"
+        );
+        assert_eq!(
+            schedule_for(&schedules, d("2024-12-31")).unwrap().version,
+            "NSE-2023.1"
+        );
+        assert_eq!(
+            schedule_for(&schedules, d("2025-01-01")).unwrap().version,
+            "NSE-2025.1"
+        );
+    }
+
+    #[test]
+    fn rejects_non_positive_price_and_zero_quantity() {
+        let schedule = FeeSchedule::nse_standard();
+        assert_eq!(
+            trade_cost(TradeSide::Buy, dec("0"), 10, &schedule),
+            Err(TradeCostError::InvalidPrice)
+        );
+        assert_eq!(
+            trade_cost(TradeSide::Buy, dec("1"), 0, &schedule),
+            Err(TradeCostError::ZeroQuantity)
+        );
+    }
+}